@@ -0,0 +1,160 @@
+//! Site changes made by admin chats through the Telegram bot. They are kept
+//! in the state store and applied over the sites of the config files each
+//! time these are loaded.
+
+use crate::config::{FlyingSite, WindSector};
+use crate::measures::WindSpeed;
+use crate::store::StateStore;
+
+/// Setting of the sites defined by `/addsite`, holding their definition.
+pub const ADDED: &str = "added";
+/// Setting of the sites left out by `/disable`.
+pub const DISABLED: &str = "disabled";
+/// Settings changed by `/setlimit`.
+pub const LIMITS: [&str; 2] = ["min_wind", "max_wind"];
+
+/// Splits `/addsite` arguments, e.g. "Col de la Forclaz 45.8 6.2 2mps 8mps
+/// 270-30", into the site name and its definition: coordinates, lightest
+/// and strongest flyable wind, then wind sectors.
+pub fn split_definition(arguments: &str) -> Option<(String, String)> {
+    let words: Vec<&str> = arguments.split_whitespace().collect();
+    // Names may contain numbers, the coordinates are the ones followed by a
+    // wind speed.
+    let start = (1..words.len().saturating_sub(2)).find(|&i| {
+        words[i].parse::<f64>().is_ok()
+            && words[i + 1].parse::<f64>().is_ok()
+            && words[i + 2].parse::<WindSpeed>().is_ok()
+    })?;
+    Some((words[..start].join(" "), words[start..].join(" ")))
+}
+
+/// The site `name` from its definition, see `split_definition`.
+pub fn parse_site(name: &str, definition: &str) -> Result<FlyingSite, String> {
+    let words: Vec<&str> = definition.split_whitespace().collect();
+    if words.len() < 5 {
+        return Err(String::from(
+            "expected coordinates, wind limits and wind sectors",
+        ));
+    }
+    let latitude: f64 = words[0]
+        .parse()
+        .ok()
+        .filter(|latitude| (-90.0..=90.0).contains(latitude))
+        .ok_or_else(|| format!("invalid latitude \"{}\"", words[0]))?;
+    let longitude: f64 = words[1]
+        .parse()
+        .ok()
+        .filter(|longitude| (-180.0..=180.0).contains(longitude))
+        .ok_or_else(|| format!("invalid longitude \"{}\"", words[1]))?;
+    let min = words[2]
+        .parse::<WindSpeed>()
+        .map_err(|error| error.to_string())?;
+    let max = words[3]
+        .parse::<WindSpeed>()
+        .map_err(|error| error.to_string())?;
+    if min > max {
+        return Err(String::from("the lightest wind is above the strongest"));
+    }
+    let sectors = words[4..]
+        .iter()
+        .map(|sector| parse_sector(sector))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(FlyingSite::new(
+        name, latitude, longitude, min, max, sectors,
+    ))
+}
+
+/// A sector such as "270-30", clockwise.
+fn parse_sector(text: &str) -> Result<WindSector, String> {
+    let error = || format!("invalid wind sector \"{}\", e.g. 270-30", text);
+    let (from, to) = text.split_once('-').ok_or_else(error)?;
+    let degrees = |text: &str| {
+        text.parse::<i16>()
+            .ok()
+            .filter(|degrees| (0..=360).contains(degrees))
+            .ok_or_else(error)
+    };
+    Ok(WindSector::from((degrees(from)?, degrees(to)?)))
+}
+
+/// Splits `/setlimit` arguments, e.g. "Col de la Forclaz max_wind 8mps",
+/// into the site name, one of `LIMITS` and its value.
+pub fn split_limit(arguments: &str) -> Result<(&str, &str, WindSpeed), String> {
+    let usage = || format!("Expected a site, {} and a speed.", LIMITS.join(" or "));
+    let mut words = arguments.rsplitn(3, char::is_whitespace);
+    let (value, setting, name) = match (words.next(), words.next(), words.next()) {
+        (Some(value), Some(setting), Some(name)) => (value, setting, name.trim()),
+        _ => return Err(usage()),
+    };
+    if !LIMITS.contains(&setting) {
+        return Err(usage());
+    }
+    let speed = value.parse().map_err(|error| format!("{}", error))?;
+    Ok((name, setting, speed))
+}
+
+/// `site` with `setting`, one of `LIMITS`, set to `speed`.
+pub fn with_limit(
+    site: &FlyingSite,
+    setting: &str,
+    speed: WindSpeed,
+) -> Result<FlyingSite, String> {
+    let mut site = site.clone();
+    match setting {
+        "min_wind" => site.min_flyable_wind = Some(speed),
+        "max_wind" => site.max_flyable_wind = Some(speed),
+        _ => return Err(format!("unknown limit \"{}\"", setting)),
+    }
+    if site.min_flyable_wind() > site.max_flyable_wind() {
+        return Err(String::from(
+            "the lightest wind would be above the strongest",
+        ));
+    }
+    Ok(site)
+}
+
+/// Applies the changes kept in `store` to `sites`: adds the sites defined
+/// through the bot, unless the config has one of the same name, sets their
+/// limits and leaves out the disabled ones. Changes that no longer apply
+/// are logged and skipped.
+pub fn apply_site_changes(sites: &mut Vec<FlyingSite>, store: &StateStore) -> rusqlite::Result<()> {
+    let changes = store.site_changes()?;
+    let position = |sites: &[FlyingSite], name: &str| {
+        sites
+            .iter()
+            .position(|site| site.name.eq_ignore_ascii_case(name))
+    };
+    for change in changes.iter().filter(|change| change.setting == ADDED) {
+        if position(sites, &change.site).is_some() {
+            tracing::warn!(site = %change.site, "the config defines a site added from the bot");
+            continue;
+        }
+        match parse_site(&change.site, &change.value) {
+            Ok(site) => sites.push(site),
+            Err(error) => tracing::warn!(site = %change.site, %error, "skipping an added site"),
+        }
+    }
+    for change in &changes {
+        if !LIMITS.contains(&&change.setting[..]) {
+            continue;
+        }
+        let changed = match position(sites, &change.site) {
+            Some(index) => change
+                .value
+                .parse()
+                .map_err(|error| format!("{}", error))
+                .and_then(|speed| with_limit(&sites[index], &change.setting, speed))
+                .map(|site| sites[index] = site),
+            None => Err(String::from("unknown site")),
+        };
+        if let Err(error) = changed {
+            tracing::warn!(site = %change.site, setting = %change.setting, %error, "skipping a site change");
+        }
+    }
+    sites.retain(|site| {
+        !changes.iter().any(|change| {
+            change.setting == DISABLED && change.site.eq_ignore_ascii_case(&site.name)
+        })
+    });
+    Ok(())
+}
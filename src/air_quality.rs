@@ -0,0 +1,115 @@
+//! Air quality forecasts from the OpenWeatherMap Air Pollution API, smog
+//! trapped in a valley hinting at an inversion and stable air.
+
+use crate::http::HttpClient;
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+
+// Index from which the air is poor, usually from smog under an inversion.
+const INVERSION_AQI: u8 = 4;
+const AQI_LABELS: [&str; 5] = ["good", "fair", "moderate", "poor", "very poor"];
+
+#[derive(Deserialize, Debug)]
+struct Main {
+    aqi: u8,
+}
+
+#[derive(Deserialize, Debug)]
+struct Components {
+    pm2_5: f32,
+}
+
+#[derive(Deserialize, Debug)]
+struct RawAirQuality {
+    dt: i64,
+    main: Main,
+    components: Components,
+}
+
+#[derive(Deserialize, Debug)]
+struct AirPollutionForecast {
+    list: Vec<RawAirQuality>,
+}
+
+/// The air quality for a single forecast hour.
+#[derive(Debug, Clone, Copy)]
+pub struct AirQuality {
+    /// Unix timestamp of the hour.
+    pub time: i64,
+    /// Air quality index, from 1 (good) to 5 (very poor).
+    pub aqi: u8,
+    /// Fine particles in µg/m³.
+    pub pm2_5: f32,
+}
+
+pub struct AirQualityClient {
+    url: String,
+    app_id: String,
+    http: HttpClient,
+}
+
+impl AirQualityClient {
+    pub fn new(url: String, app_id: String, http: HttpClient) -> Self {
+        AirQualityClient { url, app_id, http }
+    }
+
+    /// Calls are counted against the OpenWeatherMap quota.
+    pub fn name(&self) -> &'static str {
+        "OpenWeatherMap"
+    }
+
+    /// The hourly air quality forecast, for the next 4 days.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_air_quality(&self, lat: f64, lon: f64) -> reqwest::Result<Vec<AirQuality>> {
+        let (lat, lon) = (lat.to_string(), lon.to_string());
+        let forecast: AirPollutionForecast = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("lat", &lat[..]),
+                    ("lon", &lon[..]),
+                    ("appid", &self.app_id[..]),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        Ok(forecast
+            .list
+            .into_iter()
+            .map(|hour| AirQuality {
+                time: hour.dt,
+                aqi: hour.main.aqi,
+                pm2_5: hour.components.pm2_5,
+            })
+            .collect())
+    }
+}
+
+/// The worst air of the hours within `flown`, e.g. `Air quality poor (AQI
+/// 4, PM2.5 48 µg/m³), likely inversion: expect stable air`.
+pub fn describe(
+    air_quality: &[AirQuality],
+    flown: &[(DateTime<FixedOffset>, DateTime<FixedOffset>)],
+) -> Option<String> {
+    let worst = air_quality
+        .iter()
+        .filter(|hour| {
+            flown
+                .iter()
+                .any(|(start, end)| (start.timestamp()..end.timestamp()).contains(&hour.time))
+        })
+        .max_by(|a, b| (a.aqi, a.pm2_5).partial_cmp(&(b.aqi, b.pm2_5)).unwrap())?;
+    let label = AQI_LABELS[(worst.aqi.clamp(1, 5) - 1) as usize];
+    let mut line = format!(
+        "Air quality {} (AQI {}, PM2.5 {:.0} µg/m³)",
+        label, worst.aqi, worst.pm2_5
+    );
+    if worst.aqi >= INVERSION_AQI {
+        line.push_str(", likely inversion: expect stable air");
+    }
+    Some(line)
+}
@@ -0,0 +1,113 @@
+use crate::http::HttpClient;
+use chrono::{DateTime, FixedOffset};
+use reqwest::Result;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Notam {
+    number: String,
+    text: String,
+    effective_start: String,
+    effective_end: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CoreNotamData {
+    notam: Notam,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct NotamProperties {
+    core_notam_data: CoreNotamData,
+}
+
+#[derive(Deserialize, Debug)]
+struct NotamItem {
+    properties: NotamProperties,
+}
+
+#[derive(Deserialize, Debug)]
+struct NotamResponse {
+    items: Vec<NotamItem>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ActiveNotam {
+    pub number: String,
+    pub text: String,
+}
+
+/// Client for NOTAM APIs speaking the FAA NOTAM API response format.
+pub struct NotamClient {
+    url: String,
+    client_id: String,
+    client_secret: String,
+    http: HttpClient,
+}
+
+impl NotamClient {
+    pub fn new(url: String, client_id: String, client_secret: String, http: HttpClient) -> Self {
+        NotamClient {
+            url,
+            client_id,
+            client_secret,
+            http,
+        }
+    }
+
+    /// NOTAMs for `airspace` whose effective period overlaps `[from, to)`.
+    pub async fn get_active_notams(
+        &self,
+        airspace: &str,
+        from: DateTime<FixedOffset>,
+        to: DateTime<FixedOffset>,
+    ) -> Result<Vec<ActiveNotam>> {
+        let response: NotamResponse = self
+            .http
+            .send(|client| {
+                client
+                    .get(&self.url)
+                    .header("client_id", &self.client_id[..])
+                    .header("client_secret", &self.client_secret[..])
+                    .query(&[("icaoLocation", airspace), ("pageSize", "200")])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+
+        let notams = response
+            .items
+            .into_iter()
+            .map(|item| item.properties.core_notam_data.notam)
+            .filter(|notam| is_effective_during(notam, from, to))
+            .map(|notam| ActiveNotam {
+                number: notam.number,
+                text: notam.text,
+            })
+            .collect();
+        Ok(notams)
+    }
+}
+
+fn is_effective_during(
+    notam: &Notam,
+    from: DateTime<FixedOffset>,
+    to: DateTime<FixedOffset>,
+) -> bool {
+    let starts_before_end = match DateTime::parse_from_rfc3339(&notam.effective_start) {
+        Ok(start) => start < to,
+        Err(_) => true,
+    };
+    // Permanent NOTAMs carry "PERM" instead of an end date.
+    let ends_after_start = match DateTime::parse_from_rfc3339(&notam.effective_end) {
+        Ok(end) => end > from,
+        Err(_) => true,
+    };
+    starts_before_end && ends_after_start
+}
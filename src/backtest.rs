@@ -0,0 +1,283 @@
+//! Backtests of the limits of a site against past weather from the
+//! Open-Meteo Historical Weather API, telling how often the site would have
+//! been flyable. The quickest way to calibrate the limits of a new site.
+
+use crate::config::{DaylightConfig, FlyingSite};
+use crate::forecast_client::{
+    self, civil_dawn_and_dusk, get_time_of_day, DayWeatherForecast, HourWeatherForecast,
+};
+use crate::http::HttpClient;
+use crate::measures::{Precipitation, Pressure, Temperature, WindDirection, WindSpeed};
+use crate::timezone::Tz;
+use crate::{SiteFlyablePeriod, SkipReason};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, TimeZone, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Most days a backtest covers, each one adding 24 hours to the response.
+pub const MAX_DAYS: i64 = 366;
+// Archives have no chance of rain: hours with this much precipitation count
+// as certain rain, the others as dry.
+const RAINY_HOUR_MM: f32 = 0.1;
+// Centimeters of fresh snow per millimeter of water, as used by Open-Meteo.
+const SNOW_CM_PER_MM: f32 = 0.7;
+
+#[derive(Deserialize, Debug)]
+struct ArchiveHourly {
+    time: Vec<i64>,
+    temperature_2m: Vec<Option<f32>>,
+    apparent_temperature: Vec<Option<f32>>,
+    dew_point_2m: Vec<Option<f32>>,
+    wind_speed_10m: Vec<Option<f32>>,
+    wind_gusts_10m: Vec<Option<f32>>,
+    wind_direction_10m: Vec<Option<f32>>,
+    cloud_cover: Vec<Option<f32>>,
+    precipitation: Vec<Option<f32>>,
+    rain: Vec<Option<f32>>,
+    snowfall: Vec<Option<f32>>,
+    surface_pressure: Vec<Option<f32>>,
+    relative_humidity_2m: Vec<Option<f32>>,
+    snow_depth: Vec<Option<f32>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ArchiveDaily {
+    time: Vec<i64>,
+    sunrise: Vec<i64>,
+    sunset: Vec<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Archive {
+    latitude: f64,
+    longitude: f64,
+    timezone: String,
+    utc_offset_seconds: i32,
+    hourly: ArchiveHourly,
+    daily: ArchiveDaily,
+}
+
+pub struct ArchiveClient {
+    url: String,
+    http: HttpClient,
+    daylight: DaylightConfig,
+}
+
+impl ArchiveClient {
+    pub fn new(url: String, http: HttpClient, daylight: DaylightConfig) -> Self {
+        ArchiveClient {
+            url,
+            http,
+            daylight,
+        }
+    }
+
+    /// The weather of the days from `from` to `to` as forecasts, local to
+    /// the point. The latest days may be missing, archives lagging a few
+    /// days behind.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_history(
+        &self,
+        lat: f64,
+        lon: f64,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> reqwest::Result<Vec<DayWeatherForecast>> {
+        let (lat, lon) = (lat.to_string(), lon.to_string());
+        let (from, to) = (from.to_string(), to.to_string());
+        let archive: Archive = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("latitude", &lat[..]),
+                    ("longitude", &lon[..]),
+                    ("start_date", &from[..]),
+                    ("end_date", &to[..]),
+                    (
+                        "hourly",
+                        "temperature_2m,apparent_temperature,dew_point_2m,wind_speed_10m,\
+                         wind_gusts_10m,wind_direction_10m,cloud_cover,precipitation,rain,\
+                         snowfall,surface_pressure,relative_humidity_2m,snow_depth",
+                    ),
+                    ("daily", "sunrise,sunset"),
+                    ("wind_speed_unit", "ms"),
+                    ("timezone", "auto"),
+                    ("timeformat", "unixtime"),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        Ok(days(archive, &self.daylight))
+    }
+}
+
+/// The days of `archive` with their hours, days without any left out.
+fn days(archive: Archive, daylight: &DaylightConfig) -> Vec<DayWeatherForecast> {
+    let tz = match Tz::load(&archive.timezone) {
+        Ok(tz) => Some(tz),
+        Err(error) => {
+            tracing::warn!(timezone = %archive.timezone, %error, "using the current offset of the time zone");
+            None
+        }
+    };
+    let offset = FixedOffset::east_opt(archive.utc_offset_seconds)
+        .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+    let local = |timestamp: i64| match &tz {
+        Some(tz) => tz.at(timestamp),
+        None => offset.timestamp_opt(timestamp, 0).unwrap(),
+    };
+    let mut days: BTreeMap<NaiveDate, DayWeatherForecast> = BTreeMap::new();
+    let daily = &archive.daily;
+    for (i, time) in daily.time.iter().enumerate() {
+        let (sunrise, sunset) = match (daily.sunrise.get(i), daily.sunset.get(i)) {
+            (Some(sunrise), Some(sunset)) => (local(*sunrise), local(*sunset)),
+            _ => continue,
+        };
+        let date = local(*time).date_naive();
+        let (civil_dawn, civil_dusk) =
+            civil_dawn_and_dusk(date, archive.latitude, archive.longitude, sunrise, sunset);
+        days.insert(
+            date,
+            DayWeatherForecast {
+                date,
+                sunrise,
+                sunset,
+                civil_dawn,
+                civil_dusk,
+                hourly: vec![],
+                outlook: None,
+                alerts: vec![],
+                missing: BTreeMap::new(),
+            },
+        );
+    }
+    let hourly = &archive.hourly;
+    let value = |values: &Vec<Option<f32>>, i: usize| values.get(i).copied().flatten();
+    for (i, timestamp) in hourly.time.iter().enumerate() {
+        let time = local(*timestamp);
+        let day = match days.get_mut(&time.date_naive()) {
+            Some(day) => day,
+            None => continue,
+        };
+        let (temperature, wind_speed, wind_direction) = match (
+            value(&hourly.temperature_2m, i),
+            value(&hourly.wind_speed_10m, i),
+            value(&hourly.wind_direction_10m, i),
+        ) {
+            (Some(temperature), Some(speed), Some(direction)) => (temperature, speed, direction),
+            // Not archived yet.
+            _ => continue,
+        };
+        let precipitation = value(&hourly.precipitation, i).unwrap_or(0.0);
+        let duration = Duration::hours(1);
+        day.hourly.push(HourWeatherForecast {
+            time,
+            duration,
+            time_of_day: get_time_of_day(time, duration, day, daylight),
+            temperature: Temperature::C(temperature),
+            feels_like: Temperature::C(
+                value(&hourly.apparent_temperature, i).unwrap_or(temperature),
+            ),
+            dew_point: Temperature::C(value(&hourly.dew_point_2m, i).unwrap_or(temperature)),
+            wind_speed: WindSpeed::MPS(wind_speed),
+            wind_gust: value(&hourly.wind_gusts_10m, i).map(WindSpeed::MPS),
+            wind_direction: WindDirection::new(wind_direction.round() as i16),
+            clouds: value(&hourly.cloud_cover, i).unwrap_or(0.0).round() as i16,
+            pop: if precipitation >= RAINY_HOUR_MM {
+                1.0
+            } else {
+                0.0
+            },
+            pressure: value(&hourly.surface_pressure, i).map(Pressure::HPA),
+            humidity: value(&hourly.relative_humidity_2m, i).map(|humidity| humidity as u8),
+            uvi: None,
+            visibility: None,
+            rain: value(&hourly.rain, i)
+                .filter(|rain| *rain > 0.0)
+                .map(Precipitation::MM),
+            snow: value(&hourly.snowfall, i)
+                .filter(|snow| *snow > 0.0)
+                .map(|cm| Precipitation::MM(cm / SNOW_CM_PER_MM)),
+            lapse_rate: None,
+            thermal_top_m: None,
+            level_winds: vec![],
+            surface_wind_speed: None,
+            waves: None,
+            tide_m: None,
+            prior_rain_mm: None,
+            freezing_level_m: None,
+            snow_depth_m: value(&hourly.snow_depth, i),
+            spread: None,
+        });
+    }
+    days.into_values()
+        .filter(|day| !day.hourly.is_empty())
+        .collect()
+}
+
+/// How a site fared on a past day: its flyable periods, or why it had none.
+pub struct BacktestDay {
+    pub date: NaiveDate,
+    pub outcome: Result<Vec<SiteFlyablePeriod>, SkipReason>,
+}
+
+impl BacktestDay {
+    pub fn flyable_hours(&self) -> f32 {
+        self.outcome.as_ref().map_or(0.0, |periods| {
+            periods.iter().map(|period| period.hours()).sum()
+        })
+    }
+}
+
+/// The days of a backtest, oldest first.
+pub struct Backtest {
+    pub days: Vec<BacktestDay>,
+}
+
+impl Backtest {
+    /// Runs the checks of `site` over the past days of `history`, as the
+    /// checks of a forecast for each of them.
+    pub fn run(
+        site: &FlyingSite,
+        mut history: Vec<DayWeatherForecast>,
+        min_period_hours: i64,
+    ) -> Self {
+        if site.max_prior_rain_mm.is_some() {
+            forecast_client::apply_prior_rain(&mut history);
+        }
+        let since = DateTime::<Utc>::MIN_UTC;
+        let days = history
+            .iter()
+            .map(|day| BacktestDay {
+                date: day.date,
+                outcome: crate::flyable_periods(site, &day.hourly, min_period_hours, since),
+            })
+            .collect();
+        Backtest { days }
+    }
+
+    pub fn flyable_days(&self) -> usize {
+        self.days.iter().filter(|day| day.outcome.is_ok()).count()
+    }
+
+    pub fn flyable_hours(&self) -> f32 {
+        self.days.iter().map(BacktestDay::flyable_hours).sum()
+    }
+
+    /// Days not flyable per reason, most common first.
+    pub fn reasons(&self) -> Vec<(SkipReason, usize)> {
+        let mut counts: BTreeMap<SkipReason, usize> = BTreeMap::new();
+        for day in &self.days {
+            if let Err(reason) = &day.outcome {
+                *counts.entry(*reason).or_default() += 1;
+            }
+        }
+        let mut reasons: Vec<(SkipReason, usize)> = counts.into_iter().collect();
+        reasons.sort_by_key(|(reason, count)| (std::cmp::Reverse(*count), *reason));
+        reasons
+    }
+}
@@ -0,0 +1,800 @@
+//! Long-running Telegram bot letting chats manage their own subscriptions.
+
+use crate::admin;
+use crate::config::{FlyingSite, MessageStyle, Target};
+use crate::daemon::{Reload, Signal, Signals};
+use crate::error::ProviderError;
+use crate::i18n::{self, Strings};
+use crate::measures::Units;
+use crate::message::MessageFormat;
+use crate::notification::telegram::{
+    self, CallbackQuery, InlineKeyboardButton, InlineKeyboardMarkup, TelegramClient,
+};
+use crate::scoring::ScoringModel;
+use crate::store::{StateStore, ALL_SITES};
+use crate::systemd::{self, Watchdog};
+use crate::{ForecastSources, SiteFlyAbilityReport, SkippedSite};
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const POLL_TIMEOUT_SECONDS: u64 = 30;
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+// Callback data of the keyboard buttons, site buttons carry a `site_key` to
+// stay within Telegram's 64 byte limit.
+const TOGGLE_PREFIX: &str = "toggle:";
+const SUBSCRIBE_ALL: &str = "all";
+const UNSUBSCRIBE_ALL: &str = "none";
+
+/// Key of the site named `name` in callback data, a hash of the name rather
+/// than its index so that keyboards sent before the sites are added, removed
+/// or reordered don't toggle another site.
+fn site_key(name: &str) -> String {
+    Sha256::digest(name.as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+struct Reply {
+    text: String,
+    keyboard: Option<InlineKeyboardMarkup>,
+    /// The sites changed, to be reloaded before the next update.
+    reload: bool,
+}
+
+impl From<String> for Reply {
+    fn from(text: String) -> Self {
+        Reply {
+            text,
+            keyboard: None,
+            reload: false,
+        }
+    }
+}
+
+impl Reply {
+    fn reloading(text: String) -> Self {
+        Reply {
+            reload: true,
+            ..text.into()
+        }
+    }
+}
+
+type CheckOutcome = Result<SiteFlyAbilityReport, SkippedSite>;
+/// Site, profile and day of a check.
+type CheckKey = (String, Option<String>, NaiveDate);
+
+fn check_key(site: &FlyingSite, date: NaiveDate) -> CheckKey {
+    (site.name.clone(), site.profile.clone(), date)
+}
+
+struct CachedOutcome {
+    checked_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    outcome: CheckOutcome,
+}
+
+/// Recent answers to `/forecast` per site, profile and day, sparing the
+/// providers when many chats ask for the same site within minutes. The
+/// updates of a poll being answered in turn, a batch of them asking for a
+/// popular site costs a single check.
+pub struct ReportCache {
+    /// Minutes an answer is reused for, unless its site has its own.
+    minutes: u32,
+    outcomes: Mutex<HashMap<CheckKey, CachedOutcome>>,
+    /// Offset from UTC of the forecasts of each site, the day asked for
+    /// being resolved there as by `check_site`.
+    zones: Mutex<HashMap<String, FixedOffset>>,
+}
+
+impl ReportCache {
+    pub fn new(minutes: u32) -> Self {
+        ReportCache {
+            minutes,
+            outcomes: Mutex::new(HashMap::new()),
+            zones: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The outcome of the last check of `site` for `target`, with the time
+    /// of the check, while it is recent enough.
+    fn get(&self, site: &FlyingSite, target: Target) -> Option<(DateTime<Utc>, CheckOutcome)> {
+        let zone = *self.zones.lock().unwrap().get(&site.name)?;
+        let date = target.date(Utc::now().with_timezone(&zone).date_naive());
+        let outcomes = self.outcomes.lock().unwrap();
+        outcomes
+            .get(&check_key(site, date))
+            .filter(|cached| cached.expires_at > Utc::now())
+            .map(|cached| (cached.checked_at, cached.outcome.clone()))
+    }
+
+    /// Keeps `outcome` on `date` for the minutes of `site`, dropping the
+    /// answers too old to be reused. `zone` is the offset of its forecast.
+    fn put(&self, site: &FlyingSite, date: NaiveDate, zone: FixedOffset, outcome: &CheckOutcome) {
+        let minutes = site.forecast_cache_minutes.unwrap_or(self.minutes);
+        let now = Utc::now();
+        self.zones.lock().unwrap().insert(site.name.clone(), zone);
+        let mut outcomes = self.outcomes.lock().unwrap();
+        outcomes.retain(|_, cached| cached.expires_at > now);
+        if minutes > 0 {
+            outcomes.insert(
+                check_key(site, date),
+                CachedOutcome {
+                    checked_at: now,
+                    expires_at: now + chrono::Duration::minutes(minutes as i64),
+                    outcome: outcome.clone(),
+                },
+            );
+        }
+    }
+
+    /// Forgets every answer, e.g. once the limits of the sites change.
+    fn clear(&self) {
+        self.outcomes.lock().unwrap().clear();
+    }
+}
+
+/// E.g. "Data from 12 min ago", for answers reused from the cache.
+fn data_age(strings: &Strings, checked_at: DateTime<Utc>) -> String {
+    match (Utc::now() - checked_at).num_minutes() {
+        0 => String::from(strings.data_ages[0]),
+        minutes => i18n::fill(strings.data_ages[1], &[("minutes", &minutes.to_string())]),
+    }
+}
+
+pub struct Bot<'a> {
+    pub client: TelegramClient,
+    pub store: &'a StateStore,
+    /// Used to answer `/forecast` with a fresh report.
+    pub sources: &'a ForecastSources,
+    pub sites: Vec<FlyingSite>,
+    pub min_period_hours: i64,
+    pub scoring: ScoringModel,
+    pub format: MessageFormat,
+    /// Chats allowed to change the sites, see `admin`.
+    pub admins: Vec<String>,
+    /// The units of the chats not using those of `format`.
+    pub chat_units: HashMap<String, Units>,
+    /// Recent answers to `/forecast`, see `ReportCache`.
+    pub reports: ReportCache,
+    /// Retries the messages runs failed to deliver.
+    pub outbox: telegram::Outbox,
+    /// Replaces the sites, thresholds, wording, admins and outbox on SIGHUP
+    /// and after the sites change.
+    pub reload: Reload,
+}
+
+impl<'a> Bot<'a> {
+    /// Answers commands until SIGTERM or SIGINT, which stop the bot once the
+    /// updates being handled are answered. Failing to reach Telegram is
+    /// retried, failing to update the store is not.
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut signals = Signals::new()?;
+        let mut watchdog = Watchdog::new();
+        let mut offset = 0;
+        systemd::ready();
+        systemd::status(&format!(
+            "answering commands for {} sites",
+            self.sites.len()
+        ));
+        loop {
+            // Quiet hours ending while the bot runs don't wait for the next
+            // notification run.
+            if let Err(error) =
+                telegram::send_due_messages(&self.client, self.store, &self.outbox).await
+            {
+                tracing::warn!(%error, "failed to send the deferred messages");
+            }
+            let polled = {
+                let poll = self.client.get_updates(offset, POLL_TIMEOUT_SECONDS);
+                tokio::pin!(poll);
+                // Pinged while waiting for updates, but not while handling
+                // them.
+                loop {
+                    tokio::select! {
+                        signal = signals.recv() => break Err(signal),
+                        polled = &mut poll => break Ok(polled),
+                        _ = watchdog.tick() => {}
+                    }
+                }
+            };
+            let updates = match polled {
+                Err(Signal::Stop) => {
+                    tracing::info!("stopping");
+                    systemd::stopping();
+                    return Ok(());
+                }
+                Err(Signal::Reload) => {
+                    self.reload();
+                    continue;
+                }
+                Ok(Ok(updates)) => updates,
+                Ok(Err(error)) => {
+                    tracing::warn!(%error, "failed to fetch Telegram updates");
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            };
+            for update in updates {
+                offset = offset.max(update.update_id + 1);
+                // A failing update is skipped rather than stopping the bot,
+                // which would fetch and fail it again once restarted.
+                if let Some(query) = update.callback_query {
+                    if let Err(error) = self.handle_callback(query).await {
+                        tracing::error!(%error, "failed to handle a callback query");
+                    }
+                    continue;
+                }
+                let message = match update.message {
+                    Some(message) => message,
+                    None => continue,
+                };
+                let text = match message.text {
+                    Some(text) => text,
+                    None => continue,
+                };
+                let chat_id = message.chat.id.to_string();
+                let reply = match self.handle(&chat_id, &text).await {
+                    Ok(reply) => reply,
+                    Err(error) => {
+                        tracing::error!(%chat_id, %error, "failed to handle a command");
+                        continue;
+                    }
+                };
+                if let Some(reply) = reply {
+                    if reply.reload {
+                        self.reload();
+                    }
+                    let sent = match &reply.keyboard {
+                        Some(keyboard) => {
+                            self.client
+                                .send_keyboard(&chat_id, &reply.text, keyboard)
+                                .await
+                        }
+                        None => self.client.send_text(&chat_id, &reply.text).await,
+                    };
+                    if let Err(error) = sent {
+                        tracing::warn!(%chat_id, %error, "failed to reply");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Swaps in the settings of the config file, keeping the current ones
+    /// when it is invalid.
+    fn reload(&mut self) {
+        match (self.reload)() {
+            Ok(settings) => {
+                self.sites = settings.sites;
+                self.min_period_hours = settings.min_period_hours;
+                self.scoring = settings.scoring;
+                self.format = settings.format;
+                self.admins = settings.admins;
+                self.chat_units = settings.chat_units;
+                if let Some(outbox) = settings.outbox {
+                    self.outbox = outbox;
+                }
+                self.reports.clear();
+                tracing::info!("reloaded the config");
+                systemd::status(&format!(
+                    "answering commands for {} sites",
+                    self.sites.len()
+                ));
+            }
+            Err(error) => tracing::error!(%error, "failed to reload the config"),
+        }
+    }
+
+    async fn handle(&self, chat_id: &str, text: &str) -> Result<Option<Reply>, Box<dyn Error>> {
+        let (command, arguments) = parse_command(text);
+        let strings = self.chat_format(chat_id)?.strings();
+        let reply = match command {
+            "/forecast" => self.forecast(chat_id, arguments).await?.into(),
+            "/start" | "/help" if self.is_admin(chat_id) => {
+                format!("{}\n\n{}", strings.help, strings.admin_help).into()
+            }
+            "/start" | "/help" => String::from(strings.help).into(),
+            "/sites" => self.list_sites(chat_id)?.into(),
+            "/subscribe" if arguments.is_empty() => Reply {
+                text: String::from(strings.pick_sites),
+                keyboard: Some(self.keyboard(chat_id)?),
+                reload: false,
+            },
+            "/subscribe" => self.subscribe(chat_id, arguments, strings)?.into(),
+            "/unsubscribe" => self.unsubscribe(chat_id, arguments, strings)?.into(),
+            "/style" => self.set_style(chat_id, arguments, strings)?.into(),
+            "/profile" => self.set_profile(chat_id, arguments, strings)?.into(),
+            "/addsite" | "/setlimit" | "/disable" | "/enable" if !self.is_admin(chat_id) => {
+                String::from(strings.admins_only).into()
+            }
+            "/addsite" => self.add_site(arguments, strings)?,
+            "/setlimit" => self.set_limit(arguments, strings)?,
+            "/disable" => self.disable(arguments, strings)?,
+            "/enable" => self.enable(arguments, strings)?,
+            _ => return Ok(None),
+        };
+        Ok(Some(reply))
+    }
+
+    fn is_admin(&self, chat_id: &str) -> bool {
+        self.admins.iter().any(|admin| admin == chat_id)
+    }
+
+    fn add_site(&self, arguments: &str, strings: &Strings) -> Result<Reply, Box<dyn Error>> {
+        let (name, definition) = match admin::split_definition(arguments) {
+            Some(split) => split,
+            None => return Ok(String::from(strings.add_site_usage).into()),
+        };
+        if self.find_site(&name).is_some() {
+            return Ok(i18n::fill(strings.site_exists, &[("site", &name)]).into());
+        }
+        if let Err(error) = admin::parse_site(&name, &definition) {
+            return Ok(i18n::fill(
+                strings.site_not_added,
+                &[("site", &name), ("error", &error)],
+            )
+            .into());
+        }
+        self.store
+            .set_site_change(&name, admin::ADDED, &definition)?;
+        self.store.remove_site_change(&name, admin::DISABLED)?;
+        Ok(Reply::reloading(i18n::fill(
+            strings.site_added,
+            &[("site", &name)],
+        )))
+    }
+
+    fn set_limit(&self, arguments: &str, strings: &Strings) -> Result<Reply, Box<dyn Error>> {
+        let (name, setting, speed) = match admin::split_limit(arguments) {
+            Ok(split) => split,
+            Err(error) => return Ok(i18n::fill(strings.limit_usage, &[("error", &error)]).into()),
+        };
+        let site = match self.find_site(name) {
+            Some(site) => site,
+            None => return Ok(i18n::fill(strings.unknown_site, &[("site", name)]).into()),
+        };
+        if let Err(error) = admin::with_limit(site, setting, speed) {
+            return Ok(i18n::fill(
+                strings.limit_not_set,
+                &[("site", &site.name), ("error", &error)],
+            )
+            .into());
+        }
+        self.store
+            .set_site_change(&site.name, setting, &speed.to_string())?;
+        Ok(Reply::reloading(i18n::fill(
+            strings.limit_set,
+            &[
+                ("setting", setting),
+                ("site", &site.name),
+                ("speed", &speed.to_string()),
+            ],
+        )))
+    }
+
+    fn disable(&self, arguments: &str, strings: &Strings) -> Result<Reply, Box<dyn Error>> {
+        let site = match self.find_site(arguments) {
+            Some(site) => site,
+            None => return Ok(i18n::fill(strings.unknown_site, &[("site", arguments)]).into()),
+        };
+        self.store
+            .set_site_change(&site.name, admin::DISABLED, "true")?;
+        Ok(Reply::reloading(i18n::fill(
+            strings.site_disabled,
+            &[("site", &site.name)],
+        )))
+    }
+
+    fn enable(&self, arguments: &str, strings: &Strings) -> Result<Reply, Box<dyn Error>> {
+        let disabled = self.store.site_changes()?.into_iter().find(|change| {
+            change.setting == admin::DISABLED && change.site.eq_ignore_ascii_case(arguments)
+        });
+        let change = match disabled {
+            Some(change) => change,
+            None => {
+                return Ok(i18n::fill(strings.site_not_disabled, &[("site", arguments)]).into())
+            }
+        };
+        self.store
+            .remove_site_change(&change.site, admin::DISABLED)?;
+        Ok(Reply::reloading(i18n::fill(
+            strings.site_enabled,
+            &[("site", &change.site)],
+        )))
+    }
+
+    fn find_site(&self, name: &str) -> Option<&FlyingSite> {
+        self.sites
+            .iter()
+            .find(|site| site.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Applies a keyboard button press and refreshes the keyboard.
+    async fn handle_callback(&self, query: CallbackQuery) -> Result<(), Box<dyn Error>> {
+        let (message, data) = match (query.message, query.data) {
+            (Some(message), Some(data)) => (message, data),
+            _ => {
+                self.answer_callback(&query.id, None).await;
+                return Ok(());
+            }
+        };
+        let chat_id = message.chat.id.to_string();
+        let mut answer = None;
+        match &data[..] {
+            SUBSCRIBE_ALL => {
+                self.store.remove_subscriptions(&chat_id)?;
+                self.store.add_subscription(&chat_id, ALL_SITES)?;
+            }
+            UNSUBSCRIBE_ALL => self.store.remove_subscriptions(&chat_id)?,
+            _ => {
+                let site = data
+                    .strip_prefix(TOGGLE_PREFIX)
+                    .and_then(|key| self.sites.iter().find(|site| site_key(&site.name) == key));
+                match site {
+                    Some(site) => self.toggle(&chat_id, &site.name)?,
+                    // Removed since the keyboard was sent, which the refresh
+                    // below catches up with.
+                    None => answer = Some(self.chat_format(&chat_id)?.strings().unknown_button),
+                }
+            }
+        }
+        self.answer_callback(&query.id, answer).await;
+        let keyboard = self.keyboard(&chat_id)?;
+        if let Err(error) = self
+            .client
+            .edit_keyboard(&chat_id, message.message_id, &keyboard)
+            .await
+        {
+            tracing::warn!(%chat_id, %error, "failed to update the keyboard");
+        }
+        Ok(())
+    }
+
+    /// Stops the loading indicator on the pressed button, showing `text`.
+    async fn answer_callback(&self, callback_query_id: &str, text: Option<&str>) {
+        if let Err(error) = self
+            .client
+            .answer_callback_query(callback_query_id, text)
+            .await
+        {
+            tracing::warn!(%error, "failed to answer callback query");
+        }
+    }
+
+    fn keyboard(&self, chat_id: &str) -> Result<InlineKeyboardMarkup, Box<dyn Error>> {
+        let strings = self.chat_format(chat_id)?.strings();
+        let subscribed = self.chat_sites(chat_id)?;
+        let everything = subscribed.iter().any(|site| site == ALL_SITES);
+        let mut rows: Vec<Vec<InlineKeyboardButton>> = self
+            .sites
+            .iter()
+            .map(|site| {
+                let marker = if everything || subscribed.contains(&site.name) {
+                    "✅"
+                } else {
+                    "▫️"
+                };
+                vec![InlineKeyboardButton {
+                    text: format!("{} {}", marker, site.name),
+                    callback_data: format!("{}{}", TOGGLE_PREFIX, site_key(&site.name)),
+                }]
+            })
+            .collect();
+        rows.push(vec![
+            InlineKeyboardButton {
+                text: String::from(strings.all_sites),
+                callback_data: String::from(SUBSCRIBE_ALL),
+            },
+            InlineKeyboardButton {
+                text: String::from(strings.no_sites),
+                callback_data: String::from(UNSUBSCRIBE_ALL),
+            },
+        ]);
+        Ok(InlineKeyboardMarkup {
+            inline_keyboard: rows,
+        })
+    }
+
+    fn toggle(&self, chat_id: &str, site: &str) -> Result<(), Box<dyn Error>> {
+        let subscribed = self.chat_sites(chat_id)?;
+        if subscribed.iter().any(|s| s == ALL_SITES || s == site) {
+            self.remove_site(chat_id, site)
+        } else {
+            Ok(self.store.add_subscription(chat_id, site)?)
+        }
+    }
+
+    fn remove_site(&self, chat_id: &str, site: &str) -> Result<(), Box<dyn Error>> {
+        // Following everything turns into following the remaining sites.
+        if self.chat_sites(chat_id)?.iter().any(|s| s == ALL_SITES) {
+            self.store.remove_subscription(chat_id, ALL_SITES)?;
+            for other in &self.sites {
+                self.store.add_subscription(chat_id, &other.name)?;
+            }
+        }
+        Ok(self.store.remove_subscription(chat_id, site)?)
+    }
+
+    fn chat_sites(&self, chat_id: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(self
+            .store
+            .subscriptions()?
+            .into_iter()
+            .filter(|subscription| subscription.chat_id == chat_id)
+            .map(|subscription| subscription.site)
+            .collect())
+    }
+
+    /// Configured site names matching the comma separated `arguments`,
+    /// or the first unknown name.
+    fn resolve_sites(&self, arguments: &str, strings: &Strings) -> Result<Vec<&str>, String> {
+        arguments
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                self.sites
+                    .iter()
+                    .find(|site| site.name.eq_ignore_ascii_case(name))
+                    .map(|site| &site.name[..])
+                    .ok_or_else(|| i18n::fill(strings.unknown_site, &[("site", name)]))
+            })
+            .collect()
+    }
+
+    fn list_sites(&self, chat_id: &str) -> Result<String, Box<dyn Error>> {
+        let subscribed = self.chat_sites(chat_id)?;
+        let everything = subscribed.iter().any(|site| site == ALL_SITES);
+        let lines: Vec<String> = self
+            .sites
+            .iter()
+            .map(|site| &site.name)
+            .map(|site| {
+                let marker = if everything || subscribed.contains(site) {
+                    "✅"
+                } else {
+                    "▫️"
+                };
+                format!("{} {}", marker, site)
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    fn subscribe(
+        &self,
+        chat_id: &str,
+        arguments: &str,
+        strings: &Strings,
+    ) -> Result<String, Box<dyn Error>> {
+        let sites = match self.resolve_sites(arguments, strings) {
+            Ok(sites) => sites,
+            Err(reply) => return Ok(reply),
+        };
+        for site in &sites {
+            self.store.add_subscription(chat_id, site)?;
+        }
+        Ok(i18n::fill(
+            strings.subscribed,
+            &[("sites", &sites.join(", "))],
+        ))
+    }
+
+    fn unsubscribe(
+        &self,
+        chat_id: &str,
+        arguments: &str,
+        strings: &Strings,
+    ) -> Result<String, Box<dyn Error>> {
+        if arguments.trim().is_empty() {
+            self.store.remove_subscriptions(chat_id)?;
+            return Ok(String::from(strings.unsubscribed_all));
+        }
+        let sites = match self.resolve_sites(arguments, strings) {
+            Ok(sites) => sites,
+            Err(reply) => return Ok(reply),
+        };
+        for site in &sites {
+            self.remove_site(chat_id, site)?;
+        }
+        Ok(i18n::fill(
+            strings.unsubscribed,
+            &[("sites", &sites.join(", "))],
+        ))
+    }
+
+    fn set_style(
+        &self,
+        chat_id: &str,
+        arguments: &str,
+        strings: &Strings,
+    ) -> Result<String, Box<dyn Error>> {
+        let style = match MessageStyle::parse(arguments) {
+            Some(style) => style,
+            None => return Ok(String::from(strings.style_usage)),
+        };
+        self.store.set_chat_style(chat_id, style.label())?;
+        Ok(i18n::fill(strings.style_set, &[("style", style.label())]))
+    }
+
+    fn set_profile(
+        &self,
+        chat_id: &str,
+        arguments: &str,
+        strings: &Strings,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut names: Vec<&str> = self
+            .sites
+            .iter()
+            .flat_map(|site| site.profiles.keys())
+            .map(String::as_str)
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        if names.is_empty() {
+            return Ok(String::from(strings.no_profiles));
+        }
+        let profiles = names.join(", ");
+        let argument = arguments.trim();
+        if argument.is_empty() {
+            return Ok(match self.store.chat_profiles()?.remove(chat_id) {
+                Some(current) => i18n::fill(
+                    strings.profile,
+                    &[("profile", &current), ("profiles", &profiles)],
+                ),
+                None => i18n::fill(strings.no_profile, &[("profiles", &profiles)]),
+            });
+        }
+        if argument.eq_ignore_ascii_case("none") {
+            self.store.set_chat_profile(chat_id, None)?;
+            return Ok(String::from(strings.profile_cleared));
+        }
+        match names
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(argument))
+        {
+            Some(name) => {
+                self.store.set_chat_profile(chat_id, Some(name))?;
+                Ok(i18n::fill(strings.profile_set, &[("profile", name)]))
+            }
+            None => Ok(i18n::fill(
+                strings.unknown_profile,
+                &[("profile", argument), ("profiles", &profiles)],
+            )),
+        }
+    }
+
+    /// The message format in the units of the chat and the style it
+    /// picked.
+    fn chat_format(&self, chat_id: &str) -> Result<MessageFormat, Box<dyn Error>> {
+        let format = match self.chat_units.get(chat_id) {
+            Some(units) => self.format.with_units(*units),
+            None => self.format.clone(),
+        };
+        let style = self
+            .store
+            .chat_styles()?
+            .get(chat_id)
+            .and_then(|style| MessageStyle::parse(style));
+        Ok(match style {
+            Some(style) => format.with_style(style),
+            None => format,
+        })
+    }
+
+    /// Checks `site` like `check_site`, keeping the outcome for the day of
+    /// `target` in the time zone of the forecast.
+    async fn check(
+        &self,
+        site: &FlyingSite,
+        target: Target,
+    ) -> Result<CheckOutcome, ProviderError> {
+        let forecast = self.sources.get_forecast(site, Some(self.store)).await?;
+        let date = target.date(crate::forecast_today(&forecast));
+        let zone = forecast.first().map(|day| day.sunrise.timezone());
+        let outcome = crate::analyze_site(
+            self.sources,
+            Some(self.store),
+            site.clone(),
+            forecast,
+            target,
+            self.min_period_hours,
+            &self.scoring,
+        )
+        .await?;
+        if let Some(zone) = zone {
+            self.reports.put(site, date, zone, &outcome);
+        }
+        Ok(outcome)
+    }
+
+    /// A report for a single site, fresh unless the site was checked for
+    /// the same day within its cache minutes. Provider failures are replied
+    /// to the chat rather than stopping the bot.
+    async fn forecast(&self, chat_id: &str, arguments: &str) -> Result<String, Box<dyn Error>> {
+        let (name, target) = match arguments.rsplit_once(char::is_whitespace) {
+            Some((name, day)) => match Target::parse(day) {
+                Ok(target) => (name, target),
+                Err(_) => (arguments, Target::default()),
+            },
+            None => (arguments, Target::default()),
+        };
+        let name = name.trim();
+        let format = self.chat_format(chat_id)?;
+        let strings = format.strings();
+        if name.is_empty() {
+            return Ok(String::from(strings.forecast_usage));
+        }
+        let site = match self
+            .sites
+            .iter()
+            .find(|site| site.name.eq_ignore_ascii_case(name))
+        {
+            Some(site) => site.clone(),
+            None => return Ok(i18n::fill(strings.unknown_site, &[("site", name)])),
+        };
+        // Checked with the limits of the chat's profile, when the site has it.
+        let site = match self.store.chat_profiles()?.get(chat_id) {
+            Some(profile) => site.with_profile(profile).unwrap_or(site),
+            None => site,
+        };
+        let site_name = site.name.clone();
+        let (report, checked_at) = match self.reports.get(&site, target) {
+            Some((checked_at, outcome)) => {
+                tracing::debug!(site = %site_name, %checked_at, "reusing the last check");
+                (Ok(outcome), Some(checked_at))
+            }
+            None => (self.check(&site, target).await, None),
+        };
+        let answer = match report {
+            Ok(Ok(report)) => report.as_string(&format),
+            Ok(Err(skipped)) => {
+                let not_flyable = i18n::fill(
+                    strings.not_flyable_for(site.site_type),
+                    &[
+                        ("site", &site_name),
+                        ("day", &strings.day(skipped.days_ahead)),
+                    ],
+                );
+                match skipped.closest_miss {
+                    Some(miss) => format!(
+                        "{}\n{}",
+                        not_flyable,
+                        i18n::fill(
+                            strings.closest_miss,
+                            &[("miss", &miss.describe(strings, format.units()))],
+                        )
+                    ),
+                    None => not_flyable,
+                }
+            }
+            Err(error) => {
+                tracing::error!(site = %site_name, %error, "failed to forecast");
+                i18n::fill(strings.forecast_failed, &[("site", &site_name)])
+            }
+        };
+        Ok(match checked_at {
+            Some(checked_at) => format!("{}\n\n{}", answer, data_age(strings, checked_at)),
+            None => answer,
+        })
+    }
+}
+
+/// Splits `/command@BotName arguments` into the command and its arguments.
+fn parse_command(text: &str) -> (&str, &str) {
+    let text = text.trim();
+    let (command, arguments) = match text.find(char::is_whitespace) {
+        Some(index) => (&text[..index], text[index..].trim()),
+        None => (text, ""),
+    };
+    let command = command.split('@').next().unwrap_or(command);
+    (command, arguments)
+}
@@ -0,0 +1,72 @@
+//! On-disk cache of raw provider responses, sparing calls when runs follow
+//! each other closely, e.g. after tweaking thresholds.
+
+use crate::config::CacheConfig;
+use chrono::Utc;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+    /// Whether cached responses are used, fresh ones being stored anyway.
+    read: bool,
+}
+
+impl ResponseCache {
+    pub fn new(config: CacheConfig, read: bool) -> std::io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+        Ok(ResponseCache {
+            dir: config.dir,
+            ttl: Duration::from_secs(config.ttl_minutes * 60),
+            read,
+        })
+    }
+
+    /// Entries are keyed by the hour they are fetched in, so that a new
+    /// hour always brings a new forecast.
+    fn path(&self, provider: &str, lat: f64, lon: f64) -> PathBuf {
+        let hour = Utc::now().format("%Y%m%d%H");
+        self.dir
+            .join(format!("{}_{:.4}_{:.4}_{}.json", provider, lat, lon, hour))
+    }
+
+    fn is_expired(&self, path: &Path) -> bool {
+        let modified = fs::metadata(path).and_then(|metadata| metadata.modified());
+        match modified {
+            Ok(modified) => SystemTime::now()
+                .duration_since(modified)
+                .is_ok_and(|age| age > self.ttl),
+            Err(_) => true,
+        }
+    }
+
+    pub fn get(&self, provider: &str, lat: f64, lon: f64) -> Option<String> {
+        if !self.read {
+            return None;
+        }
+        let path = self.path(provider, lat, lon);
+        if self.is_expired(&path) {
+            return None;
+        }
+        let response = fs::read_to_string(&path).ok()?;
+        tracing::debug!(provider, lat, lon, "using the cached response");
+        Some(response)
+    }
+
+    /// Stores `response` and drops the expired entries. Failures are only
+    /// logged, the cache being an optimization.
+    pub fn put(&self, provider: &str, lat: f64, lon: f64, response: &str) {
+        if let Err(error) = fs::write(self.path(provider, lat, lon), response) {
+            tracing::warn!(%error, "failed to cache the response");
+        }
+        if let Ok(entries) = fs::read_dir(&self.dir) {
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.extension().is_some_and(|e| e == "json") && self.is_expired(&path) {
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+    }
+}
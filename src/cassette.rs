@@ -0,0 +1,136 @@
+//! Recording of HTTP responses to a file, to be replayed in tests of the
+//! whole pipeline without network access.
+//!
+//! Recorded requests are matched by method and URL, each recorded response
+//! answering a single request in the order they were recorded.
+
+use crate::config::{CassetteConfig, CassetteMode, RetryConfig};
+use crate::retry;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, RequestBuilder, Response, Result, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::sync::Mutex;
+
+const REDACTED: &str = "REDACTED";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Interaction {
+    method: String,
+    url: String,
+    status: u16,
+    content_type: Option<String>,
+    body: String,
+}
+
+pub struct Cassette {
+    config: CassetteConfig,
+    /// Recorded interactions, and in replay mode whether each was replayed.
+    interactions: Mutex<Vec<(Interaction, bool)>>,
+}
+
+impl Cassette {
+    /// Loads the file in replay mode, recording starts over.
+    pub fn open(config: CassetteConfig) -> io::Result<Self> {
+        let interactions: Vec<Interaction> = match config.mode {
+            CassetteMode::Record => vec![],
+            CassetteMode::Replay => serde_json::from_str(&fs::read_to_string(&config.path)?)?,
+        };
+        Ok(Cassette {
+            config,
+            interactions: Mutex::new(interactions.into_iter().map(|i| (i, false)).collect()),
+        })
+    }
+
+    fn redact(&self, text: &str) -> String {
+        self.config
+            .redact
+            .iter()
+            .filter(|secret| !secret.is_empty())
+            .fold(text.to_string(), |text, secret| {
+                text.replace(secret, REDACTED)
+            })
+    }
+
+    /// Sends the request built by `request` like `retry::send`, or replays
+    /// its response. Requests without a recorded response get a 501.
+    pub async fn send<F>(
+        &self,
+        client: &Client,
+        policy: &RetryConfig,
+        request: F,
+    ) -> Result<Response>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        let built = request(client).build()?;
+        let (method, url) = (
+            built.method().to_string(),
+            self.redact(built.url().as_str()),
+        );
+        if self.config.mode == CassetteMode::Replay {
+            return Ok(self.replay(&method, &url));
+        }
+        let response = retry::send(policy, || request(client)).await?;
+        let interaction = Interaction {
+            method,
+            url,
+            status: response.status().as_u16(),
+            content_type: response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from),
+            body: response.text().await.map_err(reqwest::Error::without_url)?,
+        };
+        let replayed = to_response(&interaction);
+        self.record(Interaction {
+            body: self.redact(&interaction.body),
+            ..interaction
+        });
+        Ok(replayed)
+    }
+
+    fn replay(&self, method: &str, url: &str) -> Response {
+        let mut interactions = self.interactions.lock().unwrap();
+        let recorded = interactions
+            .iter_mut()
+            .find(|(i, replayed)| !replayed && i.method == method && i.url == url);
+        match recorded {
+            Some((interaction, replayed)) => {
+                *replayed = true;
+                to_response(interaction)
+            }
+            None => {
+                tracing::warn!(method, url, "no recorded response");
+                let response = http::Response::builder()
+                    .status(StatusCode::NOT_IMPLEMENTED)
+                    .body(format!("no recorded response for {} {}", method, url))
+                    .unwrap();
+                Response::from(response)
+            }
+        }
+    }
+
+    /// Saves the file after every interaction, as runs may end abruptly.
+    fn record(&self, interaction: Interaction) {
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push((interaction, false));
+        let recorded: Vec<&Interaction> = interactions.iter().map(|(i, _)| i).collect();
+        let saved = serde_json::to_string_pretty(&recorded)
+            .map_err(io::Error::from)
+            .and_then(|json| fs::write(&self.config.path, json));
+        if let Err(error) = saved {
+            tracing::warn!(%error, path = %self.config.path.display(), "failed to save the cassette");
+        }
+    }
+}
+
+fn to_response(interaction: &Interaction) -> Response {
+    let mut response = http::Response::builder().status(interaction.status);
+    if let Some(content_type) = &interaction.content_type {
+        response = response.header(CONTENT_TYPE, content_type);
+    }
+    Response::from(response.body(interaction.body.clone()).unwrap())
+}
@@ -0,0 +1,296 @@
+//! Hourly wind charts of a report's forecast day, and wind roses of the past
+//! days of a site, rendered as PNG images.
+
+use crate::config::ChartsConfig;
+use crate::digest::{self, SiteDigest, SPEED_BANDS_MPS};
+use crate::measures::WindSpeed;
+use crate::message::MessageFormat;
+use crate::SiteFlyAbilityReport;
+use chrono::{Datelike, Timelike};
+use image::{ImageFormat, RgbImage};
+use plotters::prelude::*;
+use plotters::style::{register_font, FontStyle};
+use std::io::Cursor;
+
+pub type ChartError = Box<dyn std::error::Error + Send + Sync>;
+
+const FONT_FAMILY: &str = "sans-serif";
+const MIN_WIND_AXIS_MPH: f32 = 10.0;
+const MIN_HOURS_AXIS: f32 = 12.0;
+// Light to strong, one per band of `SPEED_BANDS_MPS` and the one above.
+const BAND_COLORS: [RGBColor; 4] = [
+    RGBColor(158, 202, 225),
+    RGBColor(49, 130, 189),
+    RGBColor(253, 174, 107),
+    RGBColor(222, 45, 38),
+];
+// Share of its sector a wedge of the wind rose spans, leaving gaps.
+const WEDGE_SHARE: f64 = 0.85;
+const ARC_STEPS: usize = 6;
+
+pub struct ChartRenderer {
+    width: u32,
+    height: u32,
+}
+
+impl ChartRenderer {
+    pub fn new(config: &ChartsConfig) -> Result<Self, ChartError> {
+        let font = std::fs::read(&config.font)?;
+        // plotters needs fonts to outlive every chart, they are kept for
+        // the rest of the process.
+        register_font(
+            FONT_FAMILY,
+            FontStyle::Normal,
+            Box::leak(font.into_boxed_slice()),
+        )
+        .map_err(|_| format!("{} is not a valid font", config.font.display()))?;
+        Ok(ChartRenderer {
+            width: config.width,
+            height: config.height,
+        })
+    }
+
+    /// Wind speed and gusts with the direction on a secondary axis,
+    /// flyable periods shaded.
+    pub fn render(
+        &self,
+        report: &SiteFlyAbilityReport,
+        format: &MessageFormat,
+    ) -> Result<Vec<u8>, ChartError> {
+        let (strings, unit) = (format.strings(), format.units().wind);
+        let hour_of = |time: &chrono::DateTime<chrono::FixedOffset>| time.hour() as f32;
+        let top = report
+            .hourly
+            .iter()
+            .map(|hour| {
+                hour.wind_gust
+                    .unwrap_or(hour.wind_speed)
+                    .in_unit(unit)
+                    .max(hour.wind_speed.in_unit(unit))
+            })
+            .fold(WindSpeed::MPH(MIN_WIND_AXIS_MPH).in_unit(unit), f32::max)
+            * 1.2;
+
+        let mut buffer = vec![0; (self.width * self.height * 3) as usize];
+        {
+            let root = BitMapBackend::with_buffer(&mut buffer, (self.width, self.height))
+                .into_drawing_area();
+            root.fill(&WHITE)?;
+            let mut chart = ChartBuilder::on(&root)
+                .caption(
+                    format!(
+                        "{} – {} {}",
+                        report.site.name,
+                        strings.weekday(report.date.weekday()),
+                        report.date.format("%d.%m"),
+                    ),
+                    (FONT_FAMILY, 20),
+                )
+                .margin(10)
+                .x_label_area_size(30)
+                .y_label_area_size(40)
+                .right_y_label_area_size(40)
+                .build_cartesian_2d(0f32..24f32, 0f32..top)?
+                .set_secondary_coord(0f32..24f32, 0f32..360f32);
+            chart
+                .configure_mesh()
+                .x_desc("Hour")
+                .y_desc(unit.label())
+                .x_labels(13)
+                .x_label_formatter(&|hour| format!("{:02}:00", *hour as u32))
+                .y_label_formatter(&|mph| format!("{:.0}", mph))
+                .label_style((FONT_FAMILY, 12))
+                .draw()?;
+            chart
+                .configure_secondary_axes()
+                .y_desc("Direction")
+                .y_label_formatter(&|degrees| format!("{:.0}°", degrees))
+                .label_style((FONT_FAMILY, 12))
+                .draw()?;
+
+            chart.draw_series(report.periods.iter().map(|period| {
+                let start = hour_of(&period.start);
+                let end = start + period.hours();
+                Rectangle::new([(start, 0.0), (end, top)], GREEN.mix(0.15).filled())
+            }))?;
+            chart.draw_series(LineSeries::new(
+                report
+                    .hourly
+                    .iter()
+                    .map(|hour| (hour_of(&hour.time), hour.wind_speed.in_unit(unit))),
+                BLUE.stroke_width(2),
+            ))?;
+            chart.draw_series(LineSeries::new(
+                report.hourly.iter().filter_map(|hour| {
+                    let gust = hour.wind_gust?;
+                    Some((hour_of(&hour.time), gust.in_unit(unit)))
+                }),
+                RED.stroke_width(1),
+            ))?;
+            chart.draw_secondary_series(report.hourly.iter().map(|hour| {
+                Circle::new(
+                    (hour_of(&hour.time), hour.wind_direction.degrees() as f32),
+                    3,
+                    BLACK.filled(),
+                )
+            }))?;
+            root.present()?;
+        }
+
+        self.encode(buffer)
+    }
+
+    /// The wind rose of the past days of a site by speed band, next to its
+    /// flyable hours per day.
+    pub fn render_digest(
+        &self,
+        digest: &SiteDigest,
+        format: &MessageFormat,
+    ) -> Result<Vec<u8>, ChartError> {
+        let (strings, unit) = (format.strings(), format.units().wind);
+        let rose = digest.wind_rose();
+        let widest = rose
+            .iter()
+            .map(|bands| bands.iter().sum::<f32>())
+            .fold(0.0, f32::max)
+            .max(f32::EPSILON);
+        let days = digest.flyable_hours.len();
+        let top = digest
+            .flyable_hours
+            .iter()
+            .map(|(_, hours)| *hours)
+            .fold(MIN_HOURS_AXIS, f32::max)
+            * 1.2;
+
+        let mut buffer = vec![0; (self.width * self.height * 3) as usize];
+        {
+            let root = BitMapBackend::with_buffer(&mut buffer, (self.width, self.height))
+                .into_drawing_area();
+            root.fill(&WHITE)?;
+            let root = root.titled(&digest.caption(), (FONT_FAMILY, 16))?;
+            let (rose_area, hours_area) = root.split_horizontally(root.dim_in_pixel().1);
+
+            let mut chart = ChartBuilder::on(&rose_area)
+                .margin(10)
+                .build_cartesian_2d(-1.25f64..1.25f64, -1.25f64..1.25f64)?;
+            // Clockwise from north, as on a compass.
+            let point = |degrees: f64, radius: f64| {
+                let angle = degrees.to_radians();
+                (radius * angle.sin(), radius * angle.cos())
+            };
+            chart.draw_series(std::iter::once(PathElement::new(
+                (0..=72)
+                    .map(|step| point(step as f64 * 5.0, 1.0))
+                    .collect::<Vec<_>>(),
+                BLACK.mix(0.3),
+            )))?;
+            for (cardinal, degrees) in [("N", 0.0), ("E", 90.0), ("S", 180.0), ("W", 270.0)] {
+                let (x, y) = point(degrees, 1.15);
+                chart.draw_series(std::iter::once(Text::new(
+                    cardinal,
+                    (x - 0.04, y + 0.04),
+                    (FONT_FAMILY, 14),
+                )))?;
+            }
+            let sector_width = 360.0 / digest::SECTORS as f64;
+            for band in 0..BAND_COLORS.len() {
+                let wedges = rose.iter().enumerate().filter_map(|(sector, bands)| {
+                    let share = bands[band];
+                    if share <= 0.0 {
+                        return None;
+                    }
+                    let inner = bands[..band].iter().sum::<f32>() / widest;
+                    let outer = inner + share / widest;
+                    let middle = digest::sector_direction(sector).degrees() as f64;
+                    let half = sector_width * WEDGE_SHARE / 2.0;
+                    let arc = |radius: f32| {
+                        (0..=ARC_STEPS).map(move |step| {
+                            let degrees =
+                                middle - half + 2.0 * half * step as f64 / ARC_STEPS as f64;
+                            point(degrees, radius as f64)
+                        })
+                    };
+                    let mut points: Vec<(f64, f64)> = arc(outer).collect();
+                    points.extend(arc(inner).rev());
+                    Some(Polygon::new(points, BAND_COLORS[band].filled()))
+                });
+                let label = match band {
+                    0 => format!("< {}", WindSpeed::MPS(SPEED_BANDS_MPS[0]).format(unit)),
+                    band if band == SPEED_BANDS_MPS.len() => format!(
+                        "> {}",
+                        WindSpeed::MPS(SPEED_BANDS_MPS[band - 1]).format(unit)
+                    ),
+                    band => format!(
+                        "{}–{}",
+                        WindSpeed::MPS(SPEED_BANDS_MPS[band - 1]).format(unit),
+                        WindSpeed::MPS(SPEED_BANDS_MPS[band]).format(unit)
+                    ),
+                };
+                let color = BAND_COLORS[band];
+                chart
+                    .draw_series(wedges)?
+                    .label(format!("{} {}", label, unit.label()))
+                    .legend(move |(x, y)| {
+                        Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                    });
+            }
+            chart
+                .configure_series_labels()
+                .position(SeriesLabelPosition::LowerRight)
+                .label_font((FONT_FAMILY, 10))
+                .background_style(WHITE.mix(0.8))
+                .draw()?;
+            chart.draw_series(std::iter::once(Text::new(
+                format!("{:.0}%", widest * 100.0),
+                point(45.0, 1.0),
+                (FONT_FAMILY, 10),
+            )))?;
+
+            let mut chart = ChartBuilder::on(&hours_area)
+                .margin(10)
+                .x_label_area_size(30)
+                .y_label_area_size(40)
+                .build_cartesian_2d(-0.5f32..days as f32 - 0.5, 0f32..top)?;
+            chart
+                .configure_mesh()
+                .disable_x_mesh()
+                .light_line_style(TRANSPARENT)
+                .x_labels(days)
+                .x_label_formatter(&|x| {
+                    let day = x.round();
+                    if (x - day).abs() > 0.01 {
+                        return String::new();
+                    }
+                    digest
+                        .flyable_hours
+                        .get(day as usize)
+                        .map_or_else(String::new, |(date, _)| {
+                            strings.weekday(date.weekday()).to_string()
+                        })
+                })
+                .y_desc("Flyable hours")
+                .y_label_formatter(&|hours| format!("{:.0}", hours))
+                .label_style((FONT_FAMILY, 12))
+                .draw()?;
+            chart.draw_series(digest.flyable_hours.iter().enumerate().map(
+                |(day, (_, hours))| {
+                    let day = day as f32;
+                    Rectangle::new(
+                        [(day - 0.4, 0.0), (day + 0.4, *hours)],
+                        GREEN.mix(0.6).filled(),
+                    )
+                },
+            ))?;
+            root.present()?;
+        }
+        self.encode(buffer)
+    }
+
+    fn encode(&self, buffer: Vec<u8>) -> Result<Vec<u8>, ChartError> {
+        let image = RgbImage::from_raw(self.width, self.height, buffer)
+            .ok_or("chart buffer does not match its size")?;
+        let mut png = Cursor::new(vec![]);
+        image.write_to(&mut png, ImageFormat::Png)?;
+        Ok(png.into_inner())
+    }
+}
@@ -0,0 +1,66 @@
+//! Signal handling and config reloading shared by the long-running
+//! subcommands.
+
+use crate::config::{FlyingSite, ReportOrder, Target};
+use crate::measures::Units;
+use crate::message::MessageFormat;
+use crate::notification::telegram::Outbox;
+use crate::scoring::ScoringModel;
+use std::collections::HashMap;
+use std::error::Error;
+use tokio::signal::unix::{signal, SignalKind};
+
+/// What daemons re-read from the config file on SIGHUP. Provider settings,
+/// the server address and the bot token, parse mode, message rate and
+/// `forecast_cache_minutes` of the Telegram notifier only change on
+/// restart.
+pub struct Settings {
+    pub sites: Vec<FlyingSite>,
+    pub target: Target,
+    pub min_period_hours: i64,
+    pub scoring: ScoringModel,
+    pub order: ReportOrder,
+    pub format: MessageFormat,
+    /// `admin_chat_ids` of the Telegram notifier, for the bot.
+    pub admins: Vec<String>,
+    /// `chat_units` of the Telegram notifier, for the bot.
+    pub chat_units: HashMap<String, Units>,
+    /// The outbox of the Telegram notifier, `None` without one.
+    pub outbox: Option<Outbox>,
+}
+
+/// Loads fresh settings, with the command line overrides applied.
+pub type Reload = Box<dyn Fn() -> Result<Settings, Box<dyn Error>> + Send + Sync>;
+
+pub enum Signal {
+    /// SIGTERM or SIGINT, stop once the work in flight is done.
+    Stop,
+    /// SIGHUP, reload the config file.
+    Reload,
+}
+
+pub struct Signals {
+    terminate: tokio::signal::unix::Signal,
+    interrupt: tokio::signal::unix::Signal,
+    hangup: tokio::signal::unix::Signal,
+}
+
+impl Signals {
+    /// Starts listening, signals received until then keep their default
+    /// behavior.
+    pub fn new() -> std::io::Result<Self> {
+        Ok(Signals {
+            terminate: signal(SignalKind::terminate())?,
+            interrupt: signal(SignalKind::interrupt())?,
+            hangup: signal(SignalKind::hangup())?,
+        })
+    }
+
+    pub async fn recv(&mut self) -> Signal {
+        tokio::select! {
+            _ = self.terminate.recv() => Signal::Stop,
+            _ = self.interrupt.recv() => Signal::Stop,
+            _ = self.hangup.recv() => Signal::Reload,
+        }
+    }
+}
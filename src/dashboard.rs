@@ -0,0 +1,133 @@
+//! Single HTML page showing the latest reports, meant for a wall display.
+
+use crate::forecast_client::TimeOfDay;
+use crate::measures::WindDirection;
+use crate::message::MessageFormat;
+use crate::{escape_html, SiteFlyAbilityReport, SkippedSite};
+use chrono::{DateTime, Local};
+
+// The page reloads itself this often, picking up refreshed reports.
+const RELOAD_SECONDS: u32 = 300;
+const ROSE_SECTORS: usize = 16;
+const ROSE_RADIUS: f32 = 50.0;
+
+const STYLE: &str = "body { font-family: sans-serif; background: #111; color: #eee; margin: 2em; }
+.sites { display: flex; flex-wrap: wrap; gap: 1.5em; }
+.site { background: #222; border-radius: 8px; padding: 1em 1.5em; width: 24em; }
+.site.skipped { opacity: 0.6; }
+.score { float: right; color: #2ecc71; }
+svg { display: block; margin: 0 auto; }";
+
+/// A point `radius` away from the center of the rose towards `degrees`,
+/// north being up.
+fn rose_point(degrees: f32, radius: f32) -> (f32, f32) {
+    let radians = degrees.to_radians();
+    (
+        ROSE_RADIUS + radius * radians.sin(),
+        ROSE_RADIUS - radius * radians.cos(),
+    )
+}
+
+/// SVG rose of the daylight wind directions, each petal as long as the
+/// share of hours the wind comes from its sector. Petals within the wind
+/// sectors of the site are green.
+fn wind_rose(report: &SiteFlyAbilityReport) -> String {
+    let mut hours = [0; ROSE_SECTORS];
+    let daylight = report
+        .hourly
+        .iter()
+        .filter(|hour| hour.time_of_day == TimeOfDay::DAY);
+    for hour in daylight {
+        let sector = (hour.wind_direction.degrees() as f32 / 22.5).round() as usize;
+        hours[sector % ROSE_SECTORS] += 1;
+    }
+    let most = *hours.iter().max().unwrap_or(&0);
+    let size = ROSE_RADIUS * 2.0;
+    let mut svg = format!(
+        "<svg width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\
+         <circle cx=\"{1}\" cy=\"{1}\" r=\"{1}\" fill=\"none\" stroke=\"#444\"/>",
+        size, ROSE_RADIUS
+    );
+    for (sector, count) in hours.iter().enumerate().filter(|(_, c)| **c > 0) {
+        let center = sector as f32 * 22.5;
+        let radius = ROSE_RADIUS * *count as f32 / most as f32;
+        let (x1, y1) = rose_point(center - 11.25, radius);
+        let (x2, y2) = rose_point(center + 11.25, radius);
+        let within_sectors = report
+            .site
+            .wind_sectors
+            .iter()
+            .any(|s| s.contains(WindDirection::new(center as i16)));
+        let color = if within_sectors { "#2ecc71" } else { "#777" };
+        svg.push_str(&format!(
+            "<path d=\"M{c} {c} L{:.1} {:.1} A{r:.1} {r:.1} 0 0 1 {:.1} {:.1} Z\" fill=\"{}\"/>",
+            x1,
+            y1,
+            x2,
+            y2,
+            color,
+            c = ROSE_RADIUS,
+            r = radius
+        ));
+    }
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"10\" fill=\"#aaa\" font-size=\"10\" text-anchor=\"middle\">N</text></svg>",
+        ROSE_RADIUS
+    ));
+    svg
+}
+
+fn report_section(report: &SiteFlyAbilityReport, format: &MessageFormat) -> String {
+    let mut section = format!(
+        "<section class=\"site\"><h2>{}<span class=\"score\">{:.0}</span></h2>\n{}\n<ul>\n",
+        escape_html(&report.site.name),
+        report.score,
+        wind_rose(report)
+    );
+    for period in &report.periods {
+        section.push_str(&format!(
+            "<li>{}</li>\n",
+            escape_html(&report.describe_period(period, format))
+        ));
+    }
+    section.push_str("</ul></section>\n");
+    section
+}
+
+/// The dashboard page, flyable sites first then the others with the reason
+/// they are not flyable.
+pub fn render(
+    reports: &[SiteFlyAbilityReport],
+    skipped: &[SkippedSite],
+    computed_at: DateTime<Local>,
+    format: &MessageFormat,
+) -> String {
+    let strings = format.strings();
+    let days_ahead = reports
+        .first()
+        .map(|r| r.days_ahead)
+        .or_else(|| skipped.first().map(|s| s.days_ahead))
+        .unwrap_or(1);
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">\
+         <meta http-equiv=\"refresh\" content=\"{}\">\
+         <title>Flyability</title><style>{}</style></head><body>\n\
+         <h1>🪂 {}</h1><p>{}</p>\n<div class=\"sites\">\n",
+        RELOAD_SECONDS,
+        STYLE,
+        escape_html(&strings.day(days_ahead)),
+        computed_at.format("%Y-%m-%d %H:%M"),
+    );
+    for report in reports {
+        page.push_str(&report_section(report, format));
+    }
+    for site in skipped {
+        page.push_str(&format!(
+            "<section class=\"site skipped\"><h2>{}</h2><p>{}</p></section>\n",
+            escape_html(&site.site.name),
+            escape_html(strings.skip_reason(site.reason))
+        ));
+    }
+    page.push_str("</div></body></html>\n");
+    page
+}
@@ -0,0 +1,207 @@
+//! Weekly digests of the sites: the daytime wind of the past days as a wind
+//! rose and their flyable hours, sent by the `digest` subcommand, e.g. on
+//! Sunday evenings, to the notifiers able to show images.
+
+use crate::config::FlyingSite;
+use crate::measures::{WindDirection, WindSpeed};
+use crate::notification::{NotifyError, RoutedNotifier};
+use crate::solar::{civil_twilight, CivilTwilight};
+use crate::store::StateStore;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use tracing::Instrument;
+
+/// Sectors of the wind rose, the first one centered on north.
+pub const SECTORS: usize = 16;
+/// Upper bounds of the speed bands of the wind rose, anything above being
+/// the last band.
+pub const SPEED_BANDS_MPS: [f32; 3] = [3.0, 6.0, 9.0];
+// Seconds the sun takes to cross a degree of longitude.
+const SECONDS_PER_DEGREE: f64 = 240.0;
+
+/// The past days of a site.
+#[derive(Debug, Clone)]
+pub struct SiteDigest {
+    pub site: FlyingSite,
+    /// Flyable hours of every day, oldest first, after the latest report
+    /// of the day.
+    pub flyable_hours: Vec<(NaiveDate, f32)>,
+    /// The last forecast wind of every hour of the days between civil dawn
+    /// and dusk.
+    pub winds: Vec<(WindSpeed, WindDirection)>,
+}
+
+impl SiteDigest {
+    /// The `days` of `site` up to `last_day` kept in `store`.
+    pub fn collect(
+        store: &StateStore,
+        site: &FlyingSite,
+        last_day: NaiveDate,
+        days: i64,
+    ) -> rusqlite::Result<Self> {
+        let first_day = last_day - Duration::days(days - 1);
+        let periods = store.latest_periods(&first_day.format("%Y-%m-%d").to_string())?;
+        let flyable_hours = first_day
+            .iter_days()
+            .take(days as usize)
+            .map(|date| {
+                let day = date.format("%Y-%m-%d").to_string();
+                let hours = periods
+                    .iter()
+                    .filter(|period| period.site == site.name && period.date == day)
+                    .map(|period| period.duration_hours as f32)
+                    .sum();
+                (date, hours)
+            })
+            .collect();
+
+        // A day of margin on both sides, the days being local to the site.
+        let midnight = |date: NaiveDate| date.and_time(NaiveTime::MIN).and_utc().timestamp();
+        let from = midnight(first_day - Duration::days(1));
+        let to = midnight(last_day + Duration::days(2));
+        let winds = store
+            .past_forecast_wind(&site.name, from, to)?
+            .into_iter()
+            .filter(|wind| {
+                let time = DateTime::<Utc>::from_timestamp(wind.hour, 0).unwrap();
+                let solar = time
+                    + Duration::seconds((site.longitude() * SECONDS_PER_DEGREE).round() as i64);
+                let date = solar.date_naive();
+                (first_day..=last_day).contains(&date)
+                    && match civil_twilight(date, site.latitude(), site.longitude()) {
+                        CivilTwilight::Between(dawn, dusk) => dawn <= time && time < dusk,
+                        CivilTwilight::AllDay => true,
+                        CivilTwilight::AllNight => false,
+                    }
+            })
+            .filter_map(|wind| {
+                Some((
+                    WindSpeed::MPS(wind.wind_mps as f32),
+                    WindDirection::new(wind.wind_direction?),
+                ))
+            })
+            .collect();
+        Ok(SiteDigest {
+            site: site.clone(),
+            flyable_hours,
+            winds,
+        })
+    }
+
+    pub fn flyable_days(&self) -> usize {
+        self.flyable_hours
+            .iter()
+            .filter(|(_, hours)| *hours > 0.0)
+            .count()
+    }
+
+    pub fn total_hours(&self) -> f32 {
+        self.flyable_hours.iter().map(|(_, hours)| hours).sum()
+    }
+
+    /// Share of the hours of `winds` per sector and speed band, see
+    /// `SECTORS` and `SPEED_BANDS_MPS`.
+    pub fn wind_rose(&self) -> Vec<[f32; SPEED_BANDS_MPS.len() + 1]> {
+        let mut rose = vec![[0.0; SPEED_BANDS_MPS.len() + 1]; SECTORS];
+        let share = 1.0 / self.winds.len().max(1) as f32;
+        for (speed, direction) in &self.winds {
+            let sector = sector(*direction);
+            let mps = speed.meters_per_second();
+            let band = SPEED_BANDS_MPS
+                .iter()
+                .position(|limit| mps < *limit)
+                .unwrap_or(SPEED_BANDS_MPS.len());
+            rose[sector][band] += share;
+        }
+        rose
+    }
+
+    /// Middle of the sector the wind blew from the most, `None` without
+    /// wind.
+    pub fn prevailing_direction(&self) -> Option<WindDirection> {
+        if self.winds.is_empty() {
+            return None;
+        }
+        let rose = self.wind_rose();
+        let total = |sector: &[f32]| sector.iter().sum::<f32>();
+        let (sector, _) = rose
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| total(&a[..]).partial_cmp(&total(&b[..])).unwrap())?;
+        Some(sector_direction(sector))
+    }
+
+    /// E.g. `Annecy 05.10–11.10: 23 flyable hours on 4 of 7 days, wind
+    /// mostly from WNW`.
+    pub fn caption(&self) -> String {
+        let (first, last) = match (self.flyable_hours.first(), self.flyable_hours.last()) {
+            (Some((first, _)), Some((last, _))) => (*first, *last),
+            _ => return self.site.name.clone(),
+        };
+        let mut caption = format!(
+            "{} {}–{}: {} flyable hours on {} of {} days",
+            self.site.name,
+            first.format("%d.%m"),
+            last.format("%d.%m"),
+            crate::format_hours(self.total_hours()),
+            self.flyable_days(),
+            self.flyable_hours.len()
+        );
+        if let Some(direction) = self.prevailing_direction() {
+            caption.push_str(&format!(", wind mostly from {}", direction.cardinal()));
+        }
+        caption
+    }
+}
+
+/// Sector of `SECTORS` holding `direction`.
+pub fn sector(direction: WindDirection) -> usize {
+    let width = 360.0 / SECTORS as f32;
+    (direction.degrees() as f32 / width).round() as usize % SECTORS
+}
+
+/// Middle of `sector`.
+pub fn sector_direction(sector: usize) -> WindDirection {
+    WindDirection::new((sector as f32 * 360.0 / SECTORS as f32).round() as i16)
+}
+
+/// Sends each notifier the digests of its sites, all at the same time. A
+/// failing notifier leaves the others be, failing the send once they are
+/// done.
+pub async fn send_digests(
+    notifiers: &[RoutedNotifier],
+    digests: &[SiteDigest],
+) -> Result<(), NotifyError> {
+    let sends = notifiers.iter().filter_map(|routed| {
+        let digests: Vec<SiteDigest> = digests
+            .iter()
+            .filter(|digest| routed.route.matches(&digest.site))
+            .cloned()
+            .collect();
+        if digests.is_empty() {
+            return None;
+        }
+        let span = tracing::info_span!("digest", notifier = routed.kind);
+        let send = async move {
+            let result = routed.notifier.notify_digest(&digests).await;
+            if let Err(error) = &result {
+                tracing::error!(%error, "digest failed");
+            }
+            result.map_err(|error| format!("{}: {}", routed.kind, error))
+        };
+        Some(send.instrument(span))
+    });
+    let failures: Vec<String> = futures::future::join_all(sends)
+        .await
+        .into_iter()
+        .filter_map(Result::err)
+        .collect();
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(NotifyError::Failed {
+            failed: failures.len(),
+            notifiers: notifiers.len(),
+            errors: failures.join("; "),
+        })
+    }
+}
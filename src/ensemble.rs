@@ -0,0 +1,213 @@
+//! Confidence in the forecast from the spread of several models: the wind
+//! of the main provider next to the wind of the models of the Open-Meteo
+//! Forecast API. Models agreeing on a day make it worth the long drive.
+
+use crate::forecast_client::DayWeatherForecast;
+use crate::http::HttpClient;
+use crate::measures::{WindDirection, WindSpeed};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Spreads of the wind speed, as a standard deviation, and of the wind
+// direction, as a circular standard deviation, bounding the confidence
+// levels.
+const HIGH_CONFIDENCE_WIND_MPS: f32 = 1.0;
+const LOW_CONFIDENCE_WIND_MPS: f32 = 2.5;
+const HIGH_CONFIDENCE_DEGREES: f32 = 25.0;
+const LOW_CONFIDENCE_DEGREES: f32 = 60.0;
+// Members calmer than this leave the direction spread out, light winds
+// turning every way.
+const CALM_WIND_MPS: f32 = 1.0;
+
+#[derive(Deserialize, Debug)]
+struct MembersHourly {
+    time: Vec<i64>,
+    /// `wind_speed_10m_<model>` and `wind_direction_10m_<model>`, without
+    /// the suffix for a single model.
+    #[serde(flatten)]
+    values: HashMap<String, Vec<Option<f32>>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct MembersForecast {
+    hourly: MembersHourly,
+}
+
+/// How much the members disagree on the wind of an hour.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spread {
+    /// Standard deviation of the wind speeds.
+    pub wind_mps: f32,
+    /// Circular standard deviation of the directions of the members with
+    /// wind, 0 when fewer than two have any.
+    pub direction_degrees: f32,
+}
+
+impl Spread {
+    /// The spread of `members`, `None` for fewer than two.
+    pub fn of(members: &[(WindSpeed, WindDirection)]) -> Option<Self> {
+        if members.len() < 2 {
+            return None;
+        }
+        let count = members.len() as f32;
+        let speeds: Vec<f32> = members
+            .iter()
+            .map(|(speed, _)| speed.meters_per_second())
+            .collect();
+        let mean = speeds.iter().sum::<f32>() / count;
+        let variance = speeds
+            .iter()
+            .map(|speed| (speed - mean).powi(2))
+            .sum::<f32>()
+            / count;
+
+        let directions: Vec<f32> = members
+            .iter()
+            .filter(|(speed, _)| speed.meters_per_second() >= CALM_WIND_MPS)
+            .map(|(_, direction)| (direction.degrees() as f32).to_radians())
+            .collect();
+        let direction_degrees = if directions.len() < 2 {
+            0.0
+        } else {
+            let n = directions.len() as f32;
+            let (sin, cos) = directions.iter().fold((0.0, 0.0), |(sin, cos), angle| {
+                (sin + angle.sin(), cos + angle.cos())
+            });
+            let resultant = ((sin / n).powi(2) + (cos / n).powi(2)).sqrt();
+            (-2.0 * resultant.clamp(f32::EPSILON, 1.0).ln())
+                .sqrt()
+                .to_degrees()
+        };
+        Some(Spread {
+            wind_mps: variance.sqrt(),
+            direction_degrees,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Copy, Clone)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    /// High when the members agree on both the speed and the direction,
+    /// low when they are far apart on either.
+    pub fn from_spread(spread: Spread) -> Self {
+        if spread.wind_mps > LOW_CONFIDENCE_WIND_MPS
+            || spread.direction_degrees > LOW_CONFIDENCE_DEGREES
+        {
+            Confidence::Low
+        } else if spread.wind_mps <= HIGH_CONFIDENCE_WIND_MPS
+            && spread.direction_degrees <= HIGH_CONFIDENCE_DEGREES
+        {
+            Confidence::High
+        } else {
+            Confidence::Medium
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        }
+    }
+}
+
+pub struct EnsembleClient {
+    url: String,
+    models: Vec<String>,
+    http: HttpClient,
+}
+
+impl EnsembleClient {
+    pub fn new(url: String, models: Vec<String>, http: HttpClient) -> Self {
+        EnsembleClient { url, models, http }
+    }
+
+    pub fn name(&self) -> &'static str {
+        "Open-Meteo"
+    }
+
+    /// The 10 m wind of every model keyed by unix timestamp of the forecast
+    /// hour, models missing an hour left out of it.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_members(
+        &self,
+        lat: f64,
+        lon: f64,
+    ) -> reqwest::Result<HashMap<i64, Vec<(WindSpeed, WindDirection)>>> {
+        let (lat, lon) = (lat.to_string(), lon.to_string());
+        let models = self.models.join(",");
+        let forecast: MembersForecast = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("latitude", &lat[..]),
+                    ("longitude", &lon[..]),
+                    ("hourly", "wind_speed_10m,wind_direction_10m"),
+                    ("models", &models[..]),
+                    ("wind_speed_unit", "ms"),
+                    ("timeformat", "unixtime"),
+                    ("forecast_days", "3"),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+
+        let hourly = forecast.hourly;
+        let series = |variable: &str, model: &str| {
+            hourly
+                .values
+                .get(&format!("{}_{}", variable, model))
+                .or_else(|| hourly.values.get(variable))
+        };
+        let mut members: HashMap<i64, Vec<(WindSpeed, WindDirection)>> = HashMap::new();
+        for model in &self.models {
+            let (speeds, directions) = match (
+                series("wind_speed_10m", model),
+                series("wind_direction_10m", model),
+            ) {
+                (Some(speeds), Some(directions)) => (speeds, directions),
+                _ => {
+                    tracing::warn!(%model, "model missing from the response");
+                    continue;
+                }
+            };
+            for (i, time) in hourly.time.iter().enumerate() {
+                let speed = speeds.get(i).copied().flatten();
+                let direction = directions.get(i).copied().flatten();
+                if let (Some(speed), Some(direction)) = (speed, direction) {
+                    members.entry(*time).or_default().push((
+                        WindSpeed::MPS(speed),
+                        WindDirection::new(direction.round() as i16),
+                    ));
+                }
+            }
+        }
+        Ok(members)
+    }
+}
+
+/// Sets the spread of the matching hours of a forecast, the forecast
+/// itself being one more member.
+pub fn apply_spread(
+    forecasts: &mut [DayWeatherForecast],
+    members: &HashMap<i64, Vec<(WindSpeed, WindDirection)>>,
+) {
+    for hour in forecasts.iter_mut().flat_map(|day| day.hourly.iter_mut()) {
+        let mut winds = vec![(hour.wind_speed, hour.wind_direction)];
+        if let Some(models) = members.get(&hour.time.timestamp()) {
+            winds.extend(models.iter().copied());
+        }
+        hour.spread = Spread::of(&winds);
+    }
+}
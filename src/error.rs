@@ -0,0 +1,66 @@
+//! Errors of the forecast providers, and the exit codes telling the kinds
+//! of failures apart.
+
+use crate::config::ConfigError;
+use crate::notification::NotifyError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ProviderError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Parse(#[from] serde_json::Error),
+    #[error("the daily {0} quota is exhausted")]
+    QuotaExhausted(&'static str),
+    /// An error explained by the provider in the response, e.g. an invalid
+    /// API key.
+    #[error("{provider} error {code}: {message}")]
+    Api {
+        provider: &'static str,
+        code: String,
+        message: String,
+    },
+    /// The provider refuses the calls over the limits of the account.
+    #[error("{provider} refused the call over the account limits: {message}")]
+    RateLimited {
+        provider: &'static str,
+        message: String,
+    },
+    #[error(transparent)]
+    Store(#[from] rusqlite::Error),
+    /// Every site of the run failed, the summary telling why.
+    #[error("{0}")]
+    AllSitesFailed(String),
+}
+
+// Listed by `EXIT_CODES_HELP`, keep both in sync.
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_CONFIG: i32 = 2;
+/// Every site failed to be checked.
+pub const EXIT_PROVIDER: i32 = 3;
+pub const EXIT_NOTIFY: i32 = 4;
+/// Some sites failed to be checked, the others being notified.
+pub const EXIT_PARTIAL: i32 = 5;
+
+/// The exit codes, for the command line help.
+pub const EXIT_CODES_HELP: &str = "EXIT CODES:
+    0    The run succeeded, whether or not a site is flyable
+    1    Any other failure
+    2    The config is invalid
+    3    No site could be checked, e.g. the forecast provider is down
+    4    A notifier failed to send the reports
+    5    Some sites could not be checked, the others were notified";
+
+/// The exit code of a run failing with `error`.
+pub fn exit_code(error: &(dyn std::error::Error + 'static)) -> i32 {
+    if error.is::<ConfigError>() {
+        EXIT_CONFIG
+    } else if error.is::<ProviderError>() {
+        EXIT_PROVIDER
+    } else if error.is::<NotifyError>() {
+        EXIT_NOTIFY
+    } else {
+        EXIT_FAILURE
+    }
+}
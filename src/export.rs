@@ -0,0 +1,307 @@
+//! Structured, serializable views of the reports, shared by the machine
+//! readable outputs.
+
+use crate::config::{FlyingSite, SiteType};
+use crate::forecast_client::DayOutlook;
+use crate::i18n::Language;
+use crate::measures::{Units, WindSpeed};
+use crate::message::MessageFormat;
+use crate::{
+    CheckedSites, Delivery, SiteError, SiteFlyAbilityReport, SiteFlyablePeriod, SkipReason,
+    SkippedSite,
+};
+use chrono::Utc;
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+pub struct PeriodView {
+    pub start: String,
+    pub duration_hours: f32,
+    pub wind_min_mps: f32,
+    pub wind_max_mps: f32,
+    /// "strengthening" or "backing_off" when the wind notably changes.
+    pub wind_trend: Option<&'static str>,
+    /// Clockwise from `wind_degree_min` to `wind_degree_max`, which is
+    /// lower when the range wraps across north.
+    pub wind_degree_min: i16,
+    pub wind_degree_max: i16,
+    /// Cardinal range of `wind_degree_min` to `wind_degree_max`.
+    pub wind_direction: String,
+    pub temp_min_c: f32,
+    pub temp_max_c: f32,
+    pub feels_like_min_c: f32,
+    pub feels_like_max_c: f32,
+    pub pop_max: f32,
+    pub cloud_base_agl_m: f32,
+    pub thermal_top_m: Option<f32>,
+    pub thermal_quality: Option<&'static str>,
+    /// "low", "medium" or "high" with an ensemble, from the spread of the
+    /// models below.
+    pub confidence: Option<&'static str>,
+    pub wind_spread_mps: Option<f32>,
+    pub direction_spread_degrees: Option<f32>,
+    /// "morning_sledder", "strong_thermic", "thermic", "ridge" or
+    /// "smooth_evening", when the period is clearly one.
+    pub soaring_window: Option<&'static str>,
+    pub uvi_max: Option<f32>,
+    pub wave_height_max_m: Option<f32>,
+    pub wave_period_s: Option<f32>,
+    pub freezing_level_min_m: Option<f32>,
+    pub snow_depth_max_m: Option<f32>,
+    pub snowfall_max_mm: Option<f32>,
+}
+
+impl From<&SiteFlyablePeriod> for PeriodView {
+    fn from(period: &SiteFlyablePeriod) -> Self {
+        PeriodView {
+            start: period.start.to_rfc3339(),
+            duration_hours: period.hours(),
+            wind_min_mps: period.wind_min.meters_per_second(),
+            wind_max_mps: period.wind_max.meters_per_second(),
+            wind_trend: period.wind_trend().map(|trend| trend.label()),
+            wind_degree_min: period.wind_directions.from.degrees(),
+            wind_degree_max: period.wind_directions.to.degrees(),
+            wind_direction: period.wind_direction(),
+            temp_min_c: period.temp_min.celsius(),
+            temp_max_c: period.temp_max.celsius(),
+            feels_like_min_c: period.feels_like_min.celsius(),
+            feels_like_max_c: period.feels_like_max.celsius(),
+            pop_max: period.pop_max,
+            cloud_base_agl_m: period.cloud_base_min_agl,
+            thermal_top_m: period.thermal_top_max,
+            thermal_quality: period.thermal_quality().map(|q| q.label()),
+            confidence: period.confidence().map(|confidence| confidence.label()),
+            wind_spread_mps: period.spread().map(|spread| spread.wind_mps),
+            direction_spread_degrees: period.spread().map(|spread| spread.direction_degrees),
+            soaring_window: period.soaring_window().map(|window| window.label()),
+            uvi_max: period.uvi_max,
+            wave_height_max_m: period.waves_max.map(|waves| waves.height.meters()),
+            wave_period_s: period.waves_max.map(|waves| waves.period_s),
+            freezing_level_min_m: period.freezing_level_min,
+            snow_depth_max_m: period.snow_depth_max,
+            snowfall_max_mm: period.snow_max.map(|snow| snow.millimeters()),
+        }
+    }
+}
+
+/// The daily forecast of a day past the hourly forecast.
+#[derive(Serialize, Debug)]
+pub struct OutlookView {
+    pub wind_mps: f32,
+    pub wind_gust_mps: Option<f32>,
+    pub wind_degree: i16,
+    pub wind_direction: &'static str,
+    pub pop: f32,
+}
+
+impl From<&DayOutlook> for OutlookView {
+    fn from(outlook: &DayOutlook) -> Self {
+        OutlookView {
+            wind_mps: outlook.wind_speed.meters_per_second(),
+            wind_gust_mps: outlook.wind_gust.map(|gust| gust.meters_per_second()),
+            wind_degree: outlook.wind_direction.degrees(),
+            wind_direction: outlook.wind_direction.cardinal(),
+            pop: outlook.pop,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReportView {
+    pub site: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub description: Option<String>,
+    pub elevation_m: Option<f32>,
+    pub webcam_url: Option<String>,
+    pub map_url: Option<String>,
+    pub region: Option<String>,
+    pub site_type: SiteType,
+    pub date: String,
+    pub sunrise: String,
+    pub sunset: String,
+    pub flyable_hours: f32,
+    pub score: f32,
+    pub periods: Vec<PeriodView>,
+    /// Set instead of `periods` for the days past the hourly forecast.
+    pub outlook: Option<OutlookView>,
+    pub notes: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl From<&SiteFlyAbilityReport> for ReportView {
+    fn from(report: &SiteFlyAbilityReport) -> Self {
+        ReportView {
+            site: report.site.name.clone(),
+            latitude: report.site.latitude(),
+            longitude: report.site.longitude(),
+            description: report.site.description.clone(),
+            elevation_m: report.site.elevation_m,
+            webcam_url: report.site.webcam_url.clone(),
+            map_url: report.site.map_url.clone(),
+            region: report.site.region.clone(),
+            site_type: report.site.site_type,
+            date: report.date.format("%Y-%m-%d").to_string(),
+            sunrise: report.sunrise.to_rfc3339(),
+            sunset: report.sunset.to_rfc3339(),
+            flyable_hours: report.flyable_hours(),
+            score: report.score,
+            periods: report.periods.iter().map(PeriodView::from).collect(),
+            outlook: report.outlook.as_ref().map(OutlookView::from),
+            notes: report.notes_in(&MessageFormat::default()),
+            warnings: report.warnings_in(Language::En.strings()),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SiteView {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub description: Option<String>,
+    pub elevation_m: Option<f32>,
+    pub webcam_url: Option<String>,
+    pub map_url: Option<String>,
+    pub tags: Vec<String>,
+    pub region: Option<String>,
+    pub site_type: SiteType,
+    /// E.g. `{"type": "KMPH", "value": 10.0}`, as in the config.
+    pub min_flyable_wind: WindSpeed,
+    pub max_flyable_wind: WindSpeed,
+}
+
+impl From<&FlyingSite> for SiteView {
+    fn from(site: &FlyingSite) -> Self {
+        SiteView {
+            name: site.name.clone(),
+            latitude: site.latitude(),
+            longitude: site.longitude(),
+            description: site.description.clone(),
+            elevation_m: site.elevation_m,
+            webcam_url: site.webcam_url.clone(),
+            map_url: site.map_url.clone(),
+            tags: site.tags.clone(),
+            region: site.region.clone(),
+            site_type: site.site_type,
+            min_flyable_wind: site.min_flyable_wind(),
+            max_flyable_wind: site.max_flyable_wind(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SkippedView {
+    pub site: String,
+    pub reason: SkipReason,
+    /// E.g. "14:00, wind from 298°, 2° out of the sectors".
+    pub closest_miss: Option<String>,
+}
+
+impl From<&SkippedSite> for SkippedView {
+    fn from(skipped: &SkippedSite) -> Self {
+        SkippedView {
+            site: skipped.site.name.clone(),
+            reason: skipped.reason,
+            closest_miss: skipped
+                .closest_miss
+                .as_ref()
+                .map(|miss| miss.describe(Language::En.strings(), Units::default())),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReportSetView {
+    pub generated_at: String,
+    pub reports: Vec<ReportView>,
+    /// Sites without a report, along with the reason.
+    pub skipped: Vec<SkippedView>,
+}
+
+impl ReportSetView {
+    pub fn new(reports: &[SiteFlyAbilityReport], skipped: &[SkippedSite]) -> Self {
+        ReportSetView {
+            generated_at: Utc::now().to_rfc3339(),
+            reports: reports.iter().map(ReportView::from).collect(),
+            skipped: skipped.iter().map(SkippedView::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SiteErrorView {
+    pub site: String,
+    pub error: String,
+}
+
+impl From<&SiteError> for SiteErrorView {
+    fn from(error: &SiteError) -> Self {
+        SiteErrorView {
+            site: error.site.clone(),
+            error: error.error.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct DeliveryView {
+    pub notifier: &'static str,
+    pub reports: usize,
+    pub nothing_flyable: bool,
+    pub error: Option<String>,
+    pub elapsed_ms: i64,
+}
+
+impl From<&Delivery> for DeliveryView {
+    fn from(delivery: &Delivery) -> Self {
+        DeliveryView {
+            notifier: delivery.notifier,
+            reports: delivery.reports,
+            nothing_flyable: delivery.nothing_flyable,
+            error: delivery.error.clone(),
+            elapsed_ms: delivery.elapsed.num_milliseconds(),
+        }
+    }
+}
+
+/// The outcome of a notification run, for monitoring.
+#[derive(Serialize, Debug, Default)]
+pub struct RunSummaryView {
+    pub started_at: String,
+    pub finished_at: String,
+    /// See `error::EXIT_CODES_HELP`.
+    pub exit_code: i32,
+    /// Why the run failed.
+    pub error: Option<String>,
+    pub sites_checked: usize,
+    /// None when nothing is flyable.
+    pub flyable_sites: usize,
+    pub skipped_sites: usize,
+    pub site_errors: Vec<SiteErrorView>,
+    /// What each notifier was handed, and whether it failed.
+    pub deliveries: Vec<DeliveryView>,
+}
+
+impl RunSummaryView {
+    pub fn new() -> Self {
+        RunSummaryView {
+            started_at: Utc::now().to_rfc3339(),
+            ..Default::default()
+        }
+    }
+
+    pub fn add_checked(&mut self, checked: &CheckedSites) {
+        self.sites_checked = checked.reports.len() + checked.skipped.len();
+        self.flyable_sites = checked.reports.len();
+        self.skipped_sites = checked.skipped.len();
+        self.site_errors = checked.errors.iter().map(SiteErrorView::from).collect();
+    }
+
+    pub fn finish(&mut self, exit_code: i32, error: Option<String>, deliveries: &[Delivery]) {
+        self.finished_at = Utc::now().to_rfc3339();
+        self.exit_code = exit_code;
+        self.error = error;
+        self.deliveries = deliveries.iter().map(DeliveryView::from).collect();
+    }
+}
@@ -1,26 +1,44 @@
-use crate::measures::{Temperature, WindSpeed};
-use chrono::{Date, DateTime, Duration, FixedOffset, TimeZone};
-use reqwest::{Client, Result};
+use crate::config::DaylightConfig;
+use crate::ensemble::Spread;
+use crate::error::ProviderError;
+use crate::http::HttpClient;
+use crate::launch_wind::LevelWind;
+use crate::marine::Waves;
+use crate::measures::{Precipitation, Pressure, Temperature, Visibility, WindDirection, WindSpeed};
+use crate::solar::{civil_twilight, CivilTwilight};
+use crate::timezone::Tz;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveTime, TimeZone};
+use reqwest::StatusCode;
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
+/// An hour of the response, every field but `dt` possibly missing or null,
+/// see `MissingData`.
 #[derive(Deserialize, Debug)]
-pub struct WeatherEvent {
-    id: u16,
-    main: String,
-    description: String,
+pub struct HourlyWeather {
+    dt: i64,
+    temp: Option<f32>,
+    feels_like: Option<f32>,
+    dew_point: Option<f32>,
+    wind_speed: Option<f32>,
+    wind_gust: Option<f32>,
+    wind_deg: Option<i16>,
+    clouds: Option<i16>,
+    pop: Option<f32>,
+    pressure: Option<f32>,
+    humidity: Option<u8>,
+    uvi: Option<f32>,
+    /// Meters, missing from some responses.
+    visibility: Option<f32>,
+    rain: Option<Volume>,
+    snow: Option<Volume>,
 }
 
 #[derive(Deserialize, Debug)]
-pub struct HourlyWeather {
-    dt: i64,
-    temp: f32,
-    feels_like: f32,
-    wind_speed: f32,
-    wind_deg: i16,
-    clouds: i16,
-    pop: f32,
-    weather: Vec<WeatherEvent>,
+pub struct Volume {
+    #[serde(rename = "1h")]
+    one_hour: f32,
 }
 
 #[derive(Deserialize, Debug)]
@@ -28,6 +46,75 @@ pub struct DailyWeather {
     dt: i64,
     sunrise: i64,
     sunset: i64,
+    wind_speed: Option<f32>,
+    wind_gust: Option<f32>,
+    wind_deg: Option<i16>,
+    #[serde(default)]
+    pop: f32,
+}
+
+/// Data a provider left out of some hours, see
+/// `DayWeatherForecast::missing`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MissingData {
+    /// Wind or temperature, the hours lacking them being left out.
+    Hours,
+    Gusts,
+    ChanceOfRain,
+    FeelsLike,
+    DewPoint,
+    Clouds,
+}
+
+impl MissingData {
+    /// What is missing and how the analysis makes do without it.
+    pub fn describe(self) -> &'static str {
+        match self {
+            MissingData::Hours => "no wind or temperature, hours left out",
+            MissingData::Gusts => "no gusts, gust limits unchecked",
+            MissingData::ChanceOfRain => "no chance of rain, guessed from the rain amount",
+            MissingData::FeelsLike => "no feels-like temperature, air temperature used",
+            MissingData::DewPoint => "no dew point, cloud base estimated from humidity",
+            MissingData::Clouds => "no cloud cover, taken as clear",
+        }
+    }
+}
+
+/// Dew point in °C by the Magnus formula.
+fn dew_point_c(temperature_c: f32, humidity: u8) -> f32 {
+    const B: f32 = 17.62;
+    const C: f32 = 243.12;
+    let gamma = (f32::from(humidity.max(1)) / 100.0).ln() + B * temperature_c / (C + temperature_c);
+    C * gamma / (B - gamma)
+}
+
+impl DailyWeather {
+    fn outlook(&self) -> Option<DayOutlook> {
+        Some(DayOutlook {
+            wind_speed: WindSpeed::MPS(self.wind_speed?),
+            wind_gust: self.wind_gust.map(WindSpeed::MPS),
+            wind_direction: WindDirection::new(self.wind_deg?),
+            pop: self.pop,
+        })
+    }
+}
+
+/// Body of the error responses, e.g.
+/// `{"cod": 401, "message": "Invalid API key"}`.
+#[derive(Deserialize, Debug)]
+struct ApiError {
+    /// A number or a string depending on the endpoint.
+    cod: serde_json::Value,
+    message: String,
+}
+
+impl ApiError {
+    fn code(&self) -> String {
+        match &self.cod {
+            serde_json::Value::String(code) => code.clone(),
+            code => code.to_string(),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -38,123 +125,543 @@ pub struct WeatherForecast {
     timezone_offset: i32,
     daily: Vec<DailyWeather>,
     hourly: Vec<HourlyWeather>,
+    #[serde(default)]
+    alerts: Vec<RawWeatherAlert>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Deserialize, Debug)]
+struct RawWeatherAlert {
+    sender_name: String,
+    event: String,
+    start: i64,
+    end: i64,
+    #[serde(default)]
+    description: String,
+}
+
+/// An official warning, e.g. of wind gusts or thunderstorms.
+#[derive(Debug, Clone)]
+pub struct WeatherAlert {
+    pub sender: String,
+    pub event: String,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    pub description: String,
+}
+
+impl WeatherAlert {
+    /// Whether the alert is in force at some point from `start` to `end`.
+    pub fn overlaps(&self, start: DateTime<FixedOffset>, end: DateTime<FixedOffset>) -> bool {
+        self.start < end && start < self.end
+    }
+
+    /// E.g. `Wind gust warning from 14:00 to 20:00 (MeteoSwiss)`, with the
+    /// day of the times not on `date`.
+    pub fn describe(&self, date: NaiveDate) -> String {
+        let time = |time: DateTime<FixedOffset>| {
+            if time.date_naive() == date {
+                time.format("%H:%M").to_string()
+            } else {
+                time.format("%b %d %H:%M").to_string()
+            }
+        };
+        format!(
+            "{} from {} to {} ({})",
+            self.event,
+            time(self.start),
+            time(self.end),
+            self.sender
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TimeOfDay {
     NIGHT,
     TWILIGHT,
     DAY,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HourWeatherForecast {
     pub time: DateTime<FixedOffset>,
+    /// Time the forecast stands for from `time`, an hour unless split by
+    /// `subdivide`.
+    pub duration: Duration,
     pub time_of_day: TimeOfDay,
     pub temperature: Temperature,
     pub feels_like: Temperature,
+    pub dew_point: Temperature,
     pub wind_speed: WindSpeed,
-    pub wind_deg: i16,
+    pub wind_gust: Option<WindSpeed>,
+    pub wind_direction: WindDirection,
+    pub clouds: i16,
     pub pop: f32,
+    pub pressure: Option<Pressure>,
+    /// Relative humidity in percent.
+    pub humidity: Option<u8>,
+    /// UV index, 11 and above being extreme.
+    pub uvi: Option<f32>,
+    pub visibility: Option<Visibility>,
+    /// `None` when no rain is expected.
+    pub rain: Option<Precipitation>,
+    /// Snowfall in water equivalent, `None` when no snow is expected.
+    pub snow: Option<Precipitation>,
+    /// Surface to 700 hPa lapse rate in °C/km, filled in by `thermal`.
+    pub lapse_rate: Option<f32>,
+    /// Expected top of lift in meters AMSL, filled in by `sounding`.
+    pub thermal_top_m: Option<f32>,
+    /// Winds aloft, lowest first, filled in by `launch_wind`.
+    pub level_winds: Vec<LevelWind>,
+    /// The 10 m wind once `wind_speed` is replaced by the wind at launch
+    /// altitude, see `LaunchWindClient::apply_launch_wind`.
+    pub surface_wind_speed: Option<WindSpeed>,
+    /// From the marine forecast, see `marine::apply_waves`.
+    pub waves: Option<Waves>,
+    /// Tide height in meters, see `tides::apply_tides`.
+    pub tide_m: Option<f32>,
+    /// Rain of the hours before, see `apply_prior_rain`.
+    pub prior_rain_mm: Option<f32>,
+    /// Meters AMSL, see `winter::apply_snowpack`.
+    pub freezing_level_m: Option<f32>,
+    /// Meters of snow on the ground, see `winter::apply_snowpack`.
+    pub snow_depth_m: Option<f32>,
+    /// Disagreement of the models on the wind, see `ensemble::apply_spread`.
+    pub spread: Option<Spread>,
+}
+
+/// Hours of rain wetting the ground, see `apply_prior_rain`.
+pub const PRIOR_RAIN_HOURS: i64 = 24;
+
+/// Sets the `prior_rain_mm` of every hour to the rain of the
+/// `PRIOR_RAIN_HOURS` before it. Only the hours of the forecast count:
+/// providers don't return the hours already over, so the rain fallen before
+/// the run is left out and the hours of the first `PRIOR_RAIN_HOURS` may be
+/// taken as drier than they are. Backtests, run on the archived hours, count
+/// all of it.
+pub fn apply_prior_rain(forecasts: &mut [DayWeatherForecast]) {
+    let rain: Vec<(DateTime<FixedOffset>, f32)> = forecasts
+        .iter()
+        .flat_map(|day| day.hourly.iter())
+        .map(|hour| {
+            let hours = hour.duration.num_minutes() as f32 / 60.0;
+            let mm = hour.rain.map_or(0.0, |rain| rain.millimeters()) * hours;
+            (hour.time, mm)
+        })
+        .collect();
+    for hour in forecasts.iter_mut().flat_map(|day| day.hourly.iter_mut()) {
+        let from = hour.time - Duration::hours(PRIOR_RAIN_HOURS);
+        hour.prior_rain_mm = Some(
+            rain.iter()
+                .filter(|(time, _)| from <= *time && *time < hour.time)
+                .map(|(_, mm)| mm)
+                .sum(),
+        );
+    }
+}
+
+/// Splits the hours of the forecast into steps of `step`, interpolating
+/// the wind, temperatures, chance of rain and the like linearly towards
+/// the next hour, so that windows shorter than an hour, e.g. before
+/// sunset, are not rounded away. Rain, snow, visibility and the winds aloft
+/// stay the ones of the hour, and so do the values of the last hour of a
+/// day. Hours already as fine as `step` are left alone.
+pub fn subdivide(forecasts: &mut [DayWeatherForecast], step: Duration, daylight: &DaylightConfig) {
+    for day in forecasts.iter_mut() {
+        let hourly = std::mem::take(&mut day.hourly);
+        for (i, hour) in hourly.iter().enumerate() {
+            let steps = (hour.duration.num_minutes() / step.num_minutes().max(1)) as i32;
+            if steps <= 1 {
+                day.hourly.push(hour.clone());
+                continue;
+            }
+            let next = hourly
+                .get(i + 1)
+                .filter(|next| next.time == hour.time + hour.duration);
+            for k in 0..steps {
+                let mut part = match next {
+                    Some(next) => interpolate(hour, next, k as f32 / steps as f32),
+                    None => hour.clone(),
+                };
+                part.time = hour.time + step * k;
+                part.duration = step;
+                part.time_of_day = get_time_of_day(part.time, step, day, daylight);
+                day.hourly.push(part);
+            }
+        }
+    }
 }
 
-#[derive(Debug)]
+/// `hour` moved `fraction` of the way to `next`.
+fn interpolate(
+    hour: &HourWeatherForecast,
+    next: &HourWeatherForecast,
+    fraction: f32,
+) -> HourWeatherForecast {
+    let lerp = |a: f32, b: f32| a + (b - a) * fraction;
+    let temperature =
+        |a: Temperature, b: Temperature| Temperature::C(lerp(a.celsius(), b.celsius()));
+    let speed = |a: WindSpeed, b: WindSpeed| {
+        WindSpeed::MPS(lerp(a.meters_per_second(), b.meters_per_second()))
+    };
+    let optional = |a: Option<f32>, b: Option<f32>| match (a, b) {
+        (Some(a), Some(b)) => Some(lerp(a, b)),
+        _ => a,
+    };
+    // The short way around, e.g. through north from 350° to 10°.
+    let turn = next.wind_direction.clockwise_from(hour.wind_direction);
+    let turn = if turn > 180 { turn - 360 } else { turn };
+    let degrees = hour.wind_direction.degrees() as f32 + turn as f32 * fraction;
+    HourWeatherForecast {
+        temperature: temperature(hour.temperature, next.temperature),
+        feels_like: temperature(hour.feels_like, next.feels_like),
+        dew_point: temperature(hour.dew_point, next.dew_point),
+        wind_speed: speed(hour.wind_speed, next.wind_speed),
+        wind_gust: match (hour.wind_gust, next.wind_gust) {
+            (Some(a), Some(b)) => Some(speed(a, b)),
+            (gust, _) => gust,
+        },
+        wind_direction: WindDirection::new(degrees.round() as i16),
+        clouds: lerp(hour.clouds as f32, next.clouds as f32).round() as i16,
+        pop: lerp(hour.pop, next.pop),
+        pressure: optional(
+            hour.pressure.map(|p| p.hectopascals()),
+            next.pressure.map(|p| p.hectopascals()),
+        )
+        .map(Pressure::HPA),
+        humidity: optional(hour.humidity.map(f32::from), next.humidity.map(f32::from))
+            .map(|humidity| humidity.round() as u8),
+        lapse_rate: optional(hour.lapse_rate, next.lapse_rate),
+        thermal_top_m: optional(hour.thermal_top_m, next.thermal_top_m),
+        surface_wind_speed: match (hour.surface_wind_speed, next.surface_wind_speed) {
+            (Some(a), Some(b)) => Some(speed(a, b)),
+            (surface, _) => surface,
+        },
+        tide_m: optional(hour.tide_m, next.tide_m),
+        freezing_level_m: optional(hour.freezing_level_m, next.freezing_level_m),
+        snow_depth_m: optional(hour.snow_depth_m, next.snow_depth_m),
+        ..hour.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct DayWeatherForecast {
-    pub date: Date<FixedOffset>,
+    pub date: NaiveDate,
     pub sunrise: DateTime<FixedOffset>,
     pub sunset: DateTime<FixedOffset>,
+    /// Start of civil dawn, sunrise when the sun stays well below the
+    /// horizon and midnight when it stays up.
+    pub civil_dawn: DateTime<FixedOffset>,
+    /// End of civil dusk, see `civil_dawn`.
+    pub civil_dusk: DateTime<FixedOffset>,
     pub hourly: Vec<HourWeatherForecast>,
+    /// The day as a whole, used instead of `hourly` on the days past the
+    /// hourly forecast.
+    pub outlook: Option<DayOutlook>,
+    /// Official alerts in force at some point of the day.
+    pub alerts: Vec<WeatherAlert>,
+    /// Hours of the provider response lacking each kind of data.
+    pub missing: BTreeMap<MissingData, usize>,
+}
+
+/// The daily forecast of a day, less reliable than its hours.
+#[derive(Debug, Clone, Copy)]
+pub struct DayOutlook {
+    pub wind_speed: WindSpeed,
+    pub wind_gust: Option<WindSpeed>,
+    pub wind_direction: WindDirection,
+    pub pop: f32,
+}
+
+/// A provider of hourly forecasts, fetched raw so responses can be cached
+/// before being parsed.
+#[async_trait]
+pub trait ForecastProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// The forecast response as sent by the API, see `parse_forecast`.
+    async fn get_raw_forecast(&self, lat: f64, lon: f64) -> Result<String, ProviderError>;
+
+    /// Daily forecasts with their hours, in the local time of the site.
+    fn parse_forecast(&self, raw: &str) -> serde_json::Result<Vec<DayWeatherForecast>>;
 }
 
 pub struct OpenWeatherMapClient {
     url: String,
     app_id: String,
+    http: HttpClient,
+    daylight: DaylightConfig,
 }
 
 impl OpenWeatherMapClient {
-    pub fn new(url: String, app_id: String) -> Self {
-        OpenWeatherMapClient { url, app_id }
+    pub fn new(url: String, app_id: String, http: HttpClient, daylight: DaylightConfig) -> Self {
+        OpenWeatherMapClient {
+            url,
+            app_id,
+            http,
+            daylight,
+        }
+    }
+}
+
+#[async_trait]
+impl ForecastProvider for OpenWeatherMapClient {
+    fn name(&self) -> &'static str {
+        "OpenWeatherMap"
     }
 
-    pub async fn get_forecast(&self, lat: f64, lon: f64) -> Result<Vec<DayWeatherForecast>> {
-        let client = Client::new();
-        let raw_forecast = client
-            .get(&self.url)
-            .query(&[
-                ("lat", &lat.to_string()[..]),
-                ("lon", &lon.to_string()[..]),
-                ("appid", &self.app_id[..]),
-                ("exclude", "current,minutely,alerts&units=metric"),
-                ("units", "metric"),
-            ])
-            .send()
-            .await?
-            .json::<WeatherForecast>()
-            .await?;
-        let tz_offset = FixedOffset::east(raw_forecast.timezone_offset);
-        let mut date_to_forecast: HashMap<Date<FixedOffset>, DayWeatherForecast> = HashMap::new();
+    #[tracing::instrument(skip(self), err)]
+    async fn get_raw_forecast(&self, lat: f64, lon: f64) -> Result<String, ProviderError> {
+        let (lat, lon) = (lat.to_string(), lon.to_string());
+        let sent = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("lat", &lat[..]),
+                    ("lon", &lon[..]),
+                    ("appid", &self.app_id[..]),
+                    ("exclude", "current,minutely"),
+                    ("units", "metric"),
+                ])
+            })
+            .await;
+        // Still rate limited once out of retries.
+        let response = match sent {
+            Err(error) if error.status() == Some(StatusCode::TOO_MANY_REQUESTS) => {
+                return Err(ProviderError::RateLimited {
+                    provider: self.name(),
+                    message: error.to_string(),
+                })
+            }
+            sent => sent?,
+        };
+        let status = response.status();
+        let body = response.text().await.map_err(reqwest::Error::without_url)?;
+        if status.is_success() {
+            return Ok(body);
+        }
+        let error: ApiError = match serde_json::from_str(&body) {
+            Ok(error) => error,
+            Err(_) => {
+                return Err(ProviderError::Api {
+                    provider: self.name(),
+                    code: status.as_u16().to_string(),
+                    message: String::from(status.canonical_reason().unwrap_or("unknown error")),
+                })
+            }
+        };
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::RateLimited {
+                provider: self.name(),
+                message: error.message,
+            });
+        }
+        Err(ProviderError::Api {
+            provider: self.name(),
+            code: error.code(),
+            message: error.message,
+        })
+    }
+
+    fn parse_forecast(&self, raw: &str) -> serde_json::Result<Vec<DayWeatherForecast>> {
+        let raw_forecast: WeatherForecast = serde_json::from_str(raw)?;
+        let tz = match Tz::load(&raw_forecast.timezone) {
+            Ok(tz) => Some(tz),
+            Err(error) => {
+                tracing::warn!(timezone = %raw_forecast.timezone, %error, "using the current offset of the time zone");
+                None
+            }
+        };
+        let tz_offset = FixedOffset::east_opt(raw_forecast.timezone_offset).ok_or_else(|| {
+            serde::de::Error::custom(format!(
+                "invalid timezone_offset {}",
+                raw_forecast.timezone_offset
+            ))
+        })?;
+        // Local time of a timestamp, with the offset of the time zone at the
+        // time rather than now, the two differing across a DST change.
+        let local = |timestamp: i64| match &tz {
+            Some(tz) => tz.at(timestamp),
+            None => tz_offset.timestamp_opt(timestamp, 0).unwrap(),
+        };
+        let mut date_to_forecast: HashMap<NaiveDate, DayWeatherForecast> = HashMap::new();
         for day_forecast in raw_forecast.daily.iter() {
-            let date = tz_offset.timestamp(day_forecast.dt, 0).date();
-            let sunrise = tz_offset.timestamp(day_forecast.sunrise, 0);
-            let sunset = tz_offset.timestamp(day_forecast.sunset, 0);
+            let date = local(day_forecast.dt).date_naive();
+            let sunrise = local(day_forecast.sunrise);
+            let sunset = local(day_forecast.sunset);
+            let lat = raw_forecast.lat as f64;
+            let lon = raw_forecast.lon as f64;
+            let (civil_dawn, civil_dusk) = civil_dawn_and_dusk(date, lat, lon, sunrise, sunset);
             date_to_forecast.insert(
                 date,
                 DayWeatherForecast {
                     date,
                     sunrise,
                     sunset,
+                    civil_dawn,
+                    civil_dusk,
                     hourly: vec![],
+                    outlook: day_forecast.outlook(),
+                    alerts: vec![],
+                    missing: BTreeMap::new(),
                 },
             );
         }
+        for raw_alert in raw_forecast.alerts.iter() {
+            let alert = WeatherAlert {
+                sender: raw_alert.sender_name.clone(),
+                event: raw_alert.event.clone(),
+                start: local(raw_alert.start),
+                end: local(raw_alert.end),
+                description: raw_alert.description.clone(),
+            };
+            for day_forecast in date_to_forecast.values_mut() {
+                if (alert.start.date_naive()..=alert.end.date_naive()).contains(&day_forecast.date)
+                {
+                    day_forecast.alerts.push(alert.clone());
+                }
+            }
+        }
         for hour_forecast in raw_forecast.hourly.iter() {
-            let date_time = tz_offset.timestamp(hour_forecast.dt, 0);
-            let day_forecast = date_to_forecast.get_mut(&date_time.date()).unwrap();
-            let time_of_day = get_time_of_day(date_time, day_forecast.sunrise, day_forecast.sunset);
+            let date_time = local(hour_forecast.dt);
+            let day_forecast = match date_to_forecast.get_mut(&date_time.date_naive()) {
+                Some(day_forecast) => day_forecast,
+                None => continue,
+            };
+            let (temp, wind_speed, wind_deg) = match (
+                hour_forecast.temp,
+                hour_forecast.wind_speed,
+                hour_forecast.wind_deg,
+            ) {
+                (Some(temp), Some(wind_speed), Some(wind_deg)) => (temp, wind_speed, wind_deg),
+                _ => {
+                    *day_forecast.missing.entry(MissingData::Hours).or_default() += 1;
+                    continue;
+                }
+            };
+            let mut missing = vec![];
+            if hour_forecast.wind_gust.is_none() {
+                missing.push(MissingData::Gusts);
+            }
+            let rain = hour_forecast
+                .rain
+                .as_ref()
+                .map(|rain| Precipitation::MM(rain.one_hour));
+            let pop = hour_forecast.pop.unwrap_or_else(|| {
+                missing.push(MissingData::ChanceOfRain);
+                if rain.is_some() {
+                    1.0
+                } else {
+                    0.0
+                }
+            });
+            let feels_like = hour_forecast.feels_like.unwrap_or_else(|| {
+                missing.push(MissingData::FeelsLike);
+                temp
+            });
+            let dew_point = hour_forecast.dew_point.unwrap_or_else(|| {
+                missing.push(MissingData::DewPoint);
+                // Without humidity either, saturated air: the cloud base on the ground.
+                hour_forecast
+                    .humidity
+                    .map_or(temp, |humidity| dew_point_c(temp, humidity))
+            });
+            let clouds = hour_forecast.clouds.unwrap_or_else(|| {
+                missing.push(MissingData::Clouds);
+                0
+            });
+            for data in missing {
+                *day_forecast.missing.entry(data).or_default() += 1;
+            }
+            let duration = Duration::hours(1);
+            let time_of_day = get_time_of_day(date_time, duration, day_forecast, &self.daylight);
 
             let forecast = HourWeatherForecast {
                 time: date_time,
+                duration,
                 time_of_day,
-                temperature: Temperature::C(hour_forecast.temp),
-                feels_like: Temperature::C(hour_forecast.feels_like),
-                wind_speed: WindSpeed::MPS(hour_forecast.wind_speed),
-                wind_deg: hour_forecast.wind_deg,
-                pop: hour_forecast.pop,
+                temperature: Temperature::C(temp),
+                feels_like: Temperature::C(feels_like),
+                dew_point: Temperature::C(dew_point),
+                wind_speed: WindSpeed::MPS(wind_speed),
+                wind_gust: hour_forecast.wind_gust.map(WindSpeed::MPS),
+                wind_direction: WindDirection::new(wind_deg),
+                clouds,
+                pop,
+                pressure: hour_forecast.pressure.map(Pressure::HPA),
+                humidity: hour_forecast.humidity,
+                uvi: hour_forecast.uvi,
+                visibility: hour_forecast.visibility.map(Visibility::M),
+                rain,
+                snow: hour_forecast
+                    .snow
+                    .as_ref()
+                    .map(|snow| Precipitation::MM(snow.one_hour)),
+                lapse_rate: None,
+                thermal_top_m: None,
+                level_winds: vec![],
+                surface_wind_speed: None,
+                waves: None,
+                tide_m: None,
+                prior_rain_mm: None,
+                freezing_level_m: None,
+                snow_depth_m: None,
+                spread: None,
             };
             day_forecast.hourly.push(forecast);
         }
         let mut day_forecasts: Vec<DayWeatherForecast> = date_to_forecast
             .into_iter()
             .map(|x| x.1)
-            .filter(|x| !x.hourly.is_empty())
+            .filter(|x| !x.hourly.is_empty() || x.outlook.is_some())
             .collect();
         day_forecasts.sort_by_key(|k| k.date);
+        for day in day_forecasts.iter().filter(|day| !day.missing.is_empty()) {
+            tracing::warn!(date = %day.date, missing = ?day.missing, "incomplete forecast");
+        }
+        tracing::debug!(days = day_forecasts.len(), "parsed forecast");
         Ok(day_forecasts)
     }
 }
 
-fn get_time_of_day(
-    date_time: DateTime<FixedOffset>,
+/// Start of civil dawn and end of civil dusk on `date`, in the offset of
+/// `sunrise`, see `DayWeatherForecast::civil_dawn`.
+pub fn civil_dawn_and_dusk(
+    date: NaiveDate,
+    latitude: f64,
+    longitude: f64,
     sunrise: DateTime<FixedOffset>,
     sunset: DateTime<FixedOffset>,
+) -> (DateTime<FixedOffset>, DateTime<FixedOffset>) {
+    let offset = *sunrise.offset();
+    match civil_twilight(date, latitude, longitude) {
+        CivilTwilight::Between(dawn, dusk) => {
+            (dawn.with_timezone(&offset), dusk.with_timezone(&offset))
+        }
+        CivilTwilight::AllDay => {
+            let midnight = date.and_time(NaiveTime::MIN).and_local_timezone(offset);
+            let midnight = midnight.unwrap();
+            (midnight, midnight + Duration::days(1))
+        }
+        CivilTwilight::AllNight => (sunrise, sunset),
+    }
+}
+
+/// Whether the `duration` starting at `date_time` is mostly in daylight,
+/// less the margins of `daylight`, or in civil twilight.
+pub fn get_time_of_day(
+    date_time: DateTime<FixedOffset>,
+    duration: Duration,
+    day: &DayWeatherForecast,
+    daylight: &DaylightConfig,
 ) -> TimeOfDay {
-    let end_hour = date_time + Duration::hours(1);
+    let middle = date_time + duration / 2;
+    let launch = day.sunrise + Duration::minutes(daylight.launch_margin_minutes as i64);
+    let landing = day.sunset - Duration::minutes(daylight.landing_margin_minutes as i64);
 
-    if sunset.ge(&end_hour) && sunrise.lt(&date_time) {
-        TimeOfDay::DAY
-    } else if sunrise.ge(&end_hour) || sunset.lt(&date_time) {
-        TimeOfDay::NIGHT
-    } else if sunset < end_hour {
-        if sunset < date_time + Duration::minutes(14) {
-            TimeOfDay::NIGHT
-        } else if sunset < date_time + Duration::minutes(45) {
-            TimeOfDay::TWILIGHT
-        } else {
-            TimeOfDay::DAY
-        }
-    } else if sunrise < date_time + Duration::minutes(14) {
+    if launch <= middle && middle < landing {
         TimeOfDay::DAY
-    } else if sunset < date_time + Duration::minutes(45) {
+    } else if day.civil_dawn <= middle && middle < day.civil_dusk {
         TimeOfDay::TWILIGHT
     } else {
         TimeOfDay::NIGHT
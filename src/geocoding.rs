@@ -0,0 +1,103 @@
+//! Coordinates of the sites defined by a place name, from the
+//! OpenWeatherMap geocoding API.
+
+use crate::config::{ConfigError, FlyingSite};
+use crate::http::HttpClient;
+use crate::store::StateStore;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+struct Place {
+    lat: f64,
+    lon: f64,
+}
+
+pub struct Geocoder {
+    url: String,
+    app_id: String,
+    http: HttpClient,
+}
+
+impl Geocoder {
+    pub fn new(url: String, app_id: String, http: HttpClient) -> Self {
+        Geocoder { url, app_id, http }
+    }
+
+    /// Latitude and longitude of the best match for `location`, if any.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn locate(&self, location: &str) -> reqwest::Result<Option<(f64, f64)>> {
+        let places: Vec<Place> = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("q", location),
+                    ("limit", "1"),
+                    ("appid", &self.app_id[..]),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        Ok(places.first().map(|place| (place.lat, place.lon)))
+    }
+}
+
+/// Fills in the coordinates of the sites defined by their `location`,
+/// remembering them in `store` so that only new locations are looked up.
+pub async fn resolve_sites(
+    sites: &mut [FlyingSite],
+    geocoder: &Geocoder,
+    store: Option<&StateStore>,
+) -> Result<(), ConfigError> {
+    let mut problems = vec![];
+    for site in sites.iter_mut().filter(|site| site.needs_geocoding()) {
+        let location = match &site.location {
+            Some(location) => location.clone(),
+            None => continue,
+        };
+        let cached = store.and_then(|store| match store.geocoded_location(&location) {
+            Ok(coordinates) => coordinates,
+            Err(error) => {
+                tracing::warn!(%error, "failed to read the geocoded locations");
+                None
+            }
+        });
+        let coordinates = match cached {
+            Some(coordinates) => coordinates,
+            None => match geocoder.locate(&location).await {
+                Ok(Some((latitude, longitude))) => {
+                    if let Some(Err(error)) = store
+                        .map(|store| store.record_geocoded_location(&location, latitude, longitude))
+                    {
+                        tracing::warn!(%error, "failed to remember a geocoded location");
+                    }
+                    (latitude, longitude)
+                }
+                Ok(None) => {
+                    problems.push(format!(
+                        "site \"{}\": location \"{}\" was not found",
+                        site.name, location
+                    ));
+                    continue;
+                }
+                Err(error) => {
+                    problems.push(format!(
+                        "site \"{}\": failed to geocode \"{}\": {}",
+                        site.name, location, error
+                    ));
+                    continue;
+                }
+            },
+        };
+        site.latitude = Some(coordinates.0);
+        site.longitude = Some(coordinates.1);
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(problems))
+    }
+}
@@ -0,0 +1,33 @@
+//! Dead man's switch pings, e.g. to Healthchecks.io, alerting operators
+//! when runs stop happening or start failing.
+
+use crate::http::HttpClient;
+
+pub struct Heartbeat {
+    url: String,
+    http: HttpClient,
+}
+
+impl Heartbeat {
+    pub fn new(url: String, http: HttpClient) -> Self {
+        Heartbeat { url, http }
+    }
+
+    /// Pings the URL after a run, or `url/fail` after a failed one. Ping
+    /// errors are only logged, they never fail the run.
+    pub async fn ping(&self, success: bool) {
+        let url = if success {
+            self.url.clone()
+        } else {
+            format!("{}/fail", self.url.trim_end_matches('/'))
+        };
+        let result = self
+            .http
+            .send(|client| client.get(&url))
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(error) = result {
+            tracing::warn!(%error, "failed to ping the heartbeat URL");
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! HTTP client built from the `[http]` and `[retry]` settings.
+
+#[cfg(feature = "cassette")]
+use crate::cassette::Cassette;
+use crate::config::{HttpConfig, RetryConfig};
+use crate::retry;
+use reqwest::{Certificate, Client, Proxy, RequestBuilder, Response, Result};
+#[cfg(feature = "cassette")]
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Client shared by the forecast sources and the notifiers, so that requests
+/// reuse pooled connections. Cloning it is cheap.
+#[derive(Clone)]
+pub struct HttpClient {
+    client: Client,
+    /// Clients of the requests going through a proxy of their own, see
+    /// `for_weather` and `for_telegram`.
+    weather: Option<Client>,
+    telegram: Option<Client>,
+    retry: RetryConfig,
+    timeout: Duration,
+    #[cfg(feature = "cassette")]
+    cassette: Option<Arc<Cassette>>,
+}
+
+impl HttpClient {
+    pub fn new(
+        config: &HttpConfig,
+        retry: RetryConfig,
+    ) -> std::result::Result<Self, Box<dyn std::error::Error>> {
+        let timeout = Duration::from_secs(config.timeout_seconds);
+        let mut certificates = vec![];
+        for path in &config.ca_certificates {
+            let pem = std::fs::read(path)
+                .map_err(|error| format!("cannot read {}: {}", path.display(), error))?;
+            let bundle = Certificate::from_pem_bundle(&pem)
+                .map_err(|error| format!("{}: {}", path.display(), error))?;
+            if bundle.is_empty() {
+                return Err(format!("{} holds no PEM certificate", path.display()).into());
+            }
+            certificates.extend(bundle);
+        }
+        let build = |proxy: Option<&String>| {
+            let mut builder = Client::builder()
+                .connect_timeout(Duration::from_secs(config.connect_timeout_seconds))
+                .timeout(timeout)
+                .user_agent(&config.user_agent)
+                .tls_built_in_root_certs(config.system_roots);
+            for certificate in &certificates {
+                builder = builder.add_root_certificate(certificate.clone());
+            }
+            if let Some(proxy) = proxy {
+                builder = builder.proxy(Proxy::all(proxy)?);
+            }
+            builder.build()
+        };
+        Ok(HttpClient {
+            client: build(config.proxy.as_ref())?,
+            weather: config
+                .weather_proxy
+                .as_ref()
+                .map(|proxy| build(Some(proxy)))
+                .transpose()?,
+            telegram: config
+                .telegram_proxy
+                .as_ref()
+                .map(|proxy| build(Some(proxy)))
+                .transpose()?,
+            retry,
+            timeout,
+            #[cfg(feature = "cassette")]
+            cassette: match &config.cassette {
+                Some(cassette) => Some(Arc::new(Cassette::open(cassette.clone())?)),
+                None => None,
+            },
+        })
+    }
+
+    /// The client of the forecast sources, going through `weather_proxy`.
+    pub fn for_weather(&self) -> Self {
+        HttpClient {
+            client: self.weather.clone().unwrap_or_else(|| self.client.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// The client of Telegram, going through `telegram_proxy`.
+    pub fn for_telegram(&self) -> Self {
+        HttpClient {
+            client: self.telegram.clone().unwrap_or_else(|| self.client.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// The limit on a whole request.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Sends the request built by `request` once, whatever the status, for
+    /// callers handling rate limits themselves.
+    pub async fn send_once<F>(&self, request: F) -> Result<Response>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        #[cfg(feature = "cassette")]
+        if let Some(cassette) = &self.cassette {
+            let once = RetryConfig {
+                attempts: 1,
+                ..self.retry
+            };
+            return cassette.send(&self.client, &once, request).await;
+        }
+        request(&self.client)
+            .send()
+            .await
+            .map_err(reqwest::Error::without_url)
+    }
+
+    /// Sends the request built by `request`, retrying transient failures.
+    pub async fn send<F>(&self, request: F) -> Result<Response>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        #[cfg(feature = "cassette")]
+        if let Some(cassette) = &self.cassette {
+            return cassette.send(&self.client, &self.retry, request).await;
+        }
+        retry::send(&self.retry, || request(&self.client)).await
+    }
+}
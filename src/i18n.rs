@@ -0,0 +1,942 @@
+//! Per-language wording of the report messages. Phrases mark the values
+//! filled in at render time with `{name}` placeholders.
+
+use crate::config::SiteType;
+use crate::ensemble::Confidence;
+use crate::thermal::ThermalQuality;
+use crate::{Check, SkipReason, SoaringWindow, WindTrend};
+use chrono::Weekday;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    De,
+    Fr,
+    Ru,
+}
+
+pub struct Strings {
+    /// `{site}`, `{day}` and `{score}`.
+    pub header: &'static str,
+    pub today: &'static str,
+    pub tomorrow: &'static str,
+    /// `{days}`.
+    pub in_days: &'static str,
+    /// `{site}` and `{day}`.
+    pub not_flyable: &'static str,
+    /// `header` and `not_flyable` of the spots ridden rather than flown.
+    pub rideable_header: &'static str,
+    pub not_rideable: &'static str,
+    /// `{day}`.
+    pub nothing_flyable: &'static str,
+    /// No forecast, rain, too windy, too light, wrong direction, site rule,
+    /// periods too short, low visibility, too cold, high waves, high tide,
+    /// switchy wind, wet ground, closed site, snow and high freezing level.
+    pub skip_reasons: [&'static str; 16],
+    /// A failed check of `Check`, in its order: `{value}` and `{limit}`,
+    /// with the `{unit}` of winds and temperatures, and the `{hours}` of
+    /// prior rain.
+    pub misses: [&'static str; 15],
+    /// `{miss}`, after the skip reason of a site.
+    pub closest_miss_note: &'static str,
+    /// `{miss}`, on a line of its own.
+    pub closest_miss: &'static str,
+    /// `{mm}` of rain in the `{hours}` before the flyable hours.
+    pub wet_ground: &'static str,
+    /// `{airspace}` restricted by the NOTAM `{number}` reading `{text}`.
+    pub notam: &'static str,
+    /// `{wind}` in `{unit}` read live at the `{station}` of `{source}`,
+    /// followed by `live_gust` and `live_direction`.
+    pub live: &'static str,
+    /// `{gust}`.
+    pub live_gust: &'static str,
+    /// `{degrees}`.
+    pub live_direction: &'static str,
+    /// `{wind}` in `{unit}` and `{degrees}` forecast for the hour of a live
+    /// reading, and its `{verdict}`.
+    pub live_forecast: &'static str,
+    /// The live reading agreeing and disagreeing with the forecast.
+    pub live_verdicts: [&'static str; 2],
+    /// `{gaps}` of the forecast.
+    pub incomplete_forecast: &'static str,
+    pub top_pick: &'static str,
+    /// `{site}`, `{day}` and `{score}`, sent on top of the report of a site
+    /// scoring at least `epic_score`.
+    pub epic_day: &'static str,
+    /// Header of the sites without a region when others have one.
+    pub other_sites: &'static str,
+    /// `{sunrise}`, `{sunset}`, `{hours}` flyable and `{daylight}` hours.
+    pub daylight: &'static str,
+    /// `{sunrise}`, `{sunset}`, wind `{speed}` and `{gust}` in `{unit}`,
+    /// `{cardinal}` and `{pop}` percent, from the daily forecast.
+    pub outlook: &'static str,
+    /// `{altitude}`.
+    pub takeoff: &'static str,
+    pub webcam: &'static str,
+    pub map: &'static str,
+    /// `{time}` and `{hours}`.
+    pub period_start: &'static str,
+    /// `{min}`, `{max}` and `{unit}`.
+    pub wind: &'static str,
+    /// `{min}`, `{max}` and `{unit}`, when the wind aloft is evaluated.
+    pub launch_wind: &'static str,
+    /// `{max}` and `{unit}`.
+    pub surface_wind: &'static str,
+    /// `{cardinal}`, `{min}` and `{max}`.
+    pub direction: &'static str,
+    /// `{min}`, `{max}` and `{unit}`.
+    pub temperature: &'static str,
+    /// `{min}`, `{max}` and `{unit}`.
+    pub feels_like: &'static str,
+    /// `{height}`.
+    pub cloud_base_amsl: &'static str,
+    /// `{height}`.
+    pub cloud_base_agl: &'static str,
+    /// `{height}`.
+    pub top_of_lift: &'static str,
+    /// `{km}`.
+    pub visibility: &'static str,
+    /// `{mm}`.
+    pub rain: &'static str,
+    /// `{hpa}`.
+    pub pressure: &'static str,
+    /// `{percent}`.
+    pub humidity: &'static str,
+    /// `{height}` in meters, `{period}` in seconds and `{cardinal}`.
+    pub waves: &'static str,
+    /// `{height}` in meters AMSL.
+    pub freezing_level: &'static str,
+    /// `{cm}`.
+    pub snow_depth: &'static str,
+    /// `{mm}` per hour.
+    pub snowfall: &'static str,
+    /// `{uvi}` and its `{level}`.
+    pub uv: &'static str,
+    /// Low, moderate, high, very high and extreme.
+    pub uv_levels: [&'static str; 5],
+    /// Strengthening and backing off, after the wind of a period.
+    pub wind_trends: [&'static str; 2],
+    /// Strengthening and backing off over `{runs}` forecast runs, from
+    /// `{from}` to `{to}` in `{unit}`.
+    pub forecast_trends: [&'static str; 2],
+    /// `{quality}` and `{hours}`.
+    pub thermals: &'static str,
+    /// Stable, weak, moderate and strong.
+    pub thermal_qualities: [&'static str; 4],
+    pub confidence: &'static str,
+    /// Low, medium and high.
+    pub confidence_levels: [&'static str; 3],
+    /// Morning sledder, strong thermic midday, thermic midday, ridge
+    /// soaring and smooth evening soaring, after the start of a period.
+    pub soaring_windows: [&'static str; 5],
+    /// Monday first.
+    pub weekdays: [&'static str; 7],
+    /// Answer to `/help`, followed by `admin_help` in the chats of admins.
+    pub help: &'static str,
+    pub admin_help: &'static str,
+    /// Above the subscription keyboard.
+    pub pick_sites: &'static str,
+    /// Keyboard buttons following and unfollowing every site.
+    pub all_sites: &'static str,
+    pub no_sites: &'static str,
+    /// `{site}`.
+    pub unknown_site: &'static str,
+    /// A keyboard button of a site removed since the keyboard was sent.
+    pub unknown_button: &'static str,
+    /// `{sites}`.
+    pub subscribed: &'static str,
+    /// `{sites}`.
+    pub unsubscribed: &'static str,
+    pub unsubscribed_all: &'static str,
+    pub style_usage: &'static str,
+    /// `{style}`.
+    pub style_set: &'static str,
+    pub no_profiles: &'static str,
+    /// The `{profile}` of the chat and the `{profiles}` of the sites.
+    pub profile: &'static str,
+    /// `{profiles}`.
+    pub no_profile: &'static str,
+    /// `{profile}`.
+    pub profile_set: &'static str,
+    pub profile_cleared: &'static str,
+    /// `{profile}` and `{profiles}`.
+    pub unknown_profile: &'static str,
+    pub forecast_usage: &'static str,
+    /// `{site}`.
+    pub forecast_failed: &'static str,
+    /// Age of a reused `/forecast` answer: under a minute and `{minutes}`.
+    pub data_ages: [&'static str; 2],
+    pub admins_only: &'static str,
+    pub add_site_usage: &'static str,
+    /// `{site}`.
+    pub site_exists: &'static str,
+    /// `{site}` and `{error}`.
+    pub site_not_added: &'static str,
+    /// `{site}`.
+    pub site_added: &'static str,
+    /// `{error}`.
+    pub limit_usage: &'static str,
+    /// `{site}` and `{error}`.
+    pub limit_not_set: &'static str,
+    /// `{setting}`, `{site}` and `{speed}`.
+    pub limit_set: &'static str,
+    /// `{site}`.
+    pub site_disabled: &'static str,
+    /// `{site}`.
+    pub site_not_disabled: &'static str,
+    /// `{site}`.
+    pub site_enabled: &'static str,
+}
+
+const ENGLISH: Strings = Strings {
+    header: "{site} is flyable {day} (score {score}):",
+    today: "today",
+    tomorrow: "tomorrow",
+    in_days: "in {days} days",
+    not_flyable: "{site} is not flyable {day}.",
+    rideable_header: "{site} is rideable {day} (score {score}):",
+    not_rideable: "{site} is not rideable {day}.",
+    nothing_flyable: "Nothing is flyable {day}.",
+    skip_reasons: [
+        "no forecast",
+        "rain",
+        "too windy",
+        "too light",
+        "wrong wind direction",
+        "site rule not met",
+        "flyable periods too short",
+        "low visibility",
+        "too cold",
+        "waves too high",
+        "tide too high",
+        "switchy wind direction",
+        "ground wet from recent rain",
+        "site closed",
+        "takeoff snowed in",
+        "freezing level too high",
+    ],
+    misses: [
+        "{value}% chance of rain (max {limit}%)",
+        "rain {value} mm/h (max {limit})",
+        "wind {value} {unit} (max {limit})",
+        "wind {value} {unit} (min {limit})",
+        "gusts {value} {unit} (max {limit})",
+        "wind from {value}°, {limit}° out of the sectors",
+        "visibility {value} km (min {limit})",
+        "feels like {value}{unit} (min {limit})",
+        "waves {value} m (max {limit})",
+        "tide {value} m (max {limit})",
+        "{value} cm of snow (max {limit})",
+        "snowfall {value} mm/h (max {limit})",
+        "freezing level {value} m (max {limit})",
+        "{value} mm of rain in the {hours} hours before (max {limit})",
+        "wind turning {value}° in an hour (max {limit})",
+    ],
+    closest_miss_note: "(closest miss at {miss})",
+    closest_miss: "Closest miss at {miss}",
+    wet_ground: "Wet ground: {mm} mm of rain in the {hours} hours before, thermals may be weak",
+    notam: "Airspace {airspace} restricted (NOTAM {number}): {text}",
+    live: "Live at {station} ({source}): {wind} {unit}",
+    live_gust: " gusting {gust}",
+    live_direction: " from {degrees}°",
+    live_forecast: ", forecast {wind} {unit} from {degrees}° ({verdict})",
+    live_verdicts: ["agrees", "disagrees"],
+    incomplete_forecast: "Incomplete forecast: {gaps}",
+    top_pick: "🏆 Top pick: ",
+    epic_day: "🔥 Epic day at {site} {day}, score {score}. Don't miss it!",
+    other_sites: "Other sites",
+    daylight: "Sunrise {sunrise}, sunset {sunset}, {hours} of {daylight} daylight hours flyable",
+    outlook: "Outlook only, from the daily forecast: sunrise {sunrise}, sunset {sunset}, wind {speed} {unit} gusting {gust} {unit} from {cardinal}, {pop}% chance of rain",
+    takeoff: "Takeoff at {altitude} m AMSL",
+    webcam: "Webcam",
+    map: "Map",
+    period_start: "Starting at {time} for {hours} hours",
+    wind: "Wind from {min} to {max} {unit}",
+    launch_wind: "Wind at launch from {min} to {max} {unit}",
+    surface_wind: "Surface wind up to {max} {unit}",
+    direction: "Direction {cardinal} ({min}–{max}°)",
+    temperature: "Temperature from {min}{unit} to {max}{unit}",
+    feels_like: "Feels like {min}{unit} to {max}{unit}",
+    cloud_base_amsl: "Cloud base ~{height} m AMSL",
+    cloud_base_agl: "Cloud base ~{height} m AGL",
+    top_of_lift: "Top of lift ~{height} m AMSL",
+    visibility: "Visibility at least {km} km",
+    rain: "Rain up to {mm} mm/h",
+    pressure: "Pressure down to {hpa} hPa",
+    humidity: "Humidity up to {percent}%",
+    waves: "Waves up to {height} m every {period} s from {cardinal}",
+    freezing_level: "Freezing level down to ~{height} m",
+    snow_depth: "Snow on the ground up to {cm} cm",
+    snowfall: "Snowfall up to {mm} mm/h",
+    uv: "Peak UV index {uvi} ({level})",
+    uv_levels: ["low", "moderate", "high", "very high", "extreme"],
+    wind_trends: ["strengthening", "backing off"],
+    thermals: "Thermals {quality} ({hours} usable hours)",
+    thermal_qualities: ["stable", "weak", "moderate", "strong"],
+    confidence: "Forecast confidence {level}",
+    confidence_levels: ["low", "medium", "high"],
+    soaring_windows: [
+        "morning sledder",
+        "strong thermic midday",
+        "thermic midday",
+        "ridge soaring",
+        "smooth evening soaring",
+    ],
+    forecast_trends: [
+        "Forecast trend over {runs} runs: daytime wind up from {from} to {to} {unit}",
+        "Forecast trend over {runs} runs: daytime wind down from {from} to {to} {unit}",
+    ],
+    weekdays: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+    help: "I send flyability forecasts for the sites you follow.\n\
+        /sites - list the sites and your subscriptions\n\
+        /subscribe - pick the sites to follow\n\
+        /subscribe Site A, Site B - follow some sites\n\
+        /unsubscribe - stop all forecasts\n\
+        /unsubscribe Site A - stop following a site\n\
+        /forecast Site A - check a site for tomorrow\n\
+        /forecast Site A today - check a site for today\n\
+        /forecast Site A +2 - check a site in two days\n\
+        /style compact - one line per period\n\
+        /style paragraph - a sentence per measure\n\
+        /profile students - get the reports for the limits of a pilot profile\n\
+        /profile none - get the reports for the limits of the sites",
+    admin_help: "/addsite Site A 45.8 6.2 2mps 8mps 270-30 - add a site flyable in 2 to 8 m/s from 270° to 30°\n\
+        /setlimit Site A max_wind 8mps - change the min_wind or max_wind of a site\n\
+        /disable Site A - stop checking a site\n\
+        /enable Site A - check a disabled site again",
+    pick_sites: "Tap the sites you want forecasts for.",
+    all_sites: "All sites",
+    no_sites: "None",
+    unknown_site: "Unknown site \"{site}\", see /sites.",
+    unknown_button: "Unknown site, see /sites.",
+    subscribed: "Subscribed to {sites}.",
+    unsubscribed: "Unsubscribed from {sites}.",
+    unsubscribed_all: "Unsubscribed from every site.",
+    style_usage: "Pick /style compact or /style paragraph.",
+    style_set: "Your reports now use the {style} style.",
+    no_profiles: "No site has pilot profiles.",
+    profile: "Your profile: {profile}. Pick one of {profiles}, or /profile none.",
+    no_profile: "You have no profile. Pick one of {profiles}.",
+    profile_set: "Your reports now use the {profile} limits.",
+    profile_cleared: "Your reports now use the limits of the sites.",
+    unknown_profile: "Unknown profile \"{profile}\", pick one of {profiles}.",
+    forecast_usage: "Which site? For example: /forecast Site A today",
+    forecast_failed: "Could not fetch the forecast for {site}, try again later.",
+    data_ages: [
+        "Data from less than a minute ago",
+        "Data from {minutes} min ago",
+    ],
+    admins_only: "Only admins can change the sites.",
+    add_site_usage: "For example: /addsite Site A 45.8 6.2 2mps 8mps 270-30",
+    site_exists: "There already is a site \"{site}\".",
+    site_not_added: "Could not add \"{site}\": {error}.",
+    site_added: "Added {site}.",
+    limit_usage: "{error} For example: /setlimit Site A max_wind 8mps",
+    limit_not_set: "Could not change {site}: {error}.",
+    limit_set: "Set the {setting} of {site} to {speed}.",
+    site_disabled: "Disabled {site}.",
+    site_not_disabled: "\"{site}\" is not disabled.",
+    site_enabled: "Enabled {site}.",
+};
+
+const GERMAN: Strings = Strings {
+    header: "{site} ist {day} fliegbar (Score {score}):",
+    today: "heute",
+    tomorrow: "morgen",
+    in_days: "in {days} Tagen",
+    not_flyable: "{site} ist {day} nicht fliegbar.",
+    rideable_header: "{site} ist {day} fahrbar (Score {score}):",
+    not_rideable: "{site} ist {day} nicht fahrbar.",
+    nothing_flyable: "Nichts ist {day} fliegbar.",
+    skip_reasons: [
+        "keine Vorhersage",
+        "Regen",
+        "zu viel Wind",
+        "zu wenig Wind",
+        "falsche Windrichtung",
+        "Regel des Fluggebiets nicht erfüllt",
+        "fliegbare Zeiträume zu kurz",
+        "schlechte Sicht",
+        "zu kalt",
+        "Wellen zu hoch",
+        "Flut zu hoch",
+        "drehende Windrichtung",
+        "Boden nass vom Regen",
+        "Gelände gesperrt",
+        "Startplatz eingeschneit",
+        "Nullgradgrenze zu hoch",
+    ],
+    misses: [
+        "{value}% Regenwahrscheinlichkeit (max. {limit}%)",
+        "Regen {value} mm/h (max. {limit})",
+        "Wind {value} {unit} (max. {limit})",
+        "Wind {value} {unit} (min. {limit})",
+        "Böen {value} {unit} (max. {limit})",
+        "Wind aus {value}°, {limit}° außerhalb der Sektoren",
+        "Sicht {value} km (min. {limit})",
+        "gefühlt {value}{unit} (min. {limit})",
+        "Wellen {value} m (max. {limit})",
+        "Flut {value} m (max. {limit})",
+        "{value} cm Schnee (max. {limit})",
+        "Schneefall {value} mm/h (max. {limit})",
+        "Nullgradgrenze {value} m (max. {limit})",
+        "{value} mm Regen in den {hours} Stunden davor (max. {limit})",
+        "Wind dreht {value}° pro Stunde (max. {limit})",
+    ],
+    closest_miss_note: "(am knappsten um {miss})",
+    closest_miss: "Am knappsten um {miss}",
+    wet_ground: "Nasser Boden: {mm} mm Regen in den {hours} Stunden davor, Thermik kann schwach sein",
+    notam: "Luftraum {airspace} eingeschränkt (NOTAM {number}): {text}",
+    live: "Live an {station} ({source}): {wind} {unit}",
+    live_gust: ", Böen {gust}",
+    live_direction: " aus {degrees}°",
+    live_forecast: ", Vorhersage {wind} {unit} aus {degrees}° ({verdict})",
+    live_verdicts: ["stimmt überein", "weicht ab"],
+    incomplete_forecast: "Unvollständige Vorhersage: {gaps}",
+    top_pick: "🏆 Top-Tipp: ",
+    epic_day: "🔥 Traumtag am {site} {day}, Score {score}. Nicht verpassen!",
+    other_sites: "Weitere Fluggebiete",
+    daylight: "Sonnenaufgang {sunrise}, Sonnenuntergang {sunset}, {hours} von {daylight} Stunden Tageslicht fliegbar",
+    outlook: "Nur Ausblick, aus der Tagesvorhersage: Sonnenaufgang {sunrise}, Sonnenuntergang {sunset}, Wind {speed} {unit} in Böen {gust} {unit} aus {cardinal}, Regenwahrscheinlichkeit {pop}%",
+    takeoff: "Startplatz auf {altitude} m MSL",
+    webcam: "Webcam",
+    map: "Karte",
+    period_start: "Ab {time} für {hours} Stunden",
+    wind: "Wind von {min} bis {max} {unit}",
+    launch_wind: "Wind am Startplatz von {min} bis {max} {unit}",
+    surface_wind: "Bodenwind bis {max} {unit}",
+    direction: "Richtung {cardinal} ({min}–{max}°)",
+    temperature: "Temperatur von {min}{unit} bis {max}{unit}",
+    feels_like: "Gefühlt {min}{unit} bis {max}{unit}",
+    cloud_base_amsl: "Wolkenbasis ~{height} m MSL",
+    cloud_base_agl: "Wolkenbasis ~{height} m über Grund",
+    top_of_lift: "Thermikobergrenze ~{height} m MSL",
+    visibility: "Sicht mindestens {km} km",
+    rain: "Regen bis {mm} mm/h",
+    pressure: "Luftdruck bis {hpa} hPa",
+    humidity: "Luftfeuchtigkeit bis {percent} %",
+    waves: "Wellen bis {height} m alle {period} s aus {cardinal}",
+    freezing_level: "Nullgradgrenze bis ~{height} m",
+    snow_depth: "Schneehöhe bis {cm} cm",
+    snowfall: "Schneefall bis {mm} mm/h",
+    uv: "UV-Index bis {uvi} ({level})",
+    uv_levels: ["niedrig", "mäßig", "hoch", "sehr hoch", "extrem"],
+    wind_trends: ["zunehmend", "abnehmend"],
+    thermals: "Thermik {quality} ({hours} nutzbare Stunden)",
+    thermal_qualities: ["stabil", "schwach", "mäßig", "stark"],
+    confidence: "Vorhersagesicherheit {level}",
+    confidence_levels: ["niedrig", "mittel", "hoch"],
+    soaring_windows: [
+        "Morgen-Abgleiter",
+        "starke Mittagsthermik",
+        "Mittagsthermik",
+        "Hangsoaring",
+        "ruhiges Abendsoaring",
+    ],
+    forecast_trends: [
+        "Vorhersagetrend über {runs} Läufe: Wind tagsüber steigend von {from} auf {to} {unit}",
+        "Vorhersagetrend über {runs} Läufe: Wind tagsüber fallend von {from} auf {to} {unit}",
+    ],
+    weekdays: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+    help: "Ich sende Flugwetter-Vorhersagen für die Fluggebiete, denen du folgst.\n\
+        /sites - Fluggebiete und deine Abos anzeigen\n\
+        /subscribe - Fluggebiete zum Folgen auswählen\n\
+        /subscribe Site A, Site B - einigen Fluggebieten folgen\n\
+        /unsubscribe - alle Vorhersagen abbestellen\n\
+        /unsubscribe Site A - einem Fluggebiet nicht mehr folgen\n\
+        /forecast Site A - ein Fluggebiet für morgen prüfen\n\
+        /forecast Site A today - ein Fluggebiet für heute prüfen\n\
+        /forecast Site A +2 - ein Fluggebiet in zwei Tagen prüfen\n\
+        /style compact - eine Zeile pro Zeitraum\n\
+        /style paragraph - ein Satz pro Messwert\n\
+        /profile students - Berichte mit den Grenzwerten eines Pilotenprofils\n\
+        /profile none - Berichte mit den Grenzwerten der Fluggebiete",
+    admin_help: "/addsite Site A 45.8 6.2 2mps 8mps 270-30 - ein Fluggebiet hinzufügen, fliegbar bei 2 bis 8 m/s aus 270° bis 30°\n\
+        /setlimit Site A max_wind 8mps - min_wind oder max_wind eines Fluggebiets ändern\n\
+        /disable Site A - ein Fluggebiet nicht mehr prüfen\n\
+        /enable Site A - ein deaktiviertes Fluggebiet wieder prüfen",
+    pick_sites: "Tippe die Fluggebiete an, für die du Vorhersagen möchtest.",
+    all_sites: "Alle Fluggebiete",
+    no_sites: "Keine",
+    unknown_site: "Unbekanntes Fluggebiet „{site}“, siehe /sites.",
+    unknown_button: "Unbekanntes Fluggebiet, siehe /sites.",
+    subscribed: "Du folgst jetzt {sites}.",
+    unsubscribed: "Du folgst {sites} nicht mehr.",
+    unsubscribed_all: "Du folgst keinem Fluggebiet mehr.",
+    style_usage: "Wähle /style compact oder /style paragraph.",
+    style_set: "Deine Berichte nutzen jetzt den Stil {style}.",
+    no_profiles: "Kein Fluggebiet hat Pilotenprofile.",
+    profile: "Dein Profil: {profile}. Wähle eines von {profiles} oder /profile none.",
+    no_profile: "Du hast kein Profil. Wähle eines von {profiles}.",
+    profile_set: "Deine Berichte nutzen jetzt die Grenzwerte von {profile}.",
+    profile_cleared: "Deine Berichte nutzen jetzt die Grenzwerte der Fluggebiete.",
+    unknown_profile: "Unbekanntes Profil „{profile}“, wähle eines von {profiles}.",
+    forecast_usage: "Welches Fluggebiet? Zum Beispiel: /forecast Site A today",
+    forecast_failed: "Die Vorhersage für {site} ist nicht abrufbar, versuche es später noch einmal.",
+    data_ages: [
+        "Daten von vor weniger als einer Minute",
+        "Daten von vor {minutes} Min.",
+    ],
+    admins_only: "Nur Admins können die Fluggebiete ändern.",
+    add_site_usage: "Zum Beispiel: /addsite Site A 45.8 6.2 2mps 8mps 270-30",
+    site_exists: "Es gibt schon ein Fluggebiet „{site}“.",
+    site_not_added: "„{site}“ konnte nicht hinzugefügt werden: {error}.",
+    site_added: "{site} hinzugefügt.",
+    limit_usage: "{error} Zum Beispiel: /setlimit Site A max_wind 8mps",
+    limit_not_set: "{site} konnte nicht geändert werden: {error}.",
+    limit_set: "{setting} von {site} ist jetzt {speed}.",
+    site_disabled: "{site} deaktiviert.",
+    site_not_disabled: "„{site}“ ist nicht deaktiviert.",
+    site_enabled: "{site} aktiviert.",
+};
+
+const FRENCH: Strings = Strings {
+    header: "{site} est volable {day} (score {score}) :",
+    today: "aujourd'hui",
+    tomorrow: "demain",
+    in_days: "dans {days} jours",
+    not_flyable: "{site} n'est pas volable {day}.",
+    rideable_header: "{site} est navigable {day} (score {score}) :",
+    not_rideable: "{site} n'est pas navigable {day}.",
+    nothing_flyable: "Rien n'est volable {day}.",
+    skip_reasons: [
+        "pas de prévision",
+        "pluie",
+        "trop de vent",
+        "pas assez de vent",
+        "mauvaise orientation du vent",
+        "règle du site non remplie",
+        "créneaux volables trop courts",
+        "visibilité insuffisante",
+        "trop froid",
+        "vagues trop hautes",
+        "marée trop haute",
+        "vent tournant",
+        "sol détrempé par la pluie",
+        "site fermé",
+        "décollage enneigé",
+        "isotherme 0 °C trop haute",
+    ],
+    misses: [
+        "{value} % de risque de pluie (max {limit} %)",
+        "pluie {value} mm/h (max {limit})",
+        "vent {value} {unit} (max {limit})",
+        "vent {value} {unit} (min {limit})",
+        "rafales {value} {unit} (max {limit})",
+        "vent du {value}°, {limit}° hors des secteurs",
+        "visibilité {value} km (min {limit})",
+        "ressenti {value}{unit} (min {limit})",
+        "vagues {value} m (max {limit})",
+        "marée {value} m (max {limit})",
+        "{value} cm de neige (max {limit})",
+        "chutes de neige {value} mm/h (max {limit})",
+        "isotherme 0 °C à {value} m (max {limit})",
+        "{value} mm de pluie dans les {hours} heures précédentes (max {limit})",
+        "vent tournant de {value}° en une heure (max {limit})",
+    ],
+    closest_miss_note: "(au plus près à {miss})",
+    closest_miss: "Au plus près à {miss}",
+    wet_ground: "Sol mouillé : {mm} mm de pluie dans les {hours} heures précédentes, thermiques possiblement faibles",
+    notam: "Espace aérien {airspace} restreint (NOTAM {number}) : {text}",
+    live: "En direct à {station} ({source}) : {wind} {unit}",
+    live_gust: ", rafales {gust}",
+    live_direction: " du {degrees}°",
+    live_forecast: ", prévu {wind} {unit} du {degrees}° ({verdict})",
+    live_verdicts: ["concordant", "discordant"],
+    incomplete_forecast: "Prévision incomplète : {gaps}",
+    top_pick: "🏆 Meilleur choix : ",
+    epic_day: "🔥 Journée exceptionnelle à {site} {day}, score {score}. À ne pas manquer !",
+    other_sites: "Autres sites",
+    daylight: "Lever {sunrise}, coucher {sunset}, {hours} heures volables sur {daylight} de jour",
+    outlook: "Tendance seulement, d'après la prévision journalière : lever {sunrise}, coucher {sunset}, vent {speed} {unit} en rafales {gust} {unit} de {cardinal}, {pop}% de risque de pluie",
+    takeoff: "Décollage à {altitude} m d'altitude",
+    webcam: "Webcam",
+    map: "Carte",
+    period_start: "À partir de {time} pendant {hours} heures",
+    wind: "Vent de {min} à {max} {unit}",
+    launch_wind: "Vent au décollage de {min} à {max} {unit}",
+    surface_wind: "Vent au sol jusqu'à {max} {unit}",
+    direction: "Direction {cardinal} ({min}–{max}°)",
+    temperature: "Température de {min}{unit} à {max}{unit}",
+    feels_like: "Ressenti de {min}{unit} à {max}{unit}",
+    cloud_base_amsl: "Base des nuages ~{height} m d'altitude",
+    cloud_base_agl: "Base des nuages ~{height} m sol",
+    top_of_lift: "Plafond thermique ~{height} m d'altitude",
+    visibility: "Visibilité d'au moins {km} km",
+    rain: "Pluie jusqu'à {mm} mm/h",
+    pressure: "Pression jusqu'à {hpa} hPa",
+    humidity: "Humidité jusqu'à {percent} %",
+    waves: "Vagues jusqu'à {height} m toutes les {period} s de {cardinal}",
+    freezing_level: "Isotherme 0 °C jusqu'à ~{height} m",
+    snow_depth: "Neige au sol jusqu'à {cm} cm",
+    snowfall: "Chutes de neige jusqu'à {mm} mm/h",
+    uv: "Indice UV jusqu'à {uvi} ({level})",
+    uv_levels: ["faible", "modéré", "élevé", "très élevé", "extrême"],
+    wind_trends: ["en renforcement", "en baisse"],
+    thermals: "Thermiques {quality} ({hours} heures exploitables)",
+    thermal_qualities: ["stables", "faibles", "modérés", "forts"],
+    confidence: "Confiance {level}",
+    confidence_levels: ["faible", "moyenne", "élevée"],
+    soaring_windows: [
+        "plouf du matin",
+        "thermique forte à midi",
+        "thermique à midi",
+        "vol de pente",
+        "restitution du soir",
+    ],
+    forecast_trends: [
+        "Tendance sur {runs} prévisions : vent de journée en hausse de {from} à {to} {unit}",
+        "Tendance sur {runs} prévisions : vent de journée en baisse de {from} à {to} {unit}",
+    ],
+    weekdays: ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."],
+    help: "J'envoie des prévisions de volabilité pour les sites que vous suivez.\n\
+        /sites - lister les sites et vos abonnements\n\
+        /subscribe - choisir les sites à suivre\n\
+        /subscribe Site A, Site B - suivre quelques sites\n\
+        /unsubscribe - arrêter toutes les prévisions\n\
+        /unsubscribe Site A - ne plus suivre un site\n\
+        /forecast Site A - vérifier un site pour demain\n\
+        /forecast Site A today - vérifier un site pour aujourd'hui\n\
+        /forecast Site A +2 - vérifier un site dans deux jours\n\
+        /style compact - une ligne par période\n\
+        /style paragraph - une phrase par mesure\n\
+        /profile students - recevoir les rapports aux limites d'un profil de pilote\n\
+        /profile none - recevoir les rapports aux limites des sites",
+    admin_help: "/addsite Site A 45.8 6.2 2mps 8mps 270-30 - ajouter un site volable de 2 à 8 m/s de 270° à 30°\n\
+        /setlimit Site A max_wind 8mps - changer le min_wind ou le max_wind d'un site\n\
+        /disable Site A - ne plus vérifier un site\n\
+        /enable Site A - vérifier à nouveau un site désactivé",
+    pick_sites: "Touchez les sites dont vous voulez les prévisions.",
+    all_sites: "Tous les sites",
+    no_sites: "Aucun",
+    unknown_site: "Site « {site} » inconnu, voir /sites.",
+    unknown_button: "Site inconnu, voir /sites.",
+    subscribed: "Abonné à {sites}.",
+    unsubscribed: "Désabonné de {sites}.",
+    unsubscribed_all: "Désabonné de tous les sites.",
+    style_usage: "Choisissez /style compact ou /style paragraph.",
+    style_set: "Vos rapports utilisent désormais le style {style}.",
+    no_profiles: "Aucun site n'a de profil de pilote.",
+    profile: "Votre profil : {profile}. Choisissez parmi {profiles}, ou /profile none.",
+    no_profile: "Vous n'avez pas de profil. Choisissez parmi {profiles}.",
+    profile_set: "Vos rapports utilisent désormais les limites {profile}.",
+    profile_cleared: "Vos rapports utilisent désormais les limites des sites.",
+    unknown_profile: "Profil « {profile} » inconnu, choisissez parmi {profiles}.",
+    forecast_usage: "Quel site ? Par exemple : /forecast Site A today",
+    forecast_failed: "Impossible d'obtenir la prévision pour {site}, réessayez plus tard.",
+    data_ages: [
+        "Données d'il y a moins d'une minute",
+        "Données d'il y a {minutes} min",
+    ],
+    admins_only: "Seuls les administrateurs peuvent modifier les sites.",
+    add_site_usage: "Par exemple : /addsite Site A 45.8 6.2 2mps 8mps 270-30",
+    site_exists: "Le site « {site} » existe déjà.",
+    site_not_added: "Impossible d'ajouter « {site} » : {error}.",
+    site_added: "{site} ajouté.",
+    limit_usage: "{error} Par exemple : /setlimit Site A max_wind 8mps",
+    limit_not_set: "Impossible de modifier {site} : {error}.",
+    limit_set: "{setting} de {site} fixé à {speed}.",
+    site_disabled: "{site} désactivé.",
+    site_not_disabled: "« {site} » n'est pas désactivé.",
+    site_enabled: "{site} réactivé.",
+};
+
+const RUSSIAN: Strings = Strings {
+    header: "{site}: лётно {day} (оценка {score}):",
+    today: "сегодня",
+    tomorrow: "завтра",
+    in_days: "через {days} дн.",
+    not_flyable: "{site}: {day} не лётно.",
+    rideable_header: "{site}: можно кататься {day} (оценка {score}):",
+    not_rideable: "{site}: {day} кататься нельзя.",
+    nothing_flyable: "Нигде не лётно {day}.",
+    skip_reasons: [
+        "нет прогноза",
+        "дождь",
+        "слишком сильный ветер",
+        "слишком слабый ветер",
+        "неподходящее направление ветра",
+        "правило площадки не выполнено",
+        "лётные окна слишком короткие",
+        "плохая видимость",
+        "слишком холодно",
+        "слишком высокие волны",
+        "слишком высокий прилив",
+        "неустойчивое направление ветра",
+        "земля мокрая после дождя",
+        "площадка закрыта",
+        "старт занесён снегом",
+        "нулевая изотерма слишком высоко",
+    ],
+    misses: [
+        "вероятность дождя {value}% (макс. {limit}%)",
+        "дождь {value} мм/ч (макс. {limit})",
+        "ветер {value} {unit} (макс. {limit})",
+        "ветер {value} {unit} (мин. {limit})",
+        "порывы {value} {unit} (макс. {limit})",
+        "ветер с {value}°, на {limit}° вне секторов",
+        "видимость {value} км (мин. {limit})",
+        "ощущается как {value}{unit} (мин. {limit})",
+        "волны {value} м (макс. {limit})",
+        "прилив {value} м (макс. {limit})",
+        "{value} см снега (макс. {limit})",
+        "снегопад {value} мм/ч (макс. {limit})",
+        "нулевая изотерма {value} м (макс. {limit})",
+        "{value} мм дождя за {hours} ч до этого (макс. {limit})",
+        "ветер поворачивает на {value}° за час (макс. {limit})",
+    ],
+    closest_miss_note: "(ближе всего к условиям в {miss})",
+    closest_miss: "Ближе всего к условиям в {miss}",
+    wet_ground: "Мокрая земля: {mm} мм дождя за {hours} ч до этого, термики могут быть слабыми",
+    notam: "Воздушное пространство {airspace} ограничено (NOTAM {number}): {text}",
+    live: "Сейчас на {station} ({source}): {wind} {unit}",
+    live_gust: ", порывы {gust}",
+    live_direction: " с {degrees}°",
+    live_forecast: ", прогноз {wind} {unit} с {degrees}° ({verdict})",
+    live_verdicts: ["совпадает", "расходится"],
+    incomplete_forecast: "Неполный прогноз: {gaps}",
+    top_pick: "🏆 Лучший выбор: ",
+    epic_day: "🔥 Эпичный день на {site} {day}, оценка {score}. Не пропустите!",
+    other_sites: "Другие места",
+    daylight: "Восход {sunrise}, закат {sunset}, лётно {hours} из {daylight} ч светового дня",
+    outlook: "Только общий прогноз на день: восход {sunrise}, закат {sunset}, ветер {speed} {unit}, порывы {gust} {unit}, направление {cardinal}, вероятность дождя {pop}%",
+    takeoff: "Старт на высоте {altitude} м над уровнем моря",
+    webcam: "Веб-камера",
+    map: "Карта",
+    period_start: "С {time} в течение {hours} ч",
+    wind: "Ветер от {min} до {max} {unit}",
+    launch_wind: "Ветер на старте от {min} до {max} {unit}",
+    surface_wind: "Ветер у земли до {max} {unit}",
+    direction: "Направление {cardinal} ({min}–{max}°)",
+    temperature: "Температура от {min}{unit} до {max}{unit}",
+    feels_like: "Ощущается от {min}{unit} до {max}{unit}",
+    cloud_base_amsl: "Нижняя граница облаков ~{height} м над уровнем моря",
+    cloud_base_agl: "Нижняя граница облаков ~{height} м над землёй",
+    top_of_lift: "Потолок термиков ~{height} м над уровнем моря",
+    visibility: "Видимость не менее {km} км",
+    rain: "Дождь до {mm} мм/ч",
+    pressure: "Давление до {hpa} гПа",
+    humidity: "Влажность до {percent}%",
+    waves: "Волны до {height} м каждые {period} с, направление {cardinal}",
+    freezing_level: "Нулевая изотерма до ~{height} м",
+    snow_depth: "Снежный покров до {cm} см",
+    snowfall: "Снегопад до {mm} мм/ч",
+    uv: "УФ-индекс до {uvi} ({level})",
+    uv_levels: ["низкий", "умеренный", "высокий", "очень высокий", "экстремальный"],
+    wind_trends: ["усиливается", "ослабевает"],
+    thermals: "Термики: {quality} ({hours} ч пригодны для полётов)",
+    thermal_qualities: ["стабильно", "слабые", "умеренные", "сильные"],
+    confidence: "Достоверность прогноза: {level}",
+    confidence_levels: ["низкая", "средняя", "высокая"],
+    soaring_windows: [
+        "утренний спуск",
+        "сильная дневная термика",
+        "дневная термика",
+        "динамическое парение",
+        "спокойное вечернее парение",
+    ],
+    forecast_trends: [
+        "Тренд за {runs} прогнозов: дневной ветер усиливается с {from} до {to} {unit}",
+        "Тренд за {runs} прогнозов: дневной ветер ослабевает с {from} до {to} {unit}",
+    ],
+    weekdays: ["пн", "вт", "ср", "чт", "пт", "сб", "вс"],
+    help: "Я присылаю прогнозы лётности для мест, на которые вы подписаны.\n\
+        /sites - список мест и ваших подписок\n\
+        /subscribe - выбрать места для подписки\n\
+        /subscribe Site A, Site B - подписаться на несколько мест\n\
+        /unsubscribe - отключить все прогнозы\n\
+        /unsubscribe Site A - отписаться от места\n\
+        /forecast Site A - проверить место на завтра\n\
+        /forecast Site A today - проверить место на сегодня\n\
+        /forecast Site A +2 - проверить место через два дня\n\
+        /style compact - одна строка на период\n\
+        /style paragraph - по предложению на показатель\n\
+        /profile students - отчёты по ограничениям профиля пилота\n\
+        /profile none - отчёты по ограничениям мест",
+    admin_help: "/addsite Site A 45.8 6.2 2mps 8mps 270-30 - добавить место, лётное при 2–8 м/с с 270° до 30°\n\
+        /setlimit Site A max_wind 8mps - изменить min_wind или max_wind места\n\
+        /disable Site A - перестать проверять место\n\
+        /enable Site A - снова проверять отключённое место",
+    pick_sites: "Выберите места, для которых нужны прогнозы.",
+    all_sites: "Все места",
+    no_sites: "Ни одного",
+    unknown_site: "Неизвестное место «{site}», см. /sites.",
+    unknown_button: "Неизвестное место, см. /sites.",
+    subscribed: "Подписка на {sites} оформлена.",
+    unsubscribed: "Подписка на {sites} отменена.",
+    unsubscribed_all: "Все подписки отменены.",
+    style_usage: "Выберите /style compact или /style paragraph.",
+    style_set: "Теперь отчёты в стиле {style}.",
+    no_profiles: "Ни у одного места нет профилей пилотов.",
+    profile: "Ваш профиль: {profile}. Выберите один из {profiles} или /profile none.",
+    no_profile: "У вас нет профиля. Выберите один из {profiles}.",
+    profile_set: "Теперь отчёты по ограничениям профиля {profile}.",
+    profile_cleared: "Теперь отчёты по ограничениям мест.",
+    unknown_profile: "Неизвестный профиль «{profile}», выберите один из {profiles}.",
+    forecast_usage: "Какое место? Например: /forecast Site A today",
+    forecast_failed: "Не удалось получить прогноз для {site}, попробуйте позже.",
+    data_ages: [
+        "Данные менее минуты назад",
+        "Данные {minutes} мин назад",
+    ],
+    admins_only: "Изменять места могут только администраторы.",
+    add_site_usage: "Например: /addsite Site A 45.8 6.2 2mps 8mps 270-30",
+    site_exists: "Место «{site}» уже есть.",
+    site_not_added: "Не удалось добавить «{site}»: {error}.",
+    site_added: "{site} добавлено.",
+    limit_usage: "{error} Например: /setlimit Site A max_wind 8mps",
+    limit_not_set: "Не удалось изменить {site}: {error}.",
+    limit_set: "{setting} для {site} теперь {speed}.",
+    site_disabled: "{site} отключено.",
+    site_not_disabled: "«{site}» не отключено.",
+    site_enabled: "{site} снова включено.",
+};
+
+impl Language {
+    pub fn strings(self) -> &'static Strings {
+        match self {
+            Language::En => &ENGLISH,
+            Language::De => &GERMAN,
+            Language::Fr => &FRENCH,
+            Language::Ru => &RUSSIAN,
+        }
+    }
+}
+
+impl Strings {
+    /// The `header` of the reports of a site of `site_type`.
+    pub fn header_for(&self, site_type: SiteType) -> &'static str {
+        if site_type.is_flown() {
+            self.header
+        } else {
+            self.rideable_header
+        }
+    }
+
+    /// The `not_flyable` of a site of `site_type`.
+    pub fn not_flyable_for(&self, site_type: SiteType) -> &'static str {
+        if site_type.is_flown() {
+            self.not_flyable
+        } else {
+            self.not_rideable
+        }
+    }
+
+    pub fn day(&self, days_ahead: i64) -> String {
+        match days_ahead {
+            0 => String::from(self.today),
+            1 => String::from(self.tomorrow),
+            days => fill(self.in_days, &[("days", &days.to_string())]),
+        }
+    }
+
+    pub fn weekday(&self, weekday: Weekday) -> &'static str {
+        self.weekdays[weekday.num_days_from_monday() as usize]
+    }
+
+    pub fn skip_reason(&self, reason: SkipReason) -> &'static str {
+        match reason {
+            SkipReason::NoForecast => self.skip_reasons[0],
+            SkipReason::Rain => self.skip_reasons[1],
+            SkipReason::TooWindy => self.skip_reasons[2],
+            SkipReason::TooLight => self.skip_reasons[3],
+            SkipReason::WrongDirection => self.skip_reasons[4],
+            SkipReason::Rule => self.skip_reasons[5],
+            SkipReason::TooShort => self.skip_reasons[6],
+            SkipReason::LowVisibility => self.skip_reasons[7],
+            SkipReason::TooCold => self.skip_reasons[8],
+            SkipReason::HighWaves => self.skip_reasons[9],
+            SkipReason::HighTide => self.skip_reasons[10],
+            SkipReason::SwitchyWind => self.skip_reasons[11],
+            SkipReason::WetGround => self.skip_reasons[12],
+            SkipReason::Closed => self.skip_reasons[13],
+            SkipReason::Snow => self.skip_reasons[14],
+            SkipReason::HighFreezingLevel => self.skip_reasons[15],
+        }
+    }
+
+    pub fn miss(&self, check: Check) -> &'static str {
+        match check {
+            Check::ChanceOfRain => self.misses[0],
+            Check::Rain => self.misses[1],
+            Check::MaxWind => self.misses[2],
+            Check::MinWind => self.misses[3],
+            Check::Gusts => self.misses[4],
+            Check::Sector => self.misses[5],
+            Check::Visibility => self.misses[6],
+            Check::FeelsLike => self.misses[7],
+            Check::Waves => self.misses[8],
+            Check::Tide => self.misses[9],
+            Check::SnowDepth => self.misses[10],
+            Check::Snowfall => self.misses[11],
+            Check::FreezingLevel => self.misses[12],
+            Check::PriorRain => self.misses[13],
+            Check::DirectionChange => self.misses[14],
+        }
+    }
+
+    /// The WHO exposure category of a UV index.
+    pub fn uv_level(&self, uvi: f32) -> &'static str {
+        let level = match uvi.round() as i32 {
+            i32::MIN..=2 => 0,
+            3..=5 => 1,
+            6..=7 => 2,
+            8..=10 => 3,
+            _ => 4,
+        };
+        self.uv_levels[level]
+    }
+
+    pub fn wind_trend(&self, trend: WindTrend) -> &'static str {
+        match trend {
+            WindTrend::Strengthening => self.wind_trends[0],
+            WindTrend::BackingOff => self.wind_trends[1],
+        }
+    }
+
+    pub fn forecast_trend(&self, trend: WindTrend) -> &'static str {
+        match trend {
+            WindTrend::Strengthening => self.forecast_trends[0],
+            WindTrend::BackingOff => self.forecast_trends[1],
+        }
+    }
+
+    pub fn thermal_quality(&self, quality: ThermalQuality) -> &'static str {
+        match quality {
+            ThermalQuality::Stable => self.thermal_qualities[0],
+            ThermalQuality::Weak => self.thermal_qualities[1],
+            ThermalQuality::Moderate => self.thermal_qualities[2],
+            ThermalQuality::Strong => self.thermal_qualities[3],
+        }
+    }
+
+    pub fn confidence_level(&self, confidence: Confidence) -> &'static str {
+        match confidence {
+            Confidence::Low => self.confidence_levels[0],
+            Confidence::Medium => self.confidence_levels[1],
+            Confidence::High => self.confidence_levels[2],
+        }
+    }
+
+    pub fn soaring_window(&self, window: SoaringWindow) -> &'static str {
+        match window {
+            SoaringWindow::MorningSledder => self.soaring_windows[0],
+            SoaringWindow::StrongThermic => self.soaring_windows[1],
+            SoaringWindow::Thermic => self.soaring_windows[2],
+            SoaringWindow::Ridge => self.soaring_windows[3],
+            SoaringWindow::SmoothEvening => self.soaring_windows[4],
+        }
+    }
+}
+
+/// Replaces the `{name}` placeholders of `phrase` with their values.
+pub fn fill(phrase: &str, values: &[(&str, &str)]) -> String {
+    let mut text = String::from(phrase);
+    for (name, value) in values {
+        text = text.replace(&format!("{{{}}}", name), value);
+    }
+    text
+}
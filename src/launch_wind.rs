@@ -0,0 +1,174 @@
+use crate::config::FlyingSite;
+use crate::forecast_client::DayWeatherForecast;
+use crate::http::HttpClient;
+use crate::measures::{WindDirection, WindSpeed};
+use reqwest::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Levels around the altitudes of most launches, ~750 m to ~3000 m.
+const PRESSURE_LEVELS_HPA: [u16; 3] = [925, 850, 700];
+
+#[derive(Deserialize, Debug)]
+struct LevelWindHourly {
+    time: Vec<i64>,
+    #[serde(flatten)]
+    levels: HashMap<String, Vec<Option<f32>>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LevelWindForecast {
+    hourly: LevelWindHourly,
+}
+
+/// The wind at a pressure level for a single forecast hour.
+#[derive(Debug, Clone, Copy)]
+pub struct LevelWind {
+    pub pressure_hpa: u16,
+    /// Geopotential height in meters AMSL.
+    pub height_m: f32,
+    pub speed: WindSpeed,
+    pub direction: WindDirection,
+}
+
+/// Fetches the winds aloft from Open-Meteo, the 10 m wind of the main
+/// provider underestimating the conditions at high launches.
+pub struct LaunchWindClient {
+    url: String,
+    /// Share of the wind aloft in the blended wind, 1 ignoring the surface.
+    blend: f32,
+    http: HttpClient,
+}
+
+impl LaunchWindClient {
+    pub fn new(url: String, blend: f32, http: HttpClient) -> Self {
+        LaunchWindClient { url, blend, http }
+    }
+
+    pub fn name(&self) -> &'static str {
+        "Open-Meteo"
+    }
+
+    /// The winds at `PRESSURE_LEVELS_HPA`, lowest first, keyed by unix
+    /// timestamp of the forecast hour.
+    pub async fn get_level_winds(
+        &self,
+        lat: f64,
+        lon: f64,
+    ) -> Result<HashMap<i64, Vec<LevelWind>>> {
+        let mut hourly = vec![];
+        for level in PRESSURE_LEVELS_HPA.iter() {
+            hourly.push(format!("wind_speed_{}hPa", level));
+            hourly.push(format!("wind_direction_{}hPa", level));
+            hourly.push(format!("geopotential_height_{}hPa", level));
+        }
+        let (lat, lon, hourly) = (lat.to_string(), lon.to_string(), hourly.join(","));
+        let raw_forecast: LevelWindForecast = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("latitude", &lat[..]),
+                    ("longitude", &lon[..]),
+                    ("hourly", &hourly[..]),
+                    ("wind_speed_unit", "ms"),
+                    ("timeformat", "unixtime"),
+                    ("forecast_days", "3"),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+
+        let series = |name: String, i: usize| {
+            raw_forecast
+                .hourly
+                .levels
+                .get(&name)
+                .and_then(|values| values.get(i).copied().flatten())
+        };
+        let mut level_winds = HashMap::new();
+        for (i, time) in raw_forecast.hourly.time.iter().enumerate() {
+            let mut winds = vec![];
+            for level in PRESSURE_LEVELS_HPA.iter() {
+                let wind = (
+                    series(format!("wind_speed_{}hPa", level), i),
+                    series(format!("wind_direction_{}hPa", level), i),
+                    series(format!("geopotential_height_{}hPa", level), i),
+                );
+                if let (Some(speed), Some(deg), Some(height_m)) = wind {
+                    winds.push(LevelWind {
+                        pressure_hpa: *level,
+                        height_m,
+                        speed: WindSpeed::MPS(speed),
+                        direction: WindDirection::new(deg.round() as i16),
+                    });
+                }
+            }
+            level_winds.insert(*time, winds);
+        }
+        Ok(level_winds)
+    }
+
+    /// Replaces the wind of the hours with the wind at the level nearest to
+    /// the takeoff of `site`, blended with the surface wind, which is kept
+    /// in `surface_wind_speed`. Sites without `elevation_m` are left alone.
+    pub fn apply_launch_wind(&self, forecasts: &mut [DayWeatherForecast], site: &FlyingSite) {
+        let altitude = match site.elevation_m {
+            Some(altitude) => altitude,
+            None => return,
+        };
+        for hour in forecasts.iter_mut().flat_map(|day| day.hourly.iter_mut()) {
+            let nearest = hour.level_winds.iter().min_by(|a, b| {
+                let distance = |level: &LevelWind| (level.height_m - altitude).abs();
+                distance(a).partial_cmp(&distance(b)).unwrap()
+            });
+            if let Some(level) = nearest {
+                let (speed, direction) = blend(
+                    (hour.wind_speed, hour.wind_direction),
+                    (level.speed, level.direction),
+                    self.blend,
+                );
+                hour.surface_wind_speed = Some(hour.wind_speed);
+                hour.wind_speed = speed;
+                hour.wind_direction = direction;
+            }
+        }
+    }
+}
+
+/// Speeds are blended linearly and directions as unit vectors, so that
+/// blending 350° and 10° gives 0°.
+fn blend(
+    surface: (WindSpeed, WindDirection),
+    aloft: (WindSpeed, WindDirection),
+    weight: f32,
+) -> (WindSpeed, WindDirection) {
+    let speed =
+        surface.0.meters_per_second() * (1.0 - weight) + aloft.0.meters_per_second() * weight;
+    let vector = |direction: WindDirection, weight: f32| {
+        let radians = (direction.degrees() as f32).to_radians();
+        (radians.sin() * weight, radians.cos() * weight)
+    };
+    let (x1, y1) = vector(surface.1, 1.0 - weight);
+    let (x2, y2) = vector(aloft.1, weight);
+    let deg = (x1 + x2).atan2(y1 + y2).to_degrees().round() as i16;
+    (WindSpeed::MPS(speed), WindDirection::new(deg))
+}
+
+/// Attaches the winds aloft to the matching hours of a forecast.
+pub fn apply_level_winds(
+    forecasts: &mut [DayWeatherForecast],
+    level_winds: &HashMap<i64, Vec<LevelWind>>,
+) {
+    for day in forecasts.iter_mut() {
+        for hour in day.hourly.iter_mut() {
+            hour.level_winds = level_winds
+                .get(&hour.time.timestamp())
+                .cloned()
+                .unwrap_or_default();
+        }
+    }
+}
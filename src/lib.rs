@@ -0,0 +1,3091 @@
+//! Flyability reports of paragliding sites from weather forecasts, and the
+//! notifiers delivering them.
+//!
+//! `prepare_report_for_site` turns a forecast into the report of a site for
+//! a day. `ForecastSources` fetches the forecasts, which `check_sites`
+//! combines into the reports of a run for `send_notifications`.
+
+pub mod admin;
+pub mod air_quality;
+pub mod airspace;
+pub mod backtest;
+pub mod bot;
+pub mod cache;
+#[cfg(feature = "cassette")]
+pub mod cassette;
+pub mod charts;
+pub mod config;
+pub mod daemon;
+pub mod dashboard;
+pub mod digest;
+pub mod ensemble;
+pub mod error;
+pub mod export;
+pub mod forecast_client;
+pub mod geocoding;
+pub mod heartbeat;
+pub mod http;
+pub mod i18n;
+pub mod launch_wind;
+pub mod logging;
+pub mod marine;
+pub mod measures;
+pub mod message;
+pub mod notification;
+pub mod paragliding_earth;
+pub mod quota;
+pub mod rate_limit;
+pub mod retry;
+pub mod rule;
+pub mod scan;
+pub mod scoring;
+pub mod server;
+pub mod solar;
+pub mod sounding;
+pub mod state_dump;
+pub mod stations;
+pub mod store;
+pub mod systemd;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod thermal;
+pub mod tides;
+pub mod timezone;
+pub mod waypoints;
+pub mod winter;
+
+use crate::air_quality::AirQualityClient;
+use crate::airspace::{ActiveNotam, NotamClient};
+use crate::cache::ResponseCache;
+use crate::config::{
+    ApplicationConfig, DaylightConfig, FlyingSite, MessageStyle, ReportOrder, SiteType, Target,
+};
+use crate::ensemble::{Confidence, EnsembleClient, Spread};
+use crate::error::ProviderError;
+use crate::forecast_client::{
+    DayOutlook, DayWeatherForecast, ForecastProvider, HourWeatherForecast, MissingData,
+    OpenWeatherMapClient, TimeOfDay, WeatherAlert,
+};
+use crate::http::HttpClient;
+use crate::i18n::Strings;
+use crate::launch_wind::LaunchWindClient;
+use crate::marine::{MarineClient, Waves};
+use crate::measures::{
+    DirectionRange, Precipitation, Pressure, Temperature, Units, Visibility, WindSpeed,
+};
+use crate::message::MessageFormat;
+use crate::notification::{NotifyError, RoutedNotifier};
+use crate::quota::Quotas;
+use crate::scoring::ScoringModel;
+use crate::sounding::SoundingClient;
+use crate::stations::{
+    AmbientClient, EcowittClient, FfvlClient, HolfuyClient, LiveReading, StationSource,
+};
+use crate::store::{ForecastRun, PastPeriod, StateStore};
+use crate::thermal::{OpenMeteoClient, ThermalQuality};
+use crate::tides::TideClient;
+use crate::winter::WinterClient;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveTime, Timelike, Utc};
+
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use tracing::Instrument;
+
+impl FlyingSite {
+    fn is_flyable(&self, hour: &HourWeatherForecast) -> bool {
+        if let Some(rule) = &self.rule {
+            return rule.evaluate(hour);
+        }
+        self.flies_at(hour.time_of_day) && self.unflyable_reason(hour).is_none()
+    }
+
+    /// Whether the wind turns more than `max_direction_change` an hour
+    /// since the `previous` hour or step, too switchy to fly.
+    fn is_switchy(
+        &self,
+        hour: &HourWeatherForecast,
+        previous: Option<&HourWeatherForecast>,
+    ) -> bool {
+        match (self.max_direction_change, previous) {
+            (Some(max), Some(previous)) => hourly_turn(hour, previous) > max,
+            _ => false,
+        }
+    }
+
+    /// Whether the wind picks up more than `max_wind_increase` an hour
+    /// since the `previous` hour or step, ending the period.
+    fn is_picking_up(&self, hour: &HourWeatherForecast, previous: &HourWeatherForecast) -> bool {
+        self.max_wind_increase.is_some_and(|max| {
+            let increase =
+                hour.wind_speed.meters_per_second() - previous.wind_speed.meters_per_second();
+            increase * steps_per_hour(hour, previous) > max.meters_per_second()
+        })
+    }
+
+    fn max_pop(&self) -> f32 {
+        self.max_pop.unwrap_or(DEFAULT_MAX_POP)
+    }
+
+    /// Whether `gust` is above `max_gust`, unknown gusts never being.
+    fn is_too_gusty(&self, gust: Option<WindSpeed>) -> bool {
+        self.max_gust
+            .is_some_and(|max| gust.is_some_and(|gust| gust > max))
+    }
+
+    /// Whether the site is flown at this time of day, twilight only for
+    /// sites with `fly_twilight`.
+    fn flies_at(&self, time_of_day: TimeOfDay) -> bool {
+        time_of_day == TimeOfDay::DAY || self.fly_twilight && time_of_day == TimeOfDay::TWILIGHT
+    }
+
+    /// The first check a daylight `hour` fails, if any.
+    fn unflyable_reason(&self, hour: &HourWeatherForecast) -> Option<SkipReason> {
+        if let Some(rule) = &self.rule {
+            return (!rule.evaluate(hour)).then_some(SkipReason::Rule);
+        }
+        if hour.pop > self.max_pop() || self.max_rain.is_some_and(|max| hour.rain > Some(max)) {
+            Some(SkipReason::Rain)
+        } else if hour.wind_speed > self.max_flyable_wind() || self.is_too_gusty(hour.wind_gust) {
+            Some(SkipReason::TooWindy)
+        } else if self.min_flyable_wind() > hour.wind_speed {
+            Some(SkipReason::TooLight)
+        } else if !self
+            .wind_sectors
+            .iter()
+            .any(|s| s.contains(hour.wind_direction))
+        {
+            Some(SkipReason::WrongDirection)
+        } else if self
+            .min_visibility
+            .is_some_and(|min| hour.visibility.is_some_and(|visibility| visibility < min))
+        {
+            Some(SkipReason::LowVisibility)
+        } else if self.min_feels_like.is_some_and(|min| hour.feels_like < min) {
+            Some(SkipReason::TooCold)
+        } else if self
+            .max_wave_height
+            .is_some_and(|max| hour.waves.is_some_and(|waves| waves.height > max))
+        {
+            Some(SkipReason::HighWaves)
+        } else if self
+            .max_tide_height_m
+            .is_some_and(|max| hour.tide_m.is_some_and(|tide| tide > max))
+        {
+            Some(SkipReason::HighTide)
+        } else if self
+            .max_prior_rain_mm
+            .is_some_and(|max| hour.prior_rain_mm.is_some_and(|rain| rain > max))
+        {
+            Some(SkipReason::WetGround)
+        } else if self
+            .max_snow_depth_m
+            .is_some_and(|max| hour.snow_depth_m.is_some_and(|depth| depth > max))
+            || self.max_snowfall.is_some_and(|max| hour.snow > Some(max))
+        {
+            Some(SkipReason::Snow)
+        } else if self
+            .max_freezing_level_m
+            .is_some_and(|max| hour.freezing_level_m.is_some_and(|level| level > max))
+        {
+            Some(SkipReason::HighFreezingLevel)
+        } else {
+            None
+        }
+    }
+
+    /// The first check the daily forecast of a day fails, if any. Site rules
+    /// need the hours and are never met.
+    fn outlook_unflyable_reason(&self, outlook: &DayOutlook) -> Option<SkipReason> {
+        if self.rule.is_some() {
+            Some(SkipReason::Rule)
+        } else if outlook.pop > self.max_pop() {
+            Some(SkipReason::Rain)
+        } else if outlook.wind_speed > self.max_flyable_wind()
+            || self.is_too_gusty(outlook.wind_gust)
+        {
+            Some(SkipReason::TooWindy)
+        } else if self.min_flyable_wind() > outlook.wind_speed {
+            Some(SkipReason::TooLight)
+        } else if !self
+            .wind_sectors
+            .iter()
+            .any(|s| s.contains(outlook.wind_direction))
+        {
+            Some(SkipReason::WrongDirection)
+        } else {
+            None
+        }
+    }
+
+    /// The flown hour of `date` still to come failing the checks by the
+    /// least, with the checks it fails. Misses are weighed by how far they
+    /// are past their limit, relative to it. None for site rules and
+    /// outlooks.
+    fn closest_miss(
+        &self,
+        forecasts: &[DayWeatherForecast],
+        date: NaiveDate,
+    ) -> Option<ClosestMiss> {
+        if self.rule.is_some() {
+            return None;
+        }
+        let hours = &forecasts.iter().find(|day| day.date == date)?.hourly;
+        let now = Utc::now();
+        let mut closest: Option<(f32, ClosestMiss)> = None;
+        for (i, hour) in hours.iter().enumerate() {
+            if !self.flies_at(hour.time_of_day) || hour.time + hour.duration <= now {
+                continue;
+            }
+            let previous = i.checked_sub(1).map(|i| &hours[i]);
+            let misses = self.misses(hour, previous);
+            if misses.is_empty() {
+                continue;
+            }
+            let distance: f32 = misses.iter().map(|(distance, _)| distance).sum();
+            if closest
+                .as_ref()
+                .is_none_or(|(closest, _)| distance < *closest)
+            {
+                let misses = misses.into_iter().map(|(_, miss)| miss).collect();
+                closest = Some((
+                    distance,
+                    ClosestMiss {
+                        time: hour.time,
+                        misses,
+                    },
+                ));
+            }
+        }
+        closest.map(|(_, closest)| closest)
+    }
+
+    /// Every check `hour` fails, with how far past its limit relative to
+    /// the limit.
+    fn misses(
+        &self,
+        hour: &HourWeatherForecast,
+        previous: Option<&HourWeatherForecast>,
+    ) -> Vec<(f32, Miss)> {
+        let relative = |excess: f32, limit: f32| excess / limit.abs().max(f32::EPSILON);
+        let miss = |check: Check, value: f32, limit: f32| Miss {
+            check,
+            value,
+            limit,
+        };
+        let mps = |speed: WindSpeed| speed.meters_per_second();
+        let mut misses = vec![];
+        let max_pop = self.max_pop();
+        if hour.pop > max_pop {
+            misses.push((
+                relative(hour.pop - max_pop, max_pop),
+                miss(Check::ChanceOfRain, hour.pop * 100.0, max_pop * 100.0),
+            ));
+        }
+        if let (Some(max), Some(rain)) = (self.max_rain, hour.rain) {
+            if rain > max {
+                misses.push((
+                    relative(rain.millimeters() - max.millimeters(), max.millimeters()),
+                    miss(Check::Rain, rain.millimeters(), max.millimeters()),
+                ));
+            }
+        }
+        let (speed, max, min) = (
+            hour.wind_speed,
+            self.max_flyable_wind(),
+            self.min_flyable_wind(),
+        );
+        if speed > max {
+            misses.push((
+                relative(mps(speed) - mps(max), mps(max)),
+                miss(Check::MaxWind, mps(speed), mps(max)),
+            ));
+        }
+        if min > speed {
+            misses.push((
+                relative(mps(min) - mps(speed), mps(min)),
+                miss(Check::MinWind, mps(speed), mps(min)),
+            ));
+        }
+        if let (Some(max), Some(gust)) = (self.max_gust, hour.wind_gust) {
+            if gust > max {
+                misses.push((
+                    relative(mps(gust) - mps(max), mps(max)),
+                    miss(Check::Gusts, mps(gust), mps(max)),
+                ));
+            }
+        }
+        let off_sector = self
+            .wind_sectors
+            .iter()
+            .map(|sector| sector.distance(hour.wind_direction))
+            .min()
+            .unwrap_or(0);
+        if off_sector > 0 {
+            misses.push((
+                relative(off_sector as f32, MISS_SECTOR_DEGREES),
+                miss(
+                    Check::Sector,
+                    hour.wind_direction.degrees() as f32,
+                    off_sector as f32,
+                ),
+            ));
+        }
+        if let (Some(min), Some(visibility)) = (self.min_visibility, hour.visibility) {
+            if visibility < min {
+                misses.push((
+                    relative(min.kilometers() - visibility.kilometers(), min.kilometers()),
+                    miss(Check::Visibility, visibility.kilometers(), min.kilometers()),
+                ));
+            }
+        }
+        if let Some(min) = self.min_feels_like.filter(|min| hour.feels_like < *min) {
+            let colder = min.celsius() - hour.feels_like.celsius();
+            misses.push((
+                relative(colder, MISS_FEELS_LIKE_C),
+                miss(Check::FeelsLike, hour.feels_like.celsius(), min.celsius()),
+            ));
+        }
+        if let (Some(max), Some(waves)) = (self.max_wave_height, hour.waves) {
+            if waves.height > max {
+                misses.push((
+                    relative(waves.height.meters() - max.meters(), max.meters()),
+                    miss(Check::Waves, waves.height.meters(), max.meters()),
+                ));
+            }
+        }
+        if let (Some(max), Some(tide)) = (self.max_tide_height_m, hour.tide_m) {
+            if tide > max {
+                misses.push((relative(tide - max, max), miss(Check::Tide, tide, max)));
+            }
+        }
+        if let (Some(max), Some(depth)) = (self.max_snow_depth_m, hour.snow_depth_m) {
+            if depth > max {
+                misses.push((
+                    relative(depth - max, max),
+                    miss(Check::SnowDepth, depth * 100.0, max * 100.0),
+                ));
+            }
+        }
+        if let (Some(max), Some(snow)) = (self.max_snowfall, hour.snow) {
+            if snow > max {
+                misses.push((
+                    relative(snow.millimeters() - max.millimeters(), max.millimeters()),
+                    miss(Check::Snowfall, snow.millimeters(), max.millimeters()),
+                ));
+            }
+        }
+        if let (Some(max), Some(level)) = (self.max_freezing_level_m, hour.freezing_level_m) {
+            if level > max {
+                misses.push((
+                    relative(level - max, max),
+                    miss(Check::FreezingLevel, level, max),
+                ));
+            }
+        }
+        if let (Some(max), Some(rain)) = (self.max_prior_rain_mm, hour.prior_rain_mm) {
+            if rain > max {
+                misses.push((relative(rain - max, max), miss(Check::PriorRain, rain, max)));
+            }
+        }
+        if let (Some(max), Some(previous)) = (self.max_direction_change, previous) {
+            let change = hourly_turn(hour, previous);
+            if change > max {
+                misses.push((
+                    relative((change - max) as f32, max as f32),
+                    miss(Check::DirectionChange, change as f32, max as f32),
+                ));
+            }
+        }
+        misses
+    }
+
+    /// The reason most flown hours of the day are not flyable.
+    fn main_unflyable_reason(&self, hours: &[HourWeatherForecast]) -> SkipReason {
+        let mut counts: BTreeMap<SkipReason, usize> = BTreeMap::new();
+        for (i, hour) in hours.iter().enumerate() {
+            if !self.flies_at(hour.time_of_day) {
+                continue;
+            }
+            let previous = i.checked_sub(1).map(|i| &hours[i]);
+            let reason = self.unflyable_reason(hour).or_else(|| {
+                self.is_switchy(hour, previous)
+                    .then_some(SkipReason::SwitchyWind)
+            });
+            if let Some(reason) = reason {
+                *counts.entry(reason).or_default() += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map_or(SkipReason::NoForecast, |(reason, _)| reason)
+    }
+}
+
+/// Why a site has no report for the day.
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    NoForecast,
+    Rain,
+    TooWindy,
+    TooLight,
+    WrongDirection,
+    /// The site's custom rule.
+    Rule,
+    /// Flyable hours never add up to `min_period_hours`.
+    TooShort,
+    /// Below the site's `min_visibility`.
+    LowVisibility,
+    /// Feels colder than the site's `min_feels_like`.
+    TooCold,
+    /// Waves above the site's `max_wave_height`.
+    HighWaves,
+    /// Tide above the site's `max_tide_height_m`.
+    HighTide,
+    /// Direction turning more than the site's `max_direction_change`.
+    SwitchyWind,
+    /// More rain over the hours before than the site's
+    /// `max_prior_rain_mm`.
+    WetGround,
+    /// On a day of one of the site's `closures`.
+    Closed,
+    /// Above the site's `max_snow_depth_m` or `max_snowfall`.
+    Snow,
+    /// Above the site's `max_freezing_level_m`.
+    HighFreezingLevel,
+}
+
+/// A site left out of a run, along with the reason.
+#[derive(Debug, Clone)]
+pub struct SkippedSite {
+    pub site: FlyingSite,
+    pub days_ahead: i64,
+    pub reason: SkipReason,
+    /// The flown hour closest to being flyable and how it misses, see
+    /// `FlyingSite::closest_miss`.
+    pub closest_miss: Option<ClosestMiss>,
+}
+
+/// A check of the flown hours, see `Miss`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Check {
+    /// In percent.
+    ChanceOfRain,
+    /// In mm per hour.
+    Rain,
+    /// In m/s.
+    MaxWind,
+    /// In m/s.
+    MinWind,
+    /// In m/s.
+    Gusts,
+    /// The direction the wind blows from, against how many degrees it is
+    /// out of the sectors.
+    Sector,
+    /// In km.
+    Visibility,
+    /// In °C.
+    FeelsLike,
+    /// In meters.
+    Waves,
+    /// In meters.
+    Tide,
+    /// In cm.
+    SnowDepth,
+    /// In mm per hour.
+    Snowfall,
+    /// In meters AMSL.
+    FreezingLevel,
+    /// In mm over `forecast_client::PRIOR_RAIN_HOURS`.
+    PriorRain,
+    /// In degrees per hour.
+    DirectionChange,
+}
+
+/// A check an hour fails: the forecast value and the limit it misses, in
+/// the unit of the `check`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Miss {
+    pub check: Check,
+    pub value: f32,
+    pub limit: f32,
+}
+
+impl Miss {
+    /// E.g. "wind 8.2 m/s (max 7.0)", in the language of `strings` with
+    /// winds and temperatures in `units`.
+    pub fn describe(&self, strings: &Strings, units: Units) -> String {
+        let (value, limit, unit) = match self.check {
+            Check::MaxWind | Check::MinWind | Check::Gusts => (
+                WindSpeed::MPS(self.value).format(units.wind),
+                WindSpeed::MPS(self.limit).format(units.wind),
+                units.wind.label(),
+            ),
+            Check::FeelsLike => (
+                format!(
+                    "{:.1}",
+                    Temperature::C(self.value).in_unit(units.temperature)
+                ),
+                format!(
+                    "{:.1}",
+                    Temperature::C(self.limit).in_unit(units.temperature)
+                ),
+                units.temperature.label(),
+            ),
+            Check::ChanceOfRain
+            | Check::Sector
+            | Check::SnowDepth
+            | Check::FreezingLevel
+            | Check::DirectionChange => (
+                format!("{:.0}", self.value),
+                format!("{:.0}", self.limit),
+                "",
+            ),
+            _ => (
+                format!("{:.1}", self.value),
+                format!("{:.1}", self.limit),
+                "",
+            ),
+        };
+        i18n::fill(
+            strings.miss(self.check),
+            &[
+                ("value", &value),
+                ("limit", &limit),
+                ("unit", unit),
+                ("hours", &forecast_client::PRIOR_RAIN_HOURS.to_string()),
+            ],
+        )
+    }
+}
+
+/// The flown hour closest to being flyable, with the checks it fails.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosestMiss {
+    pub time: DateTime<FixedOffset>,
+    pub misses: Vec<Miss>,
+}
+
+impl ClosestMiss {
+    /// E.g. "14:00, wind from 298°, 2° out of the sectors".
+    pub fn describe(&self, strings: &Strings, units: Units) -> String {
+        let mut parts = vec![self.time.format("%H:%M").to_string()];
+        parts.extend(self.misses.iter().map(|miss| miss.describe(strings, units)));
+        parts.join(", ")
+    }
+}
+
+/// A site that could not be checked, for operators rather than pilots.
+#[derive(Debug, Clone)]
+pub struct SiteError {
+    pub site: String,
+    pub error: String,
+}
+
+/// What a notifier was handed by `send_notifications`.
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    /// The `type` of the notifier in the config.
+    pub notifier: &'static str,
+    pub reports: usize,
+    /// Whether it was told that no site is flyable.
+    pub nothing_flyable: bool,
+    /// Why it failed, failing the run once the other notifiers are done.
+    pub error: Option<String>,
+    /// Time taken to send, deferred messages included.
+    pub elapsed: Duration,
+}
+
+/// The outcome of checking the sites of a run.
+pub struct CheckedSites {
+    pub reports: Vec<SiteFlyAbilityReport>,
+    /// Flyable reports of the pilot profiles of the sites, kept apart from
+    /// `reports` for the notifiers asking for them.
+    pub profile_reports: Vec<SiteFlyAbilityReport>,
+    /// Failed sites are skipped as without forecast.
+    pub skipped: Vec<SkippedSite>,
+    pub errors: Vec<SiteError>,
+}
+
+impl CheckedSites {
+    pub fn all_failed(&self) -> bool {
+        !self.errors.is_empty() && self.errors.len() == self.reports.len() + self.skipped.len()
+    }
+
+    /// Which sites failed and why, `None` when every site was checked.
+    pub fn error_summary(&self) -> Option<String> {
+        if self.errors.is_empty() {
+            return None;
+        }
+        let mut summary = format!(
+            "{} of {} sites could not be checked:",
+            self.errors.len(),
+            self.reports.len() + self.skipped.len()
+        );
+        for error in &self.errors {
+            summary.push_str(&format!("\n{}: {}", error.site, error.error));
+        }
+        Some(summary)
+    }
+}
+
+// Chance of rain above which hours are not flyable, unless the site sets
+// `max_pop`.
+const DEFAULT_MAX_POP: f32 = 0.3;
+// Convective cloud base rises ~125 m per °C of temperature/dew point spread.
+const CLOUD_BASE_M_PER_SPREAD_C: f32 = 125.0;
+// Difference between the felt and the actual temperature worth mentioning.
+const FEELS_LIKE_DIVERGENCE_C: f32 = 3.0;
+// Change of the wind from the first to the last hour of a period worth an
+// arrow.
+const WIND_TREND_MPS: f32 = 2.0;
+// Share of a site's `max_prior_rain_mm` from which the ground is noted as
+// wet.
+const WET_GROUND_SHARE: f32 = 0.5;
+// Change of the forecast daytime wind across runs worth mentioning.
+const FORECAST_TREND_MPS: f64 = 1.0;
+// Misses out of the wind sectors and below `min_feels_like` are weighed
+// against the other misses as if these were their limits.
+const MISS_SECTOR_DEGREES: f32 = 45.0;
+const MISS_FEELS_LIKE_C: f32 = 10.0;
+// Bars of the wind sparkline, from calm to the strongest wind.
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+// Wind from which a slope gives ridge lift, and below which it is light.
+const RIDGE_WIND_MPS: f32 = 4.0;
+// Local hours of the soaring windows: mornings end, thermals peak between
+// the midday hours, evenings start.
+const MORNING_END_HOUR: i64 = 10;
+const MIDDAY_HOURS: (i64, i64) = (11, 16);
+const EVENING_START_HOUR: i64 = 16;
+
+fn cloud_base_agl(hour: &HourWeatherForecast) -> f32 {
+    let spread = hour.temperature.celsius() - hour.dew_point.celsius();
+    spread.max(0.0) * CLOUD_BASE_M_PER_SPREAD_C
+}
+
+/// How the wind changes over a period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindTrend {
+    Strengthening,
+    BackingOff,
+}
+
+impl WindTrend {
+    pub fn arrow(&self) -> &'static str {
+        match self {
+            WindTrend::Strengthening => "↗",
+            WindTrend::BackingOff => "↘",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WindTrend::Strengthening => "strengthening",
+            WindTrend::BackingOff => "backing_off",
+        }
+    }
+}
+
+/// The kind of flying a period offers, from its wind, time of day and
+/// thermals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoaringWindow {
+    /// Light morning wind before the thermals, a sled ride down.
+    MorningSledder,
+    /// Strong midday thermals, for experienced pilots.
+    StrongThermic,
+    /// Usable midday thermals.
+    Thermic,
+    /// Wind strong enough for ridge lift all along.
+    Ridge,
+    /// Light wind late in the day, smooth soaring as thermals die down.
+    SmoothEvening,
+}
+
+impl SoaringWindow {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SoaringWindow::MorningSledder => "morning_sledder",
+            SoaringWindow::StrongThermic => "strong_thermic",
+            SoaringWindow::Thermic => "thermic",
+            SoaringWindow::Ridge => "ridge",
+            SoaringWindow::SmoothEvening => "smooth_evening",
+        }
+    }
+}
+
+/// How the forecast daytime wind of a day changed from the oldest to the
+/// latest of `runs`, when notably, along with the change.
+pub fn forecast_trend(runs: &[ForecastRun]) -> Option<(WindTrend, WindSpeed)> {
+    let change = runs.last()?.wind_avg_mps - runs.first()?.wind_avg_mps;
+    let trend = if change >= FORECAST_TREND_MPS {
+        WindTrend::Strengthening
+    } else if change <= -FORECAST_TREND_MPS {
+        WindTrend::BackingOff
+    } else {
+        return None;
+    };
+    Some((trend, WindSpeed::MPS(change as f32)))
+}
+
+/// The daytime wind of `day`, `None` without hours between sunrise and
+/// sunset.
+fn forecast_run(day: &DayWeatherForecast, fetched_at: i64) -> Option<ForecastRun> {
+    let hours: Vec<&HourWeatherForecast> = day
+        .hourly
+        .iter()
+        .filter(|hour| day.sunrise <= hour.time && hour.time < day.sunset)
+        .collect();
+    if hours.is_empty() {
+        return None;
+    }
+    let winds: Vec<f64> = hours
+        .iter()
+        .map(|hour| hour.wind_speed.meters_per_second() as f64)
+        .collect();
+    Some(ForecastRun {
+        fetched_at,
+        wind_avg_mps: winds.iter().sum::<f64>() / winds.len() as f64,
+        wind_max_mps: winds.iter().cloned().fold(f64::MIN, f64::max),
+        gust_max_mps: hours
+            .iter()
+            .filter_map(|hour| hour.wind_gust)
+            .map(|gust| gust.meters_per_second() as f64)
+            .reduce(f64::max),
+    })
+}
+
+/// Consecutive flyable hours of a site, or steps of them once subdivided.
+#[derive(Debug, Clone)]
+pub struct SiteFlyablePeriod {
+    pub start: DateTime<FixedOffset>,
+    pub duration: Duration,
+    pub wind_min: WindSpeed,
+    pub wind_max: WindSpeed,
+    /// Wind of the first and the last hour, for the trend.
+    pub wind_first: WindSpeed,
+    pub wind_last: WindSpeed,
+    /// Wraps across north for winds around it, e.g. 340°–20°.
+    pub wind_directions: DirectionRange,
+    pub temp_min: Temperature,
+    pub temp_max: Temperature,
+    pub feels_like_min: Temperature,
+    pub feels_like_max: Temperature,
+    pub pop_max: f32,
+    /// Sums over the hours, steps counting for their share of an hour.
+    clouds_total: f32,
+    direction_offset_total: f32,
+    lapse_rate_total: f32,
+    lapse_rate_hours: i64,
+    /// Time of the period with usable thermals.
+    thermal_duration: Duration,
+    /// Spreads of the hours times their share of an hour, and the hours
+    /// with one.
+    wind_spread_total: f32,
+    direction_spread_total: f32,
+    spread_hours: f32,
+    pub cloud_base_min_agl: f32,
+    pub thermal_top_max: Option<f32>,
+    /// Strongest 10 m wind when the wind is the one at launch altitude.
+    pub surface_wind_max: Option<WindSpeed>,
+    pub visibility_min: Option<Visibility>,
+    pub rain_max: Option<Precipitation>,
+    pub pressure_min: Option<Pressure>,
+    pub humidity_max: Option<u8>,
+    pub uvi_max: Option<f32>,
+    /// The hour with the highest waves, for coastal sites.
+    pub waves_max: Option<Waves>,
+    /// Meters AMSL, see `winter`.
+    pub freezing_level_min: Option<f32>,
+    /// Meters of snow on the ground.
+    pub snow_depth_max: Option<f32>,
+    pub snow_max: Option<Precipitation>,
+}
+
+impl SiteFlyablePeriod {
+    fn from_hour(hour: &HourWeatherForecast, site: &FlyingSite) -> Self {
+        let mut period = Self {
+            start: hour.time,
+            duration: hour.duration,
+            wind_min: hour.wind_speed,
+            wind_max: hour.wind_speed,
+            wind_first: hour.wind_speed,
+            wind_last: hour.wind_speed,
+            wind_directions: DirectionRange::new(hour.wind_direction),
+            temp_min: hour.temperature,
+            temp_max: hour.temperature,
+            feels_like_min: hour.feels_like,
+            feels_like_max: hour.feels_like,
+            pop_max: hour.pop,
+            clouds_total: hour.clouds as f32 * hours(hour.duration),
+            direction_offset_total: site.direction_offset(hour.wind_direction)
+                * hours(hour.duration),
+            lapse_rate_total: 0.0,
+            lapse_rate_hours: 0,
+            thermal_duration: Duration::zero(),
+            wind_spread_total: 0.0,
+            direction_spread_total: 0.0,
+            spread_hours: 0.0,
+            cloud_base_min_agl: cloud_base_agl(hour),
+            thermal_top_max: hour.thermal_top_m,
+            surface_wind_max: hour.surface_wind_speed,
+            visibility_min: hour.visibility,
+            rain_max: hour.rain,
+            pressure_min: hour.pressure,
+            humidity_max: hour.humidity,
+            uvi_max: hour.uvi,
+            waves_max: hour.waves,
+            freezing_level_min: hour.freezing_level_m,
+            snow_depth_max: hour.snow_depth_m,
+            snow_max: hour.snow,
+        };
+        period.add_lapse_rate(hour);
+        period.add_spread(hour);
+        period
+    }
+
+    fn add_lapse_rate(&mut self, hour: &HourWeatherForecast) {
+        if let Some(lapse_rate) = hour.lapse_rate {
+            self.lapse_rate_total += lapse_rate;
+            self.lapse_rate_hours += 1;
+            if ThermalQuality::from_lapse_rate(lapse_rate).is_usable() {
+                self.thermal_duration += hour.duration;
+            }
+        }
+    }
+
+    fn add_spread(&mut self, hour: &HourWeatherForecast) {
+        if let Some(spread) = hour.spread {
+            let share = hours(hour.duration);
+            self.wind_spread_total += spread.wind_mps * share;
+            self.direction_spread_total += spread.direction_degrees * share;
+            self.spread_hours += share;
+        }
+    }
+
+    pub fn end(&self) -> DateTime<FixedOffset> {
+        self.start + self.duration
+    }
+
+    /// The duration in hours, e.g. 2.5.
+    pub fn hours(&self) -> f32 {
+        hours(self.duration)
+    }
+
+    pub fn thermal_quality(&self) -> Option<ThermalQuality> {
+        if self.lapse_rate_hours == 0 {
+            return None;
+        }
+        let average = self.lapse_rate_total / self.lapse_rate_hours as f32;
+        Some(ThermalQuality::from_lapse_rate(average))
+    }
+
+    /// The spread of the models averaged over the period, `None` without
+    /// an ensemble.
+    pub fn spread(&self) -> Option<Spread> {
+        if self.spread_hours <= 0.0 {
+            return None;
+        }
+        Some(Spread {
+            wind_mps: self.wind_spread_total / self.spread_hours,
+            direction_degrees: self.direction_spread_total / self.spread_hours,
+        })
+    }
+
+    pub fn confidence(&self) -> Option<Confidence> {
+        self.spread().map(Confidence::from_spread)
+    }
+
+    /// Whether wind chill or humidity make the period feel notably colder
+    /// or warmer than the temperature.
+    pub fn feels_like_diverges(&self) -> bool {
+        let difference = |a: Temperature, b: Temperature| (a.celsius() - b.celsius()).abs();
+        difference(self.feels_like_min, self.temp_min) >= FEELS_LIKE_DIVERGENCE_C
+            || difference(self.feels_like_max, self.temp_max) >= FEELS_LIKE_DIVERGENCE_C
+    }
+
+    /// Whether the wind notably picks up or eases from the first to the
+    /// last hour.
+    pub fn wind_trend(&self) -> Option<WindTrend> {
+        let change = self.wind_last.meters_per_second() - self.wind_first.meters_per_second();
+        if change >= WIND_TREND_MPS {
+            Some(WindTrend::Strengthening)
+        } else if change <= -WIND_TREND_MPS {
+            Some(WindTrend::BackingOff)
+        } else {
+            None
+        }
+    }
+
+    /// What kind of flying the period offers, when it is clearly one. The
+    /// wind is compared with `RIDGE_WIND_MPS`, and the local hours of the
+    /// period with the morning, midday and evening hours.
+    pub fn soaring_window(&self) -> Option<SoaringWindow> {
+        let hour = |time: DateTime<FixedOffset>| time.hour() as f32 + time.minute() as f32 / 60.0;
+        let start = hour(self.start);
+        let end = start + self.hours();
+        let light = self.wind_max.meters_per_second() < RIDGE_WIND_MPS;
+        let thermals = self.thermal_quality();
+        let midday = start < MIDDAY_HOURS.1 as f32 && end > MIDDAY_HOURS.0 as f32;
+        if end <= MORNING_END_HOUR as f32 && light {
+            Some(SoaringWindow::MorningSledder)
+        } else if start >= EVENING_START_HOUR as f32 && light {
+            Some(SoaringWindow::SmoothEvening)
+        } else if midday && thermals == Some(ThermalQuality::Strong) {
+            Some(SoaringWindow::StrongThermic)
+        } else if midday && thermals.is_some_and(|quality| quality.is_usable()) {
+            Some(SoaringWindow::Thermic)
+        } else if self.wind_min.meters_per_second() >= RIDGE_WIND_MPS {
+            Some(SoaringWindow::Ridge)
+        } else {
+            None
+        }
+    }
+
+    /// The cardinal range of the wind, e.g. "SW–WSW".
+    pub fn wind_direction(&self) -> String {
+        self.wind_directions.cardinal()
+    }
+
+    fn is_next_hour(&self, hour: &HourWeatherForecast) -> bool {
+        self.end() == hour.time
+    }
+
+    fn add_hour(&mut self, hour: &HourWeatherForecast, site: &FlyingSite) {
+        self.duration += hour.duration;
+        self.clouds_total += hour.clouds as f32 * hours(hour.duration);
+        self.direction_offset_total +=
+            site.direction_offset(hour.wind_direction) * hours(hour.duration);
+        self.add_lapse_rate(hour);
+        self.add_spread(hour);
+        if hour.thermal_top_m > self.thermal_top_max {
+            self.thermal_top_max = hour.thermal_top_m;
+        }
+        if let Some(speed) = hour.surface_wind_speed {
+            self.surface_wind_max = Some(self.surface_wind_max.map_or(speed, |max| max.max(speed)));
+        }
+        if let Some(visibility) = hour.visibility {
+            if self.visibility_min.is_none_or(|min| min > visibility) {
+                self.visibility_min = Some(visibility);
+            }
+        }
+        if hour.rain > self.rain_max {
+            self.rain_max = hour.rain;
+        }
+        if let Some(pressure) = hour.pressure {
+            if self
+                .pressure_min
+                .is_none_or(|min| min.hectopascals() > pressure.hectopascals())
+            {
+                self.pressure_min = Some(pressure);
+            }
+        }
+        if hour.humidity > self.humidity_max {
+            self.humidity_max = hour.humidity;
+        }
+        if hour.uvi > self.uvi_max {
+            self.uvi_max = hour.uvi;
+        }
+        if let Some(waves) = hour.waves {
+            if self.waves_max.is_none_or(|max| waves.height > max.height) {
+                self.waves_max = Some(waves);
+            }
+        }
+        if let Some(level) = hour.freezing_level_m {
+            if self.freezing_level_min.is_none_or(|min| min > level) {
+                self.freezing_level_min = Some(level);
+            }
+        }
+        if hour.snow_depth_m > self.snow_depth_max {
+            self.snow_depth_max = hour.snow_depth_m;
+        }
+        if hour.snow > self.snow_max {
+            self.snow_max = hour.snow;
+        }
+        if self.cloud_base_min_agl > cloud_base_agl(hour) {
+            self.cloud_base_min_agl = cloud_base_agl(hour);
+        }
+        if self.pop_max < hour.pop {
+            self.pop_max = hour.pop;
+        }
+        self.wind_min = self.wind_min.min(hour.wind_speed);
+        self.wind_max = self.wind_max.max(hour.wind_speed);
+        self.wind_last = hour.wind_speed;
+        self.wind_directions.extend(hour.wind_direction);
+        self.temp_min = self.temp_min.min(hour.temperature);
+        self.temp_max = self.temp_max.max(hour.temperature);
+        self.feels_like_min = self.feels_like_min.min(hour.feels_like);
+        self.feels_like_max = self.feels_like_max.max(hour.feels_like);
+    }
+}
+
+/// The flyable periods of a site on a day, scored by `ScoringModel`.
+#[derive(Debug, Clone)]
+pub struct SiteFlyAbilityReport {
+    pub site: FlyingSite,
+    pub date: NaiveDate,
+    /// Days from the run to `date`, 0 for today.
+    pub days_ahead: i64,
+    pub sunrise: DateTime<FixedOffset>,
+    pub sunset: DateTime<FixedOffset>,
+    pub periods: Vec<SiteFlyablePeriod>,
+    /// Every forecast hour of `date`, flyable or not.
+    pub hourly: Vec<HourWeatherForecast>,
+    /// The daily forecast, for a day past the hourly forecast that is only
+    /// reported as a whole, without `periods` nor `hourly`.
+    pub outlook: Option<DayOutlook>,
+    pub score: f32,
+    pub notes: Vec<String>,
+    /// The data the provider left out of some hours of `date`, noted in the
+    /// language of the message.
+    pub missing: BTreeMap<MissingData, usize>,
+    /// How the daytime wind changed over the recent runs, see
+    /// `trend_runs`.
+    pub trend: Option<ForecastTrend>,
+    pub warnings: Vec<String>,
+    /// The NOTAMs restricting the `airspace` of the site on `date`.
+    pub notams: Vec<ActiveNotam>,
+    /// The live station readings of morning runs.
+    pub readings: Vec<LiveReading>,
+}
+
+/// How the forecast daytime wind of a day changed over the recent runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForecastTrend {
+    pub runs: usize,
+    pub trend: WindTrend,
+    pub from: WindSpeed,
+    pub to: WindSpeed,
+}
+
+impl ForecastTrend {
+    pub fn describe(&self, strings: &Strings, units: Units) -> String {
+        i18n::fill(
+            strings.forecast_trend(self.trend),
+            &[
+                ("runs", &self.runs.to_string()),
+                ("from", &self.from.format(units.wind)),
+                ("to", &self.to.format(units.wind)),
+                ("unit", units.wind.label()),
+            ],
+        )
+    }
+}
+
+impl SiteFlyAbilityReport {
+    /// `notes` and those of the wet ground, the `missing` data, the `trend`
+    /// and the live `readings`, in the language and units of `format`.
+    pub fn notes_in(&self, format: &MessageFormat) -> Vec<String> {
+        let (strings, units) = (format.strings(), format.units());
+        let mut notes = self.notes.clone();
+        notes.extend(wet_ground_note(self, strings));
+        notes.extend(missing_data_note(&self.missing, strings));
+        notes.extend(self.trend.map(|trend| trend.describe(strings, units)));
+        notes.extend(self.readings.iter().map(|reading| reading.describe(format)));
+        notes
+    }
+
+    /// `warnings` and those of the `notams`, in the language of `strings`.
+    pub fn warnings_in(&self, strings: &Strings) -> Vec<String> {
+        let mut warnings = self.warnings.clone();
+        let airspace = self.site.airspace.as_deref().unwrap_or_default();
+        warnings.extend(self.notams.iter().map(|notam| {
+            i18n::fill(
+                strings.notam,
+                &[
+                    ("airspace", airspace),
+                    ("number", &notam.number),
+                    ("text", &notam.text),
+                ],
+            )
+        }));
+        warnings
+    }
+
+    /// Whether the report is about the `target` day of its run, rather than
+    /// a day some recipients want instead.
+    pub fn is_about(&self, target: Target) -> bool {
+        let run_day = self.date - Duration::days(self.days_ahead);
+        target.date(run_day) == self.date
+    }
+
+    /// The header and details of the configured template, its first line
+    /// being the header.
+    fn template_sections(&self, format: &MessageFormat) -> Option<(String, String)> {
+        let text = format.render_template(self)?;
+        let mut lines = text.trim().splitn(2, '\n');
+        let header = lines.next().unwrap_or("").trim_end().to_string();
+        let details = lines.next().unwrap_or("").to_string();
+        Some((header, details))
+    }
+
+    /// The header of the message and everything below it: one line per
+    /// period, then notes and warnings.
+    fn sections(&self, format: &MessageFormat) -> (String, String) {
+        self.template_sections(format)
+            .unwrap_or_else(|| (self.built_in_header(format), self.built_in_details(format)))
+    }
+
+    fn built_in_header(&self, format: &MessageFormat) -> String {
+        let strings = format.strings();
+        let header = i18n::fill(
+            strings.header_for(self.site.site_type),
+            &[
+                ("site", &self.site.name),
+                ("day", &strings.day(self.days_ahead)),
+                ("score", &format!("{:.0}", self.score)),
+            ],
+        );
+        match format.style() {
+            MessageStyle::Paragraph => header,
+            MessageStyle::Compact => format!("{} {}", self.site.site_type.emoji(), header),
+        }
+    }
+
+    /// The alert sent on top of the report of an epic day, e.g. "🔥 Epic
+    /// day at Annecy tomorrow, score 92. Don't miss it!".
+    pub fn epic_day_alert(&self, format: &MessageFormat) -> String {
+        i18n::fill(
+            format.strings().epic_day,
+            &[
+                ("site", &self.site.name),
+                ("day", &format.strings().day(self.days_ahead)),
+                ("score", &format!("{:.0}", self.score)),
+            ],
+        )
+    }
+
+    /// Start and end of the periods, or the daylight of an outlook.
+    pub fn flown_times(&self) -> Vec<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
+        if self.outlook.is_some() {
+            return vec![(self.sunrise, self.sunset)];
+        }
+        self.periods.iter().map(|p| (p.start, p.end())).collect()
+    }
+
+    pub fn flyable_hours(&self) -> f32 {
+        self.periods.iter().map(|p| p.hours()).sum()
+    }
+
+    /// Sunrise, sunset and how much of the daylight is flyable, or the
+    /// daily forecast of an outlook.
+    fn describe_daylight(&self, format: &MessageFormat) -> String {
+        if let Some(outlook) = &self.outlook {
+            return self.describe_outlook(outlook, format);
+        }
+        let sunrise = self.sunrise.format("%H:%M").to_string();
+        let sunset = self.sunset.format("%H:%M").to_string();
+        let daylight = format!(
+            "{:.1}",
+            (self.sunset - self.sunrise).num_minutes() as f32 / 60.0
+        );
+        let flyable = format_hours(self.flyable_hours());
+        match format.style() {
+            MessageStyle::Paragraph => i18n::fill(
+                format.strings().daylight,
+                &[
+                    ("sunrise", &sunrise),
+                    ("sunset", &sunset),
+                    ("hours", &flyable),
+                    ("daylight", &daylight),
+                ],
+            ),
+            MessageStyle::Compact => {
+                format!("🌅 {} 🌇 {} ☀️ {}/{}h", sunrise, sunset, flyable, daylight)
+            }
+        }
+    }
+
+    fn describe_outlook(&self, outlook: &DayOutlook, format: &MessageFormat) -> String {
+        let units = format.units();
+        let sunrise = self.sunrise.format("%H:%M").to_string();
+        let sunset = self.sunset.format("%H:%M").to_string();
+        let speed = outlook.wind_speed.format(units.wind);
+        let gust = outlook
+            .wind_gust
+            .unwrap_or(outlook.wind_speed)
+            .format(units.wind);
+        let cardinal = outlook.wind_direction.cardinal();
+        let pop = format!("{:.0}", outlook.pop * 100.0);
+        match format.style() {
+            MessageStyle::Paragraph => i18n::fill(
+                format.strings().outlook,
+                &[
+                    ("sunrise", &sunrise),
+                    ("sunset", &sunset),
+                    ("speed", &speed),
+                    ("gust", &gust),
+                    ("unit", units.wind.label()),
+                    ("cardinal", cardinal),
+                    ("pop", &pop),
+                ],
+            ),
+            MessageStyle::Compact => format!(
+                "🔭 🌅 {} 🌇 {} 💨 {}–{} {} {} ☔ {}%",
+                sunrise,
+                sunset,
+                speed,
+                gust,
+                units.wind.label(),
+                cardinal,
+                pop
+            ),
+        }
+    }
+
+    pub fn describe_period(&self, period: &SiteFlyablePeriod, format: &MessageFormat) -> String {
+        if format.style() == MessageStyle::Compact {
+            return describe_period_compact(period, self.site.site_type, format);
+        }
+        let (strings, units) = (format.strings(), format.units());
+        let mut parts = vec![
+            i18n::fill(
+                strings.period_start,
+                &[
+                    ("time", &period.start.format("%H:%M").to_string()),
+                    ("hours", &format_hours(period.hours())),
+                ],
+            ),
+            i18n::fill(
+                if period.surface_wind_max.is_some() {
+                    strings.launch_wind
+                } else {
+                    strings.wind
+                },
+                &[
+                    ("min", &period.wind_min.format(units.wind)),
+                    ("max", &period.wind_max.format(units.wind)),
+                    ("unit", units.wind.label()),
+                ],
+            ),
+            i18n::fill(
+                strings.direction,
+                &[
+                    ("cardinal", &period.wind_direction()),
+                    ("min", &period.wind_directions.from.degrees().to_string()),
+                    ("max", &period.wind_directions.to.degrees().to_string()),
+                ],
+            ),
+            i18n::fill(
+                strings.temperature,
+                &[
+                    (
+                        "min",
+                        &format!("{:.1}", period.temp_min.in_unit(units.temperature)),
+                    ),
+                    (
+                        "max",
+                        &format!("{:.1}", period.temp_max.in_unit(units.temperature)),
+                    ),
+                    ("unit", units.temperature.label()),
+                ],
+            ),
+        ];
+        if let Some(trend) = period.wind_trend() {
+            parts[1].push_str(&format!(" {} {}", trend.arrow(), strings.wind_trend(trend)));
+        }
+        if let Some(window) = period
+            .soaring_window()
+            .filter(|_| self.site.site_type.is_flown())
+        {
+            parts[0].push_str(&format!(" ({})", strings.soaring_window(window)));
+        }
+        if period.feels_like_diverges() {
+            parts.push(i18n::fill(
+                strings.feels_like,
+                &[
+                    (
+                        "min",
+                        &format!("{:.1}", period.feels_like_min.in_unit(units.temperature)),
+                    ),
+                    (
+                        "max",
+                        &format!("{:.1}", period.feels_like_max.in_unit(units.temperature)),
+                    ),
+                    ("unit", units.temperature.label()),
+                ],
+            ));
+        }
+        if let Some(surface) = period.surface_wind_max {
+            parts.insert(
+                2,
+                i18n::fill(
+                    strings.surface_wind,
+                    &[
+                        ("max", &surface.format(units.wind)),
+                        ("unit", units.wind.label()),
+                    ],
+                ),
+            );
+        }
+        parts.push(match self.site.elevation_m {
+            Some(elevation) => i18n::fill(
+                strings.cloud_base_amsl,
+                &[(
+                    "height",
+                    &format!(
+                        "{:.0}",
+                        round_to_hundreds(elevation + period.cloud_base_min_agl)
+                    ),
+                )],
+            ),
+            None => i18n::fill(
+                strings.cloud_base_agl,
+                &[(
+                    "height",
+                    &format!("{:.0}", round_to_hundreds(period.cloud_base_min_agl)),
+                )],
+            ),
+        });
+        if let Some(top) = period.thermal_top_max {
+            parts.push(i18n::fill(
+                strings.top_of_lift,
+                &[("height", &format!("{:.0}", round_to_hundreds(top)))],
+            ));
+        }
+        if let Some(quality) = period.thermal_quality() {
+            parts.push(i18n::fill(
+                strings.thermals,
+                &[
+                    ("quality", strings.thermal_quality(quality)),
+                    ("hours", &format_hours(hours(period.thermal_duration))),
+                ],
+            ));
+        }
+        if let Some(confidence) = period.confidence() {
+            parts.push(i18n::fill(
+                strings.confidence,
+                &[("level", strings.confidence_level(confidence))],
+            ));
+        }
+        if let Some(visibility) = period.visibility_min {
+            parts.push(i18n::fill(
+                strings.visibility,
+                &[("km", &format!("{:.0}", visibility.kilometers()))],
+            ));
+        }
+        if let Some(rain) = period.rain_max {
+            parts.push(i18n::fill(
+                strings.rain,
+                &[("mm", &format!("{:.1}", rain.millimeters()))],
+            ));
+        }
+        if let Some(pressure) = period.pressure_min {
+            parts.push(i18n::fill(
+                strings.pressure,
+                &[("hpa", &format!("{:.0}", pressure.hectopascals()))],
+            ));
+        }
+        if let Some(humidity) = period.humidity_max {
+            parts.push(i18n::fill(
+                strings.humidity,
+                &[("percent", &humidity.to_string())],
+            ));
+        }
+        if let Some(uvi) = period.uvi_max {
+            parts.push(i18n::fill(
+                strings.uv,
+                &[
+                    ("uvi", &format!("{:.0}", uvi)),
+                    ("level", strings.uv_level(uvi)),
+                ],
+            ));
+        }
+        if let Some(waves) = period.waves_max.filter(|_| self.site.coastal) {
+            parts.push(i18n::fill(
+                strings.waves,
+                &[
+                    ("height", &format!("{:.1}", waves.height.meters())),
+                    ("period", &format!("{:.0}", waves.period_s)),
+                    ("cardinal", waves.direction.cardinal()),
+                ],
+            ));
+        }
+        if winter::is_winter(self.date, self.site.latitude()) {
+            if let Some(level) = period.freezing_level_min {
+                parts.push(i18n::fill(
+                    strings.freezing_level,
+                    &[("height", &format!("{:.0}", round_to_hundreds(level)))],
+                ));
+            }
+            if let Some(depth) = period.snow_depth_max.filter(|depth| *depth > 0.0) {
+                parts.push(i18n::fill(
+                    strings.snow_depth,
+                    &[("cm", &format!("{:.0}", depth * 100.0))],
+                ));
+            }
+            if let Some(snow) = period.snow_max {
+                parts.push(i18n::fill(
+                    strings.snowfall,
+                    &[("mm", &format!("{:.1}", snow.millimeters()))],
+                ));
+            }
+        }
+        parts.join(". ")
+    }
+
+    /// The description and takeoff altitude of the site.
+    fn describe_site(&self, strings: &Strings) -> Option<String> {
+        let mut parts = vec![];
+        if let Some(altitude) = self.site.elevation_m {
+            parts.push(i18n::fill(
+                strings.takeoff,
+                &[("altitude", &format!("{:.0}", altitude))],
+            ));
+        }
+        if let Some(description) = &self.site.description {
+            parts.push(description.trim_end_matches('.').to_string());
+        }
+        if parts.is_empty() {
+            None
+        } else {
+            Some(format!("{}.", parts.join(". ")))
+        }
+    }
+
+    /// Labelled webcam and map links of the site.
+    fn links(&self, strings: &Strings) -> Vec<(&'static str, &str)> {
+        let mut links = vec![];
+        if let Some(url) = &self.site.webcam_url {
+            links.push((strings.webcam, &url[..]));
+        }
+        if let Some(url) = &self.site.map_url {
+            links.push((strings.map, &url[..]));
+        }
+        links
+    }
+
+    /// Built-in details with the links left out, for channels rendering
+    /// them on their own.
+    fn built_in_text(&self, format: &MessageFormat) -> String {
+        let mut lines = vec![];
+        lines.extend(self.describe_site(format.strings()));
+        lines.push(self.describe_daylight(format));
+        for period in &self.periods {
+            let line = self.describe_period(period, format);
+            lines.push(match format.style() {
+                MessageStyle::Paragraph => format!("- {}", line),
+                MessageStyle::Compact => line,
+            });
+        }
+        if format.sparkline() {
+            lines.extend(self.wind_sparkline(format.units()));
+        }
+        lines.extend(self.notes_in(format));
+        for warning in self.warnings_in(format.strings()) {
+            lines.push(format!("⚠️ {}", warning));
+        }
+        lines.join("\n")
+    }
+
+    /// Bars of the wind of the hours flown at the site, from none to the
+    /// strongest of the day or the site's `max_flyable_wind`, whichever is
+    /// stronger, then arrows of its direction. Nothing for outlooks, and
+    /// only the whole hours of a subdivided forecast.
+    fn wind_sparkline(&self, units: Units) -> Vec<String> {
+        let hours: Vec<&HourWeatherForecast> = self
+            .hourly
+            .iter()
+            .filter(|hour| self.site.flies_at(hour.time_of_day) && hour.time.minute() == 0)
+            .collect();
+        let first = match hours.first() {
+            Some(first) => first.time.format("%H:%M").to_string(),
+            None => return vec![],
+        };
+        let strongest = hours
+            .iter()
+            .map(|hour| hour.wind_speed)
+            .fold(self.site.max_flyable_wind(), WindSpeed::max);
+        let scale = strongest.meters_per_second().max(f32::EPSILON);
+        let bars: String = hours
+            .iter()
+            .map(|hour| {
+                let level = hour.wind_speed.meters_per_second() / scale;
+                let index = (level * (SPARKLINE_BARS.len() - 1) as f32).round() as usize;
+                SPARKLINE_BARS[index.min(SPARKLINE_BARS.len() - 1)]
+            })
+            .collect();
+        let arrows: String = hours
+            .iter()
+            .map(|hour| hour.wind_direction.arrow())
+            .collect();
+        let peak = hours
+            .iter()
+            .map(|hour| hour.wind_speed)
+            .fold(WindSpeed::MPS(0.0), WindSpeed::max);
+        vec![
+            format!(
+                "🌬️ {} {} ≤{} {}",
+                first,
+                bars,
+                peak.format(units.wind),
+                units.wind.label()
+            ),
+            format!("🧭 {} {}", first, arrows),
+        ]
+    }
+
+    fn built_in_details(&self, format: &MessageFormat) -> String {
+        let mut details = self.built_in_text(format);
+        for (label, url) in self.links(format.strings()) {
+            details.push_str(&format!("\n{}: {}", label, url));
+        }
+        details
+    }
+
+    pub fn as_string(&self, format: &MessageFormat) -> String {
+        let (header, details) = self.sections(format);
+        format!("{}\n{}", header, details)
+    }
+
+    pub fn as_html(&self, format: &MessageFormat) -> String {
+        if let Some((header, details)) = format.render_template(self).map(|_| self.sections(format))
+        {
+            let mut repr = format!("<h3>{}</h3>\n", escape_html(&header));
+            for line in details.lines().filter(|line| !line.trim().is_empty()) {
+                repr.push_str(&format!("<p>{}</p>\n", escape_html(line)));
+            }
+            return repr;
+        }
+        let mut repr = format!("<h3>{}</h3>\n", escape_html(&self.built_in_header(format)));
+        if let Some(site) = self.describe_site(format.strings()) {
+            repr.push_str(&format!("<p>{}</p>\n", escape_html(&site)));
+        }
+        repr.push_str(&format!(
+            "<p>{}</p>\n<ul>\n",
+            escape_html(&self.describe_daylight(format))
+        ));
+        for period in &self.periods {
+            repr.push_str(&format!(
+                "<li>{}</li>\n",
+                escape_html(&self.describe_period(period, format))
+            ));
+        }
+        repr.push_str("</ul>\n");
+        if format.sparkline() {
+            for line in self.wind_sparkline(format.units()) {
+                repr.push_str(&format!("<p>{}</p>\n", escape_html(&line)));
+            }
+        }
+        for note in self.notes_in(format) {
+            repr.push_str(&format!("<p>{}</p>\n", escape_html(&note)));
+        }
+        for warning in self.warnings_in(format.strings()) {
+            repr.push_str(&format!(
+                "<p><strong>⚠️ {}</strong></p>\n",
+                escape_html(&warning)
+            ));
+        }
+        let links: Vec<String> = self
+            .links(format.strings())
+            .into_iter()
+            .map(|(label, url)| {
+                format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape_html(url),
+                    escape_html(label)
+                )
+            })
+            .collect();
+        if !links.is_empty() {
+            repr.push_str(&format!("<p>{}</p>\n", links.join(" | ")));
+        }
+        repr
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Steps from `previous` to `hour` in an hour, 2 for half-hour steps.
+fn steps_per_hour(hour: &HourWeatherForecast, previous: &HourWeatherForecast) -> f32 {
+    60.0 / (hour.time - previous.time).num_minutes().max(1) as f32
+}
+
+/// Degrees the wind turns from `previous` to `hour`, scaled to an hour.
+fn hourly_turn(hour: &HourWeatherForecast, previous: &HourWeatherForecast) -> i16 {
+    let turn = hour.wind_direction.distance(previous.wind_direction) as f32;
+    (turn * steps_per_hour(hour, previous)).round() as i16
+}
+
+/// `duration` in hours, e.g. 2.5.
+fn hours(duration: Duration) -> f32 {
+    duration.num_minutes() as f32 / 60.0
+}
+
+/// Hours with up to two decimals and without trailing zeros, e.g. "6" or
+/// "1.5".
+pub fn format_hours(hours: f32) -> String {
+    let text = format!("{:.2}", hours);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// A period on one line, e.g.
+/// `🕑 11:00 +5h 💨 4.2–8.1 MPH ↗ SW–WSW 🌡️ 64–71F ☔ 10%`.
+fn describe_period_compact(
+    period: &SiteFlyablePeriod,
+    site_type: SiteType,
+    format: &MessageFormat,
+) -> String {
+    let units = format.units();
+    let window = period.soaring_window().filter(|_| site_type.is_flown());
+    let window = window.map_or(String::new(), |window| {
+        format!(" 🏷️ {}", format.strings().soaring_window(window))
+    });
+    let confidence = period.confidence().map_or(String::new(), |confidence| {
+        format!(" 🎯 {}", format.strings().confidence_level(confidence))
+    });
+    format!(
+        "🕑 {} +{}h 💨 {}–{} {}{} {} 🌡️ {:.0}–{:.0}{} ☔ {:.0}%{}{}",
+        period.start.format("%H:%M"),
+        format_hours(period.hours()),
+        period.wind_min.format(units.wind),
+        period.wind_max.format(units.wind),
+        units.wind.label(),
+        period
+            .wind_trend()
+            .map_or(String::new(), |trend| format!(" {}", trend.arrow())),
+        period.wind_direction(),
+        period.temp_min.in_unit(units.temperature),
+        period.temp_max.in_unit(units.temperature),
+        units.temperature.label(),
+        period.pop_max * 100.0,
+        window,
+        confidence,
+    )
+}
+
+/// Official alerts in force at some point of the flown times of `report`,
+/// in the order of the provider.
+fn alert_warnings(alerts: &[WeatherAlert], report: &SiteFlyAbilityReport) -> Vec<String> {
+    let flown = report.flown_times();
+    alerts
+        .iter()
+        .filter(|alert| {
+            flown
+                .iter()
+                .any(|(start, end)| alert.overlaps(*start, *end))
+        })
+        .map(|alert| alert.describe(report.date))
+        .collect()
+}
+
+fn round_to_hundreds(meters: f32) -> f32 {
+    (meters / 100.0).round() * 100.0
+}
+
+/// Today where the forecast is, or in UTC without any forecast.
+pub fn forecast_today(forecasts: &[DayWeatherForecast]) -> NaiveDate {
+    match forecasts.first() {
+        Some(day) => Utc::now()
+            .with_timezone(&day.sunrise.timezone())
+            .date_naive(),
+        None => Utc::now().date_naive(),
+    }
+}
+
+/// The report of `site` on `date` from its `forecasts`, or the reason it is
+/// not flyable. Periods shorter than the `min_period_hours` of the site, or
+/// `default_min_period_hours`, are left out. The `forecasts` of sites with a
+/// `max_prior_rain_mm` are expected to have gone through
+/// `forecast_client::apply_prior_rain`.
+pub fn prepare_report_for_site(
+    forecasts: Vec<DayWeatherForecast>,
+    site: FlyingSite,
+    date: NaiveDate,
+    default_min_period_hours: i64,
+    scoring: &ScoringModel,
+) -> Result<SiteFlyAbilityReport, SkipReason> {
+    let days_ahead = (date - forecast_today(&forecasts)).num_days();
+    let forecast = forecasts
+        .into_iter()
+        .find(|f| f.date == date)
+        .ok_or(SkipReason::NoForecast)?;
+    if forecast.hourly.is_empty() {
+        let outlook = forecast.outlook.ok_or(SkipReason::NoForecast)?;
+        if let Some(reason) = site.outlook_unflyable_reason(&outlook) {
+            return Err(reason);
+        }
+        let score = scoring.score_outlook(&outlook, site.direction_offset(outlook.wind_direction));
+        let mut report = SiteFlyAbilityReport {
+            site,
+            date,
+            days_ahead,
+            sunrise: forecast.sunrise,
+            sunset: forecast.sunset,
+            periods: vec![],
+            hourly: vec![],
+            outlook: Some(outlook),
+            score,
+            notes: vec![],
+            missing: BTreeMap::new(),
+            trend: None,
+            warnings: vec![],
+            notams: vec![],
+            readings: vec![],
+        };
+        report.warnings = alert_warnings(&forecast.alerts, &report);
+        return Ok(report);
+    }
+
+    // Hours already over are left out of same-day reports.
+    let periods = flyable_periods(
+        &site,
+        &forecast.hourly,
+        default_min_period_hours,
+        Utc::now(),
+    )?;
+    let score = scoring.score_periods(&periods);
+    let mut report = SiteFlyAbilityReport {
+        site,
+        date,
+        days_ahead,
+        sunrise: forecast.sunrise,
+        sunset: forecast.sunset,
+        periods,
+        hourly: forecast.hourly,
+        outlook: None,
+        score,
+        notes: vec![],
+        missing: forecast.missing,
+        trend: None,
+        warnings: vec![],
+        notams: vec![],
+        readings: vec![],
+    };
+    report.warnings = alert_warnings(&forecast.alerts, &report);
+    Ok(report)
+}
+
+/// The periods of `hourly` flyable at `site` and lasting at least its
+/// `min_period_hours`, the hours over by `now` left out, or why there are
+/// none.
+pub fn flyable_periods(
+    site: &FlyingSite,
+    hourly: &[HourWeatherForecast],
+    default_min_period_hours: i64,
+    now: DateTime<Utc>,
+) -> Result<Vec<SiteFlyablePeriod>, SkipReason> {
+    let mut flying_hours = vec![];
+    for (i, hour) in hourly.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|i| &hourly[i]);
+        if hour.time + hour.duration > now
+            && site.is_flyable(hour)
+            && !site.is_switchy(hour, previous)
+        {
+            flying_hours.push(hour);
+        }
+    }
+    if flying_hours.is_empty() {
+        return Err(site.main_unflyable_reason(hourly));
+    }
+    let mut periods = vec![];
+    let mut current_period = SiteFlyablePeriod::from_hour(flying_hours[0], site);
+    for pair in flying_hours.windows(2) {
+        let (previous, hour) = (pair[0], pair[1]);
+        if current_period.is_next_hour(hour) && !site.is_picking_up(hour, previous) {
+            current_period.add_hour(hour, site);
+        } else {
+            periods.push(current_period);
+            current_period = SiteFlyablePeriod::from_hour(hour, site);
+        }
+    }
+    periods.push(current_period);
+
+    let min_period_hours = site.min_period_hours.unwrap_or(default_min_period_hours);
+    periods.retain(|p| p.duration >= Duration::hours(min_period_hours));
+    if periods.is_empty() {
+        return Err(SkipReason::TooShort);
+    }
+    Ok(periods)
+}
+
+/// Notes the rain fallen before the flyable hours of sites with a
+/// `max_prior_rain_mm` once it reaches half of it, thermals then being
+/// weaker on the wet ground.
+fn wet_ground_note(report: &SiteFlyAbilityReport, strings: &Strings) -> Option<String> {
+    let max = report.site.max_prior_rain_mm?;
+    let flown_times = report.flown_times();
+    let rain = report
+        .hourly
+        .iter()
+        .filter(|hour| {
+            flown_times
+                .iter()
+                .any(|(from, to)| *from <= hour.time && hour.time < *to)
+        })
+        .filter_map(|hour| hour.prior_rain_mm)
+        .reduce(f32::max)?;
+    (rain >= max * WET_GROUND_SHARE).then(|| {
+        i18n::fill(
+            strings.wet_ground,
+            &[
+                ("mm", &format!("{:.1}", rain)),
+                ("hours", &forecast_client::PRIOR_RAIN_HOURS.to_string()),
+            ],
+        )
+    })
+}
+
+/// Notes the data the provider left out of some hours of the day, and how
+/// the analysis made do without it.
+fn missing_data_note(missing: &BTreeMap<MissingData, usize>, strings: &Strings) -> Option<String> {
+    if missing.is_empty() {
+        return None;
+    }
+    let gaps: Vec<String> = missing
+        .iter()
+        .map(|(data, hours)| match hours {
+            1 => format!("{} (1 hour)", data.describe()),
+            _ => format!("{} ({} hours)", data.describe(), hours),
+        })
+        .collect();
+    Some(i18n::fill(
+        strings.incomplete_forecast,
+        &[("gaps", &gaps.join("; "))],
+    ))
+}
+
+/// The main forecast provider plus optional sources enriching its hours
+/// and the resulting reports.
+pub struct ForecastSources {
+    weather: Box<dyn ForecastProvider>,
+    thermal: Option<OpenMeteoClient>,
+    sounding: Option<SoundingClient>,
+    launch_wind: Option<LaunchWindClient>,
+    notams: Option<NotamClient>,
+    air_quality: Option<AirQualityClient>,
+    marine: Option<MarineClient>,
+    tides: Option<TideClient>,
+    winter: Option<WinterClient>,
+    ensemble: Option<EnsembleClient>,
+    holfuy: Option<HolfuyClient>,
+    ffvl: Option<FfvlClient>,
+    ecowitt: Option<EcowittClient>,
+    ambient: Option<AmbientClient>,
+    morning_until_hour: u32,
+    /// Forecast runs the trend note of the reports spans, see
+    /// `forecast_trend`.
+    trend_runs: Option<usize>,
+    cache: Option<ResponseCache>,
+    quotas: Quotas,
+    /// Cells per degree of the grid sites share forecasts in.
+    grid_scale: Option<f64>,
+    /// Sites checked at the same time.
+    concurrency: usize,
+    /// Steps the hours of the forecasts are split into, see
+    /// `forecast_client::subdivide`.
+    time_step: Option<Duration>,
+    daylight: DaylightConfig,
+}
+
+impl ForecastSources {
+    /// The sources configured in `config`, `read_cache` telling whether
+    /// cached responses may be used.
+    pub fn new(config: &ApplicationConfig, http: HttpClient, read_cache: bool) -> io::Result<Self> {
+        let http = http.for_weather();
+        let weather_api_url = config
+            .weather_api_url
+            .clone()
+            .unwrap_or_else(|| config.weather_api_version.default_url().to_string());
+        let air_quality = config.air_quality.as_ref().map(|air_quality| {
+            AirQualityClient::new(
+                air_quality.api_url.clone(),
+                config.weather_api_token.clone(),
+                http.clone(),
+            )
+        });
+        let marine = config
+            .marine
+            .as_ref()
+            .map(|marine| MarineClient::new(marine.api_url.clone(), http.clone()));
+        let tides = config
+            .tides
+            .as_ref()
+            .map(|tides| TideClient::new(tides.api_url.clone(), http.clone()));
+        let winter = config
+            .winter
+            .as_ref()
+            .map(|winter| WinterClient::new(winter.api_url.clone(), http.clone()));
+        let ensemble = config.ensemble.as_ref().map(|ensemble| {
+            EnsembleClient::new(
+                ensemble.api_url.clone(),
+                ensemble.models.clone(),
+                http.clone(),
+            )
+        });
+        let weather = OpenWeatherMapClient::new(
+            weather_api_url,
+            config.weather_api_token.clone(),
+            http.clone(),
+            config.daylight,
+        );
+        Ok(ForecastSources {
+            thermal: config
+                .thermal
+                .as_ref()
+                .map(|thermal| OpenMeteoClient::new(thermal.api_url.clone(), http.clone())),
+            sounding: config.sounding.as_ref().map(|sounding| {
+                SoundingClient::new(
+                    sounding.api_url.clone(),
+                    sounding.model.clone(),
+                    http.clone(),
+                )
+            }),
+            launch_wind: config.launch_wind.as_ref().map(|launch_wind| {
+                LaunchWindClient::new(launch_wind.api_url.clone(), launch_wind.blend, http.clone())
+            }),
+            notams: config.airspace.as_ref().map(|airspace| {
+                NotamClient::new(
+                    airspace.api_url.clone(),
+                    airspace.client_id.clone(),
+                    airspace.client_secret.clone(),
+                    http.clone(),
+                )
+            }),
+            air_quality,
+            marine,
+            tides,
+            winter,
+            ensemble,
+            holfuy: config.stations.as_ref().and_then(|stations| {
+                let key = stations.holfuy_api_key.clone()?;
+                Some(HolfuyClient::new(
+                    stations.holfuy_api_url.clone(),
+                    key,
+                    http.clone(),
+                ))
+            }),
+            ffvl: config.stations.as_ref().and_then(|stations| {
+                let key = stations.ffvl_api_key.clone()?;
+                Some(FfvlClient::new(
+                    stations.ffvl_api_url.clone(),
+                    key,
+                    http.clone(),
+                ))
+            }),
+            // Gateways on the local network are read without keys.
+            ecowitt: config.stations.as_ref().map(|stations| {
+                let keys = stations
+                    .ecowitt_application_key
+                    .clone()
+                    .zip(stations.ecowitt_api_key.clone());
+                EcowittClient::new(stations.ecowitt_api_url.clone(), keys, http.clone())
+            }),
+            ambient: config.stations.as_ref().and_then(|stations| {
+                Some(AmbientClient::new(
+                    stations.ambient_api_url.clone(),
+                    stations.ambient_application_key.clone()?,
+                    stations.ambient_api_key.clone()?,
+                    http.clone(),
+                ))
+            }),
+            morning_until_hour: config
+                .stations
+                .as_ref()
+                .map_or(0, |stations| stations.morning_until_hour),
+            trend_runs: config.forecast_trend_runs,
+            cache: config
+                .cache
+                .clone()
+                .map(|cache| ResponseCache::new(cache, read_cache))
+                .transpose()?,
+            quotas: Quotas::new(config.quotas.clone()),
+            grid_scale: config
+                .grid_precision
+                .map(|precision| 10f64.powi(precision as i32)),
+            concurrency: config.concurrency,
+            time_step: config.time_step_minutes.map(Duration::minutes),
+            daylight: config.daylight,
+            ..ForecastSources::with_provider(Box::new(weather))
+        })
+    }
+
+    /// Forecasts of `weather` alone, without cache nor quotas, checking one
+    /// site at a time.
+    pub fn with_provider(weather: Box<dyn ForecastProvider>) -> Self {
+        ForecastSources {
+            weather,
+            thermal: None,
+            sounding: None,
+            launch_wind: None,
+            notams: None,
+            air_quality: None,
+            marine: None,
+            tides: None,
+            winter: None,
+            ensemble: None,
+            holfuy: None,
+            ffvl: None,
+            ecowitt: None,
+            ambient: None,
+            morning_until_hour: 0,
+            trend_runs: None,
+            cache: None,
+            quotas: Quotas::new(HashMap::new()),
+            grid_scale: None,
+            concurrency: 1,
+            time_step: None,
+            daylight: DaylightConfig::default(),
+        }
+    }
+
+    /// Splits the hours of `forecast` into the configured steps, once
+    /// everything keyed by the hour is applied.
+    fn subdivide(&self, forecast: &mut [DayWeatherForecast]) {
+        if let Some(step) = self.time_step {
+            forecast_client::subdivide(forecast, step, &self.daylight);
+        }
+    }
+
+    /// The grid cell of `site` when sites close to each other share their
+    /// forecast.
+    fn grid_cell(&self, site: &FlyingSite) -> Option<(i64, i64)> {
+        let scale = self.grid_scale?;
+        Some((
+            (site.latitude() * scale).round() as i64,
+            (site.longitude() * scale).round() as i64,
+        ))
+    }
+
+    /// The forecast of the main provider, enriched with the optional sources
+    /// whose quota is not exhausted. Forecasts are for the center of the grid
+    /// cell of `site` when there are grid cells.
+    #[tracing::instrument(skip_all, fields(site = %site.name))]
+    async fn get_forecast(
+        &self,
+        site: &FlyingSite,
+        store: Option<&StateStore>,
+    ) -> Result<Vec<DayWeatherForecast>, ProviderError> {
+        let (lat, lon) = match (self.grid_cell(site), self.grid_scale) {
+            (Some((lat, lon)), Some(scale)) => (lat as f64 / scale, lon as f64 / scale),
+            _ => (site.latitude(), site.longitude()),
+        };
+        let provider = self.weather.name();
+        let cached = self.cache.as_ref().and_then(|c| c.get(provider, lat, lon));
+        let raw_forecast = match cached {
+            Some(raw_forecast) => raw_forecast,
+            None => {
+                if !self.quotas.spend(provider, store).await? {
+                    return Err(ProviderError::QuotaExhausted(provider));
+                }
+                let raw_forecast = self.weather.get_raw_forecast(lat, lon).await?;
+                if let Some(cache) = &self.cache {
+                    cache.put(provider, lat, lon, &raw_forecast);
+                }
+                raw_forecast
+            }
+        };
+        let mut forecast = self.weather.parse_forecast(&raw_forecast)?;
+        if let Some(thermal) = &self.thermal {
+            if self.quotas.spend(thermal.name(), store).await? {
+                let lapse_rates = thermal.get_lapse_rates(lat, lon).await;
+                if let Some(lapse_rates) = optional("lapse rates", lapse_rates) {
+                    thermal::apply_lapse_rates(&mut forecast, &lapse_rates);
+                }
+            }
+        }
+        if let Some(sounding) = &self.sounding {
+            if self.quotas.spend(sounding.name(), store).await? {
+                let soundings = sounding.get_soundings(lat, lon).await;
+                if let Some(soundings) = optional("soundings", soundings) {
+                    sounding::apply_thermal_tops(&mut forecast, &soundings);
+                }
+            }
+        }
+        if let Some(launch_wind) = &self.launch_wind {
+            if self.quotas.spend(launch_wind.name(), store).await? {
+                let level_winds = launch_wind.get_level_winds(lat, lon).await;
+                if let Some(level_winds) = optional("winds aloft", level_winds) {
+                    launch_wind::apply_level_winds(&mut forecast, &level_winds);
+                }
+            }
+        }
+        Ok(forecast)
+    }
+
+    fn stations_for<'a>(&'a self, site: &'a FlyingSite) -> Vec<(&'a dyn StationSource, &'a str)> {
+        let mut stations: Vec<(&dyn StationSource, &str)> = vec![];
+        if let (Some(holfuy), Some(id)) = (&self.holfuy, &site.holfuy_station_id) {
+            stations.push((holfuy, id));
+        }
+        if let (Some(ffvl), Some(id)) = (&self.ffvl, &site.ffvl_balise_id) {
+            stations.push((ffvl, id));
+        }
+        if let (Some(ecowitt), Some(id)) = (&self.ecowitt, &site.ecowitt_station) {
+            stations.push((ecowitt, id));
+        }
+        if let (Some(ambient), Some(id)) = (&self.ambient, &site.ambient_station_mac) {
+            stations.push((ambient, id));
+        }
+        stations
+    }
+
+    /// Live station readings for morning runs, with the forecast for the
+    /// current hour. Readings are recorded for forecast verification on
+    /// every run when a state store is available.
+    async fn observe(
+        &self,
+        site: &FlyingSite,
+        forecast: &[DayWeatherForecast],
+        store: Option<&StateStore>,
+    ) -> Result<Vec<LiveReading>, ProviderError> {
+        let mut readings = vec![];
+        if forecast.is_empty() {
+            return Ok(readings);
+        }
+        let now = Utc::now().with_timezone(&forecast[0].sunrise.timezone());
+        let is_morning = now.hour() < self.morning_until_hour;
+        if !is_morning && store.is_none() {
+            return Ok(readings);
+        }
+        let current_hour = forecast
+            .iter()
+            .flat_map(|day| day.hourly.iter())
+            .find(|hour| hour.time <= now && now < hour.time + hour.duration);
+        for (source, station_id) in self.stations_for(site) {
+            let observation = source.get_observation(station_id).await;
+            if let Some(observation) = optional("station readings", observation).flatten() {
+                if let (Some(store), Some(hour)) = (store, current_hour) {
+                    store.record_observed_wind(
+                        &site.name,
+                        source.name(),
+                        hour.time.timestamp(),
+                        observation.wind_speed.meters_per_second(),
+                    )?;
+                }
+                if is_morning {
+                    readings.push(LiveReading {
+                        source: source.name(),
+                        observation,
+                        forecast: current_hour.cloned(),
+                    });
+                }
+            }
+        }
+        Ok(readings)
+    }
+
+    fn record_forecast(
+        &self,
+        store: &StateStore,
+        site: &FlyingSite,
+        forecast: &[DayWeatherForecast],
+    ) -> Result<(), ProviderError> {
+        let now = Utc::now().timestamp();
+        for hour in forecast.iter().flat_map(|day| day.hourly.iter()) {
+            if hour.time.timestamp() > now {
+                store.record_forecast_wind(
+                    &site.name,
+                    self.weather.name(),
+                    hour.time.timestamp(),
+                    hour.wind_speed.meters_per_second(),
+                    hour.wind_direction.degrees(),
+                    now,
+                )?;
+            }
+        }
+        // Days already started would average fewer and fewer hours.
+        for day in forecast.iter().filter(|day| day.sunrise.timestamp() > now) {
+            if let Some(run) = forecast_run(day, now) {
+                let date = day.date.format("%Y-%m-%d").to_string();
+                store.record_forecast_run(&site.name, &date, &run)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn annotate_report(
+        &self,
+        report: &mut SiteFlyAbilityReport,
+        store: Option<&StateStore>,
+    ) -> Result<(), ProviderError> {
+        if let (Some(notams), Some(airspace)) = (&self.notams, &report.site.airspace) {
+            let tz = report.sunrise.timezone();
+            let from = report.date.and_time(NaiveTime::MIN);
+            let from = from.and_local_timezone(tz).unwrap();
+            let active = notams
+                .get_active_notams(airspace, from, from + Duration::days(1))
+                .await;
+            report.notams = optional("NOTAMs", active).unwrap_or_default();
+        }
+        if let Some(client) = &self.air_quality {
+            if self.quotas.spend(client.name(), store).await? {
+                let (lat, lon) = (report.site.latitude(), report.site.longitude());
+                let hours = client.get_air_quality(lat, lon).await;
+                if let Some(hours) = optional("air quality", hours) {
+                    report
+                        .notes
+                        .extend(air_quality::describe(&hours, &report.flown_times()));
+                }
+            }
+        }
+        if let (Some(store), Some(limit)) = (store, self.trend_runs) {
+            let date = report.date.format("%Y-%m-%d").to_string();
+            let runs = store.forecast_runs(&report.site.name, &date, limit)?;
+            if let (Some((trend, _)), Some(first), Some(last)) =
+                (forecast_trend(&runs), runs.first(), runs.last())
+            {
+                report.trend = Some(ForecastTrend {
+                    runs: runs.len(),
+                    trend,
+                    from: WindSpeed::MPS(first.wind_avg_mps as f32),
+                    to: WindSpeed::MPS(last.wind_avg_mps as f32),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks `sites` for the `target` day, fetching a forecast per grid cell,
+/// `sources.concurrency` at a time.
+pub async fn check_sites(
+    sources: &ForecastSources,
+    store: Option<&StateStore>,
+    sites: Vec<FlyingSite>,
+    target: Target,
+    min_period_hours: i64,
+    scoring: &ScoringModel,
+    order: ReportOrder,
+) -> CheckedSites {
+    // Sites within the same grid cell share a single forecast.
+    let mut groups: Vec<Vec<(usize, FlyingSite)>> = vec![];
+    let mut cells: HashMap<(i64, i64), usize> = HashMap::new();
+    for (index, site) in sites.into_iter().enumerate() {
+        let group = match sources.grid_cell(&site) {
+            Some(cell) => *cells.entry(cell).or_insert(groups.len()),
+            None => groups.len(),
+        };
+        if group == groups.len() {
+            groups.push(vec![]);
+        }
+        groups[group].push((index, site));
+    }
+    let checked: Vec<Vec<_>> = stream::iter(groups)
+        .map(|group| async move {
+            let forecast = sources.get_forecast(&group[0].1, store).await;
+            // The day failed sites are reported on, as `evaluate_site` would.
+            let today = match &forecast {
+                Ok(forecast) => forecast_today(forecast),
+                Err(_) => forecast_today(&[]),
+            };
+            let mut checked = vec![];
+            for (index, site) in group {
+                let analyzed = match &forecast {
+                    Ok(forecast) => {
+                        let site = site.clone();
+                        let forecast = forecast.clone();
+                        analyze_site_profiles(
+                            sources,
+                            store,
+                            site,
+                            forecast,
+                            target,
+                            min_period_hours,
+                            scoring,
+                        )
+                        .await
+                        .map_err(|error| describe_error(&error))
+                    }
+                    Err(error) => Err(describe_error(error)),
+                };
+                if let Err(error) = &analyzed {
+                    tracing::error!(site = %site.name, %error, "failed to check the site");
+                }
+                checked.push((index, site, today, analyzed));
+            }
+            checked
+        })
+        .buffer_unordered(sources.concurrency.max(1))
+        .collect()
+        .await;
+    let mut checked: Vec<_> = checked.into_iter().flatten().collect();
+    // Sites complete in a different order on every run, the config order
+    // stays the same.
+    checked.sort_by_key(|(index, _, _, _)| *index);
+    let mut reports: Vec<SiteFlyAbilityReport> = vec![];
+    let mut profile_reports: Vec<SiteFlyAbilityReport> = vec![];
+    let mut skipped: Vec<SkippedSite> = vec![];
+    let mut errors: Vec<SiteError> = vec![];
+    for (_, site, today, checked) in checked {
+        match checked {
+            Ok((report, profiled)) => {
+                profile_reports.extend(profiled);
+                match report {
+                    Ok(report) => reports.push(report),
+                    Err(skipped_site) => skipped.push(skipped_site),
+                }
+            }
+            // Failed sites do not keep the others from being reported.
+            Err(error) => {
+                errors.push(SiteError {
+                    site: site.name.clone(),
+                    error,
+                });
+                skipped.push(SkippedSite {
+                    site,
+                    days_ahead: target.days_ahead(today),
+                    reason: SkipReason::NoForecast,
+                    closest_miss: None,
+                });
+            }
+        }
+    }
+    if let Some(store) = store {
+        if let Err(error) = record_history(store, &reports) {
+            tracing::warn!(%error, "failed to record the reports");
+        }
+    }
+    if order == ReportOrder::Score {
+        reports.sort_by(|a, b| b.score.total_cmp(&a.score));
+        profile_reports.sort_by(|a, b| b.score.total_cmp(&a.score));
+    }
+    CheckedSites {
+        reports,
+        profile_reports,
+        skipped,
+        errors,
+    }
+}
+
+/// Keeps the flyable periods of `reports` for the `history` subcommand.
+fn record_history(store: &StateStore, reports: &[SiteFlyAbilityReport]) -> rusqlite::Result<()> {
+    let computed_at = Utc::now().timestamp();
+    for report in reports {
+        for period in &report.periods {
+            store.record_period(&PastPeriod {
+                site: report.site.name.clone(),
+                date: report.date.format("%Y-%m-%d").to_string(),
+                computed_at,
+                days_ahead: report.days_ahead,
+                score: report.score as f64,
+                start: period.start.timestamp(),
+                utc_offset: period.start.offset().local_minus_utc(),
+                duration_hours: period.hours() as f64,
+                wind_min_mps: period.wind_min.meters_per_second() as f64,
+                wind_max_mps: period.wind_max.meters_per_second() as f64,
+                pop_max: period.pop_max as f64,
+                wind_direction: Some(period.wind_directions.middle().degrees()),
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Reports on the days the recipients of `notifiers` want instead of the one
+/// of `reports`, see `Notifier::report_dates`. Only the flyable sites are
+/// kept, the others having been reported with the run.
+#[allow(clippy::too_many_arguments)]
+pub async fn check_report_dates(
+    sources: &ForecastSources,
+    store: Option<&StateStore>,
+    sites: &[FlyingSite],
+    notifiers: &[RoutedNotifier],
+    reports: &[SiteFlyAbilityReport],
+    min_period_hours: i64,
+    scoring: &ScoringModel,
+    order: ReportOrder,
+) -> Vec<SiteFlyAbilityReport> {
+    let mut dates: Vec<NaiveDate> = notifiers
+        .iter()
+        .flat_map(|routed| routed.notifier.report_dates())
+        .collect();
+    dates.sort();
+    dates.dedup();
+    let mut other_days = vec![];
+    for date in dates {
+        if !reports.is_empty() && reports.iter().all(|report| report.date == date) {
+            continue;
+        }
+        let checked = check_sites(
+            sources,
+            store,
+            sites.to_vec(),
+            Target::Date(date),
+            min_period_hours,
+            scoring,
+            order,
+        )
+        .await;
+        let checked_reports = checked.reports.into_iter().chain(checked.profile_reports);
+        other_days.extend(checked_reports.filter(|report| {
+            !reports.iter().any(|r| {
+                r.site.name == report.site.name
+                    && r.site.profile == report.site.profile
+                    && r.date == report.date
+            })
+        }));
+    }
+    other_days
+}
+
+/// Fetches a fresh forecast for `site` and reports on its flyability on the
+/// `target` day, or tells why it is not flyable.
+pub async fn check_site(
+    sources: &ForecastSources,
+    store: Option<&StateStore>,
+    site: FlyingSite,
+    target: Target,
+    min_period_hours: i64,
+    scoring: &ScoringModel,
+) -> Result<Result<SiteFlyAbilityReport, SkippedSite>, ProviderError> {
+    let forecast = sources.get_forecast(&site, store).await?;
+    analyze_site(
+        sources,
+        store,
+        site,
+        forecast,
+        target,
+        min_period_hours,
+        scoring,
+    )
+    .await
+}
+
+/// Reports on the flyability of `site` from its `forecast`, along with the
+/// live observations and airspace restrictions.
+#[tracing::instrument(skip_all, fields(site = %site.name, provider = sources.weather.name()))]
+pub async fn analyze_site(
+    sources: &ForecastSources,
+    store: Option<&StateStore>,
+    site: FlyingSite,
+    forecast: Vec<DayWeatherForecast>,
+    target: Target,
+    min_period_hours: i64,
+    scoring: &ScoringModel,
+) -> Result<Result<SiteFlyAbilityReport, SkippedSite>, ProviderError> {
+    let (forecast, observations) = enrich_forecast(sources, store, &site, forecast).await?;
+    let report = evaluate_site(forecast, site, target, min_period_hours, scoring);
+    complete_report(sources, store, report, &observations).await
+}
+
+/// Like `analyze_site`, along with the flyable reports of the pilot
+/// profiles of the site, evaluated on the same forecast.
+#[tracing::instrument(skip_all, fields(site = %site.name, provider = sources.weather.name()))]
+async fn analyze_site_profiles(
+    sources: &ForecastSources,
+    store: Option<&StateStore>,
+    site: FlyingSite,
+    forecast: Vec<DayWeatherForecast>,
+    target: Target,
+    min_period_hours: i64,
+    scoring: &ScoringModel,
+) -> Result<
+    (
+        Result<SiteFlyAbilityReport, SkippedSite>,
+        Vec<SiteFlyAbilityReport>,
+    ),
+    ProviderError,
+> {
+    let (forecast, observations) = enrich_forecast(sources, store, &site, forecast).await?;
+    let mut profile_reports = vec![];
+    for name in site.profiles.keys() {
+        let profiled = match site.with_profile(name) {
+            Some(profiled) => profiled,
+            None => continue,
+        };
+        let _span = tracing::info_span!("profile", %name).entered();
+        let report = evaluate_site(
+            forecast.clone(),
+            profiled,
+            target,
+            min_period_hours,
+            scoring,
+        );
+        drop(_span);
+        if let Ok(report) = complete_report(sources, store, report, &observations).await? {
+            profile_reports.push(report);
+        }
+    }
+    let report = evaluate_site(forecast, site, target, min_period_hours, scoring);
+    let report = complete_report(sources, store, report, &observations).await?;
+    Ok((report, profile_reports))
+}
+
+/// The forecast of `site` with the waves, tides, snow, model spread and launch
+/// wind, and its live station readings.
+async fn enrich_forecast(
+    sources: &ForecastSources,
+    store: Option<&StateStore>,
+    site: &FlyingSite,
+    mut forecast: Vec<DayWeatherForecast>,
+) -> Result<(Vec<DayWeatherForecast>, Vec<LiveReading>), ProviderError> {
+    if let Some(store) = store {
+        sources.record_forecast(store, site, &forecast)?;
+    }
+    let observations = sources.observe(site, &forecast, store).await?;
+    if let Some(marine) = &sources.marine {
+        let wants_waves = site.coastal || site.max_wave_height.is_some();
+        if wants_waves && sources.quotas.spend(marine.name(), store).await? {
+            let waves = marine.get_waves(site.latitude(), site.longitude()).await;
+            if let Some(waves) = optional("waves", waves) {
+                marine::apply_waves(&mut forecast, &waves);
+            }
+        }
+    }
+    if let (Some(client), Some(station)) = (&sources.tides, &site.tide_station) {
+        if sources.quotas.spend(client.name(), store).await? {
+            let heights = client.get_tides(station).await;
+            if let Some(heights) = optional("tides", heights) {
+                tides::apply_tides(&mut forecast, &heights);
+            }
+        }
+    }
+    if let Some(client) = &sources.winter {
+        let wants_snowpack = site.max_freezing_level_m.is_some()
+            || site.max_snow_depth_m.is_some()
+            || forecast
+                .first()
+                .is_some_and(|day| winter::is_winter(day.date, site.latitude()));
+        if wants_snowpack && sources.quotas.spend(client.name(), store).await? {
+            let snowpack = client.get_snowpack(site.latitude(), site.longitude()).await;
+            if let Some(snowpack) = optional("snowpack", snowpack) {
+                winter::apply_snowpack(&mut forecast, &snowpack);
+            }
+        }
+    }
+    if let Some(client) = &sources.ensemble {
+        if sources.quotas.spend(client.name(), store).await? {
+            let members = client.get_members(site.latitude(), site.longitude()).await;
+            if let Some(members) = optional("model spread", members) {
+                ensemble::apply_spread(&mut forecast, &members);
+            }
+        }
+    }
+    // Stations and forecast accuracy are about the surface wind.
+    if let Some(launch_wind) = &sources.launch_wind {
+        launch_wind.apply_launch_wind(&mut forecast, site);
+    }
+    sources.subdivide(&mut forecast);
+    Ok((forecast, observations))
+}
+
+/// The report of `site` on the `target` day, or the site skipped along with
+/// its closest miss.
+#[allow(clippy::result_large_err)]
+fn evaluate_site(
+    mut forecast: Vec<DayWeatherForecast>,
+    site: FlyingSite,
+    target: Target,
+    min_period_hours: i64,
+    scoring: &ScoringModel,
+) -> Result<SiteFlyAbilityReport, SkippedSite> {
+    let today = forecast_today(&forecast);
+    let date = target.date(today);
+    let closure = site.closure(date).cloned();
+    if closure.as_ref().is_some_and(|closure| !closure.annotate) {
+        tracing::info!("closed");
+        return Err(SkippedSite {
+            site,
+            days_ahead: (date - today).num_days(),
+            reason: SkipReason::Closed,
+            closest_miss: None,
+        });
+    }
+    if site.max_prior_rain_mm.is_some() {
+        forecast_client::apply_prior_rain(&mut forecast);
+    }
+    let closest_miss = site.closest_miss(&forecast, date);
+    match prepare_report_for_site(forecast, site.clone(), date, min_period_hours, scoring) {
+        Ok(mut sfar) => {
+            tracing::info!(score = sfar.score, periods = sfar.periods.len(), "flyable");
+            if let Some(closure) = closure {
+                sfar.warnings.push(closure.describe());
+            }
+            Ok(sfar)
+        }
+        Err(reason) => {
+            tracing::info!(?reason, ?closest_miss, "not flyable");
+            Err(SkippedSite {
+                site,
+                days_ahead: (date - today).num_days(),
+                reason,
+                closest_miss,
+            })
+        }
+    }
+}
+
+/// Adds the `observations` and annotations to a flyable `report`.
+async fn complete_report(
+    sources: &ForecastSources,
+    store: Option<&StateStore>,
+    report: Result<SiteFlyAbilityReport, SkippedSite>,
+    observations: &[LiveReading],
+) -> Result<Result<SiteFlyAbilityReport, SkippedSite>, ProviderError> {
+    let mut sfar = match report {
+        Ok(sfar) => sfar,
+        Err(skipped) => return Ok(Err(skipped)),
+    };
+    sfar.readings = observations.to_vec();
+    sources.annotate_report(&mut sfar, store).await?;
+    Ok(Ok(sfar))
+}
+
+/// `reports` grouped by the region of their site, regions in the order of
+/// their first report, i.e. by best score when the run is ordered by score.
+/// The first report of each region comes with its header, unless no site
+/// has a region.
+pub fn by_region<'a>(
+    reports: &'a [SiteFlyAbilityReport],
+    strings: &Strings,
+) -> Vec<(Option<String>, &'a SiteFlyAbilityReport)> {
+    if reports.iter().all(|report| report.site.region.is_none()) {
+        return reports.iter().map(|report| (None, report)).collect();
+    }
+    let mut regions: Vec<(Option<&str>, Vec<&SiteFlyAbilityReport>)> = vec![];
+    for report in reports {
+        let region = report.site.region.as_deref();
+        match regions.iter_mut().find(|(name, _)| *name == region) {
+            Some((_, group)) => group.push(report),
+            None => regions.push((region, vec![report])),
+        }
+    }
+    regions
+        .into_iter()
+        .flat_map(|(region, group)| {
+            let header = format!("📍 {}", region.unwrap_or(strings.other_sites));
+            group
+                .into_iter()
+                .enumerate()
+                .map(move |(i, report)| ((i == 0).then(|| header.clone()), report))
+        })
+        .collect()
+}
+
+/// Renders all reports of a run as a single message, in the order of the run
+/// grouped `by_region`.
+pub fn render_digest(reports: &[SiteFlyAbilityReport], format: &MessageFormat) -> String {
+    let mut message = String::from("");
+    for (i, (region, report)) in by_region(reports, format.strings()).into_iter().enumerate() {
+        if i > 0 {
+            message.push_str("\n\n");
+        }
+        if let Some(region) = region {
+            message.push_str(&region);
+            message.push_str("\n\n");
+        }
+        if i == 0 && format.labels_top_pick(reports.len()) {
+            message.push_str(format.strings().top_pick);
+        }
+        message.push_str(&report.as_string(format)[..]);
+    }
+    message
+}
+
+/// HTML counterpart of `render_digest`, for channels supporting rich text.
+pub fn render_digest_html(reports: &[SiteFlyAbilityReport], format: &MessageFormat) -> String {
+    let mut message = String::from("<html><body>\n");
+    for (i, (region, report)) in by_region(reports, format.strings()).into_iter().enumerate() {
+        if let Some(region) = region {
+            message.push_str(&format!("<h2>{}</h2>\n", escape_html(&region)));
+        }
+        if i == 0 && format.labels_top_pick(reports.len()) {
+            message.push_str(&format!(
+                "<p>{}</p>\n",
+                escape_html(format.strings().top_pick.trim_end())
+            ));
+        }
+        message.push_str(&report.as_html(format));
+    }
+    message.push_str("</body></html>\n");
+    message
+}
+
+/// The headline of a run where no site is flyable, and the reason per
+/// site below it when `explain_when_empty` is set, with the closest miss
+/// when `explain_closest_miss` is too.
+fn nothing_flyable_sections(skipped: &[SkippedSite], format: &MessageFormat) -> (String, String) {
+    let strings = format.strings();
+    let days_ahead = skipped.first().map_or(1, |s| s.days_ahead);
+    let headline = i18n::fill(
+        strings.nothing_flyable,
+        &[("day", &strings.day(days_ahead))],
+    );
+    if !format.explain_when_empty() {
+        return (headline, String::new());
+    }
+    let reasons: Vec<String> = skipped
+        .iter()
+        .map(|s| {
+            let reason = format!("{}: {}", s.site.name, strings.skip_reason(s.reason));
+            match s
+                .closest_miss
+                .as_ref()
+                .filter(|_| format.explain_closest_miss())
+            {
+                Some(miss) => format!(
+                    "{} {}",
+                    reason,
+                    i18n::fill(
+                        strings.closest_miss_note,
+                        &[("miss", &miss.describe(strings, format.units()))],
+                    )
+                ),
+                None => reason,
+            }
+        })
+        .collect();
+    (headline, reasons.join("\n"))
+}
+
+/// Renders `nothing_flyable_sections` as a single message.
+pub fn render_nothing_flyable(skipped: &[SkippedSite], format: &MessageFormat) -> String {
+    let (headline, reasons) = nothing_flyable_sections(skipped, format);
+    if reasons.is_empty() {
+        headline
+    } else {
+        format!("{}\n{}", headline, reasons)
+    }
+}
+
+/// Sends each notifier the reports of its sites, all at the same time, and
+/// records what each was handed in `deliveries`, in the order of
+/// `notifiers`. A failing notifier leaves the others be, failing the send
+/// once they are done.
+pub async fn send_notifications(
+    notifiers: &[RoutedNotifier],
+    reports: Vec<SiteFlyAbilityReport>,
+    profile_reports: Vec<SiteFlyAbilityReport>,
+    other_days: Vec<SiteFlyAbilityReport>,
+    skipped: Vec<SkippedSite>,
+    format: &MessageFormat,
+    deliveries: &mut Vec<Delivery>,
+) -> Result<(), NotifyError> {
+    let sends = notifiers.iter().map(|routed| {
+        let span = tracing::info_span!("notify", notifier = routed.kind);
+        deliver(
+            routed,
+            &reports,
+            &profile_reports,
+            &other_days,
+            &skipped,
+            format,
+        )
+        .instrument(span)
+    });
+    let sent: Vec<Delivery> = futures::future::join_all(sends)
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+    let failures: Vec<String> = sent
+        .iter()
+        .filter_map(|delivery| {
+            let error = delivery.error.as_ref()?;
+            Some(format!("{}: {}", delivery.notifier, error))
+        })
+        .collect();
+    tracing::info!(
+        notifiers = sent.len(),
+        failed = failures.len(),
+        "notifications sent"
+    );
+    deliveries.extend(sent);
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(NotifyError::Failed {
+            failed: failures.len(),
+            notifiers: notifiers.len(),
+            errors: failures.join("; "),
+        })
+    }
+}
+
+/// Sends `routed` the reports of its sites, or that none is flyable, `None`
+/// when it has nothing to send.
+async fn deliver(
+    routed: &RoutedNotifier,
+    reports: &[SiteFlyAbilityReport],
+    profile_reports: &[SiteFlyAbilityReport],
+    other_days: &[SiteFlyAbilityReport],
+    skipped: &[SkippedSite],
+    format: &MessageFormat,
+) -> Option<Delivery> {
+    let RoutedNotifier {
+        kind,
+        route,
+        notifier,
+    } = routed;
+    let started = std::time::Instant::now();
+    // Delivered first to keep the chats' messages in order, a failure
+    // leaving them for the next run.
+    if let Err(error) = notifier.send_deferred().await {
+        tracing::warn!(%error, "failed to send the deferred messages");
+    }
+    let wants_other_days = !notifier.report_dates().is_empty();
+    let profiles = notifier.profiles();
+    let reports: Vec<SiteFlyAbilityReport> = reports
+        .iter()
+        .chain(profile_reports)
+        .chain(other_days.iter().filter(|_| wants_other_days))
+        .filter(|report| match &report.site.profile {
+            Some(profile) => profiles.contains(profile),
+            None => true,
+        })
+        .filter(|report| route.matches(&report.site))
+        .cloned()
+        .collect();
+    let mut delivery = Delivery {
+        notifier: kind,
+        reports: 0,
+        nothing_flyable: false,
+        error: None,
+        elapsed: Duration::zero(),
+    };
+    let finish = |mut delivery: Delivery| {
+        delivery.elapsed =
+            Duration::from_std(started.elapsed()).unwrap_or_else(|_| Duration::zero());
+        Some(delivery)
+    };
+    if reports.is_empty() && format.notify_when_empty() {
+        let skipped: Vec<SkippedSite> = skipped
+            .iter()
+            .filter(|s| route.matches(&s.site))
+            .cloned()
+            .collect();
+        if !skipped.is_empty() {
+            tracing::debug!(skipped = skipped.len(), "notifying that nothing is flyable");
+            delivery.nothing_flyable = true;
+            if let Err(e) = notifier.notify_nothing_flyable(&skipped).await {
+                tracing::error!(error = %e, "notification failed");
+                delivery.error = Some(e.to_string());
+                return finish(delivery);
+            }
+        }
+    }
+    if reports.is_empty() && !notifier.notify_when_empty() {
+        if delivery.nothing_flyable {
+            return finish(delivery);
+        }
+        return None;
+    }
+    tracing::debug!(reports = reports.len(), "notifying");
+    delivery.reports = reports.len();
+    if let Err(e) = notifier.notify(&reports).await {
+        tracing::error!(error = %e, "notification failed");
+        delivery.error = Some(e.to_string());
+    }
+    finish(delivery)
+}
+
+/// The data fetched from an optional source, or `None` once its failure is
+/// logged: sites are still reported without it rather than failing.
+fn optional<T>(data: &str, fetched: reqwest::Result<T>) -> Option<T> {
+    match fetched {
+        Ok(fetched) => Some(fetched),
+        Err(error) => {
+            tracing::warn!(data, error = %describe_error(&error), "going on without the data");
+            None
+        }
+    }
+}
+
+/// `error` followed by its causes, which are often the telling part, e.g.
+/// "error sending request: client error (Connect): connection refused".
+fn describe_error(error: &dyn std::error::Error) -> String {
+    let mut description = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        description.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    description
+}
+
+/// Sends the failures of a run to the notifiers with an operator audience.
+/// Failing to do so is only logged.
+pub async fn send_error_summary(notifiers: &[RoutedNotifier], summary: &str) {
+    for RoutedNotifier { kind, notifier, .. } in notifiers {
+        if let Err(error) = notifier.notify_errors(summary).await {
+            tracing::warn!(notifier = kind, %error, "failed to send the error summary");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MessageConfig;
+    use crate::i18n::Language;
+    use crate::measures::WindUnit;
+    use crate::testing::{at, site, DayBuilder, HourBuilder};
+
+    fn tomorrow() -> NaiveDate {
+        Utc::now().date_naive() + Duration::days(1)
+    }
+
+    fn format() -> MessageFormat {
+        MessageFormat::new(&MessageConfig::default()).unwrap()
+    }
+
+    fn hour() -> HourBuilder {
+        HourBuilder::new(at(tomorrow(), 12))
+    }
+
+    #[test]
+    fn flyable_hour() {
+        assert!(site("Planfait").is_flyable(&hour().build()));
+    }
+
+    #[test]
+    fn unflyable_hours() {
+        let site = site("Planfait");
+        let cases = [
+            (hour().wind(WindSpeed::MPS(10.0), 270), SkipReason::TooWindy),
+            (hour().wind(WindSpeed::MPS(0.5), 270), SkipReason::TooLight),
+            (
+                hour().wind(WindSpeed::MPS(4.0), 90),
+                SkipReason::WrongDirection,
+            ),
+            (hour().pop(0.9), SkipReason::Rain),
+        ];
+        for (hour, reason) in cases {
+            let hour = hour.build();
+            assert!(!site.is_flyable(&hour));
+            assert_eq!(site.unflyable_reason(&hour), Some(reason));
+        }
+        assert!(!site.is_flyable(&hour().time_of_day(TimeOfDay::NIGHT).build()));
+    }
+
+    #[test]
+    fn flyable_hours_merge_into_periods() {
+        let day = DayBuilder::new(tomorrow())
+            .hours(0, 24, |h| h.wind(WindSpeed::MPS(10.0), 270))
+            .hours(9, 12, |h| h.wind(WindSpeed::MPS(4.0), 270))
+            .hours(14, 18, |h| h.wind(WindSpeed::MPS(4.0), 270))
+            .build();
+        let now = at(tomorrow(), 0).with_timezone(&Utc);
+        let periods = flyable_periods(&site("Planfait"), &day.hourly, 1, now).unwrap();
+
+        let periods: Vec<_> = periods
+            .iter()
+            .map(|p| (p.start, p.duration.num_hours()))
+            .collect();
+        assert_eq!(
+            periods,
+            vec![(at(tomorrow(), 9), 3), (at(tomorrow(), 14), 4)]
+        );
+    }
+
+    #[test]
+    fn short_periods_are_dropped() {
+        let day = DayBuilder::new(tomorrow())
+            .hours(0, 24, |h| h.wind(WindSpeed::MPS(10.0), 270))
+            .hours(9, 11, |h| h.wind(WindSpeed::MPS(4.0), 270))
+            .hours(14, 18, |h| h.wind(WindSpeed::MPS(4.0), 270))
+            .build();
+        let now = at(tomorrow(), 0).with_timezone(&Utc);
+        let site = site("Planfait");
+
+        let periods = flyable_periods(&site, &day.hourly, 3, now).unwrap();
+        assert_eq!(periods.len(), 1);
+        assert_eq!(periods[0].start, at(tomorrow(), 14));
+        assert_eq!(
+            flyable_periods(&site, &day.hourly, 5, now).unwrap_err(),
+            SkipReason::TooShort
+        );
+    }
+
+    #[test]
+    fn past_hours_are_left_out() {
+        let day = DayBuilder::new(tomorrow())
+            .hours(0, 24, |h| h.wind(WindSpeed::MPS(10.0), 270))
+            .hours(9, 15, |h| h.wind(WindSpeed::MPS(4.0), 270))
+            .build();
+        let now = at(tomorrow(), 12).with_timezone(&Utc);
+        let periods = flyable_periods(&site("Planfait"), &day.hourly, 1, now).unwrap();
+
+        assert_eq!(periods[0].start, at(tomorrow(), 12));
+        assert_eq!(periods[0].duration, Duration::hours(3));
+    }
+
+    #[test]
+    fn renders_flyable_sites() {
+        let day = DayBuilder::new(tomorrow())
+            .hours(0, 24, |h| h.wind(WindSpeed::MPS(10.0), 270))
+            .hours(10, 14, |h| h.wind(WindSpeed::MPS(4.0), 270))
+            .build();
+        let report = prepare_report_for_site(
+            vec![day],
+            site("Planfait"),
+            tomorrow(),
+            1,
+            &ScoringModel::default(),
+        )
+        .unwrap();
+        let message = render_digest(&[report], &format());
+
+        assert!(message.contains("Planfait"), "{}", message);
+        assert!(message.contains("10:00"), "{}", message);
+    }
+
+    #[test]
+    fn renders_sites_with_nothing_flyable() {
+        let skipped = SkippedSite {
+            site: site("Planfait"),
+            days_ahead: 1,
+            reason: SkipReason::TooWindy,
+            closest_miss: None,
+        };
+        assert_eq!(
+            render_nothing_flyable(std::slice::from_ref(&skipped), &format()),
+            "Nothing is flyable tomorrow."
+        );
+
+        let explaining = MessageFormat::new(&MessageConfig {
+            explain_when_empty: true,
+            ..MessageConfig::default()
+        })
+        .unwrap();
+        let message = render_nothing_flyable(&[skipped], &explaining);
+        assert!(
+            message.starts_with("Nothing is flyable tomorrow.\nPlanfait: "),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn renders_the_closest_miss_in_the_message_language() {
+        let skipped = SkippedSite {
+            site: site("Planfait"),
+            days_ahead: 1,
+            reason: SkipReason::TooWindy,
+            closest_miss: Some(ClosestMiss {
+                time: at(tomorrow(), 14),
+                misses: vec![Miss {
+                    check: Check::MaxWind,
+                    value: 8.2,
+                    limit: 7.0,
+                }],
+            }),
+        };
+        let german = MessageFormat::new(&MessageConfig {
+            language: Language::De,
+            units: Units {
+                wind: WindUnit::Mps,
+                ..Units::default()
+            },
+            explain_when_empty: true,
+            explain_closest_miss: true,
+            ..MessageConfig::default()
+        })
+        .unwrap();
+        let message = render_nothing_flyable(&[skipped], &german);
+        assert!(
+            message.ends_with("(am knappsten um 14:00, Wind 8.2 m/s (max. 7.0))"),
+            "{}",
+            message
+        );
+    }
+}
@@ -0,0 +1,39 @@
+//! Diagnostics written to stderr, as text for terminals or as JSON lines
+//! for log collectors under systemd or Kubernetes.
+
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Levels selected by repeating `-v` on top of the default `warn`.
+const VERBOSITY_LEVELS: [&str; 4] = ["warn", "info", "debug", "trace"];
+
+/// The level for `verbosity` times `-v`, `--log-level` taking precedence.
+pub fn level(log_level: Option<&str>, verbosity: u64) -> &str {
+    log_level
+        .unwrap_or_else(|| VERBOSITY_LEVELS[(verbosity as usize).min(VERBOSITY_LEVELS.len() - 1)])
+}
+
+/// Installs the global subscriber. `RUST_LOG` overrides `level`, e.g. to
+/// trace a single module. Errors are also sent to Sentry once `init_sentry`
+/// has run, warnings becoming their breadcrumbs.
+pub fn init(level: &str, json: bool) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("weather_notifier={}", level)));
+    let registry = tracing_subscriber::registry()
+        .with(filter)
+        .with(sentry_tracing::layer());
+    let fmt = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+    if json {
+        registry.with(fmt.json()).init();
+    } else {
+        registry.with(fmt).init();
+    }
+}
+
+/// Reports panics and errors to Sentry until the returned guard is dropped.
+pub fn init_sentry(dsn: &str) -> sentry::ClientInitGuard {
+    let mut options = sentry::ClientOptions::default();
+    options.release = sentry::release_name!();
+    sentry::init((dsn, options))
+}
@@ -1,175 +1,562 @@
-mod config;
-mod forecast_client;
-mod measures;
-mod notification;
-
-use crate::config::FlyingSite;
-use crate::forecast_client::{
-    DayWeatherForecast, HourWeatherForecast, OpenWeatherMapClient, TimeOfDay,
+use chrono::{Datelike, Duration, FixedOffset, Local, NaiveDate, TimeZone};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use weather_notifier::backtest::{self, ArchiveClient, Backtest, BacktestDay};
+use weather_notifier::config::{
+    ApplicationConfig, FlyingSite, HttpConfig, NotifierConfig, RetryConfig, Target,
+};
+use weather_notifier::digest::{self, SiteDigest};
+use weather_notifier::error::ProviderError;
+use weather_notifier::geocoding::{self, Geocoder};
+use weather_notifier::measures::{Units, WindDirection, WindSpeed};
+use weather_notifier::message::MessageFormat;
+use weather_notifier::paragliding_earth::{self, ParaglidingEarthClient};
+use weather_notifier::scan::{self, Scan, ScanOutcome};
+use weather_notifier::scoring::ScoringModel;
+use weather_notifier::state_dump::{self, StateDump};
+use weather_notifier::store::{StateStore, ALL_SITES};
+use weather_notifier::waypoints::Waypoint;
+use weather_notifier::{
+    admin, bot, check_report_dates, check_site, check_sites, config, daemon, error, export,
+    forecast_trend, format_hours, heartbeat, http, i18n, logging, notification, send_error_summary,
+    send_notifications, server, waypoints, Delivery, ForecastSources, WindTrend,
 };
-use crate::measures::{Temperature, WindSpeed};
-use crate::notification::TelegramClient;
-use chrono::{DateTime, Duration, FixedOffset, Utc};
-use clap::{App, Arg};
-use std::path::Path;
 
-impl FlyingSite {
-    fn is_flyable(&self, hour: &HourWeatherForecast) -> bool {
-        !(hour.pop > 0.3
-            || hour.time_of_day != TimeOfDay::DAY
-            || self.min_flyable_wind_degree > hour.wind_deg
-            || hour.wind_deg > self.max_flyable_wind_degree
-            || self.min_flyable_wind > hour.wind_speed
-            || hour.wind_speed > self.max_flyable_wind)
+fn print_stats(store: Option<&StateStore>) -> Result<(), Box<dyn std::error::Error>> {
+    let store = store.ok_or("`state_db` must be configured to collect statistics")?;
+    println!(
+        "{:<24} {:<16} {:<16} {:>7} {:>10} {:>10}",
+        "Site", "Provider", "Station", "Samples", "Bias MPH", "MAE MPH"
+    );
+    for stats in store.wind_accuracy()? {
+        println!(
+            "{:<24} {:<16} {:<16} {:>7} {:>+10.1} {:>10.1}",
+            stats.site,
+            stats.provider,
+            stats.station,
+            stats.samples,
+            WindSpeed::MPS(stats.bias_mps as f32).miles_per_hour(),
+            WindSpeed::MPS(stats.mean_absolute_error_mps as f32).miles_per_hour(),
+        );
     }
+    Ok(())
 }
 
-#[derive(Debug)]
-struct SiteFlyablePeriod {
-    start: DateTime<FixedOffset>,
-    duration_hours: i64,
-    wind_min: WindSpeed,
-    wind_max: WindSpeed,
-    wind_degree_min: i16,
-    wind_degree_max: i16,
-    temp_min: Temperature,
-    temp_max: Temperature,
-}
-
-impl SiteFlyablePeriod {
-    fn from_hour(hour: &HourWeatherForecast) -> Self {
-        Self {
-            start: hour.time,
-            duration_hours: 1,
-            wind_min: hour.wind_speed,
-            wind_max: hour.wind_speed,
-            wind_degree_min: hour.wind_deg,
-            wind_degree_max: hour.wind_deg,
-            temp_min: hour.temperature,
-            temp_max: hour.temperature,
+/// Flyable days per month of every site from `since`, with the average
+/// length of the periods and the directions flown most, after the latest
+/// report of each day.
+fn print_flyability_stats(
+    store: Option<&StateStore>,
+    since: Option<NaiveDate>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let store = store.ok_or("`state_db` must be configured to keep the report history")?;
+    let since = since.map_or(String::new(), |since| since.format("%Y-%m-%d").to_string());
+    let periods = store.latest_periods(&since)?;
+    println!(
+        "{:<24} {:<7} {:>4} {:>10} Directions",
+        "Site", "Month", "Days", "Avg window"
+    );
+    for site_periods in periods.chunk_by(|a, b| a.site == b.site) {
+        for month in site_periods.chunk_by(|a, b| a.date[..7] == b.date[..7]) {
+            let mut days: Vec<&str> = month.iter().map(|period| &period.date[..]).collect();
+            days.dedup();
+            let hours: f64 = month.iter().map(|period| period.duration_hours).sum();
+            let mut directions: Vec<(&str, f64)> = vec![];
+            for period in month {
+                let cardinal = match period.wind_direction {
+                    Some(degrees) => WindDirection::new(degrees).cardinal(),
+                    None => continue,
+                };
+                match directions.iter_mut().find(|(c, _)| *c == cardinal) {
+                    Some((_, total)) => *total += period.duration_hours,
+                    None => directions.push((cardinal, period.duration_hours)),
+                }
+            }
+            directions.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+            let directions: Vec<String> = directions
+                .iter()
+                .take(3)
+                .map(|(cardinal, total)| format!("{} {:.0}%", cardinal, 100.0 * total / hours))
+                .collect();
+            println!(
+                "{:<24} {:<7} {:>4} {:>9.1}h {}",
+                month[0].site,
+                &month[0].date[..7],
+                days.len(),
+                hours / month.len() as f64,
+                directions.join(", "),
+            );
         }
     }
+    Ok(())
+}
 
-    fn is_next_hour(&self, hour: &HourWeatherForecast) -> bool {
-        self.start + Duration::hours(self.duration_hours) == hour.time
+/// Lists the flyable periods reported for `site` on days from `since`, and
+/// how many days had some.
+fn print_history(
+    store: Option<&StateStore>,
+    site: &str,
+    since: NaiveDate,
+    units: Units,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let store = store.ok_or("`state_db` must be configured to keep the report history")?;
+    let periods = store.past_periods(site, &since.format("%Y-%m-%d").to_string())?;
+    let wind_label = format!("Wind {}", units.wind.label());
+    println!(
+        "{:<10} {:<16} {:>5} {:>5} {:<11} {:>11} {:>6}",
+        "Day", "Computed", "Ahead", "Score", "Window", wind_label, "Rain"
+    );
+    for period in &periods {
+        let offset = FixedOffset::east_opt(period.utc_offset).ok_or("invalid UTC offset")?;
+        let start = offset.timestamp_opt(period.start, 0).unwrap();
+        let end = start + Duration::minutes((period.duration_hours * 60.0).round() as i64);
+        println!(
+            "{:<10} {:<16} {:>5} {:>5.0} {:<11} {:>11} {:>5.0}%",
+            period.date,
+            Local
+                .timestamp_opt(period.computed_at, 0)
+                .unwrap()
+                .format("%Y-%m-%d %H:%M"),
+            period.days_ahead,
+            period.score,
+            format!("{}–{}", start.format("%H:%M"), end.format("%H:%M")),
+            format!(
+                "{}–{}",
+                WindSpeed::MPS(period.wind_min_mps as f32).format(units.wind),
+                WindSpeed::MPS(period.wind_max_mps as f32).format(units.wind)
+            ),
+            period.pop_max * 100.0,
+        );
     }
+    let mut days: Vec<&str> = periods.iter().map(|period| &period.date[..]).collect();
+    days.dedup();
+    println!(
+        "{} flyable days predicted for {} since {}",
+        days.len(),
+        site,
+        since
+    );
+    Ok(())
+}
 
-    fn add_hour(&mut self, hour: &HourWeatherForecast) {
-        self.duration_hours += 1;
-        if self.wind_min > hour.wind_speed {
-            self.wind_min = hour.wind_speed;
+/// Lists the daytime wind forecast for `site` on `date` by the latest `runs`
+/// runs, and whether it has been trending up or down.
+fn print_trend(
+    store: Option<&StateStore>,
+    site: &str,
+    date: NaiveDate,
+    runs: usize,
+    units: Units,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let store = store.ok_or("`state_db` must be configured to keep the forecasts")?;
+    let runs = store.forecast_runs(site, &date.format("%Y-%m-%d").to_string(), runs)?;
+    let wind = |mps: f64| WindSpeed::MPS(mps as f32).format(units.wind);
+    let label = units.wind.label();
+    println!(
+        "{:<16} {:>10} {:>10} {:>10}",
+        "Fetched",
+        format!("Avg {}", label),
+        format!("Max {}", label),
+        format!("Gust {}", label)
+    );
+    for run in &runs {
+        println!(
+            "{:<16} {:>10} {:>10} {:>10}",
+            Local
+                .timestamp_opt(run.fetched_at, 0)
+                .unwrap()
+                .format("%Y-%m-%d %H:%M"),
+            wind(run.wind_avg_mps),
+            wind(run.wind_max_mps),
+            run.gust_max_mps.map_or_else(|| String::from("–"), wind),
+        );
+    }
+    let verdict = if runs.len() < 2 {
+        String::from("not enough forecasts yet")
+    } else {
+        match forecast_trend(&runs) {
+            Some((trend, change)) => format!(
+                "trending {} by {}",
+                match trend {
+                    WindTrend::Strengthening => "up",
+                    WindTrend::BackingOff => "down",
+                },
+                WindSpeed::MPS(change.meters_per_second().abs()).format(units.wind)
+            ),
+            None => String::from("steady"),
         }
-        if self.wind_max < hour.wind_speed {
-            self.wind_max = hour.wind_speed;
+    };
+    println!(
+        "Daytime wind forecast for {} on {} over {} runs: {}",
+        site,
+        date,
+        runs.len(),
+        verdict
+    );
+    Ok(())
+}
+
+/// Lists the flyable spots found by a scan, best first, with their longest
+/// period.
+fn print_scan(outcome: &ScanOutcome, units: Units) {
+    let wind_label = format!("Wind {}", units.wind.label());
+    println!(
+        "{:>4} {:>9} {:>10} {:>5} {:>5} {:<11} {:>11} Direction",
+        "Rank", "Latitude", "Longitude", "Score", "Hours", "Window", wind_label
+    );
+    for (rank, report) in outcome.reports.iter().enumerate() {
+        let period = report.periods.iter().max_by_key(|p| p.duration);
+        let (window, wind, direction) = match period {
+            Some(period) => (
+                format!(
+                    "{}–{}",
+                    period.start.format("%H:%M"),
+                    period.end().format("%H:%M")
+                ),
+                format!(
+                    "{}–{}",
+                    period.wind_min.format(units.wind),
+                    period.wind_max.format(units.wind)
+                ),
+                period.wind_directions.cardinal(),
+            ),
+            None => (String::from("all day"), String::new(), String::new()),
+        };
+        println!(
+            "{:>4} {:>9.3} {:>10.3} {:>5.0} {:>5} {:<11} {:>11} {}",
+            rank + 1,
+            report.site.latitude(),
+            report.site.longitude(),
+            report.score,
+            format_hours(report.flyable_hours()),
+            window,
+            wind,
+            direction
+        );
+    }
+    println!(
+        "{} flyable of {} points checked, {} without forecast",
+        outcome.reports.len(),
+        outcome.checked,
+        outcome.failed
+    );
+}
+
+/// Lists how many days of a backtest of `site` were flyable per month, why
+/// the others were not, and with `per_day` the outcome of every day.
+fn print_backtest(backtest: &Backtest, site: &str, per_day: bool, format: &MessageFormat) {
+    let strings = format.strings();
+    if per_day {
+        println!("{:<10} Outcome", "Day");
+        for day in &backtest.days {
+            let outcome = match &day.outcome {
+                Ok(periods) => periods
+                    .iter()
+                    .map(|period| {
+                        format!(
+                            "{}–{}",
+                            period.start.format("%H:%M"),
+                            period.end().format("%H:%M")
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                Err(reason) => strings.skip_reason(*reason).to_string(),
+            };
+            println!("{:<10} {}", day.date, outcome);
         }
-        if self.wind_degree_min > hour.wind_deg {
-            self.wind_degree_min = hour.wind_deg;
+        println!();
+    }
+    println!(
+        "{:<7} {:>4} {:>7} {:>6}",
+        "Month", "Days", "Flyable", "Hours"
+    );
+    let same_month = |a: &BacktestDay, b: &BacktestDay| {
+        (a.date.year(), a.date.month()) == (b.date.year(), b.date.month())
+    };
+    for month in backtest.days.chunk_by(same_month) {
+        println!(
+            "{:<7} {:>4} {:>7} {:>6}",
+            month[0].date.format("%Y-%m"),
+            month.len(),
+            month.iter().filter(|day| day.outcome.is_ok()).count(),
+            format_hours(month.iter().map(BacktestDay::flyable_hours).sum()),
+        );
+    }
+    let days = backtest.days.len();
+    let flyable = backtest.flyable_days();
+    println!(
+        "{} of {} days flyable at {} ({:.0}%), {} flyable hours",
+        flyable,
+        days,
+        site,
+        100.0 * flyable as f32 / days.max(1) as f32,
+        format_hours(backtest.flyable_hours()),
+    );
+    for (reason, count) in backtest.reasons() {
+        println!("{:>4} days {}", count, strings.skip_reason(reason));
+    }
+}
+
+/// Whether `name` matches `pattern`, ignoring case, where `*` stands for
+/// any run of characters and `?` for a single one.
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where to resume after the last `*` when the rest fails to match.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
         }
-        if self.wind_degree_max < hour.wind_deg {
-            self.wind_degree_max = hour.wind_deg;
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Command line options taking precedence over the config file, applied
+/// again whenever a daemon reloads it.
+#[derive(Clone)]
+struct Overrides {
+    site_patterns: Vec<String>,
+    target: Option<Target>,
+    min_flyable_wind: Option<WindSpeed>,
+    max_flyable_wind: Option<WindSpeed>,
+}
+
+impl Overrides {
+    fn apply(&self, app_config: &mut ApplicationConfig) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.site_patterns.is_empty() {
+            app_config.sites.retain(|site| {
+                self.site_patterns
+                    .iter()
+                    .any(|pattern| matches_glob(pattern, &site.name))
+            });
+            if app_config.sites.is_empty() {
+                return Err(format!("no site matches {}", self.site_patterns.join(", ")).into());
+            }
         }
-        if self.temp_min > hour.temperature {
-            self.temp_min = hour.temperature;
+        if let Some(target) = self.target {
+            app_config.target = target;
         }
-        if self.temp_max < hour.temperature {
-            self.temp_max = hour.temperature;
+        for site in &mut app_config.sites {
+            if let Some(min) = self.min_flyable_wind {
+                site.min_flyable_wind = Some(min);
+            }
+            if let Some(max) = self.max_flyable_wind {
+                site.max_flyable_wind = Some(max);
+            }
         }
+        Ok(())
     }
 }
 
-#[derive(Debug)]
-struct SiteFlyAbilityReport {
-    site: FlyingSite,
-    periods: Vec<SiteFlyablePeriod>,
-}
-
-impl SiteFlyAbilityReport {
-    fn as_string(&self) -> String {
-        let mut repr = format!("{name} is flyable tomorrow:", name = self.site.name);
-        for period in &self.periods {
-            let period_descr = format!(
-                "\n- Starting at {time} for {duration} hours. \
-            Wind from {min_wind:.1} to {max_wind:.1} MPH. \
-            Direction from {min_deg:.1} to {max_deg:.1} degrees. \
-            Temperature from {min_t:.1}F to {max_t:.1}F",
-                time = period.start.format("%H:%M"),
-                duration = period.duration_hours,
-                min_wind = period.wind_min.miles_per_hour(),
-                max_wind = period.wind_max.miles_per_hour(),
-                min_deg = period.wind_degree_min,
-                max_deg = period.wind_degree_max,
-                min_t = period.temp_min.fahrenheit(),
-                max_t = period.temp_max.fahrenheit(),
-            );
-            repr.push_str(&period_descr[..]);
+/// Loads the daemon settings from `config_paths` on every call.
+fn settings_loader(
+    config_paths: Vec<PathBuf>,
+    overrides: Overrides,
+    http: http::HttpClient,
+) -> daemon::Reload {
+    Box::new(move || {
+        let mut app_config = config::load_config(&config_paths)?;
+        let store = app_config
+            .state_db
+            .as_deref()
+            .map(StateStore::open)
+            .transpose()?;
+        if let Some(store) = &store {
+            admin::apply_site_changes(&mut app_config.sites, store)?;
         }
-        repr
-    }
+        overrides.apply(&mut app_config)?;
+        // Called from the runtime, which must not block on its own tasks.
+        tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(resolve_locations(
+                &mut app_config,
+                &http,
+                store.as_ref(),
+            ))
+        })?;
+        let telegram = app_config
+            .notifiers
+            .iter()
+            .find_map(|entry| match &entry.notifier {
+                NotifierConfig::Telegram(telegram) => Some(telegram),
+                _ => None,
+            });
+        Ok(daemon::Settings {
+            admins: telegram.map_or_else(Vec::new, |telegram| telegram.admin_chat_ids.clone()),
+            chat_units: telegram.map_or_else(HashMap::new, |telegram| telegram.chat_units.clone()),
+            outbox: telegram.map(notification::telegram::Outbox::new),
+            format: MessageFormat::new(&app_config.message)?,
+            sites: app_config.sites,
+            target: app_config.target,
+            min_period_hours: app_config.min_period_hours,
+            scoring: app_config.scoring,
+            order: app_config.message.order,
+        })
+    })
 }
 
-fn prepare_report_for_site(
-    forecasts: Vec<DayWeatherForecast>,
-    site: FlyingSite,
-) -> Option<SiteFlyAbilityReport> {
-    if forecasts.is_empty() {
-        return None;
-    }
-
-    let tz = forecasts[0].date.timezone();
-    let tomorrow = (Utc::now().with_timezone(&tz) + Duration::days(1)).date();
-    let forecast = forecasts.into_iter().find(|f| f.date == tomorrow);
-    forecast.as_ref()?;
+/// Geocodes the sites defined by their `location`.
+async fn resolve_locations(
+    app_config: &mut ApplicationConfig,
+    http: &http::HttpClient,
+    store: Option<&StateStore>,
+) -> Result<(), config::ConfigError> {
+    let geocoder = Geocoder::new(
+        app_config.geocoding_api_url.clone(),
+        app_config.weather_api_token.clone(),
+        http.clone(),
+    );
+    geocoding::resolve_sites(&mut app_config.sites, &geocoder, store).await
+}
 
-    let forecast = forecast.unwrap();
-    let mut flying_hours = vec![];
-    for hour in forecast.hourly {
-        if site.is_flyable(&hour) {
-            flying_hours.push(hour);
-        }
+/// Appends the site stubs of `waypoints` to `to`, or prints them on dry
+/// runs.
+fn import_sites(
+    waypoints: &[Waypoint],
+    to: &Path,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let stubs = waypoints::site_stubs(waypoints);
+    if dry_run {
+        print!("{}", stubs);
+        return Ok(());
     }
-    if flying_hours.is_empty() {
-        return None;
+    let mut sites_file = fs::OpenOptions::new().create(true).append(true).open(to)?;
+    sites_file.write_all(stubs.as_bytes())?;
+    println!(
+        "Added {} sites to {}, set their wind limits and sectors",
+        waypoints.len(),
+        to.display()
+    );
+    Ok(())
+}
+
+/// Completes `summary` with the end of the run and writes it to the file of
+/// `--summary-file`, if any. Failing to do so is only logged.
+fn write_summary(
+    matches: &ArgMatches,
+    mut summary: export::RunSummaryView,
+    exit_code: i32,
+    error: Option<String>,
+    deliveries: &[Delivery],
+) {
+    let path = match matches.value_of("summary-file") {
+        Some(path) => path,
+        None => return,
+    };
+    summary.finish(exit_code, error, deliveries);
+    let written = serde_json::to_string_pretty(&summary)
+        .map_err(|error| error.to_string())
+        .and_then(|json| fs::write(path, json + "\n").map_err(|error| error.to_string()));
+    if let Err(error) = written {
+        tracing::error!(%path, %error, "failed to write the run summary");
     }
-    let mut periods = vec![];
-    let mut current_period = SiteFlyablePeriod::from_hour(&flying_hours[0]);
-    for hour in flying_hours.iter().skip(1) {
-        if current_period.is_next_hour(hour) {
-            current_period.add_hour(hour);
-        } else {
-            periods.push(current_period);
-            current_period = SiteFlyablePeriod::from_hour(hour);
-        }
+}
+
+/// The `--to` file of an import, or the last config file. The sites are
+/// written as TOML, so a YAML or JSON target is refused.
+fn import_target(
+    import: &ArgMatches,
+    config_paths: &[PathBuf],
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let target = import
+        .value_of("to")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config_paths[config_paths.len() - 1].clone());
+    if target
+        .extension()
+        .is_none_or(|extension| extension != "toml")
+    {
+        return Err(format!(
+            "{} is not a TOML file, pass one with --to",
+            target.display()
+        )
+        .into());
     }
-    periods.push(current_period);
-    Some(SiteFlyAbilityReport { site, periods })
+    Ok(target)
 }
 
-async fn check_sites(
-    client: &OpenWeatherMapClient,
-    sites: Vec<FlyingSite>,
-) -> Result<Vec<SiteFlyAbilityReport>, Box<dyn std::error::Error>> {
-    let mut reports: Vec<SiteFlyAbilityReport> = vec![];
+/// Prints the configured sites with their wind limits in `units`.
+fn print_sites(sites: &[FlyingSite], units: Units, default_min_period_hours: i64) {
+    let wind_label = format!("Wind {}", units.wind.label());
+    println!(
+        "{:<24} {:>9} {:>10} {:>11} {:>10} Sectors",
+        "Site", "Latitude", "Longitude", wind_label, "Min period"
+    );
     for site in sites {
-        let forecast = client.get_forecast(site.latitude, site.longitude).await?;
-        let report = prepare_report_for_site(forecast, site);
-        if let Some(sfar) = report {
-            reports.push(sfar);
+        let mut sectors: Vec<String> = site
+            .wind_sectors
+            .iter()
+            .map(|sector| format!("{}–{}°", sector.from, sector.to))
+            .collect();
+        if site.rule.is_some() {
+            sectors = vec![String::from("custom rule")];
         }
+        println!(
+            "{:<24} {:>9.4} {:>10.4} {:>11} {:>9}h {}",
+            site.name,
+            site.latitude(),
+            site.longitude(),
+            format!(
+                "{}–{}",
+                site.min_flyable_wind().format(units.wind),
+                site.max_flyable_wind().format(units.wind)
+            ),
+            site.min_period_hours.unwrap_or(default_min_period_hours),
+            sectors.join(", "),
+        );
     }
-    Ok(reports)
 }
 
-async fn send_notifications(
-    client: &TelegramClient,
-    user_ids: Vec<String>,
-    reports: Vec<SiteFlyAbilityReport>,
+/// Prints the flyability of a single site to stdout, without notifying
+/// anyone nor recording the forecast.
+async fn print_forecast(
+    sources: &ForecastSources,
+    site: FlyingSite,
+    target: Target,
+    min_period_hours: i64,
+    scoring: &ScoringModel,
+    format: &MessageFormat,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut message = String::from("");
-    for report in reports {
-        message.push_str(&report.as_string()[..]);
-    }
-    for user_id in user_ids {
-        client.notify(user_id, &message).await?;
+    let (site_name, site_type) = (site.name.clone(), site.site_type);
+    let days_ahead = target.days_ahead(Local::now().date_naive());
+    match check_site(sources, None, site, target, min_period_hours, scoring).await? {
+        Ok(report) => println!("{}", report.as_string(format)),
+        Err(skipped) => {
+            let strings = format.strings();
+            println!(
+                "{}\n{}",
+                i18n::fill(
+                    strings.not_flyable_for(site_type),
+                    &[("site", &site_name), ("day", &strings.day(days_ahead))],
+                ),
+                strings.skip_reason(skipped.reason)
+            );
+            if let Some(miss) = &skipped.closest_miss {
+                println!(
+                    "{}",
+                    i18n::fill(
+                        strings.closest_miss,
+                        &[("miss", &miss.describe(strings, format.units()))],
+                    )
+                );
+            }
+        }
     }
     Ok(())
 }
@@ -184,23 +571,865 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .long("config")
                 .required(true)
                 .value_name("FILE")
-                .help("Sets a custom config file")
+                .help("Sets a custom config file, repeat to merge several")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("Prints the messages to stdout instead of sending them"),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .multiple(true)
+                .help("Logs more details, repeat for even more"),
+        )
+        .arg(
+            Arg::with_name("log-level")
+                .long("log-level")
+                .value_name("LEVEL")
+                .help("Logs events at LEVEL and above, overriding -v")
+                .takes_value(true)
+                .possible_values(&["error", "warn", "info", "debug", "trace"]),
+        )
+        .arg(
+            Arg::with_name("log-format")
+                .long("log-format")
+                .value_name("FORMAT")
+                .help("Writes the logs to stderr in FORMAT")
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .default_value("text"),
+        )
+        .arg(
+            Arg::with_name("no-cache")
+                .long("no-cache")
+                .help("Fetches fresh forecasts even when cached ones are still valid"),
+        )
+        .arg(
+            Arg::with_name("site")
+                .long("site")
+                .value_name("NAME")
+                .help("Only checks the sites matching NAME, which may use * and ? wildcards")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("min-wind")
+                .long("min-wind")
+                .value_name("SPEED")
+                .help("Overrides the lightest flyable wind of every site, e.g. \"8 kmh\"")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-wind")
+                .long("max-wind")
+                .value_name("SPEED")
+                .help("Overrides the strongest flyable wind of every site, e.g. \"12 mph\"")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .value_name("FORMAT")
+                .help("Prints the reports in FORMAT to stdout instead of sending them")
+                .takes_value(true)
+                .possible_values(&["json"]),
+        )
+        .arg(
+            Arg::with_name("date")
+                .long("date")
+                .value_name("YYYY-MM-DD")
+                .help("Reports on this day, within the forecast horizon")
+                .takes_value(true)
+                .conflicts_with("target"),
+        )
+        .arg(
+            Arg::with_name("target")
+                .long("target")
+                .value_name("DAY")
+                .help("Reports on today, tomorrow or +N days, overriding `target`")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("summary-file")
+                .long("summary-file")
+                .value_name("FILE")
+                .help("Writes a JSON summary of the notification run to FILE")
+                .takes_value(true),
+        )
+        .after_help(error::EXIT_CODES_HELP)
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("Reports forecast wind accuracy, or the flyability of past seasons")
+                .arg(
+                    Arg::with_name("kind")
+                        .value_name("KIND")
+                        .help("wind: forecast bias and error against station observations, flyability: flyable days per month from the report history")
+                        .possible_values(&["wind", "flyability"])
+                        .default_value("wind"),
+                )
+                .arg(
+                    Arg::with_name("since")
+                        .long("since")
+                        .value_name("YYYY-MM-DD")
+                        .help("First day of the flyability statistics")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("Lists the flyable periods reported by past runs for a site")
+                .arg(
+                    Arg::with_name("site")
+                        .long("site")
+                        .required(true)
+                        .value_name("NAME")
+                        .help("Name of a configured site")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("since")
+                        .long("since")
+                        .required(true)
+                        .value_name("YYYY-MM-DD")
+                        .help("First day to list")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("trend")
+                .about("Shows how the forecast wind for a site on the target day changed across runs")
+                .arg(
+                    Arg::with_name("site")
+                        .long("site")
+                        .required(true)
+                        .value_name("NAME")
+                        .help("Name of a configured site")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("runs")
+                        .long("runs")
+                        .value_name("N")
+                        .help("Number of latest runs to compare")
+                        .takes_value(true)
+                        .default_value("5"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("scan")
+                .about("Scans a bounding box for flyable spots on the target day, wind from any direction")
+                .arg(
+                    Arg::with_name("bbox")
+                        .long("bbox")
+                        .required(true)
+                        .value_name("SOUTH,WEST,NORTH,EAST")
+                        .help("Corners of the area in degrees, e.g. 45.5,5.8,46.2,6.9")
+                        .takes_value(true)
+                        .allow_hyphen_values(true),
+                )
+                .arg(
+                    Arg::with_name("step")
+                        .long("step")
+                        .value_name("DEGREES")
+                        .help("Distance between the grid points")
+                        .takes_value(true)
+                        .default_value("0.25"),
+                )
+                .arg(
+                    Arg::with_name("min-wind")
+                        .long("min-wind")
+                        .value_name("SPEED")
+                        .help("Lightest flyable wind")
+                        .takes_value(true)
+                        .default_value("2 mps"),
+                )
+                .arg(
+                    Arg::with_name("max-wind")
+                        .long("max-wind")
+                        .value_name("SPEED")
+                        .help("Strongest flyable wind")
+                        .takes_value(true)
+                        .default_value("7 mps"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .value_name("COUNT")
+                        .help("Most flyable spots listed")
+                        .takes_value(true)
+                        .default_value("10"),
+                )
+                .arg(
+                    Arg::with_name("notify")
+                        .long("notify")
+                        .help("Sends the listed spots through the notifiers as well"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("backtest")
+                .about("Replays the limits of a site over past weather, counting the flyable days")
+                .arg(
+                    Arg::with_name("site")
+                        .long("site")
+                        .required(true)
+                        .value_name("NAME")
+                        .help("Name of a configured site")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .required(true)
+                        .value_name("YYYY-MM-DD")
+                        .help("First day to replay")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .value_name("YYYY-MM-DD")
+                        .help("Last day to replay, defaults to yesterday")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("days")
+                        .long("days")
+                        .help("Lists the flyable periods of every day as well"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("digest")
+                .about("Sends a wind rose and the flyable hours of the past days per site, e.g. on Sunday evenings")
+                .arg(
+                    Arg::with_name("days")
+                        .long("days")
+                        .value_name("N")
+                        .help("Number of days up to today")
+                        .takes_value(true)
+                        .default_value("7"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("bot")
+                .about("Runs the Telegram bot letting chats subscribe to sites"),
+        )
+        .subcommand(
+            SubCommand::with_name("export-state")
+                .about("Dumps the bot subscriptions, chat settings and site changes of `state_db` as JSON")
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .value_name("FILE")
+                        .help("Writes to FILE instead of the standard output")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import-state")
+                .about("Loads a dump of export-state into `state_db`, e.g. on a new server")
+                .arg(
+                    Arg::with_name("file")
+                        .required(true)
+                        .value_name("FILE")
+                        .help("JSON written by export-state"),
+                )
+                .arg(
+                    Arg::with_name("replace")
+                        .long("replace")
+                        .help("Drops the bot state already in `state_db` instead of merging with it"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("validate-config")
+                .about("Checks the config file and explains the problems found"),
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("Serves the sites and the latest reports over an HTTP API"),
+        )
+        .subcommand(
+            SubCommand::with_name("list-sites")
+                .about("Lists the configured sites with their wind sectors and limits"),
+        )
+        .subcommand(
+            SubCommand::with_name("forecast")
+                .about("Prints the flyability of one site without sending notifications")
+                .arg(
+                    Arg::with_name("site")
+                        .long("site")
+                        .required(true)
+                        .value_name("NAME")
+                        .help("Name of a configured site")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("date")
+                        .long("date")
+                        .value_name("YYYY-MM-DD")
+                        .help("Day to analyze, defaults to the configured target")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import-sites")
+                .about("Appends site stubs for the waypoints of a KML or GPX file")
+                .arg(
+                    Arg::with_name("file")
+                        .required(true)
+                        .value_name("FILE")
+                        .help("KML or GPX file of the sites"),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .value_name("FILE")
+                        .help("Appends to FILE, e.g. in `sites_dir`, instead of the config file")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import-paragliding-earth")
+                .about("Appends the sites of Paragliding Earth, with their wind sectors")
+                .arg(
+                    Arg::with_name("around")
+                        .long("around")
+                        .value_name("LAT,LON")
+                        .help("Imports the sites around this coordinate")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .required_unless("id")
+                        .conflicts_with("id"),
+                )
+                .arg(
+                    Arg::with_name("radius")
+                        .long("radius")
+                        .value_name("KM")
+                        .help("Distance from --around to search within")
+                        .takes_value(true)
+                        .default_value("20"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .value_name("COUNT")
+                        .help("Most sites imported around the coordinate")
+                        .takes_value(true)
+                        .default_value("20"),
+                )
+                .arg(
+                    Arg::with_name("id")
+                        .long("id")
+                        .value_name("ID")
+                        .help("Imports the site with this Paragliding Earth id")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("api-url")
+                        .long("api-url")
+                        .value_name("URL")
+                        .takes_value(true)
+                        .default_value(paragliding_earth::DEFAULT_API_URL),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .value_name("FILE")
+                        .help("Appends to FILE, e.g. in `sites_dir`, instead of the config file")
+                        .takes_value(true),
+                ),
+        )
         .get_matches();
-    let config_path = matches.value_of("config").unwrap();
-
-    let app_config = config::load_config(&Path::new(config_path));
-    let client = forecast_client::OpenWeatherMapClient::new(
-        app_config.weather_api_url,
-        app_config.weather_api_token,
+    logging::init(
+        logging::level(
+            matches.value_of("log-level"),
+            matches.occurrences_of("verbose"),
+        ),
+        matches.value_of("log-format") == Some("json"),
     );
-    let sites = app_config.sites;
-    let reports = check_sites(&client, sites).await?;
-    if !reports.is_empty() {
-        let telegram_client = TelegramClient::new(app_config.telegram.bot_token);
-        send_notifications(&telegram_client, app_config.telegram.chat_ids, reports).await?;
+    let config_paths: Vec<PathBuf> = matches
+        .values_of("config")
+        .unwrap()
+        .map(PathBuf::from)
+        .collect();
+    let dry_run = matches.is_present("dry-run");
+    // Before loading the config, which may have no sites yet.
+    if let Some(import) = matches.subcommand_matches("import-sites") {
+        let file = Path::new(import.value_of("file").unwrap());
+        let waypoints = waypoints::read_waypoints(file)?;
+        if waypoints.is_empty() {
+            return Err(format!("{} has no named waypoints", file.display()).into());
+        }
+        return import_sites(&waypoints, &import_target(import, &config_paths)?, dry_run);
+    }
+    if let Some(import) = matches.subcommand_matches("import-paragliding-earth") {
+        // Before loading the config, so without its `[http]` settings.
+        let http = http::HttpClient::new(&HttpConfig::default(), RetryConfig::default())?;
+        let client =
+            ParaglidingEarthClient::new(import.value_of("api-url").unwrap().to_string(), http);
+        let waypoints = match (import.value_of("around"), import.value_of("id")) {
+            (Some(around), _) => {
+                let (latitude, longitude) = around
+                    .split_once(',')
+                    .and_then(|(lat, lon)| {
+                        Some((lat.trim().parse().ok()?, lon.trim().parse().ok()?))
+                    })
+                    .ok_or_else(|| format!("\"{}\" is not LAT,LON", around))?;
+                client
+                    .sites_around(
+                        latitude,
+                        longitude,
+                        import.value_of("radius").unwrap().parse()?,
+                        import.value_of("limit").unwrap().parse()?,
+                    )
+                    .await?
+            }
+            (None, Some(id)) => client.site(id.parse()?).await?,
+            (None, None) => unreachable!("clap requires --around or --id"),
+        };
+        if waypoints.is_empty() {
+            return Err("Paragliding Earth returned no sites".into());
+        }
+        return import_sites(&waypoints, &import_target(import, &config_paths)?, dry_run);
     }
 
+    let mut summary = export::RunSummaryView::new();
+    let mut app_config = match config::load_config(&config_paths) {
+        Ok(app_config) => app_config,
+        Err(error) => {
+            for problem in error.problems() {
+                eprintln!("error: {}", problem);
+            }
+            let code = error::exit_code(&error);
+            write_summary(&matches, summary, code, Some(error.to_string()), &[]);
+            std::process::exit(code);
+        }
+    };
+    let _sentry = app_config.sentry_dsn.as_deref().map(logging::init_sentry);
+    let overrides = Overrides {
+        site_patterns: matches
+            .values_of("site")
+            .map_or(vec![], |patterns| patterns.map(String::from).collect()),
+        target: match (matches.value_of("date"), matches.value_of("target")) {
+            (Some(date), _) => Some(Target::Date(NaiveDate::parse_from_str(date, "%Y-%m-%d")?)),
+            (None, Some(target)) => Some(Target::parse(target)?),
+            (None, None) => None,
+        },
+        min_flyable_wind: matches.value_of("min-wind").map(str::parse).transpose()?,
+        max_flyable_wind: matches.value_of("max-wind").map(str::parse).transpose()?,
+    };
+    let store = match &app_config.state_db {
+        Some(path) => Some(StateStore::open(path)?),
+        None => None,
+    };
+    if let Some(args) = matches.subcommand_matches("export-state") {
+        let store = store
+            .as_ref()
+            .ok_or("`state_db` must be configured to export its state")?;
+        let dump = StateDump::export(store)?;
+        let json = serde_json::to_string_pretty(&dump)? + "\n";
+        match args.value_of("to") {
+            Some(path) => {
+                fs::write(path, json)?;
+                eprintln!("Exported {} to {}", dump.describe(), path);
+            }
+            None => print!("{}", json),
+        }
+        return Ok(());
+    }
+    if let Some(args) = matches.subcommand_matches("import-state") {
+        let store = store
+            .as_ref()
+            .ok_or("`state_db` must be configured to import a state")?;
+        let path = args.value_of("file").unwrap();
+        let dump: StateDump = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|error| format!("{} is not a state dump: {}", path, error))?;
+        if dump.version > state_dump::VERSION {
+            return Err(format!(
+                "{} is a version {} dump, this version reads up to {}",
+                path,
+                dump.version,
+                state_dump::VERSION
+            )
+            .into());
+        }
+        let added: HashSet<&str> = dump
+            .site_changes
+            .iter()
+            .filter(|change| change.setting == admin::ADDED)
+            .map(|change| change.site.as_str())
+            .collect();
+        let unknown: BTreeSet<&str> = dump
+            .subscriptions
+            .iter()
+            .map(|subscription| subscription.site.as_str())
+            .filter(|site| {
+                *site != ALL_SITES
+                    && !added.contains(site)
+                    && !app_config.sites.iter().any(|known| known.name == *site)
+            })
+            .collect();
+        if !unknown.is_empty() {
+            eprintln!(
+                "warning: subscriptions to sites missing from the config: {}",
+                unknown.into_iter().collect::<Vec<_>>().join(", ")
+            );
+        }
+        if dry_run {
+            println!("Would import {} from {}", dump.describe(), path);
+            return Ok(());
+        }
+        dump.import(store, args.is_present("replace"))?;
+        println!("Imported {} from {}", dump.describe(), path);
+        return Ok(());
+    }
+    if let Some(store) = &store {
+        admin::apply_site_changes(&mut app_config.sites, store)?;
+    }
+    overrides.apply(&mut app_config)?;
+    let http = http::HttpClient::new(&app_config.http, app_config.retry)?;
+    if let Err(error) = resolve_locations(&mut app_config, &http, store.as_ref()).await {
+        for problem in error.problems() {
+            eprintln!("error: {}", problem);
+        }
+        let code = error::exit_code(&error);
+        write_summary(&matches, summary, code, Some(error.to_string()), &[]);
+        std::process::exit(code);
+    }
+    if matches.subcommand_matches("validate-config").is_some() {
+        println!(
+            "{} is valid: {} sites, {} notifiers",
+            matches
+                .values_of("config")
+                .unwrap()
+                .collect::<Vec<_>>()
+                .join(", "),
+            app_config.sites.len(),
+            app_config.notifiers.len()
+        );
+        return Ok(());
+    }
+    let target = app_config.target;
+    if matches.subcommand_matches("list-sites").is_some() {
+        print_sites(
+            &app_config.sites,
+            app_config.message.units,
+            app_config.min_period_hours,
+        );
+        return Ok(());
+    }
+    if let Some(stats) = matches.subcommand_matches("stats") {
+        if stats.value_of("kind") == Some("flyability") {
+            let since = stats
+                .value_of("since")
+                .map(|since| NaiveDate::parse_from_str(since, "%Y-%m-%d"))
+                .transpose()?;
+            return print_flyability_stats(store.as_ref(), since);
+        }
+        return print_stats(store.as_ref());
+    }
+    if let Some(history) = matches.subcommand_matches("history") {
+        let name = history.value_of("site").unwrap();
+        let site = app_config
+            .sites
+            .iter()
+            .find(|site| site.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown site \"{}\"", name))?;
+        let since = NaiveDate::parse_from_str(history.value_of("since").unwrap(), "%Y-%m-%d")?;
+        return print_history(store.as_ref(), &site.name, since, app_config.message.units);
+    }
+    if let Some(trend) = matches.subcommand_matches("trend") {
+        let name = trend.value_of("site").unwrap();
+        let site = app_config
+            .sites
+            .iter()
+            .find(|site| site.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown site \"{}\"", name))?;
+        let date = target.date(Local::now().date_naive());
+        let runs = trend.value_of("runs").unwrap().parse()?;
+        return print_trend(
+            store.as_ref(),
+            &site.name,
+            date,
+            runs,
+            app_config.message.units,
+        );
+    }
+    let sources = ForecastSources::new(&app_config, http.clone(), !matches.is_present("no-cache"))?;
+    let format = MessageFormat::new(&app_config.message)?;
+    if let Some(args) = matches.subcommand_matches("scan") {
+        let scan = Scan::new(
+            args.value_of("bbox").unwrap().parse()?,
+            args.value_of("step").unwrap().parse()?,
+            args.value_of("min-wind").unwrap().parse()?,
+            args.value_of("max-wind").unwrap().parse()?,
+        )?;
+        let mut outcome = scan::scan(
+            &sources,
+            store.as_ref(),
+            &scan,
+            target,
+            app_config.min_period_hours,
+            &app_config.scoring,
+        )
+        .await;
+        outcome
+            .reports
+            .truncate(args.value_of("limit").unwrap().parse()?);
+        print_scan(&outcome, app_config.message.units);
+        if args.is_present("notify") && !outcome.reports.is_empty() {
+            let notifiers = notification::build_notifiers(
+                app_config.notifiers,
+                &app_config.sites,
+                store.as_ref(),
+                target,
+                &format,
+                &http,
+                dry_run,
+            )?;
+            send_notifications(
+                &notifiers,
+                outcome.reports,
+                vec![],
+                vec![],
+                vec![],
+                &format,
+                &mut vec![],
+            )
+            .await?;
+        }
+        return Ok(());
+    }
+    if let Some(args) = matches.subcommand_matches("backtest") {
+        let name = args.value_of("site").unwrap();
+        let site = app_config
+            .sites
+            .iter()
+            .find(|site| site.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown site \"{}\"", name))?;
+        let from = NaiveDate::parse_from_str(args.value_of("from").unwrap(), "%Y-%m-%d")?;
+        let to = match args.value_of("to") {
+            Some(to) => NaiveDate::parse_from_str(to, "%Y-%m-%d")?,
+            None => Local::now().date_naive() - Duration::days(1),
+        };
+        if from > to {
+            return Err(format!("--from {} is after --to {}", from, to).into());
+        }
+        if (to - from).num_days() >= backtest::MAX_DAYS {
+            return Err(format!("backtests cover at most {} days", backtest::MAX_DAYS).into());
+        }
+        let client = ArchiveClient::new(
+            app_config.backtest.api_url.clone(),
+            http.for_weather(),
+            app_config.daylight,
+        );
+        let history = client
+            .get_history(site.latitude(), site.longitude(), from, to)
+            .await?;
+        let backtest = Backtest::run(site, history, app_config.min_period_hours);
+        print_backtest(&backtest, &site.name, args.is_present("days"), &format);
+        return Ok(());
+    }
+    if let Some(args) = matches.subcommand_matches("digest") {
+        let store = store
+            .as_ref()
+            .ok_or("`state_db` must be configured to keep the forecasts")?;
+        let days: i64 = args.value_of("days").unwrap().parse()?;
+        if days < 1 {
+            return Err("--days must be at least 1".into());
+        }
+        let today = Local::now().date_naive();
+        let digests = app_config
+            .sites
+            .iter()
+            .map(|site| SiteDigest::collect(store, site, today, days))
+            .collect::<Result<Vec<_>, _>>()?;
+        let notifiers = notification::build_notifiers(
+            app_config.notifiers,
+            &app_config.sites,
+            Some(store),
+            target,
+            &format,
+            &http,
+            dry_run,
+        )?;
+        digest::send_digests(&notifiers, &digests).await?;
+        return Ok(());
+    }
+    if let Some(forecast) = matches.subcommand_matches("forecast") {
+        let name = forecast.value_of("site").unwrap();
+        let site = app_config
+            .sites
+            .into_iter()
+            .find(|site| site.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown site \"{}\"", name))?;
+        let target = match forecast.value_of("date") {
+            Some(date) => Target::Date(NaiveDate::parse_from_str(date, "%Y-%m-%d")?),
+            None => target,
+        };
+        return print_forecast(
+            &sources,
+            site,
+            target,
+            app_config.min_period_hours,
+            &app_config.scoring,
+            &format,
+        )
+        .await;
+    }
+    if matches.subcommand_matches("serve").is_some() {
+        let reload = settings_loader(config_paths.clone(), overrides, http.clone());
+        let server = server::Server {
+            config: app_config.server,
+            sources,
+            settings: reload()?,
+            reload,
+            heartbeat: app_config
+                .heartbeat_url
+                .map(|url| heartbeat::Heartbeat::new(url, http.clone())),
+        };
+        return server.run().await;
+    }
+    if matches.subcommand_matches("bot").is_some() {
+        let store = store.ok_or("`state_db` must be configured to keep bot subscriptions")?;
+        let telegram = app_config
+            .notifiers
+            .into_iter()
+            .find_map(|entry| match entry.notifier {
+                NotifierConfig::Telegram(telegram) => Some(telegram),
+                _ => None,
+            })
+            .ok_or("a Telegram notifier must be configured to run the bot")?;
+        let reload = settings_loader(config_paths.clone(), overrides, http.clone());
+        let settings = reload()?;
+        let outbox = notification::telegram::Outbox::new(&telegram);
+        let mut bot = bot::Bot {
+            client: notification::telegram::TelegramClient::new(
+                telegram.bot_token,
+                telegram.parse_mode,
+                telegram.messages_per_second,
+                http,
+                dry_run,
+            ),
+            store: &store,
+            sources: &sources,
+            sites: settings.sites,
+            min_period_hours: settings.min_period_hours,
+            scoring: settings.scoring,
+            format: settings.format,
+            admins: settings.admins,
+            chat_units: settings.chat_units,
+            reports: bot::ReportCache::new(telegram.forecast_cache_minutes),
+            outbox,
+            reload,
+        };
+        return bot.run().await;
+    }
+    // Moved out as the edition 2018 async block would capture the whole config.
+    let (notifier_configs, sites) = (app_config.notifiers, app_config.sites);
+    let (min_period_hours, scoring) = (app_config.min_period_hours, app_config.scoring);
+    let order = app_config.message.order;
+    let mut deliveries = vec![];
+    let result: Result<(), Box<dyn std::error::Error>> = async {
+        let notifiers = notification::build_notifiers(
+            notifier_configs,
+            &sites,
+            store.as_ref(),
+            target,
+            &format,
+            &http,
+            dry_run,
+        )?;
+        let checked = check_sites(
+            &sources,
+            store.as_ref(),
+            sites.clone(),
+            target,
+            min_period_hours,
+            &scoring,
+            order,
+        )
+        .await;
+        summary.add_checked(&checked);
+        let error_summary = checked.error_summary();
+        if let Some(summary) = &error_summary {
+            tracing::error!(
+                failed = checked.errors.len(),
+                "some sites could not be checked"
+            );
+            if matches.value_of("output") != Some("json") {
+                send_error_summary(&notifiers, summary).await;
+            }
+        }
+        if checked.all_failed() {
+            let summary = error_summary.unwrap_or_default();
+            return Err(ProviderError::AllSitesFailed(summary).into());
+        }
+        if matches.value_of("output") == Some("json") {
+            let report_set = export::ReportSetView::new(&checked.reports, &checked.skipped);
+            println!("{}", serde_json::to_string_pretty(&report_set)?);
+            return Ok(());
+        }
+        let all_reports: Vec<_> = checked
+            .reports
+            .iter()
+            .chain(&checked.profile_reports)
+            .cloned()
+            .collect();
+        let other_days = check_report_dates(
+            &sources,
+            store.as_ref(),
+            &sites,
+            &notifiers,
+            &all_reports,
+            min_period_hours,
+            &scoring,
+            order,
+        )
+        .await;
+        send_notifications(
+            &notifiers,
+            checked.reports,
+            checked.profile_reports,
+            other_days,
+            checked.skipped,
+            &format,
+            &mut deliveries,
+        )
+        .await?;
+        Ok(())
+    }
+    .await;
+    if let Some(url) = &app_config.heartbeat_url {
+        heartbeat::Heartbeat::new(url.clone(), http.clone())
+            .ping(result.is_ok())
+            .await;
+    }
+    let partial = !summary.site_errors.is_empty();
+    if let Err(error) = result {
+        tracing::error!(%error, "run failed");
+        let code = error::exit_code(error.as_ref());
+        write_summary(
+            &matches,
+            summary,
+            code,
+            Some(error.to_string()),
+            &deliveries,
+        );
+        // Exiting skips the destructors, the events must be sent beforehand.
+        drop(_sentry);
+        std::process::exit(code);
+    }
+    if partial {
+        write_summary(&matches, summary, error::EXIT_PARTIAL, None, &deliveries);
+        drop(_sentry);
+        std::process::exit(error::EXIT_PARTIAL);
+    }
+    write_summary(&matches, summary, 0, None, &deliveries);
     Ok(())
 }
@@ -0,0 +1,97 @@
+//! Wave forecasts from the Open-Meteo Marine API, for coastal sites.
+
+use crate::forecast_client::DayWeatherForecast;
+use crate::http::HttpClient;
+use crate::measures::{WaveHeight, WindDirection};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+struct MarineHourly {
+    time: Vec<i64>,
+    wave_height: Vec<Option<f32>>,
+    wave_period: Vec<Option<f32>>,
+    wave_direction: Vec<Option<f32>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct MarineForecast {
+    hourly: MarineHourly,
+}
+
+/// The waves for a single forecast hour.
+#[derive(Debug, Clone, Copy)]
+pub struct Waves {
+    pub height: WaveHeight,
+    /// Seconds between two crests.
+    pub period_s: f32,
+    /// Direction the waves come from.
+    pub direction: WindDirection,
+}
+
+pub struct MarineClient {
+    url: String,
+    http: HttpClient,
+}
+
+impl MarineClient {
+    pub fn new(url: String, http: HttpClient) -> Self {
+        MarineClient { url, http }
+    }
+
+    pub fn name(&self) -> &'static str {
+        "Open-Meteo"
+    }
+
+    /// The waves keyed by unix timestamp of the forecast hour. Points
+    /// inland have none.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_waves(&self, lat: f64, lon: f64) -> reqwest::Result<HashMap<i64, Waves>> {
+        let (lat, lon) = (lat.to_string(), lon.to_string());
+        let forecast: MarineForecast = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("latitude", &lat[..]),
+                    ("longitude", &lon[..]),
+                    ("hourly", "wave_height,wave_period,wave_direction"),
+                    ("timeformat", "unixtime"),
+                    ("forecast_days", "3"),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+
+        let hourly = forecast.hourly;
+        let mut waves = HashMap::new();
+        for (i, time) in hourly.time.iter().enumerate() {
+            let hour = (
+                hourly.wave_height.get(i).copied().flatten(),
+                hourly.wave_period.get(i).copied().flatten(),
+                hourly.wave_direction.get(i).copied().flatten(),
+            );
+            if let (Some(height), Some(period_s), Some(direction)) = hour {
+                waves.insert(
+                    *time,
+                    Waves {
+                        height: WaveHeight::M(height),
+                        period_s,
+                        direction: WindDirection::new(direction.round() as i16),
+                    },
+                );
+            }
+        }
+        Ok(waves)
+    }
+}
+
+/// Attaches the waves to the matching hours of a forecast.
+pub fn apply_waves(forecasts: &mut [DayWeatherForecast], waves: &HashMap<i64, Waves>) {
+    for hour in forecasts.iter_mut().flat_map(|day| day.hourly.iter_mut()) {
+        hour.waves = waves.get(&hour.time.timestamp()).copied();
+    }
+}
@@ -1,25 +1,109 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
 
-#[derive(Deserialize, Debug, Copy, Clone)]
+/// A measure that is not a number followed by a known unit, e.g. "12 mph".
+#[derive(Debug, thiserror::Error)]
+#[error("\"{0}\" is not a number followed by a unit, e.g. \"{1}\"")]
+pub struct ParseMeasureError(String, &'static str);
+
+/// The number and the lowercase unit of e.g. "12.5 km/h" or "20C".
+fn split_measure(text: &str) -> Option<(f32, String)> {
+    let text = text.trim();
+    let unit_start = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(text.len());
+    let value = text[..unit_start].parse().ok()?;
+    Some((value, text[unit_start..].trim().to_lowercase()))
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
 #[serde(tag = "type", content = "value")]
 pub enum Temperature {
     C(f32),
     F(f32),
+    /// Sent by some providers when no units are requested.
+    K(f32),
 }
 
+const ZERO_CELSIUS_IN_KELVIN: f32 = 273.15;
+
 impl Temperature {
     pub fn celsius(&self) -> f32 {
         match *self {
             Temperature::C(degrees) => degrees,
-            Temperature::F(degrees) => (degrees * 1.8) + 32.0,
+            Temperature::F(degrees) => (degrees - 32.0) / 1.8,
+            Temperature::K(kelvin) => kelvin - ZERO_CELSIUS_IN_KELVIN,
         }
     }
 
     pub fn fahrenheit(&self) -> f32 {
         match *self {
-            Temperature::C(degrees) => (degrees - 32.0) / 1.8,
             Temperature::F(degrees) => degrees,
+            _ => (self.celsius() * 1.8) + 32.0,
+        }
+    }
+
+    pub fn kelvin(&self) -> f32 {
+        match *self {
+            Temperature::K(kelvin) => kelvin,
+            _ => self.celsius() + ZERO_CELSIUS_IN_KELVIN,
+        }
+    }
+
+    pub fn in_unit(&self, unit: TemperatureUnit) -> f32 {
+        match unit {
+            TemperatureUnit::C => self.celsius(),
+            TemperatureUnit::F => self.fahrenheit(),
+            TemperatureUnit::K => self.kelvin(),
+        }
+    }
+
+    /// The colder of the two.
+    pub fn min(self, other: Temperature) -> Temperature {
+        if other < self {
+            other
+        } else {
+            self
+        }
+    }
+
+    /// The warmer of the two.
+    pub fn max(self, other: Temperature) -> Temperature {
+        if other > self {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+/// One decimal unless told otherwise, e.g. "20.5°C".
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let precision = f.precision().unwrap_or(1);
+        match *self {
+            Temperature::C(degrees) => write!(f, "{:.*}°C", precision, degrees),
+            Temperature::F(degrees) => write!(f, "{:.*}°F", precision, degrees),
+            Temperature::K(kelvin) => write!(f, "{:.*} K", precision, kelvin),
+        }
+    }
+}
+
+/// Accepts e.g. "20C", "68 F", "-3 °c" or "293 K".
+impl FromStr for Temperature {
+    type Err = ParseMeasureError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ParseMeasureError(text.to_string(), "20 C");
+        let (value, unit) = split_measure(text).ok_or_else(error)?;
+        match unit.trim_start_matches('°') {
+            "c" => Ok(Temperature::C(value)),
+            "f" => Ok(Temperature::F(value)),
+            "k" => Ok(Temperature::K(value)),
+            _ => Err(error()),
         }
     }
 }
@@ -42,17 +126,23 @@ impl PartialOrd for Temperature {
     }
 }
 
-#[derive(Deserialize, Debug, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
 #[serde(tag = "type", content = "value")]
 pub enum WindSpeed {
     MPH(f32),
     KMPH(f32),
     MPS(f32),
+    KNOTS(f32),
 }
 
 const MPS_TO_KMPH: f32 = 3.6;
 const MPS_TO_MPH: f32 = 2.236936;
 const MPH_TO_KMPH: f32 = 1.609344;
+const MPS_TO_KNOTS: f32 = 1.943844;
+// Upper bounds in m/s of Beaufort forces 0 to 11, anything above being 12.
+const BEAUFORT_LIMITS_MPS: [f32; 12] = [
+    0.5, 1.5, 3.3, 5.5, 7.9, 10.7, 13.8, 17.1, 20.7, 24.4, 28.4, 32.6,
+];
 
 impl WindSpeed {
     pub fn meters_per_second(&self) -> f32 {
@@ -60,6 +150,7 @@ impl WindSpeed {
             WindSpeed::MPH(mph) => mph / MPS_TO_MPH,
             WindSpeed::KMPH(kmph) => kmph / MPS_TO_KMPH,
             WindSpeed::MPS(mps) => mps,
+            WindSpeed::KNOTS(knots) => knots / MPS_TO_KNOTS,
         }
     }
 
@@ -68,14 +159,132 @@ impl WindSpeed {
             WindSpeed::MPH(mph) => mph,
             WindSpeed::KMPH(kmph) => kmph / MPH_TO_KMPH,
             WindSpeed::MPS(mps) => mps * MPS_TO_MPH,
+            WindSpeed::KNOTS(_) => self.meters_per_second() * MPS_TO_MPH,
         }
     }
 
-    pub fn kilometers_per_second(&self) -> f32 {
+    pub fn kilometers_per_hour(&self) -> f32 {
         match *self {
             WindSpeed::MPH(mph) => mph * MPH_TO_KMPH,
             WindSpeed::KMPH(kmph) => kmph,
             WindSpeed::MPS(mps) => mps * MPS_TO_KMPH,
+            WindSpeed::KNOTS(_) => self.meters_per_second() * MPS_TO_KMPH,
+        }
+    }
+
+    pub fn knots(&self) -> f32 {
+        match *self {
+            WindSpeed::KNOTS(knots) => knots,
+            _ => self.meters_per_second() * MPS_TO_KNOTS,
+        }
+    }
+
+    /// Force on the Beaufort scale, from 0 (calm) to 12 (hurricane).
+    pub fn beaufort(&self) -> u8 {
+        let mps = self.meters_per_second();
+        BEAUFORT_LIMITS_MPS
+            .iter()
+            .take_while(|limit| mps >= **limit)
+            .count() as u8
+    }
+
+    pub fn in_unit(&self, unit: WindUnit) -> f32 {
+        match unit {
+            WindUnit::Mph => self.miles_per_hour(),
+            WindUnit::Kmh => self.kilometers_per_hour(),
+            WindUnit::Mps => self.meters_per_second(),
+            WindUnit::Knots => self.knots(),
+            WindUnit::Beaufort => self.beaufort() as f32,
+        }
+    }
+
+    /// The speed in `unit` as shown in messages, e.g. "8.1", Beaufort
+    /// forces being whole numbers.
+    pub fn format(&self, unit: WindUnit) -> String {
+        match unit {
+            WindUnit::Beaufort => self.beaufort().to_string(),
+            _ => format!("{:.1}", self.in_unit(unit)),
+        }
+    }
+
+    /// The lighter of the two.
+    pub fn min(self, other: WindSpeed) -> WindSpeed {
+        if other < self {
+            other
+        } else {
+            self
+        }
+    }
+
+    /// The stronger of the two.
+    pub fn max(self, other: WindSpeed) -> WindSpeed {
+        if other > self {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+/// One decimal unless told otherwise, e.g. "12.0 MPH".
+impl fmt::Display for WindSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let precision = f.precision().unwrap_or(1);
+        let (value, unit) = match *self {
+            WindSpeed::MPH(mph) => (mph, WindUnit::Mph),
+            WindSpeed::KMPH(kmph) => (kmph, WindUnit::Kmh),
+            WindSpeed::MPS(mps) => (mps, WindUnit::Mps),
+            WindSpeed::KNOTS(knots) => (knots, WindUnit::Knots),
+        };
+        write!(f, "{:.*} {}", precision, value, unit.label())
+    }
+}
+
+/// Accepts e.g. "25 kmh", "12 mph", "4.5m/s" or "10 kn".
+impl FromStr for WindSpeed {
+    type Err = ParseMeasureError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ParseMeasureError(text.to_string(), "25 kmh");
+        let (value, unit) = split_measure(text).ok_or_else(error)?;
+        match unit.as_str() {
+            "mph" => Ok(WindSpeed::MPH(value)),
+            "kmh" | "km/h" | "kmph" | "kph" => Ok(WindSpeed::KMPH(value)),
+            "mps" | "m/s" => Ok(WindSpeed::MPS(value)),
+            "kn" | "kt" | "kts" | "knots" => Ok(WindSpeed::KNOTS(value)),
+            _ => Err(error()),
+        }
+    }
+}
+
+/// Sums are in m/s.
+impl Add for WindSpeed {
+    type Output = WindSpeed;
+
+    fn add(self, other: WindSpeed) -> WindSpeed {
+        WindSpeed::MPS(self.meters_per_second() + other.meters_per_second())
+    }
+}
+
+/// Differences are in m/s.
+impl Sub for WindSpeed {
+    type Output = WindSpeed;
+
+    fn sub(self, other: WindSpeed) -> WindSpeed {
+        WindSpeed::MPS(self.meters_per_second() - other.meters_per_second())
+    }
+}
+
+/// Scales the speed, keeping its unit.
+impl Mul<f32> for WindSpeed {
+    type Output = WindSpeed;
+
+    fn mul(self, factor: f32) -> WindSpeed {
+        match self {
+            WindSpeed::MPH(mph) => WindSpeed::MPH(mph * factor),
+            WindSpeed::KMPH(kmph) => WindSpeed::KMPH(kmph * factor),
+            WindSpeed::MPS(mps) => WindSpeed::MPS(mps * factor),
+            WindSpeed::KNOTS(knots) => WindSpeed::KNOTS(knots * factor),
         }
     }
 }
@@ -97,3 +306,379 @@ impl PartialOrd for WindSpeed {
         }
     }
 }
+
+/// Atmospheric pressure at sea level.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[serde(tag = "type", content = "value")]
+pub enum Pressure {
+    HPA(f32),
+    INHG(f32),
+}
+
+const INHG_TO_HPA: f32 = 33.863_89;
+
+impl Pressure {
+    pub fn hectopascals(&self) -> f32 {
+        match *self {
+            Pressure::HPA(hpa) => hpa,
+            Pressure::INHG(inhg) => inhg * INHG_TO_HPA,
+        }
+    }
+}
+
+/// Amount of rain falling within an hour.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[serde(tag = "type", content = "value")]
+pub enum Precipitation {
+    MM(f32),
+    IN(f32),
+}
+
+const IN_TO_MM: f32 = 25.4;
+
+impl Precipitation {
+    pub fn millimeters(&self) -> f32 {
+        match *self {
+            Precipitation::MM(mm) => mm,
+            Precipitation::IN(inches) => inches * IN_TO_MM,
+        }
+    }
+}
+
+impl PartialEq for Precipitation {
+    fn eq(&self, other: &Self) -> bool {
+        (self.millimeters() * 1000.0).round() == (other.millimeters() * 1000.0).round()
+    }
+}
+
+impl PartialOrd for Precipitation {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.eq(other) {
+            Some(Ordering::Equal)
+        } else if self.millimeters() > other.millimeters() {
+            Some(Ordering::Greater)
+        } else {
+            Some(Ordering::Less)
+        }
+    }
+}
+
+/// How far away objects can be made out, OpenWeatherMap reporting at most
+/// 10 km.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[serde(tag = "type", content = "value")]
+pub enum Visibility {
+    M(f32),
+    KM(f32),
+    MI(f32),
+}
+
+const MI_TO_M: f32 = 1609.344;
+
+impl Visibility {
+    pub fn meters(&self) -> f32 {
+        match *self {
+            Visibility::M(meters) => meters,
+            Visibility::KM(km) => km * 1000.0,
+            Visibility::MI(miles) => miles * MI_TO_M,
+        }
+    }
+
+    pub fn kilometers(&self) -> f32 {
+        self.meters() / 1000.0
+    }
+}
+
+impl PartialEq for Visibility {
+    fn eq(&self, other: &Self) -> bool {
+        self.meters().round() == other.meters().round()
+    }
+}
+
+impl PartialOrd for Visibility {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.eq(other) {
+            Some(Ordering::Equal)
+        } else if self.meters() > other.meters() {
+            Some(Ordering::Greater)
+        } else {
+            Some(Ordering::Less)
+        }
+    }
+}
+
+/// Significant height of the waves, from trough to crest.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[serde(tag = "type", content = "value")]
+pub enum WaveHeight {
+    M(f32),
+    FT(f32),
+}
+
+const FT_TO_M: f32 = 0.3048;
+
+impl WaveHeight {
+    pub fn meters(&self) -> f32 {
+        match *self {
+            WaveHeight::M(meters) => meters,
+            WaveHeight::FT(feet) => feet * FT_TO_M,
+        }
+    }
+}
+
+impl PartialEq for WaveHeight {
+    fn eq(&self, other: &Self) -> bool {
+        (self.meters() * 10.0).round() == (other.meters() * 10.0).round()
+    }
+}
+
+impl PartialOrd for WaveHeight {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.eq(other) {
+            Some(Ordering::Equal)
+        } else if self.meters() > other.meters() {
+            Some(Ordering::Greater)
+        } else {
+            Some(Ordering::Less)
+        }
+    }
+}
+
+const CARDINALS: [&str; 16] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW",
+    "NNW",
+];
+
+/// Direction the wind blows from, in degrees clockwise from north within
+/// 0–359, so that 360° and -10° become 0° and 350°.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "i16")]
+pub struct WindDirection(i16);
+
+/// E.g. "320°".
+impl fmt::Display for WindDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}°", self.0)
+    }
+}
+
+impl From<i16> for WindDirection {
+    fn from(degrees: i16) -> Self {
+        WindDirection::new(degrees)
+    }
+}
+
+impl WindDirection {
+    pub fn new(degrees: i16) -> Self {
+        WindDirection(degrees.rem_euclid(360))
+    }
+
+    pub fn degrees(&self) -> i16 {
+        self.0
+    }
+
+    /// Degrees to turn clockwise from `from` to reach this direction, 0–359.
+    pub fn clockwise_from(&self, from: WindDirection) -> i16 {
+        (self.0 - from.0).rem_euclid(360)
+    }
+
+    /// The shortest way around the compass between the directions, 0–180.
+    pub fn distance(&self, other: WindDirection) -> i16 {
+        let clockwise = self.clockwise_from(other);
+        clockwise.min(360 - clockwise)
+    }
+
+    /// The closest point of the 16 point compass, e.g. "NNE".
+    pub fn cardinal(&self) -> &'static str {
+        let index = (self.0 as f32 / 22.5).round() as usize;
+        CARDINALS[index % CARDINALS.len()]
+    }
+
+    /// Arrow pointing where the wind blows to, e.g. "↓" for a northerly.
+    pub fn arrow(&self) -> &'static str {
+        let index = (self.0 as f32 / 45.0).round() as usize;
+        ["↓", "↙", "←", "↖", "↑", "↗", "→", "↘"][index % 8]
+    }
+
+    /// "SW–WSW", or a single point when both ends round to it.
+    pub fn cardinal_range(from: WindDirection, to: WindDirection) -> String {
+        if from.cardinal() == to.cardinal() {
+            String::from(from.cardinal())
+        } else {
+            format!("{}–{}", from.cardinal(), to.cardinal())
+        }
+    }
+}
+
+/// The narrowest arc, clockwise from `from` to `to`, covering a set of
+/// directions, e.g. 350°–10° rather than 10°–350° for winds around north.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DirectionRange {
+    pub from: WindDirection,
+    pub to: WindDirection,
+}
+
+impl DirectionRange {
+    pub fn new(direction: WindDirection) -> Self {
+        DirectionRange {
+            from: direction,
+            to: direction,
+        }
+    }
+
+    pub fn width(&self) -> i16 {
+        self.to.clockwise_from(self.from)
+    }
+
+    pub fn contains(&self, direction: WindDirection) -> bool {
+        direction.clockwise_from(self.from) <= self.width()
+    }
+
+    /// Widens the range to `direction` on whichever side keeps it narrowest.
+    pub fn extend(&mut self, direction: WindDirection) {
+        if self.contains(direction) {
+            return;
+        }
+        if direction.clockwise_from(self.from) <= self.to.clockwise_from(direction) {
+            self.to = direction;
+        } else {
+            self.from = direction;
+        }
+    }
+
+    /// The direction halfway through the range.
+    pub fn middle(&self) -> WindDirection {
+        WindDirection::new(self.from.degrees() + self.width() / 2)
+    }
+
+    /// The cardinal range, e.g. "NNW–NNE".
+    pub fn cardinal(&self) -> String {
+        WindDirection::cardinal_range(self.from, self.to)
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WindUnit {
+    #[default]
+    Mph,
+    Kmh,
+    Mps,
+    Knots,
+    Beaufort,
+}
+
+impl WindUnit {
+    pub fn label(&self) -> &'static str {
+        match self {
+            WindUnit::Mph => "MPH",
+            WindUnit::Kmh => "km/h",
+            WindUnit::Mps => "m/s",
+            WindUnit::Knots => "kn",
+            WindUnit::Beaufort => "Bft",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum TemperatureUnit {
+    #[serde(alias = "c")]
+    C,
+    #[default]
+    #[serde(alias = "f")]
+    F,
+    #[serde(alias = "k")]
+    K,
+}
+
+impl TemperatureUnit {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TemperatureUnit::C => "C",
+            TemperatureUnit::F => "F",
+            TemperatureUnit::K => "K",
+        }
+    }
+}
+
+/// Units used to show measures in messages.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Units {
+    #[serde(default)]
+    pub wind: WindUnit,
+    #[serde(default)]
+    pub temperature: TemperatureUnit,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn temperature_conversions() {
+        // Every unit of each reference point, as C, F and K.
+        let points = [
+            (-40.0, -40.0, 233.15),
+            (0.0, 32.0, 273.15),
+            (20.0, 68.0, 293.15),
+            (100.0, 212.0, 373.15),
+            (-273.15, -459.67, 0.0),
+        ];
+        for (c, f, k) in points {
+            for temperature in [Temperature::C(c), Temperature::F(f), Temperature::K(k)] {
+                assert_close(temperature.celsius(), c);
+                assert_close(temperature.fahrenheit(), f);
+                assert_close(temperature.kelvin(), k);
+                assert_close(temperature.in_unit(TemperatureUnit::C), c);
+                assert_close(temperature.in_unit(TemperatureUnit::F), f);
+                assert_close(temperature.in_unit(TemperatureUnit::K), k);
+            }
+        }
+    }
+
+    #[test]
+    fn temperature_comparisons_across_units() {
+        assert_eq!(Temperature::C(20.0), Temperature::F(68.0));
+        assert_eq!(Temperature::K(273.15), Temperature::C(0.0));
+        assert!(Temperature::F(50.0) > Temperature::C(5.0));
+        assert!(Temperature::K(300.0) > Temperature::F(79.0));
+        assert!(Temperature::C(-1.0) < Temperature::K(273.15));
+    }
+
+    #[test]
+    fn temperature_min_max() {
+        let (cold, warm) = (Temperature::F(41.0), Temperature::C(10.0));
+        assert_close(cold.min(warm).fahrenheit(), 41.0);
+        assert_close(warm.min(cold).fahrenheit(), 41.0);
+        assert_close(cold.max(warm).celsius(), 10.0);
+        assert_close(warm.max(cold).celsius(), 10.0);
+        // The unit of the kept value is preserved.
+        assert!(matches!(cold.max(warm), Temperature::C(_)));
+        assert!(matches!(
+            Temperature::K(280.0).min(Temperature::C(15.0)),
+            Temperature::K(_)
+        ));
+    }
+
+    #[test]
+    fn temperature_from_str() {
+        let parsed = |text: &str| text.parse::<Temperature>().unwrap();
+        assert!(matches!(parsed("20C"), Temperature::C(t) if t == 20.0));
+        assert!(matches!(parsed("68 F"), Temperature::F(t) if t == 68.0));
+        assert!(matches!(parsed("-3 °c"), Temperature::C(t) if t == -3.0));
+        assert!(matches!(parsed(" 293.5 K "), Temperature::K(t) if t == 293.5));
+        assert!(matches!(parsed("+5°F"), Temperature::F(t) if t == 5.0));
+        for invalid in ["", "20", "C", "20 R", "twenty C", "20 °"] {
+            assert!(invalid.parse::<Temperature>().is_err(), "{:?}", invalid);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Wording of the report messages, shared by every notifier.
+
+use crate::config::{MessageConfig, MessageStyle, ReportOrder};
+use crate::export::ReportView;
+use crate::i18n::{Language, Strings};
+use crate::measures::Units;
+use crate::SiteFlyAbilityReport;
+use std::sync::Arc;
+use tera::{Context, Tera};
+
+const TEMPLATE_NAME: &str = "report";
+
+/// How reports are put into words. Cheap to clone, every notifier keeps
+/// its own copy.
+#[derive(Clone, Default)]
+pub struct MessageFormat {
+    template: Option<Arc<Tera>>,
+    language: Language,
+    units: Units,
+    style: MessageStyle,
+    order: ReportOrder,
+    notify_when_empty: bool,
+    explain_when_empty: bool,
+    explain_closest_miss: bool,
+    sparkline: bool,
+}
+
+impl MessageFormat {
+    pub fn new(config: &MessageConfig) -> Result<Self, tera::Error> {
+        let template = match &config.template {
+            Some(path) => {
+                let mut tera = Tera::default();
+                tera.add_template_file(path, Some(TEMPLATE_NAME))?;
+                Some(Arc::new(tera))
+            }
+            None => None,
+        };
+        Ok(MessageFormat {
+            template,
+            language: config.language,
+            units: config.units,
+            style: config.style,
+            order: config.order,
+            notify_when_empty: config.notify_when_empty,
+            explain_when_empty: config.explain_when_empty,
+            explain_closest_miss: config.explain_closest_miss,
+            sparkline: config.sparkline,
+        })
+    }
+
+    pub fn units(&self) -> Units {
+        self.units
+    }
+
+    pub fn style(&self) -> MessageStyle {
+        self.style
+    }
+
+    pub fn notify_when_empty(&self) -> bool {
+        self.notify_when_empty
+    }
+
+    pub fn explain_when_empty(&self) -> bool {
+        self.explain_when_empty
+    }
+
+    pub fn explain_closest_miss(&self) -> bool {
+        self.explain_closest_miss
+    }
+
+    pub fn sparkline(&self) -> bool {
+        self.sparkline
+    }
+
+    /// Whether the first of `count` reports is labelled as the top pick,
+    /// which only holds when they are ordered by score.
+    pub fn labels_top_pick(&self, count: usize) -> bool {
+        count > 1 && self.order == ReportOrder::Score
+    }
+
+    /// The same format showing measures in `units`.
+    pub fn with_units(&self, units: Units) -> Self {
+        MessageFormat {
+            units,
+            ..self.clone()
+        }
+    }
+
+    /// The same format in another `style`.
+    pub fn with_style(&self, style: MessageStyle) -> Self {
+        MessageFormat {
+            style,
+            ..self.clone()
+        }
+    }
+
+    pub fn strings(&self) -> &'static Strings {
+        self.language.strings()
+    }
+
+    /// The configured template rendered for `report`, exposed as `report`
+    /// (see `export::ReportView`), `day` ("today", "tomorrow"... in the
+    /// configured language), `wind_unit` and `temperature_unit`.
+    /// Rendering errors are logged and fall back to the built-in wording.
+    pub fn render_template(&self, report: &SiteFlyAbilityReport) -> Option<String> {
+        let tera = self.template.as_ref()?;
+        let mut context = Context::new();
+        context.insert("report", &ReportView::from(report));
+        context.insert("day", &self.strings().day(report.days_ahead));
+        context.insert("wind_unit", self.units.wind.label());
+        context.insert("temperature_unit", self.units.temperature.label());
+        match tera.render(TEMPLATE_NAME, &context) {
+            Ok(text) => Some(text),
+            Err(error) => {
+                tracing::warn!(%error, "failed to render the message template");
+                None
+            }
+        }
+    }
+}
@@ -1,34 +1,217 @@
-use reqwest::{Client, Result};
-use serde::Deserialize;
+mod csv;
+mod discord;
+mod dry_run;
+mod email;
+mod feed;
+mod ics;
+mod mqtt;
+mod ntfy;
+mod pushover;
+mod sink;
+mod slack;
+pub mod telegram;
+mod webhook;
 
-#[derive(Deserialize, Debug)]
-struct TelegramResponse {
-    ok: bool,
-}
+use crate::config::{FlyingSite, NotifierConfig, NotifierEntry, SiteRoute, Target};
+use crate::digest::SiteDigest;
+use crate::http::HttpClient;
+use crate::message::MessageFormat;
+use crate::store::StateStore;
+use crate::{SiteFlyAbilityReport, SkippedSite};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use dry_run::DryRunNotifier;
+use telegram::{BotChats, TelegramNotifier};
 
-pub struct TelegramClient {
-    url: String,
+/// Why a notifier could not be built or could not deliver the reports.
+#[derive(Debug, thiserror::Error)]
+pub enum NotifyError {
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Telegram(#[from] telegram::TelegramError),
+    #[error("state store failed: {0}")]
+    Store(#[from] rusqlite::Error),
+    #[error("invalid email address: {0}")]
+    Address(#[from] lettre::address::AddressError),
+    #[error("invalid email: {0}")]
+    Email(#[from] lettre::error::Error),
+    #[error("email delivery failed: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    #[error("MQTT publish failed: {0}")]
+    MqttClient(#[from] rumqttc::ClientError),
+    #[error("MQTT connection failed: {0}")]
+    MqttConnection(#[from] Box<rumqttc::ConnectionError>),
+    #[error("notifier task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+    #[error("feed could not be written: {0}")]
+    Feed(#[from] atom_syndication::Error),
+    #[error("charts could not be rendered: {0}")]
+    Chart(#[from] crate::charts::ChartError),
+    /// The notifier is misconfigured.
+    #[error("{0}")]
+    Config(String),
+    /// The service answered but refused the message.
+    #[error("{0}")]
+    Rejected(String),
+    /// Messages that failed to be delivered, kept in the outbox for a retry.
+    #[error("{kept} messages kept for a retry: {error}")]
+    Kept { kept: usize, error: String },
+    /// Some notifiers of a run failed, the others having been sent.
+    #[error("{failed} of {notifiers} notifiers failed: {errors}")]
+    Failed {
+        failed: usize,
+        notifiers: usize,
+        errors: String,
+    },
 }
 
-impl TelegramClient {
-    pub fn new(token: String) -> Self {
-        let url = format!("https://api.telegram.org/bot{}/sendMessage", token);
-        TelegramClient { url }
+/// A channel delivering the flyability reports of a run to its subscribers.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Whether the notifier should also run when no site is flyable.
+    fn notify_when_empty(&self) -> bool {
+        false
+    }
+
+    async fn notify(
+        &self,
+        reports: &[SiteFlyAbilityReport],
+    ) -> std::result::Result<(), NotifyError>;
+
+    /// Tells that none of the notifier's sites is flyable, `skipped` giving
+    /// the reason per site. Only called with `message.notify_when_empty` set.
+    async fn notify_nothing_flyable(
+        &self,
+        _skipped: &[SkippedSite],
+    ) -> std::result::Result<(), NotifyError> {
+        Ok(())
+    }
+
+    /// Days some recipients want reports about instead of the target of the
+    /// run, e.g. chats in another time zone than the sites.
+    fn report_dates(&self) -> Vec<NaiveDate> {
+        vec![]
     }
 
-    pub async fn notify(&self, chat_id: String, message: &str) -> Result<()> {
-        let client = Client::new();
-        client
-            .get(&self.url)
-            .query(&[
-                ("chat_id", &chat_id[..]),
-                ("parse_mode", "Markdown"),
-                ("text", message),
-            ])
-            .send()
-            .await?
-            .json::<TelegramResponse>()
-            .await?;
+    /// Pilot profiles some recipients want the reports of, see
+    /// `FlyingSite::profiles`. The reports of these profiles are handed to
+    /// `notify` along with the ones of the sites.
+    fn profiles(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// Sends the messages held back by quiet hours which have ended.
+    async fn send_deferred(&self) -> std::result::Result<(), NotifyError> {
         Ok(())
     }
+
+    /// Tells operators which sites of the run could not be checked. Only
+    /// notifiers with an operator audience send it.
+    async fn notify_errors(&self, _summary: &str) -> std::result::Result<(), NotifyError> {
+        Ok(())
+    }
+
+    /// Sends the weekly digests of the notifier's sites. Only notifiers able
+    /// to show images send them.
+    async fn notify_digest(&self, _digests: &[SiteDigest]) -> std::result::Result<(), NotifyError> {
+        Ok(())
+    }
+}
+
+/// A notifier along with the sites whose reports it receives.
+pub struct RoutedNotifier {
+    /// The `type` of the notifier in the config, for logging.
+    pub kind: &'static str,
+    pub route: SiteRoute,
+    pub notifier: Box<dyn Notifier>,
+}
+
+pub fn build_notifiers(
+    entries: Vec<NotifierEntry>,
+    sites: &[FlyingSite],
+    store: Option<&StateStore>,
+    target: Target,
+    format: &MessageFormat,
+    http: &HttpClient,
+    dry_run: bool,
+) -> std::result::Result<Vec<RoutedNotifier>, NotifyError> {
+    let bot_chats = match store {
+        Some(store) => BotChats::load(store)?,
+        None => BotChats::default(),
+    };
+    entries
+        .into_iter()
+        .map(|entry| {
+            let site_names: Vec<String> = sites
+                .iter()
+                .filter(|site| entry.route.matches(site))
+                .map(|site| site.name.clone())
+                .collect();
+            let kind = entry.notifier.kind();
+            let notifier = match entry.notifier {
+                NotifierConfig::Telegram(telegram) => Box::new(TelegramNotifier::new(
+                    telegram,
+                    bot_chats.clone(),
+                    // Dry runs print the reports right away.
+                    store.filter(|_| !dry_run).cloned(),
+                    target,
+                    format.clone(),
+                    http.clone(),
+                    dry_run,
+                )?) as Box<dyn Notifier>,
+                notifier if dry_run => {
+                    Box::new(DryRunNotifier::new(notifier.kind(), format.clone()))
+                }
+                NotifierConfig::Email(email) => {
+                    Box::new(email::EmailNotifier::new(email, format.clone())?)
+                }
+                NotifierConfig::Discord(discord) => Box::new(discord::DiscordNotifier::new(
+                    discord,
+                    format.clone(),
+                    http.clone(),
+                )),
+                NotifierConfig::Slack(slack) => Box::new(slack::SlackNotifier::new(
+                    slack,
+                    format.clone(),
+                    http.clone(),
+                )?),
+                NotifierConfig::Pushover(pushover) => Box::new(pushover::PushoverNotifier::new(
+                    pushover,
+                    format.clone(),
+                    http.clone(),
+                )),
+                NotifierConfig::Ntfy(ntfy) => {
+                    Box::new(ntfy::NtfyNotifier::new(ntfy, format.clone(), http.clone()))
+                }
+                NotifierConfig::Mqtt(mqtt) => {
+                    Box::new(mqtt::MqttNotifier::new(mqtt, site_names, format.clone()))
+                }
+                NotifierConfig::Webhook(webhook) => {
+                    Box::new(webhook::WebhookNotifier::new(webhook, http.clone()))
+                }
+                NotifierConfig::Feed(feed) => {
+                    Box::new(feed::FeedNotifier::new(feed, format.clone()))
+                }
+                NotifierConfig::Csv(csv) => Box::new(csv::CsvNotifier::new(csv, format.clone())),
+                NotifierConfig::Ics(ics) => Box::new(ics::IcsNotifier::new(ics, format.clone())),
+                NotifierConfig::File(file) => {
+                    Box::new(sink::FileNotifier::new(file, format.clone(), target))
+                }
+                NotifierConfig::S3(s3) => Box::new(sink::S3Notifier::new(
+                    s3,
+                    format.clone(),
+                    target,
+                    http.clone(),
+                )),
+            };
+            Ok(RoutedNotifier {
+                kind,
+                route: entry.route,
+                notifier,
+            })
+        })
+        .collect()
 }
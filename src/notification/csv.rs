@@ -0,0 +1,77 @@
+use super::{Notifier, NotifyError};
+use crate::config::CsvConfig;
+use crate::message::MessageFormat;
+use crate::{format_hours, SiteFlyAbilityReport};
+use async_trait::async_trait;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+const HEADER: &str = "site,date,start,duration_hours,wind_min,wind_max,wind_unit,\
+wind_degree_min,wind_degree_max,wind_direction,temp_min,temp_max,temperature_unit";
+
+/// Quotes `field` when it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+/// Appends the flyable periods of every run to a CSV file, one row per
+/// period, building a season-long record of the predicted flyable hours.
+pub struct CsvNotifier {
+    config: CsvConfig,
+    format: MessageFormat,
+}
+
+impl CsvNotifier {
+    pub fn new(config: CsvConfig, format: MessageFormat) -> Self {
+        CsvNotifier { config, format }
+    }
+
+    fn rows(&self, report: &SiteFlyAbilityReport) -> Vec<String> {
+        let units = self.format.units();
+        report
+            .periods
+            .iter()
+            .map(|period| {
+                [
+                    csv_field(&report.site.name),
+                    report.date.format("%Y-%m-%d").to_string(),
+                    period.start.to_rfc3339(),
+                    format_hours(period.hours()),
+                    period.wind_min.format(units.wind),
+                    period.wind_max.format(units.wind),
+                    csv_field(units.wind.label()),
+                    period.wind_directions.from.degrees().to_string(),
+                    period.wind_directions.to.degrees().to_string(),
+                    csv_field(&period.wind_direction()),
+                    format!("{:.1}", period.temp_min.in_unit(units.temperature)),
+                    format!("{:.1}", period.temp_max.in_unit(units.temperature)),
+                    csv_field(units.temperature.label()),
+                ]
+                .join(",")
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Notifier for CsvNotifier {
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.config.path)?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{}", HEADER)?;
+        }
+        for report in reports {
+            for row in self.rows(report) {
+                writeln!(file, "{}", row)?;
+            }
+        }
+        Ok(())
+    }
+}
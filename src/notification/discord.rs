@@ -0,0 +1,96 @@
+use super::{Notifier, NotifyError};
+use crate::config::Discord;
+use crate::http::HttpClient;
+use crate::message::MessageFormat;
+use crate::{SiteFlyAbilityReport, SkippedSite};
+use async_trait::async_trait;
+use serde::Serialize;
+
+// Discord accepts at most this many embeds per webhook message.
+const MAX_EMBEDS_PER_MESSAGE: usize = 10;
+const COLOR_FLYABLE: u32 = 0x2e_cc_71;
+const COLOR_MARGINAL: u32 = 0xf1_c4_0f;
+const COLOR_NOT_FLYABLE: u32 = 0x95_a5_a6;
+
+#[derive(Serialize, Debug)]
+struct Embed {
+    title: String,
+    description: String,
+    color: u32,
+}
+
+#[derive(Serialize, Debug)]
+struct WebhookMessage {
+    username: String,
+    embeds: Vec<Embed>,
+}
+
+/// Posts one embed per site to a Discord webhook, green for good sites and
+/// yellow for marginal ones.
+pub struct DiscordNotifier {
+    webhook_url: String,
+    username: String,
+    marginal_score: f32,
+    format: MessageFormat,
+    http: HttpClient,
+}
+
+impl DiscordNotifier {
+    pub fn new(config: Discord, format: MessageFormat, http: HttpClient) -> Self {
+        DiscordNotifier {
+            webhook_url: config.webhook_url,
+            username: config.username,
+            marginal_score: config.marginal_score,
+            format,
+            http,
+        }
+    }
+
+    async fn post(&self, message: &WebhookMessage) -> Result<(), NotifyError> {
+        self.http
+            .send(|client| client.post(&self.webhook_url).json(message))
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn embed(&self, report: &SiteFlyAbilityReport) -> Embed {
+        let (title, description) = report.sections(&self.format);
+        Embed {
+            title,
+            description,
+            color: if report.score < self.marginal_score {
+                COLOR_MARGINAL
+            } else {
+                COLOR_FLYABLE
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        for chunk in reports.chunks(MAX_EMBEDS_PER_MESSAGE) {
+            let message = WebhookMessage {
+                username: self.username.clone(),
+                embeds: chunk.iter().map(|r| self.embed(r)).collect(),
+            };
+            self.post(&message).await?;
+        }
+        Ok(())
+    }
+
+    async fn notify_nothing_flyable(&self, skipped: &[SkippedSite]) -> Result<(), NotifyError> {
+        let (title, description) = crate::nothing_flyable_sections(skipped, &self.format);
+        let message = WebhookMessage {
+            username: self.username.clone(),
+            embeds: vec![Embed {
+                title,
+                description,
+                color: COLOR_NOT_FLYABLE,
+            }],
+        };
+        self.post(&message).await
+    }
+}
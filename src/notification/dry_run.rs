@@ -0,0 +1,38 @@
+use super::{Notifier, NotifyError};
+use crate::message::MessageFormat;
+use crate::{SiteFlyAbilityReport, SkippedSite};
+use async_trait::async_trait;
+
+/// Stands in for a notifier during dry runs, printing the digest it would
+/// have sent to stdout.
+pub struct DryRunNotifier {
+    kind: &'static str,
+    format: MessageFormat,
+}
+
+impl DryRunNotifier {
+    pub fn new(kind: &'static str, format: MessageFormat) -> Self {
+        DryRunNotifier { kind, format }
+    }
+}
+
+#[async_trait]
+impl Notifier for DryRunNotifier {
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        println!(
+            "--- {} ---\n{}",
+            self.kind,
+            crate::render_digest(reports, &self.format)
+        );
+        Ok(())
+    }
+
+    async fn notify_nothing_flyable(&self, skipped: &[SkippedSite]) -> Result<(), NotifyError> {
+        println!(
+            "--- {} ---\n{}",
+            self.kind,
+            crate::render_nothing_flyable(skipped, &self.format)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use super::{Notifier, NotifyError};
+use crate::config::Email;
+use crate::message::MessageFormat;
+use crate::{SiteFlyAbilityReport, SkippedSite};
+use async_trait::async_trait;
+use lettre::message::{Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    recipients: Vec<Mailbox>,
+    format: MessageFormat,
+}
+
+impl EmailNotifier {
+    pub fn new(config: Email, format: MessageFormat) -> Result<Self, NotifyError> {
+        let mut builder = if config.starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+        };
+        builder = builder.port(config.port);
+        if let (Some(username), Some(password)) = (config.username, config.password) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        let recipients = config
+            .recipients
+            .iter()
+            .map(|r| r.parse())
+            .collect::<Result<Vec<Mailbox>, _>>()?;
+        Ok(EmailNotifier {
+            transport: builder.build(),
+            from: config.from.parse()?,
+            recipients,
+            format,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        let site_names: Vec<&str> = reports.iter().map(|r| &r.site.name[..]).collect();
+        let subject = format!("Flyable tomorrow: {}", site_names.join(", "));
+        let text = crate::render_digest(reports, &self.format);
+        let html = crate::render_digest_html(reports, &self.format);
+        for recipient in &self.recipients {
+            let email = Message::builder()
+                .from(self.from.clone())
+                .to(recipient.clone())
+                .subject(&subject)
+                .multipart(MultiPart::alternative_plain_html(
+                    text.clone(),
+                    html.clone(),
+                ))?;
+            self.transport.send(email).await?;
+        }
+        Ok(())
+    }
+
+    async fn notify_nothing_flyable(&self, skipped: &[SkippedSite]) -> Result<(), NotifyError> {
+        let (subject, _) = crate::nothing_flyable_sections(skipped, &self.format);
+        let text = crate::render_nothing_flyable(skipped, &self.format);
+        for recipient in &self.recipients {
+            let email = Message::builder()
+                .from(self.from.clone())
+                .to(recipient.clone())
+                .subject(&subject)
+                .singlepart(SinglePart::plain(text.clone()))?;
+            self.transport.send(email).await?;
+        }
+        Ok(())
+    }
+}
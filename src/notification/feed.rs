@@ -0,0 +1,86 @@
+use super::{Notifier, NotifyError};
+use crate::config::FeedConfig;
+use crate::message::MessageFormat;
+use crate::SiteFlyAbilityReport;
+use async_trait::async_trait;
+use atom_syndication::{Content, Entry, Feed, Link};
+use chrono::Utc;
+use std::fs::File;
+use std::io::{self, BufReader};
+
+/// Maintains an Atom feed file with one entry per run, newest first.
+pub struct FeedNotifier {
+    config: FeedConfig,
+    format: MessageFormat,
+}
+
+impl FeedNotifier {
+    pub fn new(config: FeedConfig, format: MessageFormat) -> Self {
+        FeedNotifier { config, format }
+    }
+
+    /// The feed written by the previous runs, a new one on the first run.
+    /// Unreadable feeds fail rather than losing their entries.
+    fn load_feed(&self) -> Result<Feed, NotifyError> {
+        match File::open(&self.config.path) {
+            Ok(file) => {
+                return Feed::read_from(BufReader::new(file)).map_err(|error| {
+                    NotifyError::Config(format!(
+                        "{} is not an Atom feed, fix or remove it: {}",
+                        self.config.path.display(),
+                        error
+                    ))
+                })
+            }
+            Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error.into()),
+            Err(_) => {}
+        }
+        let mut feed = Feed::default();
+        feed.set_id("urn:weather-notifier:feed");
+        if let Some(link) = &self.config.link {
+            let mut alternate = Link::default();
+            alternate.set_href(&link[..]);
+            feed.set_links(vec![alternate]);
+        }
+        Ok(feed)
+    }
+
+    fn entry(&self, reports: &[SiteFlyAbilityReport]) -> Entry {
+        let now = Utc::now().fixed_offset();
+        let title = if reports.is_empty() {
+            String::from("Nothing flyable tomorrow")
+        } else {
+            let names: Vec<&str> = reports.iter().map(|r| &r.site.name[..]).collect();
+            format!("Flyable tomorrow: {}", names.join(", "))
+        };
+        let mut content = Content::default();
+        content.set_content_type(String::from("html"));
+        content.set_value(crate::render_digest_html(reports, &self.format));
+
+        let mut entry = Entry::default();
+        entry.set_id(format!("urn:weather-notifier:run:{}", now.timestamp()));
+        entry.set_title(title);
+        entry.set_updated(now);
+        entry.set_content(content);
+        entry
+    }
+}
+
+#[async_trait]
+impl Notifier for FeedNotifier {
+    fn notify_when_empty(&self) -> bool {
+        true
+    }
+
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        let mut feed = self.load_feed()?;
+        let mut entries = vec![self.entry(reports)];
+        entries.extend(feed.entries().iter().cloned());
+        entries.truncate(self.config.max_entries);
+        feed.set_title(&self.config.title[..]);
+        feed.set_updated(Utc::now().fixed_offset());
+        feed.set_entries(entries);
+        feed.write_to(File::create(&self.config.path)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use super::{Notifier, NotifyError};
+use crate::config::IcsConfig;
+use crate::message::MessageFormat;
+use crate::SiteFlyAbilityReport;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use std::fs;
+
+// Content lines longer than this many octets must be folded.
+const MAX_LINE_OCTETS: usize = 75;
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Times are written in UTC, calendars showing them in their own timezone.
+fn format_time<Tz: TimeZone>(time: &DateTime<Tz>) -> String {
+    time.with_timezone(&Utc)
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// Folds `line` into CRLF terminated lines within `MAX_LINE_OCTETS`,
+/// continuation lines starting with a space.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Writes an iCalendar file with one event per flyable period, to be
+/// subscribed to from calendar apps. Every run replaces the events.
+pub struct IcsNotifier {
+    config: IcsConfig,
+    format: MessageFormat,
+}
+
+impl IcsNotifier {
+    pub fn new(config: IcsConfig, format: MessageFormat) -> Self {
+        IcsNotifier { config, format }
+    }
+
+    fn events(&self, report: &SiteFlyAbilityReport, stamp: &str) -> Vec<String> {
+        let summary = format!("🪂 {} ({:.0})", report.site.name, report.score);
+        let uid: String = report
+            .site
+            .name
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect();
+        report
+            .periods
+            .iter()
+            .flat_map(|period| {
+                let end = period.end();
+                vec![
+                    String::from("BEGIN:VEVENT"),
+                    format!(
+                        "UID:{}-{}@weather-notifier",
+                        uid.to_lowercase(),
+                        period.start.timestamp()
+                    ),
+                    format!("DTSTAMP:{}", stamp),
+                    format!("DTSTART:{}", format_time(&period.start)),
+                    format!("DTEND:{}", format_time(&end)),
+                    format!("SUMMARY:{}", escape_text(&summary)),
+                    format!(
+                        "DESCRIPTION:{}",
+                        escape_text(&report.describe_period(period, &self.format))
+                    ),
+                    format!("LOCATION:{}", escape_text(&report.site.name)),
+                    format!("GEO:{};{}", report.site.latitude(), report.site.longitude()),
+                    String::from("END:VEVENT"),
+                ]
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Notifier for IcsNotifier {
+    /// Runs without flyable sites clear the calendar.
+    fn notify_when_empty(&self) -> bool {
+        true
+    }
+
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        let stamp = format_time(&Utc::now());
+        let mut lines = vec![
+            String::from("BEGIN:VCALENDAR"),
+            String::from("VERSION:2.0"),
+            String::from("PRODID:-//weather-notifier//flyable windows//EN"),
+            format!("X-WR-CALNAME:{}", escape_text(&self.config.name)),
+        ];
+        for report in reports {
+            lines.extend(self.events(report, &stamp));
+        }
+        lines.push(String::from("END:VCALENDAR"));
+        let calendar: String = lines.iter().map(|line| fold_line(line)).collect();
+        fs::write(&self.config.path, calendar)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use super::{Notifier, NotifyError};
+use crate::config::Mqtt;
+use crate::message::MessageFormat;
+use crate::SiteFlyAbilityReport;
+use async_trait::async_trait;
+use rumqttc::{AsyncClient, Event, MqttOptions, Outgoing, QoS};
+use serde_json::json;
+use std::time::Duration;
+
+/// Payload Home Assistant reads as an unknown state.
+const UNKNOWN: &str = "None";
+
+/// Publishes per-site state for home-automation consumers:
+/// `<base>/<site>/flyable_tomorrow`, `<base>/<site>/score`,
+/// `<base>/<site>/max_wind` in m/s, `<base>/<site>/flyable_hours` and
+/// `<base>/<site>/report`, along with their Home Assistant discovery
+/// configs when `discovery_prefix` is set.
+pub struct MqttNotifier {
+    config: Mqtt,
+    site_names: Vec<String>,
+    format: MessageFormat,
+}
+
+fn topic_slug(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+impl MqttNotifier {
+    pub fn new(config: Mqtt, site_names: Vec<String>, format: MessageFormat) -> Self {
+        MqttNotifier {
+            config,
+            site_names,
+            format,
+        }
+    }
+
+    fn messages(&self, reports: &[SiteFlyAbilityReport]) -> Vec<(String, String)> {
+        let base = self.config.base_topic.trim_end_matches('/');
+        let mut messages = vec![];
+        for name in &self.site_names {
+            let topic = format!("{}/{}", base, topic_slug(name));
+            let report = reports.iter().find(|r| &r.site.name == name);
+            messages.push((
+                format!("{}/flyable_tomorrow", topic),
+                report.is_some().to_string(),
+            ));
+            messages.push((
+                format!("{}/score", topic),
+                report.map_or(0.0, |r| r.score).round().to_string(),
+            ));
+            let max_wind = report
+                .and_then(|r| r.periods.iter().map(|p| p.wind_max).reduce(|a, b| a.max(b)))
+                .map_or_else(
+                    || String::from(UNKNOWN),
+                    |wind| format!("{:.1}", wind.meters_per_second()),
+                );
+            messages.push((format!("{}/max_wind", topic), max_wind));
+            messages.push((
+                format!("{}/flyable_hours", topic),
+                report.map_or(0.0, |r| r.flyable_hours()).to_string(),
+            ));
+            messages.push((
+                format!("{}/report", topic),
+                report.map_or_else(String::new, |r| r.as_string(&self.format)),
+            ));
+        }
+        messages
+    }
+
+    /// The Home Assistant discovery configs of the entities of each site,
+    /// grouped into a device per site.
+    fn discovery_messages(&self, prefix: &str) -> Vec<(String, String)> {
+        let prefix = prefix.trim_end_matches('/');
+        let base = self.config.base_topic.trim_end_matches('/');
+        let node = topic_slug(base);
+        let mut messages = vec![];
+        for name in &self.site_names {
+            let topic = format!("{}/{}", base, topic_slug(name));
+            let device_id = format!("{}_{}", node, topic_slug(name));
+            let device = json!({
+                "identifiers": [device_id],
+                "name": name,
+                "manufacturer": "weather-notifier",
+            });
+            let entities = [
+                (
+                    "binary_sensor",
+                    "flyable_tomorrow",
+                    json!({
+                        "name": "Flyable tomorrow",
+                        "payload_on": "true",
+                        "payload_off": "false",
+                    }),
+                ),
+                (
+                    "sensor",
+                    "score",
+                    json!({"name": "Score", "state_class": "measurement"}),
+                ),
+                (
+                    "sensor",
+                    "max_wind",
+                    json!({
+                        "name": "Max wind",
+                        "device_class": "wind_speed",
+                        "unit_of_measurement": "m/s",
+                    }),
+                ),
+                (
+                    "sensor",
+                    "flyable_hours",
+                    json!({
+                        "name": "Flyable hours",
+                        "device_class": "duration",
+                        "unit_of_measurement": "h",
+                    }),
+                ),
+            ];
+            for (component, entity, mut config) in entities {
+                let unique_id = format!("{}_{}", device_id, entity);
+                config["unique_id"] = json!(unique_id);
+                config["object_id"] = json!(unique_id);
+                config["state_topic"] = json!(format!("{}/{}", topic, entity));
+                config["device"] = device.clone();
+                messages.push((
+                    format!("{}/{}/{}/{}/config", prefix, component, device_id, entity),
+                    config.to_string(),
+                ));
+            }
+        }
+        messages
+    }
+}
+
+#[async_trait]
+impl Notifier for MqttNotifier {
+    fn notify_when_empty(&self) -> bool {
+        true
+    }
+
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        let mut options =
+            MqttOptions::new(&self.config.client_id, &self.config.host, self.config.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let (Some(username), Some(password)) = (&self.config.username, &self.config.password) {
+            options.set_credentials(username, password);
+        }
+        let (client, mut event_loop) = AsyncClient::new(options, 16);
+        let connection = tokio::spawn(async move {
+            loop {
+                match event_loop.poll().await {
+                    Ok(Event::Outgoing(Outgoing::Disconnect)) => return Ok(()),
+                    Ok(_) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        });
+        // Retained for Home Assistant to find the entities after restarting.
+        if let Some(prefix) = &self.config.discovery_prefix {
+            for (topic, payload) in self.discovery_messages(prefix) {
+                client
+                    .publish(topic, QoS::AtLeastOnce, true, payload)
+                    .await?;
+            }
+        }
+        for (topic, payload) in self.messages(reports) {
+            client
+                .publish(topic, QoS::AtLeastOnce, self.config.retain, payload)
+                .await?;
+        }
+        client.disconnect().await?;
+        connection.await?.map_err(Box::new)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use super::{Notifier, NotifyError};
+use crate::config::Ntfy;
+use crate::http::HttpClient;
+use crate::message::MessageFormat;
+use crate::{SiteFlyAbilityReport, SkippedSite};
+use async_trait::async_trait;
+
+/// Publishes reports to an ntfy topic on ntfy.sh or a self-hosted server.
+pub struct NtfyNotifier {
+    config: Ntfy,
+    format: MessageFormat,
+    http: HttpClient,
+}
+
+impl NtfyNotifier {
+    pub fn new(config: Ntfy, format: MessageFormat, http: HttpClient) -> Self {
+        NtfyNotifier {
+            config,
+            format,
+            http,
+        }
+    }
+
+    async fn publish(&self, title: &str, body: String) -> Result<(), NotifyError> {
+        let url = format!(
+            "{}/{}",
+            self.config.server.trim_end_matches('/'),
+            self.config.topic
+        );
+        let tags = self.config.tags.join(",");
+        self.http
+            .send(|client| {
+                let mut request = client
+                    .post(&url)
+                    .header("Title", title)
+                    .header("Tags", &tags[..])
+                    .body(body.clone());
+                if let Some(priority) = &self.config.priority {
+                    request = request.header("Priority", &priority[..]);
+                }
+                if let Some(token) = &self.config.token {
+                    request = request.bearer_auth(token);
+                }
+                request
+            })
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for NtfyNotifier {
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        self.publish(
+            &self.config.title,
+            crate::render_digest(reports, &self.format),
+        )
+        .await
+    }
+
+    async fn notify_nothing_flyable(&self, skipped: &[SkippedSite]) -> Result<(), NotifyError> {
+        let (title, reasons) = crate::nothing_flyable_sections(skipped, &self.format);
+        let body = if reasons.is_empty() {
+            title.clone()
+        } else {
+            reasons
+        };
+        self.publish(&title, body).await
+    }
+}
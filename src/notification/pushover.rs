@@ -0,0 +1,91 @@
+use super::{Notifier, NotifyError};
+use crate::config::Pushover;
+use crate::http::HttpClient;
+use crate::message::MessageFormat;
+use crate::{SiteFlyAbilityReport, SkippedSite};
+use async_trait::async_trait;
+
+const MESSAGES_URL: &str = "https://api.pushover.net/1/messages.json";
+// Pushover rejects longer messages.
+const MAX_MESSAGE_CHARS: usize = 1024;
+// Repeated until acknowledged, needing `retry` and `expire`.
+const EMERGENCY_PRIORITY: i8 = 2;
+
+pub struct PushoverNotifier {
+    config: Pushover,
+    format: MessageFormat,
+    http: HttpClient,
+}
+
+impl PushoverNotifier {
+    pub fn new(config: Pushover, format: MessageFormat, http: HttpClient) -> Self {
+        PushoverNotifier {
+            config,
+            format,
+            http,
+        }
+    }
+
+    fn is_epic(&self, reports: &[SiteFlyAbilityReport]) -> bool {
+        reports.iter().any(|r| r.score >= self.config.epic_score)
+    }
+
+    async fn send(
+        &self,
+        title: &str,
+        message: &str,
+        priority: i8,
+        sound: &Option<String>,
+    ) -> Result<(), NotifyError> {
+        let message: String = message.chars().take(MAX_MESSAGE_CHARS).collect();
+        let emergency = priority >= EMERGENCY_PRIORITY;
+        let priority = priority.to_string();
+        let retry = self.config.emergency_retry_seconds.to_string();
+        let expire = self.config.emergency_expire_seconds.to_string();
+        let mut form = vec![
+            ("token", &self.config.app_token[..]),
+            ("user", &self.config.user_key[..]),
+            ("title", title),
+            ("message", &message[..]),
+            ("priority", &priority[..]),
+        ];
+        if emergency {
+            form.push(("retry", &retry[..]));
+            form.push(("expire", &expire[..]));
+        }
+        if let Some(sound) = sound {
+            form.push(("sound", &sound[..]));
+        }
+        self.http
+            .send(|client| client.post(MESSAGES_URL).form(&form))
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for PushoverNotifier {
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        let epic = self.is_epic(reports);
+        let (priority, sound) = if epic {
+            (self.config.epic_priority, &self.config.epic_sound)
+        } else {
+            (self.config.priority, &self.config.sound)
+        };
+        let title = if epic {
+            "Epic flying day tomorrow!"
+        } else {
+            "Flyable tomorrow"
+        };
+        let message = crate::render_digest(reports, &self.format);
+        self.send(title, &message, priority, sound).await
+    }
+
+    async fn notify_nothing_flyable(&self, skipped: &[SkippedSite]) -> Result<(), NotifyError> {
+        let (title, reasons) = crate::nothing_flyable_sections(skipped, &self.format);
+        let message = if reasons.is_empty() { &title } else { &reasons };
+        self.send(&title, message, self.config.priority, &self.config.sound)
+            .await
+    }
+}
@@ -0,0 +1,279 @@
+use super::{Notifier, NotifyError};
+use crate::config::{FileSink, S3Sink, SinkFormat, Target};
+use crate::export::ReportSetView;
+use crate::http::HttpClient;
+use crate::message::MessageFormat;
+use crate::SiteFlyAbilityReport;
+use async_trait::async_trait;
+use chrono::{DateTime, Local, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+// Characters left as they are in the paths of signed requests.
+const UNRESERVED: &str = "-._~/";
+
+/// The reports rendered as `sink_format`, with their MIME type. A run where
+/// nothing is flyable gives its headline, replacing the reports of the
+/// previous run.
+fn render(
+    reports: &[SiteFlyAbilityReport],
+    sink_format: SinkFormat,
+    format: &MessageFormat,
+    target: Target,
+) -> Result<(String, &'static str), NotifyError> {
+    let nothing_flyable = || {
+        let days_ahead = target.days_ahead(Local::now().date_naive());
+        crate::i18n::fill(
+            format.strings().nothing_flyable,
+            &[("day", &format.strings().day(days_ahead))],
+        )
+    };
+    Ok(match sink_format {
+        SinkFormat::Json => {
+            let json = serde_json::to_string_pretty(&ReportSetView::new(reports, &[]))
+                .map_err(|error| NotifyError::Config(error.to_string()))?;
+            (json + "\n", "application/json")
+        }
+        SinkFormat::Text if reports.is_empty() => {
+            (nothing_flyable() + "\n", "text/plain; charset=utf-8")
+        }
+        SinkFormat::Text => (
+            crate::render_digest(reports, format) + "\n",
+            "text/plain; charset=utf-8",
+        ),
+        SinkFormat::Html if reports.is_empty() => (
+            format!(
+                "<html><body>\n<p>{}</p>\n</body></html>\n",
+                crate::escape_html(&nothing_flyable())
+            ),
+            "text/html; charset=utf-8",
+        ),
+        SinkFormat::Html => (
+            crate::render_digest_html(reports, format),
+            "text/html; charset=utf-8",
+        ),
+    })
+}
+
+/// `template` with `{date}` replaced by the day of the run.
+fn fill_date(template: &str) -> String {
+    template.replace("{date}", &Local::now().format("%Y-%m-%d").to_string())
+}
+
+/// Writes the reports of every run to a file, swapped in whole so that
+/// readers never see half of it.
+pub struct FileNotifier {
+    config: FileSink,
+    format: MessageFormat,
+    target: Target,
+}
+
+impl FileNotifier {
+    pub fn new(config: FileSink, format: MessageFormat, target: Target) -> Self {
+        FileNotifier {
+            config,
+            format,
+            target,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for FileNotifier {
+    fn notify_when_empty(&self) -> bool {
+        true
+    }
+
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        let (body, _) = render(reports, self.config.format, &self.format, self.target)?;
+        let path = fill_date(&self.config.path);
+        let path = Path::new(&path);
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, body)?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
+
+/// Uploads the reports of every run to a bucket with requests signed by
+/// AWS Signature Version 4.
+pub struct S3Notifier {
+    config: S3Sink,
+    format: MessageFormat,
+    target: Target,
+    http: HttpClient,
+}
+
+impl S3Notifier {
+    pub fn new(config: S3Sink, format: MessageFormat, target: Target, http: HttpClient) -> Self {
+        S3Notifier {
+            config,
+            format,
+            target,
+            http,
+        }
+    }
+
+    /// The `Authorization` header of a PUT of a body hashing to
+    /// `payload_hash` to `url` at `now`.
+    fn authorization(&self, url: &Url, payload_hash: &str, now: DateTime<Utc>) -> String {
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let time = now.format("%Y%m%dT%H%M%SZ");
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            url.path(),
+            host,
+            payload_hash,
+            time,
+            signed_headers,
+            payload_hash
+        );
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.config.access_key_id,
+            scope(now, &self.config.region),
+            signed_headers,
+            signature(
+                &self.config.secret_access_key,
+                &self.config.region,
+                now,
+                &canonical_request
+            )
+        )
+    }
+}
+
+/// The credential scope of the requests to S3 in `region` on the day of `now`.
+fn scope(now: DateTime<Utc>, region: &str) -> String {
+    format!("{}/{}/s3/aws4_request", now.format("%Y%m%d"), region)
+}
+
+/// The AWS Signature Version 4 of `canonical_request`, sent to S3 in
+/// `region` at `now`.
+fn signature(
+    secret_access_key: &str,
+    region: &str,
+    now: DateTime<Utc>,
+    canonical_request: &str,
+) -> String {
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        now.format("%Y%m%dT%H%M%SZ"),
+        scope(now, region),
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = format!("AWS4{}", secret_access_key);
+    let key = hmac(key.as_bytes(), now.format("%Y%m%d").to_string().as_bytes());
+    let key = hmac(&key, region.as_bytes());
+    let key = hmac(&key, b"s3");
+    let key = hmac(&key, b"aws4_request");
+    hex(&hmac(&key, string_to_sign.as_bytes()))
+}
+
+#[async_trait]
+impl Notifier for S3Notifier {
+    fn notify_when_empty(&self) -> bool {
+        true
+    }
+
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        let (body, content_type) = render(reports, self.config.format, &self.format, self.target)?;
+        let key = fill_date(&self.config.key);
+        let path = format!("{}/{}", self.config.bucket, key);
+        let mut url = Url::parse(&self.config.endpoint)
+            .map_err(|error| NotifyError::Config(format!("invalid S3 endpoint: {}", error)))?;
+        url.set_path(&uri_encode(&path));
+        let payload_hash = hex(&Sha256::digest(body.as_bytes()));
+        let now = Utc::now();
+        let authorization = self.authorization(&url, &payload_hash, now);
+        self.http
+            .send(|client| {
+                let mut request = client
+                    .put(url.clone())
+                    .header("authorization", &authorization)
+                    .header("x-amz-content-sha256", &payload_hash)
+                    .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string())
+                    .header("content-type", content_type)
+                    .body(body.clone());
+                if let Some(cache_control) = &self.config.cache_control {
+                    request = request.header("cache-control", cache_control);
+                }
+                request
+            })
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// `path` percent-encoded as S3 expects it, slashes kept.
+fn uri_encode(path: &str) -> String {
+    let mut encoded = String::from("/");
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || UNRESERVED.as_bytes().contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// HMAC-SHA256 of `message`.
+fn hmac(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// The GET Object example of the AWS Signature Version 4 documentation
+    /// for S3.
+    #[test]
+    fn signs_the_aws_example_request() {
+        let canonical_request = "GET
+/test.txt
+
+host:examplebucket.s3.amazonaws.com
+range:bytes=0-9
+x-amz-content-sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855
+x-amz-date:20130524T000000Z
+
+host;range;x-amz-content-sha256;x-amz-date
+e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let now = Utc.with_ymd_and_hms(2013, 5, 24, 0, 0, 0).unwrap();
+        assert_eq!(
+            scope(now, "us-east-1"),
+            "20130524/us-east-1/s3/aws4_request"
+        );
+        assert_eq!(
+            signature(
+                "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY",
+                "us-east-1",
+                now,
+                canonical_request
+            ),
+            "f0e8bdb87c964420e857bd35b5d6ed310bd44f0170aba48dd91039c6036bdb41"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use super::{Notifier, NotifyError};
+use crate::config::Slack;
+use crate::http::HttpClient;
+use crate::message::MessageFormat;
+use crate::{SiteFlyAbilityReport, SkippedSite};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+const POST_MESSAGE_URL: &str = "https://slack.com/api/chat.postMessage";
+// Slack rejects messages with more than 50 blocks; each site takes three.
+const SITES_PER_MESSAGE: usize = 16;
+
+#[derive(Serialize, Debug)]
+struct Text {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    text: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Block {
+    Header { text: Text },
+    Section { text: Text },
+    Divider,
+}
+
+#[derive(Serialize, Debug)]
+struct SlackMessage<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel: Option<&'a str>,
+    text: String,
+    blocks: Vec<Block>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SlackResponse {
+    ok: bool,
+    error: Option<String>,
+}
+
+fn escape_mrkdwn(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn blocks(report: &SiteFlyAbilityReport, format: &MessageFormat) -> Vec<Block> {
+    let (header, details) = report.sections(format);
+    vec![
+        Block::Header {
+            text: Text {
+                kind: "plain_text",
+                text: header,
+            },
+        },
+        Block::Section {
+            text: Text {
+                kind: "mrkdwn",
+                text: escape_mrkdwn(&details),
+            },
+        },
+        Block::Divider,
+    ]
+}
+
+/// Posts reports as Block Kit sections, either through an incoming webhook
+/// or with a bot token to a channel.
+pub struct SlackNotifier {
+    config: Slack,
+    format: MessageFormat,
+    http: HttpClient,
+}
+
+impl SlackNotifier {
+    pub fn new(
+        config: Slack,
+        format: MessageFormat,
+        http: HttpClient,
+    ) -> Result<Self, NotifyError> {
+        let has_bot = config.bot_token.is_some() && config.channel.is_some();
+        if config.webhook_url.is_none() && !has_bot {
+            return Err(NotifyError::Config(String::from(
+                "slack notifier needs `webhook_url` or `bot_token` and `channel`",
+            )));
+        }
+        Ok(SlackNotifier {
+            config,
+            format,
+            http,
+        })
+    }
+
+    /// The channel to post to, incoming webhooks having their own.
+    fn channel(&self) -> Option<&str> {
+        if self.config.webhook_url.is_none() {
+            self.config.channel.as_deref()
+        } else {
+            None
+        }
+    }
+
+    async fn post(&self, message: &SlackMessage<'_>) -> Result<(), NotifyError> {
+        if let Some(webhook_url) = &self.config.webhook_url {
+            self.http
+                .send(|client| client.post(webhook_url).json(message))
+                .await?
+                .error_for_status()?;
+            return Ok(());
+        }
+        let token = self.config.bot_token.as_deref().unwrap_or_default();
+        let response = self
+            .http
+            .send(|client| {
+                client
+                    .post(POST_MESSAGE_URL)
+                    .bearer_auth(token)
+                    .json(message)
+            })
+            .await?
+            .json::<SlackResponse>()
+            .await?;
+        if !response.ok {
+            let error = response
+                .error
+                .unwrap_or_else(|| String::from("unknown error"));
+            return Err(NotifyError::Rejected(format!(
+                "slack rejected the message: {}",
+                error
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        let channel = self.channel();
+        for chunk in reports.chunks(SITES_PER_MESSAGE) {
+            let message = SlackMessage {
+                channel,
+                text: crate::render_digest(chunk, &self.format),
+                blocks: chunk
+                    .iter()
+                    .flat_map(|report| blocks(report, &self.format))
+                    .collect(),
+            };
+            self.post(&message).await?;
+        }
+        Ok(())
+    }
+
+    async fn notify_nothing_flyable(&self, skipped: &[SkippedSite]) -> Result<(), NotifyError> {
+        let (header, reasons) = crate::nothing_flyable_sections(skipped, &self.format);
+        let mut blocks = vec![Block::Header {
+            text: Text {
+                kind: "plain_text",
+                text: header,
+            },
+        }];
+        if !reasons.is_empty() {
+            blocks.push(Block::Section {
+                text: Text {
+                    kind: "mrkdwn",
+                    text: escape_mrkdwn(&reasons),
+                },
+            });
+        }
+        let message = SlackMessage {
+            channel: self.channel(),
+            text: crate::render_nothing_flyable(skipped, &self.format),
+            blocks,
+        };
+        self.post(&message).await
+    }
+}
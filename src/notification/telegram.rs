@@ -0,0 +1,1223 @@
+use super::{Notifier, NotifyError};
+use crate::charts::ChartRenderer;
+use crate::config::{
+    FlyingSite, MessageMode, MessageStyle, QuietHours, Target, Telegram, TelegramParseMode,
+};
+use crate::digest::SiteDigest;
+use crate::http::HttpClient;
+use crate::measures::Units;
+use crate::message::MessageFormat;
+use crate::rate_limit::TokenBucket;
+use crate::store::{StateStore, Subscription, ALL_SITES};
+use crate::timezone::{self, Tz};
+use crate::{SiteFlyAbilityReport, SkippedSite};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use reqwest::header::RETRY_AFTER;
+use reqwest::multipart::{Form, Part};
+use reqwest::{Response, StatusCode};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Characters with a meaning in MarkdownV2, which must be escaped in text.
+const MARKDOWN_V2_SPECIAL: &str = "\\_*[]()~`>#+-=|{}.!";
+
+// Telegram counts message length in UTF-16 code units.
+const MAX_MESSAGE_LENGTH: usize = 4096;
+// Waits for the rate limit of Telegram to reset before a message fails.
+const MAX_FLOOD_WAITS: u32 = 5;
+const DEFAULT_FLOOD_WAIT_SECONDS: u64 = 1;
+
+/// Telegram answers every request with this, `ok` being false along with
+/// an error status when it refused the request.
+#[derive(Deserialize, Debug)]
+struct TelegramResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    error_code: Option<u16>,
+    description: Option<String>,
+}
+
+/// Why a request to Telegram failed.
+#[derive(Debug, thiserror::Error)]
+pub enum TelegramError {
+    #[error("Telegram request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// Telegram answered but refused the request, e.g. 400 for a message it
+    /// cannot parse or 403 for a bot blocked by the chat.
+    #[error("Telegram refused the request ({code}): {description}")]
+    Api { code: u16, description: String },
+}
+
+impl TelegramError {
+    /// Whether Telegram could not be reached or is failing for everyone,
+    /// rather than refusing this request.
+    pub fn is_unreachable(&self) -> bool {
+        match self {
+            TelegramError::Http(error) => error.status().is_none_or(|status| {
+                status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+            }),
+            TelegramError::Api { code, .. } => {
+                *code == StatusCode::TOO_MANY_REQUESTS.as_u16() || *code >= 500
+            }
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, TelegramError>;
+
+/// Error of a request over the rate limit.
+#[derive(Deserialize, Debug)]
+struct FloodResponse {
+    parameters: Option<FloodParameters>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FloodParameters {
+    retry_after: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Chat {
+    pub id: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Message {
+    pub message_id: i64,
+    pub chat: Chat,
+    pub text: Option<String>,
+}
+
+/// A press on an inline keyboard button.
+#[derive(Deserialize, Debug)]
+pub struct CallbackQuery {
+    pub id: String,
+    pub message: Option<Message>,
+    pub data: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Update {
+    pub update_id: i64,
+    pub message: Option<Message>,
+    pub callback_query: Option<CallbackQuery>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct InlineKeyboardButton {
+    pub text: String,
+    pub callback_data: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct InlineKeyboardMarkup {
+    pub inline_keyboard: Vec<Vec<InlineKeyboardButton>>,
+}
+
+#[derive(Serialize, Debug)]
+struct SendMessage<'a> {
+    chat_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parse_mode: Option<&'static str>,
+    text: &'a str,
+    disable_notification: bool,
+}
+
+impl TelegramParseMode {
+    fn api_name(self) -> Option<&'static str> {
+        match self {
+            TelegramParseMode::Plain => None,
+            TelegramParseMode::MarkdownV2 => Some("MarkdownV2"),
+            TelegramParseMode::Html => Some("HTML"),
+        }
+    }
+
+    /// Escapes plain `text` so that it renders verbatim.
+    pub fn escape(self, text: &str) -> String {
+        match self {
+            TelegramParseMode::Plain => String::from(text),
+            TelegramParseMode::MarkdownV2 => {
+                let mut escaped = String::with_capacity(text.len());
+                for c in text.chars() {
+                    if MARKDOWN_V2_SPECIAL.contains(c) {
+                        escaped.push('\\');
+                    }
+                    escaped.push(c);
+                }
+                escaped
+            }
+            TelegramParseMode::Html => crate::escape_html(text),
+        }
+    }
+
+    /// A link to `url` labelled with plain `text`.
+    pub fn link(self, text: &str, url: &str) -> String {
+        match self {
+            TelegramParseMode::Plain => format!("{}: {}", text, url),
+            TelegramParseMode::MarkdownV2 => format!(
+                "[{}]({})",
+                self.escape(text),
+                url.replace('\\', "\\\\").replace(')', "\\)")
+            ),
+            TelegramParseMode::Html => format!(
+                "<a href=\"{}\">{}</a>",
+                crate::escape_html(url),
+                crate::escape_html(text)
+            ),
+        }
+    }
+
+    /// Emphasizes already escaped `text`.
+    pub fn bold(self, text: &str) -> String {
+        match self {
+            TelegramParseMode::Plain => String::from(text),
+            TelegramParseMode::MarkdownV2 => format!("*{}*", text),
+            TelegramParseMode::Html => format!("<b>{}</b>", text),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct SendKeyboard<'a> {
+    chat_id: &'a str,
+    text: &'a str,
+    reply_markup: &'a InlineKeyboardMarkup,
+}
+
+#[derive(Serialize, Debug)]
+struct EditKeyboard<'a> {
+    chat_id: &'a str,
+    message_id: i64,
+    reply_markup: &'a InlineKeyboardMarkup,
+}
+
+#[derive(Serialize, Debug)]
+struct AnswerCallbackQuery<'a> {
+    callback_query_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<&'a str>,
+}
+
+pub struct TelegramClient {
+    url: String,
+    parse_mode: TelegramParseMode,
+    http: HttpClient,
+    /// Spreads the messages of large broadcasts.
+    limiter: Arc<TokenBucket>,
+    /// Prints messages to stdout instead of sending them.
+    dry_run: bool,
+}
+
+impl TelegramClient {
+    pub fn new(
+        token: String,
+        parse_mode: TelegramParseMode,
+        messages_per_second: f64,
+        http: HttpClient,
+        dry_run: bool,
+    ) -> Self {
+        let url = format!("https://api.telegram.org/bot{}", token);
+        TelegramClient {
+            url,
+            parse_mode,
+            http: http.for_telegram(),
+            limiter: Arc::new(TokenBucket::per_second(messages_per_second)),
+            dry_run,
+        }
+    }
+
+    pub fn parse_mode(&self) -> TelegramParseMode {
+        self.parse_mode
+    }
+
+    /// Sends `message`, formatted for the client's parse mode, split into
+    /// several messages sent in order when it is over Telegram's length limit.
+    /// Silent messages arrive without a notification sound.
+    pub async fn send_message(&self, chat_id: &str, message: &str, silent: bool) -> Result<()> {
+        for text in split_message(message, self.parse_mode) {
+            if self.dry_run {
+                println!("--- Telegram message to {} ---\n{}", chat_id, text);
+                continue;
+            }
+            let body = SendMessage {
+                chat_id,
+                parse_mode: self.parse_mode.api_name(),
+                text: &text,
+                disable_notification: silent,
+            };
+            self.call("sendMessage", &body).await?;
+        }
+        Ok(())
+    }
+
+    /// Long polls for updates following `offset`, waiting up to
+    /// `timeout_seconds` for one to arrive.
+    pub async fn get_updates(&self, offset: i64, timeout_seconds: u64) -> Result<Vec<Update>> {
+        // The request timeout only starts once Telegram is done waiting.
+        let timeout = Duration::from_secs(timeout_seconds) + self.http.timeout();
+        let response = self
+            .http
+            .send(|client| {
+                let request = client.get(format!("{}/getUpdates", self.url));
+                request.timeout(timeout).query(&[
+                    ("offset", offset.to_string()),
+                    ("timeout", timeout_seconds.to_string()),
+                    (
+                        "allowed_updates",
+                        String::from("[\"message\",\"callback_query\"]"),
+                    ),
+                ])
+            })
+            .await?;
+        read_response(response).await
+    }
+
+    /// Sends plain `text`, escaped for the client's parse mode.
+    pub async fn send_text(&self, chat_id: &str, text: &str) -> Result<()> {
+        self.send_message(chat_id, &self.parse_mode.escape(text), false)
+            .await
+    }
+
+    /// Uploads a PNG image with a plain text caption.
+    pub async fn send_photo(
+        &self,
+        chat_id: &str,
+        png: Vec<u8>,
+        caption: &str,
+        silent: bool,
+    ) -> Result<()> {
+        if self.dry_run {
+            println!(
+                "--- Telegram photo to {} ---\n{} ({} bytes)",
+                chat_id,
+                caption,
+                png.len()
+            );
+            return Ok(());
+        }
+        // Forms are consumed by sending, so every attempt builds its own.
+        let form = || {
+            let photo = Part::bytes(png.clone())
+                .file_name("chart.png")
+                .mime_str("image/png")
+                .expect("a valid MIME type");
+            Form::new()
+                .text("chat_id", String::from(chat_id))
+                .text("caption", String::from(caption))
+                .text("disable_notification", silent.to_string())
+                .part("photo", photo)
+        };
+        self.send_limited(|client| {
+            client
+                .post(format!("{}/sendPhoto", self.url))
+                .multipart(form())
+        })
+        .await
+    }
+
+    pub async fn send_keyboard(
+        &self,
+        chat_id: &str,
+        text: &str,
+        keyboard: &InlineKeyboardMarkup,
+    ) -> Result<()> {
+        let body = SendKeyboard {
+            chat_id,
+            text,
+            reply_markup: keyboard,
+        };
+        self.call("sendMessage", &body).await
+    }
+
+    /// Replaces the inline keyboard of a message sent earlier.
+    pub async fn edit_keyboard(
+        &self,
+        chat_id: &str,
+        message_id: i64,
+        keyboard: &InlineKeyboardMarkup,
+    ) -> Result<()> {
+        let body = EditKeyboard {
+            chat_id,
+            message_id,
+            reply_markup: keyboard,
+        };
+        self.call("editMessageReplyMarkup", &body).await
+    }
+
+    /// Stops the loading indicator on the pressed button, showing `text` as
+    /// a notification when given.
+    pub async fn answer_callback_query(
+        &self,
+        callback_query_id: &str,
+        text: Option<&str>,
+    ) -> Result<()> {
+        let body = AnswerCallbackQuery {
+            callback_query_id,
+            text,
+        };
+        self.call("answerCallbackQuery", &body).await
+    }
+
+    async fn call<T: Serialize>(&self, method: &str, body: &T) -> Result<()> {
+        if self.dry_run {
+            println!("--- Telegram {} ---", method);
+            return Ok(());
+        }
+        self.send_limited(|client| client.post(format!("{}/{}", self.url, method)).json(body))
+            .await
+    }
+
+    /// Sends the request built by `request` once the rate limiter lets it
+    /// through. Requests over Telegram's limit hold back every message for
+    /// the time it asks, up to `MAX_FLOOD_WAITS` times, other failures get
+    /// the usual retries.
+    async fn send_limited<F>(&self, request: F) -> Result<()>
+    where
+        F: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+    {
+        let mut flood_waits = 0;
+        loop {
+            self.limiter.acquire().await;
+            let response = match self.http.send_once(&request).await {
+                Ok(response)
+                    if response.status() == StatusCode::TOO_MANY_REQUESTS
+                        && flood_waits < MAX_FLOOD_WAITS =>
+                {
+                    let wait = flood_wait(response).await;
+                    tracing::warn!(?wait, "over the Telegram rate limit, waiting");
+                    self.limiter.pause(wait);
+                    flood_waits += 1;
+                    continue;
+                }
+                Ok(response) if !response.status().is_server_error() => response,
+                _ => self.http.send(&request).await?,
+            };
+            return read_response::<IgnoredAny>(response).await.map(|_| ());
+        }
+    }
+}
+
+/// The result of a request, failing when Telegram refused it. Responses
+/// without a body of Telegram's are turned into errors by their status.
+async fn read_response<T: DeserializeOwned + Default>(response: Response) -> Result<T> {
+    let status = response.status();
+    let body = match response.json::<TelegramResponse<T>>().await {
+        Ok(body) => body,
+        Err(_) if !status.is_success() => {
+            return Err(TelegramError::Api {
+                code: status.as_u16(),
+                description: String::from(status.canonical_reason().unwrap_or("no description")),
+            })
+        }
+        Err(error) => return Err(error.into()),
+    };
+    if !body.ok {
+        return Err(TelegramError::Api {
+            code: body.error_code.unwrap_or_else(|| status.as_u16()),
+            description: body
+                .description
+                .unwrap_or_else(|| String::from("no description")),
+        });
+    }
+    Ok(body.result.unwrap_or_default())
+}
+
+/// The wait asked for by a response over the rate limit, from its body or
+/// its `Retry-After` header.
+async fn flood_wait(response: Response) -> Duration {
+    let header = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok());
+    let body = response
+        .json::<FloodResponse>()
+        .await
+        .ok()
+        .and_then(|flood| flood.parameters)
+        .and_then(|parameters| parameters.retry_after);
+    Duration::from_secs(body.or(header).unwrap_or(DEFAULT_FLOOD_WAIT_SECONDS))
+}
+
+fn message_length(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// A place where a message may be cut: its byte offset, the length of the
+/// text before it and whether it is outside of every entity.
+struct Cut {
+    at: usize,
+    length: usize,
+    outside: bool,
+}
+
+/// The places where `message`, formatted for `mode`, may be cut without
+/// parting an escaped character, a tag or an HTML entity from itself.
+fn cuts(message: &str, mode: TelegramParseMode) -> Vec<Cut> {
+    let mut cuts = vec![];
+    let mut length = 0;
+    // Open bold and link entities, or HTML elements.
+    let mut depth = 0i32;
+    let mut chars = message.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        cuts.push(Cut {
+            at,
+            length,
+            outside: depth <= 0,
+        });
+        let mut token = String::from(c);
+        let mut take_until = |end: char, token: &mut String| {
+            for (_, c) in chars.by_ref() {
+                token.push(c);
+                if c == end {
+                    break;
+                }
+            }
+        };
+        match (mode, c) {
+            (TelegramParseMode::MarkdownV2, '\\') => {
+                if let Some((_, escaped)) = chars.next() {
+                    token.push(escaped);
+                }
+            }
+            (TelegramParseMode::MarkdownV2, '*') => depth = if depth > 0 { 0 } else { 1 },
+            (TelegramParseMode::MarkdownV2, '[') => depth += 1,
+            (TelegramParseMode::MarkdownV2, ')') => depth -= 1,
+            (TelegramParseMode::Html, '<') => {
+                take_until('>', &mut token);
+                depth += if token.starts_with("</") { -1 } else { 1 };
+            }
+            (TelegramParseMode::Html, '&') => take_until(';', &mut token),
+            _ => {}
+        }
+        length += message_length(&token);
+    }
+    cuts.push(Cut {
+        at: message.len(),
+        length,
+        outside: true,
+    });
+    cuts
+}
+
+/// Splits `message`, formatted for `mode`, into pieces within
+/// `MAX_MESSAGE_LENGTH`, cutting at the last blank line that fits, else at
+/// the last line break, else between entities. Digests separate reports
+/// with blank lines, so reports are kept whole whenever they fit in a
+/// message.
+fn split_message(message: &str, mode: TelegramParseMode) -> Vec<String> {
+    let mut pieces = vec![];
+    let mut rest = message;
+    while message_length(rest) > MAX_MESSAGE_LENGTH {
+        let cuts: Vec<Cut> = cuts(rest, mode)
+            .into_iter()
+            .filter(|cut| cut.at > 0 && cut.length <= MAX_MESSAGE_LENGTH)
+            .collect();
+        let last =
+            |fits: &dyn Fn(&Cut) -> bool| cuts.iter().rev().find(|cut| fits(cut)).map(|cut| cut.at);
+        // An entity too long for a message is cut all the same.
+        let at = last(&|cut| cut.outside && rest[cut.at..].starts_with("\n\n"))
+            .or_else(|| last(&|cut| cut.outside && rest[cut.at..].starts_with('\n')))
+            .or_else(|| last(&|cut| cut.outside))
+            .or_else(|| last(&|_| true))
+            .unwrap_or(rest.len());
+        pieces.push(String::from(&rest[..at]));
+        rest = rest[at..].trim_start_matches('\n');
+    }
+    if !rest.is_empty() {
+        pieces.push(String::from(rest));
+    }
+    pieces
+}
+
+/// How the messages that fail to be delivered are kept and retried.
+#[derive(Debug, Clone, Copy)]
+pub struct Outbox {
+    pub retry_after: chrono::Duration,
+    /// Past their send time, after which kept messages are dropped.
+    pub max_age: chrono::Duration,
+}
+
+impl Outbox {
+    pub fn new(config: &Telegram) -> Self {
+        Outbox {
+            retry_after: chrono::Duration::minutes(config.outbox_retry_minutes.into()),
+            max_age: chrono::Duration::hours(config.outbox_max_age_hours.into()),
+        }
+    }
+}
+
+/// Sends the deferred messages whose quiet hours have ended or whose retry
+/// is due, removing each once delivered and dropping the expired ones. When
+/// one fails, it and the later ones of its chat are postponed by
+/// `retry_after`, and so are those of every chat when Telegram is
+/// unreachable.
+pub async fn send_due_messages(
+    client: &TelegramClient,
+    store: &StateStore,
+    outbox: &Outbox,
+) -> std::result::Result<(), NotifyError> {
+    // Dry runs would drop the messages without delivering them.
+    if client.dry_run {
+        return Ok(());
+    }
+    let now = Utc::now();
+    let retry_at = (now + outbox.retry_after).timestamp();
+    let due = store.due_messages(now.timestamp())?;
+    let mut failed_chats = HashSet::new();
+    let mut failure = None;
+    for (i, message) in due.iter().enumerate() {
+        if message
+            .expires_at
+            .is_some_and(|expires_at| expires_at < now.timestamp())
+        {
+            tracing::warn!(chat_id = %message.chat_id, "dropping a message kept for too long");
+            store.remove_deferred_message(message.id)?;
+            continue;
+        }
+        // Later messages of a chat wait for its failed one, keeping their order.
+        if failed_chats.contains(&message.chat_id) {
+            store.postpone_message(message.id, retry_at)?;
+            continue;
+        }
+        match client
+            .send_message(&message.chat_id, &message.text, message.silent)
+            .await
+        {
+            Ok(()) => store.remove_deferred_message(message.id)?,
+            Err(error) if error.is_unreachable() => {
+                // Telegram is unreachable for the others as well, which keep
+                // their order.
+                for message in &due[i..] {
+                    store.postpone_message(message.id, retry_at)?;
+                }
+                return Err(error.into());
+            }
+            Err(error) => {
+                tracing::warn!(%error, chat_id = %message.chat_id, "failed to send a kept message");
+                store.postpone_message(message.id, retry_at)?;
+                failed_chats.insert(&message.chat_id);
+                failure.get_or_insert(error);
+            }
+        }
+    }
+    match failure {
+        Some(error) => Err(error.into()),
+        None => Ok(()),
+    }
+}
+
+/// What the chats asked for through the bot.
+#[derive(Debug, Clone, Default)]
+pub struct BotChats {
+    pub subscriptions: Vec<Subscription>,
+    pub styles: HashMap<String, MessageStyle>,
+    pub profiles: HashMap<String, String>,
+}
+
+impl BotChats {
+    pub fn load(store: &StateStore) -> rusqlite::Result<Self> {
+        let styles = store
+            .chat_styles()?
+            .into_iter()
+            .filter_map(|(chat_id, style)| Some((chat_id, MessageStyle::parse(&style)?)))
+            .collect();
+        Ok(BotChats {
+            subscriptions: store.subscriptions()?,
+            styles,
+            profiles: store.chat_profiles()?,
+        })
+    }
+}
+
+/// The messages of a send kept for a retry, and why.
+#[derive(Default)]
+struct SendOutcome {
+    kept: usize,
+    failure: Option<TelegramError>,
+    /// Set by a failure of Telegram rather than of a chat, skipping the
+    /// attempts of the chats after it.
+    unreachable: bool,
+}
+
+impl SendOutcome {
+    /// Fails the run when messages were kept, so that it is known they
+    /// have not arrived yet, or when charts failed to be sent.
+    fn result(self) -> std::result::Result<(), NotifyError> {
+        match self.failure {
+            Some(error) if self.kept > 0 => Err(NotifyError::Kept {
+                kept: self.kept,
+                error: error.to_string(),
+            }),
+            Some(error) => Err(error.into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Counts the failure of a chart, which isn't kept for a retry.
+    fn chart_failed(&mut self, chat_id: &str, error: TelegramError) {
+        tracing::warn!(%error, %chat_id, "failed to send a chart to Telegram");
+        self.unreachable = error.is_unreachable();
+        self.failure.get_or_insert(error);
+    }
+}
+
+/// Sends the digest, or a message per site, to the configured chats and to
+/// the chats subscribed through the bot, each receiving only the sites it
+/// asked for in the style it picked.
+pub struct TelegramNotifier {
+    client: TelegramClient,
+    config: Telegram,
+    bot_chats: BotChats,
+    /// Keeps the messages of chats in their quiet hours or before their
+    /// delivery time, and the ones that failed to be delivered.
+    store: Option<StateStore>,
+    outbox: Outbox,
+    /// Day of the reports for the chats without a schedule.
+    target: Target,
+    /// Time zones of the chats with one in their schedule.
+    zones: HashMap<String, Tz>,
+    charts: Option<ChartRenderer>,
+    format: MessageFormat,
+}
+
+impl TelegramNotifier {
+    pub fn new(
+        config: Telegram,
+        bot_chats: BotChats,
+        store: Option<StateStore>,
+        target: Target,
+        format: MessageFormat,
+        http: HttpClient,
+        dry_run: bool,
+    ) -> std::result::Result<Self, NotifyError> {
+        let charts = match &config.charts {
+            Some(charts) => Some(ChartRenderer::new(charts)?),
+            None => None,
+        };
+        let mut zones = HashMap::new();
+        for (chat_id, schedule) in &config.chat_schedules {
+            if let Some(name) = &schedule.timezone {
+                zones.insert(chat_id.clone(), Tz::load(name)?);
+            }
+        }
+        Ok(TelegramNotifier {
+            client: TelegramClient::new(
+                config.bot_token.clone(),
+                config.parse_mode,
+                config.messages_per_second,
+                http,
+                dry_run,
+            ),
+            outbox: Outbox::new(&config),
+            config,
+            bot_chats,
+            store,
+            target,
+            zones,
+            charts,
+            format,
+        })
+    }
+
+    fn chat_format(&self, chat_id: &str) -> MessageFormat {
+        let format = match self.config.chat_units.get(chat_id) {
+            Some(units) => self.format.with_units(*units),
+            None => self.format.clone(),
+        };
+        match self.bot_chats.styles.get(chat_id) {
+            Some(style) => format.with_style(*style),
+            None => format,
+        }
+    }
+
+    /// The pilot profile of the chat, the one picked through the bot over
+    /// the configured one.
+    fn chat_profile(&self, chat_id: &str) -> Option<&String> {
+        self.bot_chats
+            .profiles
+            .get(chat_id)
+            .or_else(|| self.config.chat_profiles.get(chat_id))
+    }
+
+    /// The 🔥 alerts of the `reports` scoring at least `epic_score`.
+    fn epic_day_alerts(
+        &self,
+        reports: &[SiteFlyAbilityReport],
+        format: &MessageFormat,
+    ) -> Vec<String> {
+        let threshold = match self.config.epic_score {
+            Some(threshold) => threshold,
+            None => return vec![],
+        };
+        let mode = self.client.parse_mode();
+        reports
+            .iter()
+            .filter(|report| report.score >= threshold)
+            .map(|report| mode.bold(&mode.escape(&report.epic_day_alert(format))))
+            .collect()
+    }
+
+    /// Whether `report` is for the profile of the chat. Chats with a profile
+    /// get the reports of the sites without that profile in its place.
+    fn wants_profile(&self, chat_id: &str, report: &SiteFlyAbilityReport) -> bool {
+        match (&report.site.profile, self.chat_profile(chat_id)) {
+            (Some(report_profile), Some(profile)) => report_profile == profile,
+            (Some(_), None) => false,
+            (None, Some(profile)) => !report.site.profiles.contains_key(profile),
+            (None, None) => true,
+        }
+    }
+
+    fn is_silent(&self, chat_id: &str) -> bool {
+        self.config
+            .silent_chats
+            .get(chat_id)
+            .copied()
+            .unwrap_or(self.config.silent)
+    }
+
+    fn quiet_hours(&self, chat_id: &str) -> Option<&QuietHours> {
+        self.config
+            .chat_quiet_hours
+            .get(chat_id)
+            .or(self.config.quiet_hours.as_ref())
+    }
+
+    /// When the reports of the chat are to be sent, at its delivery time
+    /// and outside of its quiet hours, along with the day there at that
+    /// moment for the chats with a schedule.
+    fn delivery(&self, chat_id: &str) -> (DateTime<FixedOffset>, Option<NaiveDate>) {
+        let zone = self.zones.get(chat_id);
+        let schedule = self.config.chat_schedules.get(chat_id);
+        let now = timezone::now(zone);
+        let mut send_at = match schedule.and_then(|schedule| schedule.delivery_time) {
+            Some(time) => timezone::next_local(time.0, now, zone),
+            None => now,
+        };
+        if let Some(end) = self
+            .quiet_hours(chat_id)
+            .and_then(|quiet_hours| quiet_hours.end_after(send_at, zone))
+        {
+            send_at = end;
+        }
+        (send_at, schedule.map(|_| send_at.date_naive()))
+    }
+
+    /// How many days after the run `send_at` is where the chat is, the
+    /// reports kept past midnight being that many days closer.
+    fn days_until(&self, chat_id: &str, send_at: DateTime<FixedOffset>) -> i64 {
+        let now = timezone::now(self.zones.get(chat_id));
+        (send_at.date_naive() - now.date_naive()).num_days()
+    }
+
+    /// The day of the reports sent to the chat, `today` being the one of
+    /// `delivery`.
+    fn report_date(&self, chat_id: &str, today: Option<NaiveDate>) -> NaiveDate {
+        let today = today.unwrap_or_else(|| timezone::now(self.zones.get(chat_id)).date_naive());
+        self.target.date(today)
+    }
+
+    /// Drops the messages still kept for the chat with the reports of
+    /// `date`, which newer ones replace.
+    fn forget_kept(&self, chat_id: &str, date: NaiveDate) -> std::result::Result<(), NotifyError> {
+        if let Some(store) = &self.store {
+            store.forget_deferred_reports(chat_id, &date.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Keeps `messages` with the reports of `date` until `send_at` when it
+    /// is still to come, returning whether they were deferred rather than
+    /// to be sent now.
+    fn defer(
+        &self,
+        chat_id: &str,
+        date: NaiveDate,
+        messages: &[String],
+        silent: bool,
+        send_at: DateTime<FixedOffset>,
+    ) -> std::result::Result<bool, NotifyError> {
+        let store = match &self.store {
+            Some(store) => store,
+            None => return Ok(false),
+        };
+        if send_at <= Utc::now() {
+            return Ok(false);
+        }
+        for message in messages {
+            store.defer_message(
+                chat_id,
+                message,
+                silent,
+                send_at.timestamp(),
+                Some((send_at + self.outbox.max_age).timestamp()),
+                Some(&date.to_string()),
+            )?;
+        }
+        tracing::info!(%chat_id, until = %send_at, "deferring the reports");
+        Ok(true)
+    }
+
+    /// Sends `messages` in order, returning whether all were delivered.
+    /// With a store, the ones left once a message fails are kept for a
+    /// retry and counted in `outcome`, and so are the messages of the
+    /// chats after it while Telegram is unreachable.
+    async fn send_or_keep(
+        &self,
+        chat_id: &str,
+        date: NaiveDate,
+        messages: &[String],
+        silent: bool,
+        outcome: &mut SendOutcome,
+    ) -> std::result::Result<bool, NotifyError> {
+        let store = match &self.store {
+            Some(store) => store,
+            None => {
+                for message in messages {
+                    self.client.send_message(chat_id, message, silent).await?;
+                }
+                return Ok(true);
+            }
+        };
+        let mut sent = 0;
+        while sent < messages.len() && !outcome.unreachable {
+            match self
+                .client
+                .send_message(chat_id, &messages[sent], silent)
+                .await
+            {
+                Ok(()) => sent += 1,
+                Err(error) => {
+                    tracing::warn!(%error, %chat_id, "failed to send to Telegram");
+                    outcome.unreachable = error.is_unreachable();
+                    outcome.failure.get_or_insert(error);
+                    break;
+                }
+            }
+        }
+        if sent == messages.len() {
+            return Ok(true);
+        }
+        let retry_at = Utc::now() + self.outbox.retry_after;
+        for message in &messages[sent..] {
+            store.defer_message(
+                chat_id,
+                message,
+                silent,
+                retry_at.timestamp(),
+                Some((retry_at + self.outbox.max_age).timestamp()),
+                Some(&date.to_string()),
+            )?;
+        }
+        outcome.kept += messages.len() - sent;
+        tracing::info!(%chat_id, until = %retry_at, "keeping the messages for a retry");
+        Ok(false)
+    }
+
+    /// The digest of `reports` with bold region and site headers.
+    fn format_digest(&self, reports: &[SiteFlyAbilityReport], format: &MessageFormat) -> String {
+        let mode = self.client.parse_mode();
+        let mut message = String::from("");
+        for (i, (region, report)) in crate::by_region(reports, format.strings())
+            .into_iter()
+            .enumerate()
+        {
+            if i > 0 {
+                message.push_str("\n\n");
+            }
+            if let Some(region) = region {
+                message.push_str(&mode.bold(&mode.escape(&region)));
+                message.push_str("\n\n");
+            }
+            if i == 0 && format.labels_top_pick(reports.len()) {
+                message.push_str(&mode.escape(format.strings().top_pick));
+            }
+            if let Some((header, details)) = report.template_sections(format) {
+                message.push_str(&mode.bold(&mode.escape(&header)));
+                message.push('\n');
+                message.push_str(&mode.escape(&details));
+                continue;
+            }
+            message.push_str(&mode.bold(&mode.escape(&report.built_in_header(format))));
+            message.push('\n');
+            message.push_str(&mode.escape(&report.built_in_text(format)));
+            let links: Vec<String> = report
+                .links(format.strings())
+                .into_iter()
+                .map(|(label, url)| mode.link(label, url))
+                .collect();
+            if !links.is_empty() {
+                message.push('\n');
+                message.push_str(&links.join(" | "));
+            }
+        }
+        message
+    }
+
+    /// Sites wanted by every chat, `None` standing for all of them.
+    fn recipients(&self) -> BTreeMap<&str, Option<Vec<&str>>> {
+        let mut recipients: BTreeMap<&str, Option<Vec<&str>>> = BTreeMap::new();
+        for chat_id in &self.config.chat_ids {
+            recipients.insert(chat_id, None);
+        }
+        for subscription in &self.bot_chats.subscriptions {
+            let sites = recipients
+                .entry(&subscription.chat_id)
+                .or_insert_with(|| Some(vec![]));
+            if subscription.site == ALL_SITES {
+                *sites = None;
+            } else if let Some(sites) = sites {
+                sites.push(&subscription.site);
+            }
+        }
+        recipients
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    async fn notify(
+        &self,
+        reports: &[SiteFlyAbilityReport],
+    ) -> std::result::Result<(), NotifyError> {
+        // Charts are shared by the chats using the same profile and units.
+        let mut charts: HashMap<(String, Option<String>, Units), Vec<u8>> = HashMap::new();
+        let mut outcome = SendOutcome::default();
+        for (chat_id, sites) in self.recipients() {
+            let (send_at, today) = self.delivery(chat_id);
+            let days_later = self.days_until(chat_id, send_at);
+            let chat_reports: Vec<SiteFlyAbilityReport> = reports
+                .iter()
+                .filter(|report| wants_site(&sites, &report.site))
+                .filter(|report| self.wants_profile(chat_id, report))
+                .filter(|report| match today {
+                    Some(today) => report.date == self.target.date(today),
+                    None => report.is_about(self.target),
+                })
+                .map(|report| {
+                    let mut report = report.clone();
+                    // Worded for the day the report arrives.
+                    report.days_ahead = match today {
+                        Some(today) => (report.date - today).num_days(),
+                        None => report.days_ahead - days_later,
+                    };
+                    report
+                })
+                .collect();
+            if chat_reports.is_empty() {
+                continue;
+            }
+            let format = self.chat_format(chat_id);
+            let messages = match self.config.mode {
+                MessageMode::Digest => vec![self.format_digest(&chat_reports, &format)],
+                MessageMode::PerSite => chat_reports
+                    .iter()
+                    .map(|report| self.format_digest(std::slice::from_ref(report), &format))
+                    .collect(),
+            };
+            let silent = self.is_silent(chat_id);
+            let alerts = self.epic_day_alerts(&chat_reports, &format);
+            let date = self.report_date(chat_id, today);
+            self.forget_kept(chat_id, date)?;
+            if self.defer(chat_id, date, &messages, silent, send_at)? {
+                self.defer(chat_id, date, &alerts, false, send_at)?;
+                continue;
+            }
+            let sent = self
+                .send_or_keep(chat_id, date, &messages, silent, &mut outcome)
+                .await?;
+            self.send_or_keep(chat_id, date, &alerts, false, &mut outcome)
+                .await?;
+            // Kept messages go without their charts, as deferred ones.
+            if !sent {
+                continue;
+            }
+            let renderer = match &self.charts {
+                Some(renderer) => renderer,
+                None => continue,
+            };
+            for report in &chat_reports {
+                let key = (
+                    report.site.name.clone(),
+                    report.site.profile.clone(),
+                    format.units(),
+                );
+                let png = match charts.entry(key) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(renderer.render(report, &format)?),
+                };
+                let sent = self
+                    .client
+                    .send_photo(chat_id, png.clone(), &report.site.name, silent)
+                    .await;
+                if let Err(error) = sent {
+                    outcome.chart_failed(chat_id, error);
+                    break;
+                }
+            }
+        }
+        outcome.result()
+    }
+
+    async fn notify_nothing_flyable(
+        &self,
+        skipped: &[SkippedSite],
+    ) -> std::result::Result<(), NotifyError> {
+        let mut outcome = SendOutcome::default();
+        for (chat_id, sites) in self.recipients() {
+            let (send_at, today) = self.delivery(chat_id);
+            let days_later = self.days_until(chat_id, send_at);
+            let chat_skipped: Vec<SkippedSite> = skipped
+                .iter()
+                .filter(|s| wants_site(&sites, &s.site))
+                .map(|s| SkippedSite {
+                    days_ahead: s.days_ahead - days_later,
+                    ..s.clone()
+                })
+                .collect();
+            if chat_skipped.is_empty() {
+                continue;
+            }
+            let message = crate::render_nothing_flyable(&chat_skipped, &self.chat_format(chat_id));
+            let message = self.client.parse_mode().escape(&message);
+            let silent = self.is_silent(chat_id);
+            let date = self.report_date(chat_id, today);
+            self.forget_kept(chat_id, date)?;
+            if self.defer(
+                chat_id,
+                date,
+                std::slice::from_ref(&message),
+                silent,
+                send_at,
+            )? {
+                continue;
+            }
+            self.send_or_keep(
+                chat_id,
+                date,
+                std::slice::from_ref(&message),
+                silent,
+                &mut outcome,
+            )
+            .await?;
+        }
+        outcome.result()
+    }
+
+    fn profiles(&self) -> Vec<String> {
+        let mut profiles: Vec<String> = self
+            .recipients()
+            .into_keys()
+            .filter_map(|chat_id| self.chat_profile(chat_id).cloned())
+            .collect();
+        profiles.sort();
+        profiles.dedup();
+        profiles
+    }
+
+    fn report_dates(&self) -> Vec<NaiveDate> {
+        let mut dates: Vec<NaiveDate> = self
+            .recipients()
+            .into_keys()
+            .filter_map(|chat_id| self.delivery(chat_id).1)
+            .map(|today| self.target.date(today))
+            .collect();
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+
+    async fn send_deferred(&self) -> std::result::Result<(), NotifyError> {
+        match &self.store {
+            Some(store) => send_due_messages(&self.client, store, &self.outbox).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn notify_errors(&self, summary: &str) -> std::result::Result<(), NotifyError> {
+        if let Some(chat_id) = &self.config.admin_chat_id {
+            self.client.send_text(chat_id, summary).await?;
+        }
+        Ok(())
+    }
+
+    /// A wind rose chart per site with `charts`, its caption alone without.
+    async fn notify_digest(&self, digests: &[SiteDigest]) -> std::result::Result<(), NotifyError> {
+        let mut charts: HashMap<(String, Units), Vec<u8>> = HashMap::new();
+        for (chat_id, sites) in self.recipients() {
+            let format = self.chat_format(chat_id);
+            let silent = self.is_silent(chat_id);
+            for digest in digests
+                .iter()
+                .filter(|digest| wants_site(&sites, &digest.site))
+            {
+                let caption = digest.caption();
+                let renderer = match &self.charts {
+                    Some(renderer) => renderer,
+                    None => {
+                        self.client.send_text(chat_id, &caption).await?;
+                        continue;
+                    }
+                };
+                let png = match charts.entry((digest.site.name.clone(), format.units())) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(renderer.render_digest(digest, &format)?),
+                };
+                self.client
+                    .send_photo(chat_id, png.clone(), &caption, silent)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether a chat subscribed to `sites` (see `recipients`) wants `site`.
+fn wants_site(sites: &Option<Vec<&str>>, site: &FlyingSite) -> bool {
+    sites
+        .as_ref()
+        .is_none_or(|sites| sites.contains(&&site.name[..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_are_never_split() {
+        let message = "\\.".repeat(3000);
+        let pieces = split_message(&message, TelegramParseMode::MarkdownV2);
+        assert_eq!(pieces.len(), 2);
+        for piece in &pieces {
+            assert!(message_length(piece) <= MAX_MESSAGE_LENGTH);
+            assert!(piece.starts_with('\\') && piece.ends_with('.'));
+        }
+        assert_eq!(pieces.concat(), message);
+    }
+
+    #[test]
+    fn entities_are_kept_whole() {
+        let link = "[Planfait](https://example\\.org)";
+        let message = format!("{} *bold text* {}", "a".repeat(4085), link);
+        let pieces = split_message(&message, TelegramParseMode::MarkdownV2);
+        assert_eq!(pieces[1], format!("*bold text* {}", link));
+
+        let message = format!("{} <b>bold &amp; text</b>", "a".repeat(4085));
+        let pieces = split_message(&message, TelegramParseMode::Html);
+        assert_eq!(pieces[1], "<b>bold &amp; text</b>");
+    }
+
+    #[test]
+    fn blank_lines_are_preferred() {
+        let report = format!("{}\n{}", "a".repeat(1000), "b".repeat(1000));
+        let message = [&report[..]; 3].join("\n\n");
+        let pieces = split_message(&message, TelegramParseMode::Plain);
+        assert_eq!(pieces, vec![[&report[..]; 2].join("\n\n"), report]);
+    }
+}
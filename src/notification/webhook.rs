@@ -0,0 +1,58 @@
+use super::{Notifier, NotifyError};
+use crate::config::Webhook;
+use crate::export::ReportSetView;
+use crate::http::HttpClient;
+use crate::SiteFlyAbilityReport;
+use async_trait::async_trait;
+
+/// POSTs the structured report set as JSON so downstream systems can build
+/// their own presentation, and the epic days alone to the `epic_urls`.
+pub struct WebhookNotifier {
+    config: Webhook,
+    http: HttpClient,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: Webhook, http: HttpClient) -> Self {
+        WebhookNotifier { config, http }
+    }
+
+    async fn post(
+        &self,
+        urls: &[String],
+        reports: &[SiteFlyAbilityReport],
+    ) -> Result<(), NotifyError> {
+        let payload = ReportSetView::new(reports, &[]);
+        for url in urls {
+            self.http
+                .send(|client| {
+                    let mut request = client.post(url).json(&payload);
+                    for (name, value) in &self.config.headers {
+                        request = request.header(&name[..], &value[..]);
+                    }
+                    request
+                })
+                .await?
+                .error_for_status()?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        self.post(&self.config.urls, reports).await?;
+        if let Some(threshold) = self.config.epic_score {
+            let epic: Vec<SiteFlyAbilityReport> = reports
+                .iter()
+                .filter(|report| report.score >= threshold)
+                .cloned()
+                .collect();
+            if !epic.is_empty() {
+                self.post(&self.config.epic_urls, &epic).await?;
+            }
+        }
+        Ok(())
+    }
+}
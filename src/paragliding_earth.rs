@@ -0,0 +1,167 @@
+//! Site definitions from the Paragliding Earth API, turned into waypoints
+//! whose wind sectors come from the orientations rated for the site.
+
+use crate::config::WindSector;
+use crate::http::HttpClient;
+use crate::waypoints::Waypoint;
+use reqwest::Result;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub const DEFAULT_API_URL: &str = "https://www.paraglidingearth.com/api/geojson/";
+
+// Orientations clockwise from north, each covering 45°.
+const ORIENTATIONS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+// Ratings of an orientation, 0 meaning it is not flyable.
+const GOOD: u8 = 2;
+const POSSIBLE: u8 = 1;
+
+#[derive(Deserialize, Debug)]
+struct FeatureCollection {
+    #[serde(default)]
+    features: Vec<Feature>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Feature {
+    geometry: Geometry,
+    properties: Properties,
+}
+
+#[derive(Deserialize, Debug)]
+struct Geometry {
+    /// Longitude then latitude.
+    coordinates: Vec<f64>,
+}
+
+/// Numbers are sent as strings or numbers depending on the field.
+#[derive(Deserialize, Debug)]
+struct Properties {
+    name: String,
+    takeoff_altitude: Option<Value>,
+    #[serde(default)]
+    orientations: HashMap<String, Value>,
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Sectors of the orientations rated good, or possible when none is good,
+/// adjacent orientations merging into a single sector.
+fn wind_sectors(orientations: &HashMap<String, Value>) -> Vec<WindSector> {
+    let ratings: Vec<u8> = ORIENTATIONS
+        .iter()
+        .map(|o| orientations.get(*o).and_then(number).unwrap_or(0.0) as u8)
+        .collect();
+    let best = if ratings.contains(&GOOD) {
+        GOOD
+    } else {
+        POSSIBLE
+    };
+    let rated: Vec<bool> = ratings.iter().map(|rating| *rating >= best).collect();
+    if rated.iter().all(|r| *r) {
+        return vec![WindSector::from((0, 360))];
+    }
+    // Starting right after an unrated orientation, no sector wraps around
+    // the end of the list.
+    let start = (0..8).find(|i| !rated[*i]).map_or(0, |i| i + 1);
+    let mut sectors = vec![];
+    let mut run: Option<(usize, usize)> = None;
+    for offset in 0..8 {
+        let i = (start + offset) % 8;
+        run = match (rated[i], run) {
+            (true, Some((first, _))) => Some((first, i)),
+            (true, None) => Some((i, i)),
+            (false, Some((first, last))) => {
+                sectors.push(sector(first, last));
+                None
+            }
+            (false, None) => None,
+        };
+    }
+    if let Some((first, last)) = run {
+        sectors.push(sector(first, last));
+    }
+    sectors
+}
+
+/// The sector from the start of orientation `first` to the end of `last`.
+fn sector(first: usize, last: usize) -> WindSector {
+    let from = (first as f32 * 45.0 - 22.5).rem_euclid(360.0).round() as i16;
+    let to = (last as f32 * 45.0 + 22.5).rem_euclid(360.0).round() as i16;
+    WindSector::from((from, to))
+}
+
+pub struct ParaglidingEarthClient {
+    url: String,
+    http: HttpClient,
+}
+
+impl ParaglidingEarthClient {
+    pub fn new(url: String, http: HttpClient) -> Self {
+        ParaglidingEarthClient { url, http }
+    }
+
+    async fn get_sites(&self, endpoint: &str, query: &[(&str, String)]) -> Result<Vec<Waypoint>> {
+        let collection: FeatureCollection = self
+            .http
+            .send(|client| {
+                client
+                    .get(format!("{}{}", self.url, endpoint))
+                    .query(query)
+                    .query(&[("style", "detailled")])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        Ok(collection
+            .features
+            .into_iter()
+            .filter(|feature| feature.geometry.coordinates.len() >= 2)
+            .map(|feature| Waypoint {
+                name: feature.properties.name,
+                longitude: feature.geometry.coordinates[0],
+                latitude: feature.geometry.coordinates[1],
+                elevation_m: feature
+                    .properties
+                    .takeoff_altitude
+                    .as_ref()
+                    .and_then(number)
+                    .filter(|altitude| *altitude > 0.0)
+                    .map(|altitude| altitude as f32),
+                wind_sectors: wind_sectors(&feature.properties.orientations),
+            })
+            .collect())
+    }
+
+    /// The sites within `distance_km` of the coordinate, closest first.
+    pub async fn sites_around(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        distance_km: u32,
+        limit: u32,
+    ) -> Result<Vec<Waypoint>> {
+        let query = [
+            ("lat", latitude.to_string()),
+            ("lng", longitude.to_string()),
+            ("distance", distance_km.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        self.get_sites("getAroundLatLngSites.php", &query).await
+    }
+
+    pub async fn site(&self, id: u32) -> Result<Vec<Waypoint>> {
+        self.get_sites("getSiteById.php", &[("id", id.to_string())])
+            .await
+    }
+}
@@ -0,0 +1,119 @@
+//! Call budgets of the forecast providers: a rate limit per minute, waited
+//! out, and a daily quota persisted in the state store.
+
+use crate::config::{QuotaConfig, QuotaExhausted};
+use crate::store::StateStore;
+use chrono::{NaiveDate, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+struct ProviderQuota {
+    config: QuotaConfig,
+    /// When the calls of the last minute were made, oldest first.
+    recent_calls: Mutex<VecDeque<Instant>>,
+    /// Calls of the day when there is no state store.
+    calls_today: Mutex<(NaiveDate, u32)>,
+}
+
+impl ProviderQuota {
+    /// Waits until a call fits within `calls_per_minute`, and books it.
+    async fn wait_for_rate(&self, limit: u32) {
+        loop {
+            let wait = {
+                let mut recent_calls = self.recent_calls.lock().unwrap();
+                let now = Instant::now();
+                while recent_calls.front().is_some_and(|t| now - *t >= MINUTE) {
+                    recent_calls.pop_front();
+                }
+                if recent_calls.len() < limit as usize {
+                    recent_calls.push_back(now);
+                    return;
+                }
+                MINUTE - (now - recent_calls[0])
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn calls_today(&self, provider: &str, store: Option<&StateStore>) -> rusqlite::Result<u32> {
+        let today = Utc::now().date_naive();
+        match store {
+            Some(store) => store.provider_calls(provider, &today.to_string()),
+            None => {
+                let calls_today = self.calls_today.lock().unwrap();
+                Ok(if calls_today.0 == today {
+                    calls_today.1
+                } else {
+                    0
+                })
+            }
+        }
+    }
+
+    fn record_call(&self, provider: &str, store: Option<&StateStore>) -> rusqlite::Result<()> {
+        let today = Utc::now().date_naive();
+        match store {
+            Some(store) => store.record_provider_call(provider, &today.to_string()),
+            None => {
+                let mut calls_today = self.calls_today.lock().unwrap();
+                if calls_today.0 != today {
+                    *calls_today = (today, 0);
+                }
+                calls_today.1 += 1;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Budgets of the providers with a configured quota.
+pub struct Quotas {
+    providers: HashMap<String, ProviderQuota>,
+}
+
+impl Quotas {
+    pub fn new(configs: HashMap<String, QuotaConfig>) -> Self {
+        let providers = configs
+            .into_iter()
+            .map(|(provider, config)| {
+                let quota = ProviderQuota {
+                    config,
+                    recent_calls: Mutex::new(VecDeque::new()),
+                    calls_today: Mutex::new((NaiveDate::MIN, 0)),
+                };
+                (provider, quota)
+            })
+            .collect();
+        Quotas { providers }
+    }
+
+    /// Books a call to `provider`, waiting for its rate limit. Returns
+    /// whether the call should be made, which it should not once the daily
+    /// quota is exhausted unless exhaustion only warns.
+    pub async fn spend(
+        &self,
+        provider: &str,
+        store: Option<&StateStore>,
+    ) -> rusqlite::Result<bool> {
+        let quota = match self.providers.get(provider) {
+            Some(quota) => quota,
+            None => return Ok(true),
+        };
+        if let Some(limit) = quota.config.calls_per_day {
+            if quota.calls_today(provider, store)? >= limit {
+                tracing::warn!(provider, limit, "daily quota exhausted");
+                if quota.config.when_exhausted == QuotaExhausted::Skip {
+                    return Ok(false);
+                }
+            }
+        }
+        if let Some(limit) = quota.config.calls_per_minute {
+            quota.wait_for_rate(limit.max(1)).await;
+        }
+        quota.record_call(provider, store)?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,65 @@
+//! Spreading of requests over time to stay within the rate limit of an API.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Token bucket letting a second worth of requests through at once, then
+/// `rate` requests per second. Shared by the tasks sending the requests.
+pub struct TokenBucket {
+    rate: f64,
+    state: Mutex<Bucket>,
+}
+
+struct Bucket {
+    /// Negative while requests wait for their turn.
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    pub fn per_second(rate: f64) -> Self {
+        TokenBucket {
+            rate,
+            state: Mutex::new(Bucket {
+                tokens: rate.max(1.0),
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Waits until the next request may be sent.
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut bucket = self.bucket();
+            self.refill(&mut bucket);
+            // Taken right away, so that concurrent callers queue up behind
+            // each other.
+            bucket.tokens -= 1.0;
+            Duration::from_secs_f64((-bucket.tokens / self.rate).max(0.0))
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Holds back the requests to come for `wait`, e.g. when the API asks
+    /// to slow down.
+    pub fn pause(&self, wait: Duration) {
+        let mut bucket = self.bucket();
+        self.refill(&mut bucket);
+        bucket.tokens = bucket.tokens.min(0.0) - wait.as_secs_f64() * self.rate;
+    }
+
+    fn bucket(&self) -> std::sync::MutexGuard<'_, Bucket> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn refill(&self, bucket: &mut Bucket) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.rate.max(1.0));
+        bucket.updated = now;
+    }
+}
@@ -0,0 +1,73 @@
+//! Retrying of transient HTTP failures with exponential backoff.
+
+use crate::config::RetryConfig;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Error, RequestBuilder, Response, Result, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// The wait asked for by a `Retry-After` header in seconds.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    seconds.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// A random fraction in `[0, 1)`, good enough to spread retries.
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random % 1_000) as f64 / 1_000.0
+}
+
+/// The wait after the `attempt`th failed attempt, counting from 1.
+fn backoff(policy: &RetryConfig, attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(16);
+    let millis = policy
+        .initial_backoff_ms
+        .saturating_mul(1 << exponent)
+        .min(policy.max_backoff_ms);
+    let millis = if policy.jitter {
+        // Keeps at least half of the wait.
+        millis / 2 + (millis as f64 / 2.0 * random_fraction()) as u64
+    } else {
+        millis
+    };
+    Duration::from_millis(millis)
+}
+
+/// Sends the request built by `request` until it succeeds, fails for good
+/// or runs out of attempts. Responses with a transient status left after
+/// the last attempt are turned into errors. Errors leave out the URL, which
+/// may hold credentials.
+pub async fn send<F>(policy: &RetryConfig, request: F) -> Result<Response>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let mut attempt = 1;
+    loop {
+        let result = request().send().await.map_err(Error::without_url);
+        let (delay, cause) = match &result {
+            Ok(response) if is_transient(response.status()) => (
+                retry_after(response).unwrap_or_else(|| backoff(policy, attempt)),
+                response.status().to_string(),
+            ),
+            Err(error) if error.is_connect() || error.is_timeout() => {
+                (backoff(policy, attempt), error.to_string())
+            }
+            _ => return result,
+        };
+        let max_delay = Duration::from_millis(policy.max_backoff_ms);
+        if attempt >= policy.attempts || delay > max_delay {
+            return result
+                .and_then(Response::error_for_status)
+                .map_err(Error::without_url);
+        }
+        tracing::warn!(attempt, ?delay, %cause, "request failed, retrying");
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
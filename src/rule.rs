@@ -0,0 +1,587 @@
+//! Small expression language for user-defined flyability rules, e.g.
+//! `wind_speed < 8 m/s AND gust_factor < 1.5 OR (daylight AND wind < 5)`.
+//!
+//! Numbers may carry a unit (`m/s`, `km/h`, `mph`, `C`, `F`, `K`, `%`,
+//! `deg`); they are normalized to m/s, °C, fractions and degrees before
+//! comparison. `pop`, `clouds` and `humidity` only compare with percentages.
+//! `rain` is in mm/h, `visibility` in meters and `pressure` in hPa.
+
+use crate::forecast_client::{HourWeatherForecast, TimeOfDay};
+use crate::thermal::ThermalQuality;
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dimension {
+    Speed,
+    Temperature,
+    Ratio,
+    Degrees,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Number(Option<Dimension>),
+    Bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    WindSpeed,
+    WindGust,
+    GustFactor,
+    WindDeg,
+    Temperature,
+    FeelsLike,
+    Pop,
+    Clouds,
+    LapseRate,
+    Humidity,
+    Pressure,
+    Rain,
+    Visibility,
+    Daylight,
+    Twilight,
+    Thermal,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Field> {
+        match name {
+            "wind" | "wind_speed" => Some(Field::WindSpeed),
+            "gust" | "wind_gust" => Some(Field::WindGust),
+            "gust_factor" => Some(Field::GustFactor),
+            "wind_deg" | "direction" => Some(Field::WindDeg),
+            "temp" | "temperature" => Some(Field::Temperature),
+            "feels_like" => Some(Field::FeelsLike),
+            "pop" => Some(Field::Pop),
+            "clouds" => Some(Field::Clouds),
+            "lapse_rate" => Some(Field::LapseRate),
+            "humidity" => Some(Field::Humidity),
+            "pressure" => Some(Field::Pressure),
+            "rain" => Some(Field::Rain),
+            "visibility" => Some(Field::Visibility),
+            "thermal" => Some(Field::Thermal),
+            "daylight" => Some(Field::Daylight),
+            "twilight" => Some(Field::Twilight),
+            _ => None,
+        }
+    }
+
+    fn kind(self) -> Kind {
+        match self {
+            Field::WindSpeed | Field::WindGust => Kind::Number(Some(Dimension::Speed)),
+            Field::GustFactor
+            | Field::LapseRate
+            | Field::Pressure
+            | Field::Rain
+            | Field::Visibility => Kind::Number(None),
+            Field::WindDeg => Kind::Number(Some(Dimension::Degrees)),
+            Field::Temperature | Field::FeelsLike => Kind::Number(Some(Dimension::Temperature)),
+            Field::Pop | Field::Clouds | Field::Humidity => Kind::Number(Some(Dimension::Ratio)),
+            Field::Daylight | Field::Twilight | Field::Thermal => Kind::Bool,
+        }
+    }
+
+    fn number(self, hour: &HourWeatherForecast) -> f64 {
+        let wind = hour.wind_speed.meters_per_second() as f64;
+        let gust = hour
+            .wind_gust
+            .map(|g| g.meters_per_second() as f64)
+            .unwrap_or(wind);
+        match self {
+            Field::WindSpeed => wind,
+            Field::WindGust => gust,
+            Field::GustFactor if wind > 0.0 => gust / wind,
+            Field::GustFactor => 1.0,
+            Field::WindDeg => hour.wind_direction.degrees() as f64,
+            Field::Temperature => hour.temperature.celsius() as f64,
+            Field::FeelsLike => hour.feels_like.celsius() as f64,
+            Field::Pop => hour.pop as f64,
+            Field::Clouds => hour.clouds as f64 / 100.0,
+            // Unknown values fail every comparison.
+            Field::LapseRate => hour.lapse_rate.map_or(f64::NAN, |rate| rate as f64),
+            Field::Humidity => hour.humidity.map_or(f64::NAN, |h| h as f64 / 100.0),
+            Field::Pressure => hour.pressure.map_or(f64::NAN, |p| p.hectopascals() as f64),
+            Field::Rain => hour.rain.map_or(0.0, |r| r.millimeters() as f64),
+            Field::Visibility => hour.visibility.map_or(f64::NAN, |v| v.meters() as f64),
+            Field::Daylight | Field::Twilight | Field::Thermal => self.truth(hour) as u8 as f64,
+        }
+    }
+
+    fn truth(self, hour: &HourWeatherForecast) -> bool {
+        match self {
+            Field::Daylight => hour.time_of_day == TimeOfDay::DAY,
+            Field::Twilight => hour.time_of_day == TimeOfDay::TWILIGHT,
+            Field::Thermal => hour
+                .lapse_rate
+                .is_some_and(|rate| ThermalQuality::from_lapse_rate(rate).is_usable()),
+            _ => self.number(hour) != 0.0,
+        }
+    }
+}
+
+fn parse_unit(unit: &str, value: f64) -> Option<(f64, Dimension)> {
+    let converted = match unit.to_lowercase().as_str() {
+        "m/s" | "mps" => (value, Dimension::Speed),
+        "km/h" | "kmh" | "kmph" => (value / 3.6, Dimension::Speed),
+        "mph" => (value / 2.236936, Dimension::Speed),
+        "c" | "°c" => (value, Dimension::Temperature),
+        "f" | "°f" => ((value - 32.0) / 1.8, Dimension::Temperature),
+        "k" => (value - 273.15, Dimension::Temperature),
+        "%" => (value / 100.0, Dimension::Ratio),
+        "deg" | "°" => (value, Dimension::Degrees),
+        _ => return None,
+    };
+    Some(converted)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl CompareOp {
+    fn apply(self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            CompareOp::Less => lhs < rhs,
+            CompareOp::LessOrEqual => lhs <= rhs,
+            CompareOp::Greater => lhs > rhs,
+            CompareOp::GreaterOrEqual => lhs >= rhs,
+            CompareOp::Equal => (lhs - rhs).abs() < 1e-6,
+            CompareOp::NotEqual => (lhs - rhs).abs() >= 1e-6,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(f64, Option<Dimension>),
+    Field(Field),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+impl Expr {
+    fn kind(&self) -> Kind {
+        match self {
+            Expr::Number(_, dimension) => Kind::Number(*dimension),
+            Expr::Field(field) => field.kind(),
+            _ => Kind::Bool,
+        }
+    }
+
+    fn number(&self, hour: &HourWeatherForecast) -> f64 {
+        match self {
+            Expr::Number(value, _) => *value,
+            Expr::Field(field) => field.number(hour),
+            _ => self.truth(hour) as u8 as f64,
+        }
+    }
+
+    fn truth(&self, hour: &HourWeatherForecast) -> bool {
+        match self {
+            Expr::Number(value, _) => *value != 0.0,
+            Expr::Field(field) => field.truth(hour),
+            Expr::Compare(lhs, op, rhs) => op.apply(lhs.number(hour), rhs.number(hour)),
+            Expr::And(lhs, rhs) => lhs.truth(hour) && rhs.truth(hour),
+            Expr::Or(lhs, rhs) => lhs.truth(hour) || rhs.truth(hour),
+            Expr::Not(inner) => !inner.truth(hour),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Word(String),
+    Compare(CompareOp),
+    And,
+    Or,
+    Not,
+    Minus,
+    LeftParen,
+    RightParen,
+}
+
+#[derive(Debug)]
+pub struct RuleError {
+    message: String,
+    position: usize,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+fn error<T>(message: impl Into<String>, position: usize) -> Result<T, RuleError> {
+    Err(RuleError {
+        message: message.into(),
+        position,
+    })
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '/' || c == '°' || c == '%'
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, RuleError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_digit() || c == '.' {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let literal: String = chars[start..i].iter().collect();
+            match literal.parse::<f64>() {
+                Ok(value) => tokens.push((Token::Number(value), start)),
+                Err(_) => return error(format!("invalid number '{}'", literal), start),
+            }
+            continue;
+        }
+        if is_word_char(c) {
+            while i < chars.len() && is_word_char(chars[i]) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let token = match word.to_uppercase().as_str() {
+                "AND" => Token::And,
+                "OR" => Token::Or,
+                "NOT" => Token::Not,
+                _ => Token::Word(word.to_lowercase()),
+            };
+            tokens.push((token, start));
+            continue;
+        }
+        let next = chars.get(i + 1).copied();
+        let (token, len) = match (c, next) {
+            ('(', _) => (Token::LeftParen, 1),
+            ('-', _) => (Token::Minus, 1),
+            (')', _) => (Token::RightParen, 1),
+            ('<', Some('=')) => (Token::Compare(CompareOp::LessOrEqual), 2),
+            ('<', _) => (Token::Compare(CompareOp::Less), 1),
+            ('>', Some('=')) => (Token::Compare(CompareOp::GreaterOrEqual), 2),
+            ('>', _) => (Token::Compare(CompareOp::Greater), 1),
+            ('=', Some('=')) => (Token::Compare(CompareOp::Equal), 2),
+            ('=', _) => (Token::Compare(CompareOp::Equal), 1),
+            ('!', Some('=')) => (Token::Compare(CompareOp::NotEqual), 2),
+            ('!', _) => (Token::Not, 1),
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            _ => return error(format!("unexpected character '{}'", c), start),
+        };
+        tokens.push((token, start));
+        i += len;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map(|(_, offset)| *offset)
+            .unwrap_or(self.end)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(token, _)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    fn expect_bool(&self, expr: &Expr, offset: usize) -> Result<(), RuleError> {
+        match expr.kind() {
+            Kind::Bool => Ok(()),
+            Kind::Number(_) => error("expected a condition, found a number", offset),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, RuleError> {
+        let offset = self.offset();
+        let mut lhs = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            let rhs_offset = self.offset();
+            let rhs = self.parse_and()?;
+            self.expect_bool(&lhs, offset)?;
+            self.expect_bool(&rhs, rhs_offset)?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, RuleError> {
+        let offset = self.offset();
+        let mut lhs = self.parse_not()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            let rhs_offset = self.offset();
+            let rhs = self.parse_not()?;
+            self.expect_bool(&lhs, offset)?;
+            self.expect_bool(&rhs, rhs_offset)?;
+            lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, RuleError> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            let offset = self.offset();
+            let inner = self.parse_not()?;
+            self.expect_bool(&inner, offset)?;
+            return Ok(Expr::Not(Box::new(inner)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, RuleError> {
+        let offset = self.offset();
+        let lhs = self.parse_primary()?;
+        let op = match self.peek() {
+            Some(Token::Compare(op)) => *op,
+            _ => return Ok(lhs),
+        };
+        self.next();
+        let rhs = self.parse_primary()?;
+        match (lhs.kind(), rhs.kind()) {
+            (Kind::Number(Some(a)), Kind::Number(Some(b))) if a != b => {
+                error("comparison between incompatible units", offset)
+            }
+            // A bare 30 would be read as 3000%.
+            (Kind::Number(Some(Dimension::Ratio)), Kind::Number(None))
+            | (Kind::Number(None), Kind::Number(Some(Dimension::Ratio))) => {
+                error("percentages need the '%' unit, e.g. 30%", offset)
+            }
+            (Kind::Number(_), Kind::Number(_)) => {
+                Ok(Expr::Compare(Box::new(lhs), op, Box::new(rhs)))
+            }
+            _ => error("only numeric values can be compared", offset),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, RuleError> {
+        let offset = self.offset();
+        let negative = self.peek() == Some(&Token::Minus);
+        if negative {
+            self.next();
+        }
+        match self.next() {
+            Some(Token::Number(value)) => {
+                let value = if negative { -value } else { value };
+                if let Some(Token::Word(unit)) = self.peek() {
+                    if let Some((value, dimension)) = parse_unit(unit, value) {
+                        self.next();
+                        return Ok(Expr::Number(value, Some(dimension)));
+                    }
+                }
+                Ok(Expr::Number(value, None))
+            }
+            _ if negative => error("'-' is only supported before numbers", offset),
+            Some(Token::Word(name)) => match Field::from_name(&name) {
+                Some(field) => Ok(Expr::Field(field)),
+                None => error(format!("unknown field '{}'", name), offset),
+            },
+            Some(Token::LeftParen) => {
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RightParen) => Ok(inner),
+                    _ => error("expected ')'", self.offset()),
+                }
+            }
+            Some(_) => error("unexpected token", offset),
+            None => error("unexpected end of rule", offset),
+        }
+    }
+}
+
+/// A parsed flyability rule, evaluated per forecast hour.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(try_from = "String")]
+pub struct Rule {
+    expr: Expr,
+}
+
+impl Rule {
+    pub fn parse(source: &str) -> Result<Rule, RuleError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            end: source.chars().count(),
+        };
+        let expr = parser.parse_or()?;
+        if parser.peek().is_some() {
+            return error("unexpected token", parser.offset());
+        }
+        parser.expect_bool(&expr, 0)?;
+        Ok(Rule { expr })
+    }
+
+    pub fn evaluate(&self, hour: &HourWeatherForecast) -> bool {
+        self.expr.truth(hour)
+    }
+}
+
+impl TryFrom<String> for Rule {
+    type Error = RuleError;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Rule::parse(&source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measures::WindSpeed;
+    use crate::testing::{at, HourBuilder};
+    use chrono::NaiveDate;
+
+    fn hour() -> HourBuilder {
+        HourBuilder::new(at(NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(), 12))
+    }
+
+    fn holds(rule: &str, hour: &HourWeatherForecast) -> bool {
+        Rule::parse(rule)
+            .unwrap_or_else(|error| panic!("{}: {}", rule, error))
+            .evaluate(hour)
+    }
+
+    fn parse_error(rule: &str) -> String {
+        Rule::parse(rule).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let hour = hour().wind(WindSpeed::MPS(12.0), 270).build();
+        assert!(holds("wind > 10 OR wind < 5 AND wind > 30", &hour));
+        assert!(!holds("(wind > 10 OR wind < 5) AND wind > 30", &hour));
+        assert!(holds("NOT wind < 10 AND wind > 3", &hour));
+        assert!(!holds("NOT (wind < 10 OR wind > 3)", &hour));
+        assert!(holds("wind > 10 && !(wind > 30) || rain > 1", &hour));
+    }
+
+    #[test]
+    fn units_are_normalized() {
+        // 4 m/s from 270° at 20°C, see `HourBuilder::new`.
+        let hour = hour().pop(0.2).build();
+        for rule in [
+            "wind == 4 m/s",
+            "wind == 4 mps",
+            "wind == 14.4 km/h",
+            "wind == 14.4 kmh",
+            "wind == 14.4 kmph",
+            "wind > 8.9 mph AND wind < 9 MPH",
+            "temp == 20 C",
+            "temp == 20 °C",
+            "temp == 68 F",
+            "temp == 68 °F",
+            "temp == 293.15 K",
+            "temp > -5 C",
+            "pop == 20%",
+            "direction == 270 deg",
+            "direction == 270°",
+            "gust_factor == 1",
+        ] {
+            assert!(holds(rule, &hour), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn incompatible_units_are_refused_where_compared() {
+        assert_eq!(
+            parse_error("daylight AND wind < 20 C"),
+            "comparison between incompatible units at position 13"
+        );
+        assert_eq!(
+            parse_error("wind < 5 m/s OR (temp > 5 m/s)"),
+            "comparison between incompatible units at position 17"
+        );
+        assert_eq!(
+            parse_error("pop < 30"),
+            "percentages need the '%' unit, e.g. 30% at position 0"
+        );
+        assert_eq!(
+            parse_error("daylight AND 50 > humidity"),
+            "percentages need the '%' unit, e.g. 30% at position 13"
+        );
+    }
+
+    #[test]
+    fn malformed_rules_are_refused() {
+        assert_eq!(
+            parse_error("wind < 5 AND"),
+            "unexpected end of rule at position 12"
+        );
+        assert_eq!(
+            parse_error("wind AND daylight"),
+            "expected a condition, found a number at position 0"
+        );
+        assert_eq!(
+            parse_error("speed < 5"),
+            "unknown field 'speed' at position 0"
+        );
+        assert_eq!(parse_error("(wind < 5"), "expected ')' at position 9");
+    }
+
+    #[test]
+    fn unknown_values_fail_every_comparison() {
+        let unknown = hour().build();
+        assert!(!holds("lapse_rate < 1", &unknown));
+        assert!(!holds("lapse_rate >= 1", &unknown));
+        assert!(holds("lapse_rate > 6", &hour().lapse_rate(7.0).build()));
+    }
+
+    #[test]
+    fn example_rule() {
+        let rule = "wind_speed < 8 m/s AND gust_factor < 1.5 OR (thermal AND wind < 5)";
+        let steady = hour().wind(WindSpeed::MPS(6.0), 270).build();
+        assert!(holds(rule, &steady));
+        let gusty = hour()
+            .wind(WindSpeed::MPS(4.0), 270)
+            .gust(WindSpeed::MPS(8.0))
+            .build();
+        assert!(!holds(rule, &gusty));
+        let thermic = hour()
+            .wind(WindSpeed::MPS(4.0), 270)
+            .gust(WindSpeed::MPS(8.0))
+            .lapse_rate(8.5)
+            .build();
+        assert!(holds(rule, &thermic));
+    }
+}
@@ -0,0 +1,193 @@
+//! Sweeps of an area for flyable spots: a grid of points checked against
+//! generic limits, wind from any direction, rather than the limits of the
+//! configured sites. Meant for exploring on a good-looking day.
+
+use crate::config::{FlyingSite, Target, WindSector};
+use crate::error::ProviderError;
+use crate::measures::WindSpeed;
+use crate::scoring::ScoringModel;
+use crate::store::StateStore;
+use crate::{ForecastSources, SiteFlyAbilityReport};
+use futures::stream::{self, StreamExt};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// Most points a scan checks, each costing a forecast call.
+pub const MAX_POINTS: usize = 400;
+
+/// Area between two latitudes and two longitudes, in degrees, not crossing
+/// the antimeridian.
+#[derive(Debug, Clone, Copy)]
+pub struct BoundingBox {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+/// Parses "SOUTH,WEST,NORTH,EAST", e.g. "45.5,5.8,46.2,6.9".
+impl FromStr for BoundingBox {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("\"{}\" is not SOUTH,WEST,NORTH,EAST", text);
+        let values: Vec<f64> = text
+            .split(',')
+            .map(|value| value.trim().parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        let (south, west, north, east) = match values[..] {
+            [south, west, north, east] => (south, west, north, east),
+            _ => return Err(invalid()),
+        };
+        if !(-90.0..=90.0).contains(&south) || !(-90.0..=90.0).contains(&north) {
+            return Err(format!("latitudes of \"{}\" are outside ±90°", text));
+        }
+        if !(-180.0..=180.0).contains(&west) || !(-180.0..=180.0).contains(&east) {
+            return Err(format!("longitudes of \"{}\" are outside ±180°", text));
+        }
+        if south > north || west > east {
+            return Err(format!(
+                "\"{}\" must go from the south-west to the north-east corner",
+                text
+            ));
+        }
+        Ok(BoundingBox {
+            south,
+            west,
+            north,
+            east,
+        })
+    }
+}
+
+impl BoundingBox {
+    /// Points every `step` degrees from the south-west corner, row by row.
+    fn grid(&self, step: f64) -> Vec<(f64, f64)> {
+        // Rounded so that float steps still reach the far edges.
+        let rows = ((self.north - self.south) / step + 1e-9).floor() as usize;
+        let columns = ((self.east - self.west) / step + 1e-9).floor() as usize;
+        let mut points = vec![];
+        for row in 0..=rows {
+            for column in 0..=columns {
+                points.push((
+                    self.south + row as f64 * step,
+                    self.west + column as f64 * step,
+                ));
+            }
+        }
+        points
+    }
+}
+
+/// An area to sweep and the limits its points are flyable within.
+pub struct Scan {
+    points: Vec<(f64, f64)>,
+    pub min_wind: WindSpeed,
+    pub max_wind: WindSpeed,
+}
+
+impl Scan {
+    pub fn new(
+        area: BoundingBox,
+        step: f64,
+        min_wind: WindSpeed,
+        max_wind: WindSpeed,
+    ) -> Result<Self, String> {
+        if step.is_nan() || step <= 0.0 {
+            return Err(String::from("the grid step must be above zero"));
+        }
+        if min_wind > max_wind {
+            return Err(String::from(
+                "the lightest flyable wind is above the strongest",
+            ));
+        }
+        let points = area.grid(step);
+        if points.len() > MAX_POINTS {
+            return Err(format!(
+                "a step of {}° makes {} points, more than the {} a scan checks",
+                step,
+                points.len(),
+                MAX_POINTS
+            ));
+        }
+        Ok(Scan {
+            points,
+            min_wind,
+            max_wind,
+        })
+    }
+
+    /// A site at the point, named after its coordinates.
+    fn site(&self, (latitude, longitude): (f64, f64)) -> FlyingSite {
+        FlyingSite::new(
+            &format!("{:.3}, {:.3}", latitude, longitude),
+            latitude,
+            longitude,
+            self.min_wind,
+            self.max_wind,
+            vec![WindSector { from: 0, to: 360 }],
+        )
+    }
+}
+
+/// What a scan found.
+pub struct ScanOutcome {
+    /// Flyable points, best score first.
+    pub reports: Vec<SiteFlyAbilityReport>,
+    /// Points checked, one per forecast grid cell.
+    pub checked: usize,
+    /// Points without forecast, e.g. once a quota is exhausted.
+    pub failed: usize,
+}
+
+/// Checks the points of `scan` on the `target` day. Points sharing a
+/// forecast grid cell are checked once, and the forecast calls are booked
+/// against the quotas, failing the points beyond them. Nothing is recorded
+/// in the state store but the calls.
+pub async fn scan(
+    sources: &ForecastSources,
+    store: Option<&StateStore>,
+    scan: &Scan,
+    target: Target,
+    min_period_hours: i64,
+    scoring: &ScoringModel,
+) -> ScanOutcome {
+    let mut cells = HashSet::new();
+    let sites: Vec<FlyingSite> = scan
+        .points
+        .iter()
+        .map(|point| scan.site(*point))
+        .filter(|site| match sources.grid_cell(site) {
+            Some(cell) => cells.insert(cell),
+            None => true,
+        })
+        .collect();
+    let checked = sites.len();
+    let results: Vec<Result<Option<SiteFlyAbilityReport>, ProviderError>> = stream::iter(sites)
+        .map(|site| async move {
+            let mut forecast = sources.get_forecast(&site, store).await?;
+            sources.subdivide(&mut forecast);
+            let report = crate::evaluate_site(forecast, site, target, min_period_hours, scoring);
+            Ok(report.ok())
+        })
+        .buffer_unordered(sources.concurrency.max(1))
+        .collect()
+        .await;
+    let mut reports = vec![];
+    let mut failed = 0;
+    for result in results {
+        match result {
+            Ok(report) => reports.extend(report),
+            Err(error) => {
+                tracing::warn!(%error, "failed to check a point");
+                failed += 1;
+            }
+        }
+    }
+    reports.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+    ScanOutcome {
+        reports,
+        checked,
+        failed,
+    }
+}
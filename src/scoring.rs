@@ -0,0 +1,77 @@
+use crate::forecast_client::DayOutlook;
+use crate::SiteFlyablePeriod;
+use serde::Deserialize;
+
+// A period this long gets the full duration score.
+const FULL_SCORE_DURATION_HOURS: f32 = 6.0;
+// Wind spread (max - min) at which the steadiness score drops to zero.
+const MAX_WIND_SPREAD_MPS: f32 = 5.0;
+// Share of the score kept by a day known only from its daily forecast.
+const OUTLOOK_CONFIDENCE: f32 = 0.5;
+
+/// Weights of the factors making up a period score. Each factor is
+/// normalized to 0..1 before weighting, so a score ranges from 0 to the sum
+/// of the weights. Configured through the `[scoring]` section; omitted
+/// weights keep their defaults.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ScoringModel {
+    pub duration: f32,
+    pub wind_steadiness: f32,
+    pub direction_centering: f32,
+    pub pop: f32,
+    pub clouds: f32,
+}
+
+impl Default for ScoringModel {
+    fn default() -> Self {
+        ScoringModel {
+            duration: 30.0,
+            wind_steadiness: 20.0,
+            direction_centering: 20.0,
+            pop: 15.0,
+            clouds: 15.0,
+        }
+    }
+}
+
+impl ScoringModel {
+    pub fn score_period(&self, period: &SiteFlyablePeriod) -> f32 {
+        let hours = period.hours();
+        let duration = (hours / FULL_SCORE_DURATION_HOURS).min(1.0);
+        let spread = period.wind_max.meters_per_second() - period.wind_min.meters_per_second();
+        let steadiness = 1.0 - (spread / MAX_WIND_SPREAD_MPS).min(1.0);
+        let centering = 1.0 - period.direction_offset_total / hours;
+        let dryness = 1.0 - period.pop_max;
+        let clearness = 1.0 - period.clouds_total / hours / 100.0;
+
+        self.duration * duration
+            + self.wind_steadiness * steadiness
+            + self.direction_centering * centering
+            + self.pop * dryness
+            + self.clouds * clearness
+    }
+
+    /// Score of a day known only from its daily forecast, the factors it
+    /// tells nothing about counting half and the whole scaled down by
+    /// `OUTLOOK_CONFIDENCE`. `direction_offset` is from
+    /// `FlyingSite::direction_offset`.
+    pub fn score_outlook(&self, outlook: &DayOutlook, direction_offset: f32) -> f32 {
+        let gust = outlook.wind_gust.unwrap_or(outlook.wind_speed);
+        let spread = gust.meters_per_second() - outlook.wind_speed.meters_per_second();
+        let steadiness = 1.0 - (spread.max(0.0) / MAX_WIND_SPREAD_MPS).min(1.0);
+        let score = (self.duration + self.clouds) * 0.5
+            + self.wind_steadiness * steadiness
+            + self.direction_centering * (1.0 - direction_offset)
+            + self.pop * (1.0 - outlook.pop);
+        score * OUTLOOK_CONFIDENCE
+    }
+
+    /// Score of a site is the score of its best period.
+    pub fn score_periods(&self, periods: &[SiteFlyablePeriod]) -> f32 {
+        periods
+            .iter()
+            .map(|p| self.score_period(p))
+            .fold(0.0, f32::max)
+    }
+}
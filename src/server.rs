@@ -0,0 +1,234 @@
+//! HTTP API exposing the configured sites and the latest reports, e.g. for
+//! a club website to display the current flyability, along with a dashboard
+//! page at `/`.
+
+use crate::config::{FlyingSite, ServerConfig};
+use crate::daemon::{Reload, Settings, Signal, Signals};
+use crate::export::{ReportSetView, SiteView};
+use crate::heartbeat::Heartbeat;
+use crate::systemd::{self, Watchdog};
+use crate::{dashboard, ForecastSources, SiteFlyAbilityReport, SkippedSite};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::Html;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Local};
+use std::sync::Arc;
+use tokio::sync::{watch, RwLock};
+
+struct LatestRun {
+    reports: Vec<SiteFlyAbilityReport>,
+    skipped: Vec<SkippedSite>,
+    computed_at: DateTime<Local>,
+}
+
+/// The latest run, `None` until the first one completes.
+type LatestReports = RwLock<Option<LatestRun>>;
+
+pub struct Server {
+    pub config: ServerConfig,
+    pub sources: ForecastSources,
+    pub settings: Settings,
+    /// Replaces `settings` on SIGHUP.
+    pub reload: Reload,
+    /// Pinged after every refresh.
+    pub heartbeat: Option<Heartbeat>,
+}
+
+/// The server along with the reports it computed, shared by the handlers.
+struct AppState {
+    config: ServerConfig,
+    sources: ForecastSources,
+    settings: RwLock<Settings>,
+    reload: Reload,
+    heartbeat: Option<Heartbeat>,
+    latest: LatestReports,
+}
+
+type ApiError = (StatusCode, String);
+
+impl Server {
+    /// Serves the API, recomputing the reports every `refresh_minutes`
+    /// until SIGTERM or SIGINT, after which the requests and the refresh in
+    /// flight are completed.
+    pub async fn run(self) -> Result<(), Box<dyn std::error::Error>> {
+        let signals = Signals::new()?;
+        let listener = tokio::net::TcpListener::bind(&self.config.address).await?;
+        println!("Serving on http://{}", self.config.address);
+        systemd::ready();
+        let state = Arc::new(AppState {
+            config: self.config,
+            sources: self.sources,
+            settings: RwLock::new(self.settings),
+            reload: self.reload,
+            heartbeat: self.heartbeat,
+            latest: RwLock::new(None),
+        });
+        let app = Router::new()
+            .route("/", get(dashboard_page))
+            .route("/sites", get(list_sites))
+            .route("/reports/latest", get(latest_reports))
+            .route("/sites/{name}/forecast", get(site_forecast))
+            .with_state(state.clone());
+        let (stop, stopped) = watch::channel(false);
+        let mut serve_stopped = stopped.clone();
+        let serve = axum::serve(listener, app).with_graceful_shutdown(async move {
+            let _ = serve_stopped.changed().await;
+        });
+        let (served, _, _) = tokio::join!(
+            serve,
+            refresh_forever(&state, stopped),
+            handle_signals(&state, signals, stop)
+        );
+        served?;
+        tracing::info!("stopped");
+        Ok(())
+    }
+}
+
+impl AppState {
+    async fn check_sites(&self) -> Result<LatestRun, String> {
+        let settings = self.settings.read().await;
+        let checked = crate::check_sites(
+            &self.sources,
+            None,
+            settings.sites.clone(),
+            settings.target,
+            settings.min_period_hours,
+            &settings.scoring,
+            settings.order,
+        )
+        .await;
+        if checked.all_failed() {
+            return Err(checked.error_summary().unwrap_or_default());
+        }
+        Ok(LatestRun {
+            reports: checked.reports,
+            skipped: checked.skipped,
+            computed_at: Local::now(),
+        })
+    }
+
+    async fn refresh(&self) {
+        let result = self.check_sites().await;
+        if let Some(heartbeat) = &self.heartbeat {
+            heartbeat.ping(result.is_ok()).await;
+        }
+        match result {
+            Ok(latest) => *self.latest.write().await = Some(latest),
+            Err(error) => tracing::error!(%error, "failed to refresh the reports"),
+        }
+    }
+
+    /// Swaps in the settings of the config file, keeping the current ones
+    /// when it is invalid.
+    async fn reload(&self) {
+        match (self.reload)() {
+            Ok(settings) => {
+                *self.settings.write().await = settings;
+                tracing::info!("reloaded the config");
+                self.refresh().await;
+            }
+            Err(error) => tracing::error!(%error, "failed to reload the config"),
+        }
+    }
+}
+
+fn find_site<'a>(settings: &'a Settings, name: &str) -> Option<&'a FlyingSite> {
+    settings
+        .sites
+        .iter()
+        .find(|site| site.name.eq_ignore_ascii_case(name))
+}
+
+async fn refresh_forever(state: &AppState, mut stopped: watch::Receiver<bool>) {
+    let minutes = state.config.refresh_minutes.max(1);
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(minutes * 60));
+    let mut watchdog = Watchdog::new();
+    loop {
+        tokio::select! {
+            biased;
+            _ = stopped.changed() => return,
+            _ = interval.tick() => {
+                systemd::status("refreshing the reports");
+                state.refresh().await;
+                let next = Local::now() + chrono::Duration::minutes(minutes as i64);
+                systemd::status(&format!("next refresh at {}", next.format("%H:%M")));
+            }
+            _ = watchdog.tick() => {}
+        }
+    }
+}
+
+async fn handle_signals(state: &AppState, mut signals: Signals, stop: watch::Sender<bool>) {
+    loop {
+        match signals.recv().await {
+            Signal::Stop => {
+                tracing::info!("stopping");
+                systemd::stopping();
+                let _ = stop.send(true);
+                return;
+            }
+            Signal::Reload => state.reload().await,
+        }
+    }
+}
+
+async fn list_sites(State(state): State<Arc<AppState>>) -> Json<Vec<SiteView>> {
+    let settings = state.settings.read().await;
+    Json(settings.sites.iter().map(SiteView::from).collect())
+}
+
+async fn latest_reports(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ReportSetView>, ApiError> {
+    match &*state.latest.read().await {
+        Some(run) => Ok(Json(ReportSetView::new(&run.reports, &run.skipped))),
+        None => Err(not_computed_yet()),
+    }
+}
+
+async fn dashboard_page(State(state): State<Arc<AppState>>) -> Result<Html<String>, ApiError> {
+    match &*state.latest.read().await {
+        Some(run) => Ok(Html(dashboard::render(
+            &run.reports,
+            &run.skipped,
+            run.computed_at,
+            &state.settings.read().await.format,
+        ))),
+        None => Err(not_computed_yet()),
+    }
+}
+
+fn not_computed_yet() -> ApiError {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        String::from("no reports have been computed yet"),
+    )
+}
+
+/// A fresh report for a single site, or why it is not flyable.
+async fn site_forecast(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<ReportSetView>, ApiError> {
+    let settings = state.settings.read().await;
+    let site = find_site(&settings, &name)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("unknown site \"{}\"", name)))?
+        .clone();
+    let result = crate::check_site(
+        &state.sources,
+        None,
+        site,
+        settings.target,
+        settings.min_period_hours,
+        &settings.scoring,
+    )
+    .await
+    .map_err(|error| (StatusCode::BAD_GATEWAY, error.to_string()))?;
+    Ok(Json(match result {
+        Ok(report) => ReportSetView::new(&[report], &[]),
+        Err(skipped) => ReportSetView::new(&[], &[skipped]),
+    }))
+}
@@ -0,0 +1,61 @@
+//! Position of the sun, after the NOAA general solar position equations,
+//! accurate to a minute or two away from the poles.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+
+// Zenith angle of the sun at the end of civil twilight, 6° below the
+// horizon.
+const CIVIL_TWILIGHT_ZENITH_DEG: f64 = 96.0;
+
+/// Equation of time in minutes and solar declination in radians at noon UTC
+/// of `date`.
+fn equation_of_time_and_declination(date: NaiveDate) -> (f64, f64) {
+    let days_in_year = if date.leap_year() { 366.0 } else { 365.0 };
+    let gamma = 2.0 * std::f64::consts::PI / days_in_year * (date.ordinal0() as f64);
+    let equation_of_time = 229.18
+        * (0.000075 + 0.001868 * gamma.cos()
+            - 0.032077 * gamma.sin()
+            - 0.014615 * (2.0 * gamma).cos()
+            - 0.040849 * (2.0 * gamma).sin());
+    let declination = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin()
+        - 0.006758 * (2.0 * gamma).cos()
+        + 0.000907 * (2.0 * gamma).sin()
+        - 0.002697 * (3.0 * gamma).cos()
+        + 0.00148 * (3.0 * gamma).sin();
+    (equation_of_time, declination)
+}
+
+/// Civil twilight on a day, when there is enough light to fly without the
+/// sun, i.e. with the sun at most 6° below the horizon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CivilTwilight {
+    /// Start of civil dawn and end of civil dusk.
+    Between(DateTime<Utc>, DateTime<Utc>),
+    /// The sun never gets 6° below the horizon, around the summer solstice
+    /// at high latitudes.
+    AllDay,
+    /// The sun never gets above 6° below the horizon, around the winter
+    /// solstice at high latitudes.
+    AllNight,
+}
+
+/// Civil twilight on `date` at the given coordinates.
+pub fn civil_twilight(date: NaiveDate, latitude: f64, longitude: f64) -> CivilTwilight {
+    let (equation_of_time, declination) = equation_of_time_and_declination(date);
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = CIVIL_TWILIGHT_ZENITH_DEG.to_radians().cos()
+        / (latitude.cos() * declination.cos())
+        - latitude.tan() * declination.tan();
+    if cos_hour_angle < -1.0 {
+        return CivilTwilight::AllDay;
+    } else if cos_hour_angle > 1.0 {
+        return CivilTwilight::AllNight;
+    }
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+    let midnight = Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN));
+    let at = |hour_angle: f64| {
+        let minutes = 720.0 - 4.0 * (longitude + hour_angle) - equation_of_time;
+        midnight + Duration::seconds((minutes * 60.0).round() as i64)
+    };
+    CivilTwilight::Between(at(hour_angle), at(-hour_angle))
+}
@@ -0,0 +1,164 @@
+use crate::forecast_client::{DayWeatherForecast, TimeOfDay};
+use crate::http::HttpClient;
+use reqwest::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+const PRESSURE_LEVELS_HPA: [u16; 6] = [950, 925, 850, 700, 600, 500];
+// A rising parcel cools at the dry adiabatic rate.
+const DRY_ADIABATIC_LAPSE_RATE: f32 = 9.8;
+// Surface superheating needed to trigger a thermal, in °C.
+const THERMAL_TRIGGER_C: f32 = 1.0;
+
+#[derive(Deserialize, Debug)]
+struct SoundingHourly {
+    time: Vec<i64>,
+    temperature_2m: Vec<Option<f32>>,
+    #[serde(flatten)]
+    levels: HashMap<String, Vec<Option<f32>>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SoundingForecast {
+    elevation: f32,
+    hourly: SoundingHourly,
+}
+
+#[derive(Debug)]
+pub struct SoundingLevel {
+    pub height_m: f32,
+    pub temperature_c: f32,
+}
+
+/// Vertical temperature profile above a site for a single forecast hour.
+#[derive(Debug)]
+pub struct Sounding {
+    pub elevation_m: f32,
+    pub surface_temperature_c: f32,
+    pub levels: Vec<SoundingLevel>,
+}
+
+impl Sounding {
+    /// Height (AMSL) where a dry parcel lifted from the surface becomes
+    /// colder than the surrounding air, or `None` if nothing gets off the
+    /// ground.
+    pub fn thermal_top(&self) -> Option<f32> {
+        let parcel_temp = |height: f32| {
+            self.surface_temperature_c + THERMAL_TRIGGER_C
+                - DRY_ADIABATIC_LAPSE_RATE * (height - self.elevation_m) / 1000.0
+        };
+        let mut lower_height = self.elevation_m;
+        let mut lower_excess = THERMAL_TRIGGER_C;
+        for level in self.levels.iter().filter(|l| l.height_m > self.elevation_m) {
+            let excess = parcel_temp(level.height_m) - level.temperature_c;
+            if excess <= 0.0 {
+                let fraction = lower_excess / (lower_excess - excess);
+                let top = lower_height + (level.height_m - lower_height) * fraction;
+                return if top - self.elevation_m < 100.0 {
+                    None
+                } else {
+                    Some(top)
+                };
+            }
+            lower_height = level.height_m;
+            lower_excess = excess;
+        }
+        Some(lower_height)
+    }
+}
+
+/// Pulls model soundings (GFS by default) from Open-Meteo pressure levels.
+pub struct SoundingClient {
+    url: String,
+    model: String,
+    http: HttpClient,
+}
+
+impl SoundingClient {
+    pub fn new(url: String, model: String, http: HttpClient) -> Self {
+        SoundingClient { url, model, http }
+    }
+
+    pub fn name(&self) -> &'static str {
+        "Open-Meteo"
+    }
+
+    pub async fn get_soundings(&self, lat: f64, lon: f64) -> Result<HashMap<i64, Sounding>> {
+        let mut hourly = vec![String::from("temperature_2m")];
+        for level in PRESSURE_LEVELS_HPA.iter() {
+            hourly.push(format!("temperature_{}hPa", level));
+            hourly.push(format!("geopotential_height_{}hPa", level));
+        }
+        let (lat, lon, hourly) = (lat.to_string(), lon.to_string(), hourly.join(","));
+        let raw_forecast: SoundingForecast = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("latitude", &lat[..]),
+                    ("longitude", &lon[..]),
+                    ("hourly", &hourly[..]),
+                    ("models", &self.model[..]),
+                    ("timeformat", "unixtime"),
+                    ("forecast_days", "3"),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+
+        let series = |name: String, i: usize| {
+            raw_forecast
+                .hourly
+                .levels
+                .get(&name)
+                .and_then(|values| values.get(i).copied().flatten())
+        };
+        let mut soundings = HashMap::new();
+        for (i, time) in raw_forecast.hourly.time.iter().enumerate() {
+            let surface_temperature_c = match raw_forecast.hourly.temperature_2m.get(i) {
+                Some(Some(temperature)) => *temperature,
+                _ => continue,
+            };
+            let mut levels = vec![];
+            for level in PRESSURE_LEVELS_HPA.iter() {
+                let temperature = series(format!("temperature_{}hPa", level), i);
+                let height = series(format!("geopotential_height_{}hPa", level), i);
+                if let (Some(temperature_c), Some(height_m)) = (temperature, height) {
+                    levels.push(SoundingLevel {
+                        height_m,
+                        temperature_c,
+                    });
+                }
+            }
+            levels.sort_by(|a, b| a.height_m.partial_cmp(&b.height_m).unwrap());
+            soundings.insert(
+                *time,
+                Sounding {
+                    elevation_m: raw_forecast.elevation,
+                    surface_temperature_c,
+                    levels,
+                },
+            );
+        }
+        Ok(soundings)
+    }
+}
+
+/// Attaches the expected top of lift to the matching daylight hours.
+pub fn apply_thermal_tops(
+    forecasts: &mut [DayWeatherForecast],
+    soundings: &HashMap<i64, Sounding>,
+) {
+    for day in forecasts.iter_mut() {
+        for hour in day.hourly.iter_mut() {
+            if hour.time_of_day != TimeOfDay::NIGHT {
+                hour.thermal_top_m = soundings
+                    .get(&hour.time.timestamp())
+                    .and_then(|s| s.thermal_top());
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Versioned JSON dumps of what chats and admins set up through the bot,
+//! written by `export-state` and read back by `import-state` to move the
+//! bot to another server or back it up without copying the SQLite file.
+//! The histories and caches of the store are left out, later runs
+//! rebuilding them.
+
+use crate::store::StateStore;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Version of the dumps written, bumped when their shape changes. Dumps of
+/// later versions are refused.
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SubscriptionDump {
+    pub chat_id: String,
+    /// `*` for every site.
+    pub site: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SiteChangeDump {
+    pub site: String,
+    pub setting: String,
+    pub value: String,
+}
+
+/// A Telegram message waiting for the end of quiet hours or for a retry.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeferredMessageDump {
+    pub chat_id: String,
+    pub text: String,
+    pub silent: bool,
+    /// Unix timestamps.
+    pub send_at: i64,
+    pub expires_at: Option<i64>,
+    /// `YYYY-MM-DD` day of the reports in the message.
+    pub report_date: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StateDump {
+    pub version: u32,
+    pub exported_at: String,
+    pub subscriptions: Vec<SubscriptionDump>,
+    /// Message style per chat id.
+    #[serde(default)]
+    pub chat_styles: BTreeMap<String, String>,
+    /// Pilot profile per chat id.
+    #[serde(default)]
+    pub chat_profiles: BTreeMap<String, String>,
+    /// In the order they were made, later changes of a setting winning.
+    #[serde(default)]
+    pub site_changes: Vec<SiteChangeDump>,
+    #[serde(default)]
+    pub deferred_messages: Vec<DeferredMessageDump>,
+}
+
+impl StateDump {
+    /// The bot state kept in `store`.
+    pub fn export(store: &StateStore) -> rusqlite::Result<Self> {
+        Ok(StateDump {
+            version: VERSION,
+            exported_at: Utc::now().to_rfc3339(),
+            subscriptions: store
+                .subscriptions()?
+                .into_iter()
+                .map(|subscription| SubscriptionDump {
+                    chat_id: subscription.chat_id,
+                    site: subscription.site,
+                })
+                .collect(),
+            chat_styles: store.chat_styles()?.into_iter().collect(),
+            chat_profiles: store.chat_profiles()?.into_iter().collect(),
+            site_changes: store
+                .site_changes()?
+                .into_iter()
+                .map(|change| SiteChangeDump {
+                    site: change.site,
+                    setting: change.setting,
+                    value: change.value,
+                })
+                .collect(),
+            deferred_messages: store
+                .due_messages(i64::MAX)?
+                .into_iter()
+                .map(|message| DeferredMessageDump {
+                    chat_id: message.chat_id,
+                    text: message.text,
+                    silent: message.silent,
+                    send_at: message.send_at,
+                    expires_at: message.expires_at,
+                    report_date: message.report_date,
+                })
+                .collect(),
+        })
+    }
+
+    /// Writes the dump into `store` at once, over the bot state already
+    /// there with `replace`, merged with it otherwise: the settings of the
+    /// dump then win over the ones of the same chats and sites, and its
+    /// deferred messages are added to the ones waiting.
+    pub fn import(&self, store: &StateStore, replace: bool) -> rusqlite::Result<()> {
+        store.in_transaction(|store| {
+            if replace {
+                store.clear_bot_state()?;
+            }
+            for subscription in &self.subscriptions {
+                store.add_subscription(&subscription.chat_id, &subscription.site)?;
+            }
+            for (chat_id, style) in &self.chat_styles {
+                store.set_chat_style(chat_id, style)?;
+            }
+            for (chat_id, profile) in &self.chat_profiles {
+                store.set_chat_profile(chat_id, Some(profile))?;
+            }
+            for change in &self.site_changes {
+                store.set_site_change(&change.site, &change.setting, &change.value)?;
+            }
+            for message in &self.deferred_messages {
+                store.defer_message(
+                    &message.chat_id,
+                    &message.text,
+                    message.silent,
+                    message.send_at,
+                    message.expires_at,
+                    message.report_date.as_deref(),
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    /// E.g. "12 subscriptions of 5 chats, 2 chat styles, 1 chat profile, 3
+    /// site changes, 0 deferred messages".
+    pub fn describe(&self) -> String {
+        let count = |count: usize, noun: &str| match count {
+            1 => format!("1 {}", noun),
+            _ => format!("{} {}s", count, noun),
+        };
+        let chats: BTreeSet<&str> = self
+            .subscriptions
+            .iter()
+            .map(|subscription| subscription.chat_id.as_str())
+            .collect();
+        format!(
+            "{} of {}, {}, {}, {}, {}",
+            count(self.subscriptions.len(), "subscription"),
+            count(chats.len(), "chat"),
+            count(self.chat_styles.len(), "chat style"),
+            count(self.chat_profiles.len(), "chat profile"),
+            count(self.site_changes.len(), "site change"),
+            count(self.deferred_messages.len(), "deferred message"),
+        )
+    }
+}
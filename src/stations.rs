@@ -0,0 +1,478 @@
+use crate::forecast_client::HourWeatherForecast;
+use crate::http::HttpClient;
+use crate::i18n;
+use crate::measures::{WindDirection, WindSpeed};
+use crate::message::MessageFormat;
+use async_trait::async_trait;
+use reqwest::Result;
+use serde::Deserialize;
+
+// Forecast and observation "agree" when both stay within these margins.
+const AGREEMENT_WIND_MPS: f32 = 2.0;
+const AGREEMENT_DEGREES: i16 = 45;
+
+/// Current conditions reported by a weather station.
+#[derive(Debug, Clone)]
+pub struct Observation {
+    pub station_name: String,
+    pub wind_speed: WindSpeed,
+    pub wind_gust: Option<WindSpeed>,
+    /// None when the station didn't report it.
+    pub wind_direction: Option<WindDirection>,
+}
+
+impl Observation {
+    /// Whether the observation agrees with the `forecast`, None without an
+    /// observed wind direction to compare.
+    pub fn agrees_with(&self, forecast: &HourWeatherForecast) -> Option<bool> {
+        let direction = self.wind_direction?;
+        let wind_difference =
+            (self.wind_speed.meters_per_second() - forecast.wind_speed.meters_per_second()).abs();
+        Some(
+            wind_difference <= AGREEMENT_WIND_MPS
+                && direction.distance(forecast.wind_direction) <= AGREEMENT_DEGREES,
+        )
+    }
+
+    /// One-line summary of the observation, compared against the forecast for
+    /// the current hour when available, in the language and units of
+    /// `format`.
+    pub fn describe(
+        &self,
+        source: &str,
+        forecast: Option<&HourWeatherForecast>,
+        format: &MessageFormat,
+    ) -> String {
+        let (strings, unit) = (format.strings(), format.units().wind);
+        let mut repr = i18n::fill(
+            strings.live,
+            &[
+                ("station", &self.station_name),
+                ("source", source),
+                ("wind", &self.wind_speed.format(unit)),
+                ("unit", unit.label()),
+            ],
+        );
+        if let Some(gust) = self.wind_gust {
+            repr.push_str(&i18n::fill(
+                strings.live_gust,
+                &[("gust", &gust.format(unit))],
+            ));
+        }
+        if let Some(direction) = self.wind_direction {
+            repr.push_str(&i18n::fill(
+                strings.live_direction,
+                &[("degrees", &direction.degrees().to_string())],
+            ));
+        }
+        let verdict = forecast.and_then(|forecast| Some((forecast, self.agrees_with(forecast)?)));
+        if let Some((forecast, agrees)) = verdict {
+            let verdict = if agrees {
+                strings.live_verdicts[0]
+            } else {
+                strings.live_verdicts[1]
+            };
+            repr.push_str(&i18n::fill(
+                strings.live_forecast,
+                &[
+                    ("wind", &forecast.wind_speed.format(unit)),
+                    ("unit", unit.label()),
+                    ("degrees", &forecast.wind_direction.degrees().to_string()),
+                    ("verdict", verdict),
+                ],
+            ));
+        }
+        repr
+    }
+}
+
+/// An observation of a station of `source`, along with the forecast for
+/// its hour.
+#[derive(Debug, Clone)]
+pub struct LiveReading {
+    pub source: &'static str,
+    pub observation: Observation,
+    pub forecast: Option<HourWeatherForecast>,
+}
+
+impl LiveReading {
+    pub fn describe(&self, format: &MessageFormat) -> String {
+        self.observation
+            .describe(self.source, self.forecast.as_ref(), format)
+    }
+}
+
+/// A provider of live observations from on-site weather stations.
+#[async_trait]
+pub trait StationSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Latest observation, or `None` if the station has no recent data.
+    async fn get_observation(&self, station_id: &str) -> Result<Option<Observation>>;
+}
+
+#[derive(Deserialize, Debug)]
+struct HolfuyWind {
+    speed: f32,
+    gust: Option<f32>,
+    direction: i16,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct HolfuyLive {
+    station_name: String,
+    wind: HolfuyWind,
+}
+
+pub struct HolfuyClient {
+    url: String,
+    api_key: String,
+    http: HttpClient,
+}
+
+impl HolfuyClient {
+    pub fn new(url: String, api_key: String, http: HttpClient) -> Self {
+        HolfuyClient { url, api_key, http }
+    }
+}
+
+#[async_trait]
+impl StationSource for HolfuyClient {
+    fn name(&self) -> &'static str {
+        "Holfuy"
+    }
+
+    async fn get_observation(&self, station_id: &str) -> Result<Option<Observation>> {
+        let live: HolfuyLive = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("s", station_id),
+                    ("pw", &self.api_key[..]),
+                    ("m", "JSON"),
+                    ("su", "m/s"),
+                    ("tu", "C"),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        Ok(Some(Observation {
+            station_name: live.station_name,
+            wind_speed: WindSpeed::MPS(live.wind.speed),
+            wind_gust: live.wind.gust.map(WindSpeed::MPS),
+            wind_direction: Some(WindDirection::new(live.wind.direction)),
+        }))
+    }
+}
+
+// The FFVL API returns numeric readings as strings more often than not.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum FfvlNumber {
+    Number(f32),
+    Text(String),
+}
+
+impl FfvlNumber {
+    fn value(&self) -> Option<f32> {
+        match self {
+            FfvlNumber::Number(value) => Some(*value),
+            FfvlNumber::Text(text) => text.trim().parse().ok(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct FfvlReading {
+    date: String,
+    vitesse_vent_moy: Option<FfvlNumber>,
+    vitesse_vent_max: Option<FfvlNumber>,
+    direct_vent_moy: Option<FfvlNumber>,
+}
+
+/// Client for the FFVL beacon ("balise") network.
+pub struct FfvlClient {
+    url: String,
+    api_key: String,
+    http: HttpClient,
+}
+
+impl FfvlClient {
+    pub fn new(url: String, api_key: String, http: HttpClient) -> Self {
+        FfvlClient { url, api_key, http }
+    }
+}
+
+#[async_trait]
+impl StationSource for FfvlClient {
+    fn name(&self) -> &'static str {
+        "FFVL"
+    }
+
+    async fn get_observation(&self, station_id: &str) -> Result<Option<Observation>> {
+        let readings: Vec<FfvlReading> = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("base", "balises"),
+                    ("r", "releves_meteo"),
+                    ("idbalise", station_id),
+                    ("mode", "json"),
+                    ("key", &self.api_key[..]),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        let value = |number: &Option<FfvlNumber>| number.as_ref().and_then(|v| v.value());
+        let latest = readings
+            .into_iter()
+            .filter_map(|reading| Some((value(&reading.vitesse_vent_moy)?, reading)))
+            .max_by(|(_, a), (_, b)| a.date.cmp(&b.date));
+        Ok(latest.map(|(speed, reading)| Observation {
+            station_name: format!("balise {}", station_id),
+            wind_speed: WindSpeed::KMPH(speed),
+            wind_gust: value(&reading.vitesse_vent_max).map(WindSpeed::KMPH),
+            wind_direction: value(&reading.direct_vent_moy)
+                .map(|degrees| WindDirection::new(degrees as i16)),
+        }))
+    }
+}
+
+// The Ecowitt APIs return their readings as strings.
+#[derive(Deserialize, Debug)]
+struct EcowittReading {
+    value: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct EcowittWind {
+    wind_speed: Option<EcowittReading>,
+    wind_gust: Option<EcowittReading>,
+    wind_direction: Option<EcowittReading>,
+}
+
+#[derive(Deserialize, Debug)]
+struct EcowittData {
+    wind: Option<EcowittWind>,
+}
+
+#[derive(Deserialize, Debug)]
+struct EcowittRealTime {
+    code: i64,
+    msg: String,
+    /// An empty array rather than an object on errors.
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug)]
+struct EcowittLiveItem {
+    id: String,
+    val: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct EcowittLiveData {
+    #[serde(default)]
+    common_list: Vec<EcowittLiveItem>,
+}
+
+/// Client for personal Ecowitt stations, read from ecowitt.net by MAC
+/// address or from the gateway itself when the station id is its URL on the
+/// local network, e.g. "http://192.168.1.20".
+pub struct EcowittClient {
+    url: String,
+    /// Application and API keys of ecowitt.net, only needed by the stations
+    /// read from there.
+    keys: Option<(String, String)>,
+    http: HttpClient,
+}
+
+impl EcowittClient {
+    pub fn new(url: String, keys: Option<(String, String)>, http: HttpClient) -> Self {
+        EcowittClient { url, keys, http }
+    }
+
+    /// Whether `station_id` is read from the gateway rather than ecowitt.net.
+    pub fn is_local(station_id: &str) -> bool {
+        station_id.starts_with("http://") || station_id.starts_with("https://")
+    }
+
+    async fn get_cloud_observation(&self, mac: &str) -> Result<Option<Observation>> {
+        let (application_key, api_key) = match &self.keys {
+            Some(keys) => keys,
+            None => {
+                tracing::warn!(%mac, "reading Ecowitt stations from ecowitt.net needs API keys");
+                return Ok(None);
+            }
+        };
+        let real_time: EcowittRealTime = self
+            .http
+            .send(|client| {
+                client.get(format!("{}device/real_time", self.url)).query(&[
+                    ("application_key", &application_key[..]),
+                    ("api_key", &api_key[..]),
+                    ("mac", mac),
+                    ("call_back", "wind"),
+                    // m/s
+                    ("wind_speed_unitid", "6"),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        if real_time.code != 0 {
+            tracing::warn!(%mac, code = real_time.code, message = %real_time.msg, "Ecowitt refused the call");
+            return Ok(None);
+        }
+        let wind = match serde_json::from_value::<EcowittData>(real_time.data) {
+            Ok(EcowittData { wind: Some(wind) }) => wind,
+            _ => return Ok(None),
+        };
+        let value = |reading: &Option<EcowittReading>| {
+            reading
+                .as_ref()
+                .and_then(|reading| reading.value.trim().parse::<f32>().ok())
+        };
+        let speed = match value(&wind.wind_speed) {
+            Some(speed) => speed,
+            None => return Ok(None),
+        };
+        Ok(Some(Observation {
+            station_name: format!("station {}", mac),
+            wind_speed: WindSpeed::MPS(speed),
+            wind_gust: value(&wind.wind_gust).map(WindSpeed::MPS),
+            wind_direction: value(&wind.wind_direction)
+                .map(|degrees| WindDirection::new(degrees as i16)),
+        }))
+    }
+
+    async fn get_local_observation(&self, gateway: &str) -> Result<Option<Observation>> {
+        let url = format!("{}/get_livedata_info", gateway.trim_end_matches('/'));
+        let live: EcowittLiveData = self
+            .http
+            .send(|client| client.get(&url))
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        // Ids of the gateway API, the speeds carrying the unit chosen on the
+        // gateway, e.g. "9.4 km/h".
+        let reading = |id: &str| {
+            live.common_list
+                .iter()
+                .find(|item| item.id.eq_ignore_ascii_case(id))
+                .map(|item| item.val.trim())
+        };
+        let speed = match reading("0x0B").and_then(|val| val.parse::<WindSpeed>().ok()) {
+            Some(speed) => speed,
+            None => return Ok(None),
+        };
+        Ok(Some(Observation {
+            station_name: format!(
+                "station {}",
+                gateway
+                    .trim_start_matches("http://")
+                    .trim_start_matches("https://")
+            ),
+            wind_speed: speed,
+            wind_gust: reading("0x0C").and_then(|val| val.parse().ok()),
+            wind_direction: reading("0x0A")
+                .and_then(|val| val.trim_end_matches('°').parse::<f32>().ok())
+                .map(|degrees| WindDirection::new(degrees as i16)),
+        }))
+    }
+}
+
+#[async_trait]
+impl StationSource for EcowittClient {
+    fn name(&self) -> &'static str {
+        "Ecowitt"
+    }
+
+    async fn get_observation(&self, station_id: &str) -> Result<Option<Observation>> {
+        if EcowittClient::is_local(station_id) {
+            self.get_local_observation(station_id).await
+        } else {
+            self.get_cloud_observation(station_id).await
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct AmbientRecord {
+    windspeedmph: Option<f32>,
+    windgustmph: Option<f32>,
+    winddir: Option<f32>,
+}
+
+/// Client for personal Ambient Weather stations, by MAC address.
+pub struct AmbientClient {
+    url: String,
+    application_key: String,
+    api_key: String,
+    http: HttpClient,
+}
+
+impl AmbientClient {
+    pub fn new(url: String, application_key: String, api_key: String, http: HttpClient) -> Self {
+        AmbientClient {
+            url,
+            application_key,
+            api_key,
+            http,
+        }
+    }
+}
+
+#[async_trait]
+impl StationSource for AmbientClient {
+    fn name(&self) -> &'static str {
+        "Ambient Weather"
+    }
+
+    async fn get_observation(&self, station_id: &str) -> Result<Option<Observation>> {
+        let records: Vec<AmbientRecord> = self
+            .http
+            .send(|client| {
+                client
+                    .get(format!("{}devices/{}", self.url, station_id))
+                    .query(&[
+                        ("applicationKey", &self.application_key[..]),
+                        ("apiKey", &self.api_key[..]),
+                        ("limit", "1"),
+                    ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        Ok(records.into_iter().next().and_then(|record| {
+            Some(Observation {
+                station_name: format!("station {}", station_id),
+                wind_speed: WindSpeed::MPH(record.windspeedmph?),
+                wind_gust: record.windgustmph.map(WindSpeed::MPH),
+                wind_direction: record
+                    .winddir
+                    .map(|degrees| WindDirection::new(degrees as i16)),
+            })
+        }))
+    }
+}
@@ -0,0 +1,702 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Bias and mean absolute error of forecast wind against observations.
+#[derive(Debug)]
+pub struct WindAccuracy {
+    pub site: String,
+    pub provider: String,
+    pub station: String,
+    pub samples: i64,
+    pub bias_mps: f64,
+    pub mean_absolute_error_mps: f64,
+}
+
+/// Site name of a subscription covering every site.
+pub const ALL_SITES: &str = "*";
+
+/// A chat following a site through the Telegram bot.
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    pub chat_id: String,
+    pub site: String,
+}
+
+/// The daytime wind of a site on a day, as forecast by a run.
+#[derive(Debug, Clone)]
+pub struct ForecastRun {
+    pub fetched_at: i64,
+    pub wind_avg_mps: f64,
+    pub wind_max_mps: f64,
+    pub gust_max_mps: Option<f64>,
+}
+
+/// The latest forecast wind of a past hour.
+#[derive(Debug, Clone)]
+pub struct PastWind {
+    /// Unix timestamp of the hour.
+    pub hour: i64,
+    pub wind_mps: f64,
+    /// Missing from the oldest rows.
+    pub wind_direction: Option<i16>,
+}
+
+/// A flyable period of a report computed by an earlier run.
+#[derive(Debug, Clone)]
+pub struct PastPeriod {
+    pub site: String,
+    /// Day of the report, `YYYY-MM-DD` where the site is.
+    pub date: String,
+    /// Unix timestamp of the run.
+    pub computed_at: i64,
+    pub days_ahead: i64,
+    /// Score of the whole report.
+    pub score: f64,
+    /// Unix timestamp of the first hour.
+    pub start: i64,
+    /// Offset of the site from UTC in seconds, to show `start` locally.
+    pub utc_offset: i32,
+    /// Fractional once the forecast is subdivided, e.g. 1.5.
+    pub duration_hours: f64,
+    pub wind_min_mps: f64,
+    pub wind_max_mps: f64,
+    /// Highest chance of rain, from 0 to 1.
+    pub pop_max: f64,
+    /// Middle of the wind directions, missing from the oldest rows.
+    pub wind_direction: Option<i16>,
+}
+
+/// A Telegram message held back by quiet hours or kept after failing to be
+/// delivered.
+#[derive(Debug, Clone)]
+pub struct DeferredMessage {
+    pub id: i64,
+    pub chat_id: String,
+    /// Already formatted for the parse mode of the notifier.
+    pub text: String,
+    pub silent: bool,
+    /// Unix timestamp from which the message may be sent.
+    pub send_at: i64,
+    /// Unix timestamp after which the message is dropped, missing from the
+    /// oldest rows.
+    pub expires_at: Option<i64>,
+    /// `YYYY-MM-DD` day of the reports in the message, replaced by the
+    /// next ones of the chat for that day.
+    pub report_date: Option<String>,
+}
+
+/// A setting of a site changed by an admin chat through the bot, applied
+/// over the config file by `admin::apply_site_changes`.
+#[derive(Debug, Clone)]
+pub struct SiteChange {
+    pub site: String,
+    /// E.g. "max_wind", or "added" for the sites defined from the bot.
+    pub setting: String,
+    pub value: String,
+}
+
+/// Persistent application state backed by SQLite, shareable across tasks.
+/// Clones share the same connection.
+#[derive(Clone)]
+pub struct StateStore {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl StateStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS forecast_wind (
+                site TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                hour INTEGER NOT NULL,
+                wind_mps REAL NOT NULL,
+                fetched_at INTEGER NOT NULL,
+                PRIMARY KEY (site, provider, hour)
+            );
+            CREATE TABLE IF NOT EXISTS observed_wind (
+                site TEXT NOT NULL,
+                station TEXT NOT NULL,
+                hour INTEGER NOT NULL,
+                wind_mps REAL NOT NULL,
+                PRIMARY KEY (site, station, hour)
+            );
+            CREATE TABLE IF NOT EXISTS telegram_subscriptions (
+                chat_id TEXT NOT NULL,
+                site TEXT NOT NULL,
+                PRIMARY KEY (chat_id, site)
+            );
+            CREATE TABLE IF NOT EXISTS provider_calls (
+                provider TEXT NOT NULL,
+                day TEXT NOT NULL,
+                calls INTEGER NOT NULL,
+                PRIMARY KEY (provider, day)
+            );
+            CREATE TABLE IF NOT EXISTS geocoded_locations (
+                location TEXT PRIMARY KEY,
+                latitude REAL NOT NULL,
+                longitude REAL NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS report_history (
+                site TEXT NOT NULL,
+                date TEXT NOT NULL,
+                computed_at INTEGER NOT NULL,
+                days_ahead INTEGER NOT NULL,
+                score REAL NOT NULL,
+                start INTEGER NOT NULL,
+                utc_offset INTEGER NOT NULL,
+                duration_hours INTEGER NOT NULL,
+                wind_min_mps REAL NOT NULL,
+                wind_max_mps REAL NOT NULL,
+                pop_max REAL NOT NULL,
+                wind_direction INTEGER,
+                PRIMARY KEY (site, computed_at, start)
+            );
+            CREATE TABLE IF NOT EXISTS deferred_messages (
+                id INTEGER PRIMARY KEY,
+                chat_id TEXT NOT NULL,
+                text TEXT NOT NULL,
+                silent INTEGER NOT NULL,
+                send_at INTEGER NOT NULL,
+                expires_at INTEGER,
+                report_date TEXT
+            );
+            CREATE TABLE IF NOT EXISTS chat_styles (
+                chat_id TEXT PRIMARY KEY,
+                style TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS chat_profiles (
+                chat_id TEXT PRIMARY KEY,
+                profile TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS forecast_runs (
+                site TEXT NOT NULL,
+                date TEXT NOT NULL,
+                fetched_at INTEGER NOT NULL,
+                wind_avg_mps REAL NOT NULL,
+                wind_max_mps REAL NOT NULL,
+                gust_max_mps REAL,
+                PRIMARY KEY (site, date, fetched_at)
+            );
+            CREATE TABLE IF NOT EXISTS site_changes (
+                site TEXT NOT NULL,
+                setting TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (site, setting)
+            );",
+        )?;
+        // Added to the history after its first rows.
+        if connection
+            .prepare("SELECT wind_direction FROM report_history")
+            .is_err()
+        {
+            connection.execute(
+                "ALTER TABLE report_history ADD COLUMN wind_direction INTEGER",
+                [],
+            )?;
+        }
+        if connection
+            .prepare("SELECT wind_direction FROM forecast_wind")
+            .is_err()
+        {
+            connection.execute(
+                "ALTER TABLE forecast_wind ADD COLUMN wind_direction INTEGER",
+                [],
+            )?;
+        }
+        if connection
+            .prepare("SELECT expires_at FROM deferred_messages")
+            .is_err()
+        {
+            connection.execute(
+                "ALTER TABLE deferred_messages ADD COLUMN expires_at INTEGER",
+                [],
+            )?;
+        }
+        if connection
+            .prepare("SELECT report_date FROM deferred_messages")
+            .is_err()
+        {
+            connection.execute(
+                "ALTER TABLE deferred_messages ADD COLUMN report_date TEXT",
+                [],
+            )?;
+        }
+        Ok(StateStore {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Runs `change` in a transaction, rolled back when it fails. Other
+    /// clones of the store must not write meanwhile.
+    pub fn in_transaction<T>(&self, change: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        self.connection().execute_batch("BEGIN")?;
+        match change(self) {
+            Ok(value) => {
+                self.connection().execute_batch("COMMIT")?;
+                Ok(value)
+            }
+            Err(error) => {
+                self.connection().execute_batch("ROLLBACK")?;
+                Err(error)
+            }
+        }
+    }
+
+    /// Keeps the most recent forecast issued for each future hour.
+    pub fn record_forecast_wind(
+        &self,
+        site: &str,
+        provider: &str,
+        hour: i64,
+        wind_mps: f32,
+        wind_direction: i16,
+        fetched_at: i64,
+    ) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO forecast_wind
+             (site, provider, hour, wind_mps, wind_direction, fetched_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                site,
+                provider,
+                hour,
+                wind_mps as f64,
+                wind_direction,
+                fetched_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The wind last forecast for the hours of `site` from `from` to before
+    /// `to`, unix timestamps, oldest first.
+    pub fn past_forecast_wind(&self, site: &str, from: i64, to: i64) -> Result<Vec<PastWind>> {
+        let connection = self.connection();
+        // SQLite takes the other columns from the row with the maximum.
+        let mut statement = connection.prepare(
+            "SELECT hour, wind_mps, wind_direction, MAX(fetched_at) FROM forecast_wind
+             WHERE site = ?1 AND hour >= ?2 AND hour < ?3
+             GROUP BY hour ORDER BY hour",
+        )?;
+        let rows = statement.query_map(params![site, from, to], |row| {
+            Ok(PastWind {
+                hour: row.get(0)?,
+                wind_mps: row.get(1)?,
+                wind_direction: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Records the forecast daytime wind of `site` on `date`, a
+    /// `YYYY-MM-DD` date, unless it is the same as the latest one, e.g. when
+    /// the run used a cached forecast.
+    pub fn record_forecast_run(&self, site: &str, date: &str, run: &ForecastRun) -> Result<()> {
+        let connection = self.connection();
+        let latest = connection
+            .query_row(
+                "SELECT wind_avg_mps, wind_max_mps, gust_max_mps FROM forecast_runs
+                 WHERE site = ?1 AND date = ?2 ORDER BY fetched_at DESC LIMIT 1",
+                params![site, date],
+                |row| {
+                    Ok((
+                        row.get::<_, f64>(0)?,
+                        row.get::<_, f64>(1)?,
+                        row.get::<_, Option<f64>>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+        if latest == Some((run.wind_avg_mps, run.wind_max_mps, run.gust_max_mps)) {
+            return Ok(());
+        }
+        connection.execute(
+            "INSERT OR REPLACE INTO forecast_runs
+             (site, date, fetched_at, wind_avg_mps, wind_max_mps, gust_max_mps)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                site,
+                date,
+                run.fetched_at,
+                run.wind_avg_mps,
+                run.wind_max_mps,
+                run.gust_max_mps
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The latest `limit` forecasts of `site` for `date`, oldest first.
+    pub fn forecast_runs(&self, site: &str, date: &str, limit: usize) -> Result<Vec<ForecastRun>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT fetched_at, wind_avg_mps, wind_max_mps, gust_max_mps FROM forecast_runs
+             WHERE site = ?1 AND date = ?2 ORDER BY fetched_at DESC LIMIT ?3",
+        )?;
+        let rows = statement.query_map(params![site, date, limit as i64], |row| {
+            Ok(ForecastRun {
+                fetched_at: row.get(0)?,
+                wind_avg_mps: row.get(1)?,
+                wind_max_mps: row.get(2)?,
+                gust_max_mps: row.get(3)?,
+            })
+        })?;
+        let mut runs = rows.collect::<Result<Vec<_>>>()?;
+        runs.reverse();
+        Ok(runs)
+    }
+
+    pub fn record_observed_wind(
+        &self,
+        site: &str,
+        station: &str,
+        hour: i64,
+        wind_mps: f32,
+    ) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO observed_wind (site, station, hour, wind_mps)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![site, station, hour, wind_mps as f64],
+        )?;
+        Ok(())
+    }
+
+    pub fn wind_accuracy(&self) -> Result<Vec<WindAccuracy>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT f.site, f.provider, o.station, COUNT(*),
+                    AVG(f.wind_mps - o.wind_mps), AVG(ABS(f.wind_mps - o.wind_mps))
+             FROM forecast_wind f
+             JOIN observed_wind o ON o.site = f.site AND o.hour = f.hour
+             GROUP BY f.site, f.provider, o.station
+             ORDER BY f.site, f.provider, o.station",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok(WindAccuracy {
+                site: row.get(0)?,
+                provider: row.get(1)?,
+                station: row.get(2)?,
+                samples: row.get(3)?,
+                bias_mps: row.get(4)?,
+                mean_absolute_error_mps: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Calls made to `provider` on `day`, a `YYYY-MM-DD` date in UTC.
+    pub fn provider_calls(&self, provider: &str, day: &str) -> Result<u32> {
+        let calls = self
+            .connection()
+            .query_row(
+                "SELECT calls FROM provider_calls WHERE provider = ?1 AND day = ?2",
+                params![provider, day],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(calls.unwrap_or(0))
+    }
+
+    pub fn record_provider_call(&self, provider: &str, day: &str) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO provider_calls (provider, day, calls) VALUES (?1, ?2, 1)
+             ON CONFLICT (provider, day) DO UPDATE SET calls = calls + 1",
+            params![provider, day],
+        )?;
+        Ok(())
+    }
+
+    /// Latitude and longitude `location` was geocoded to, if it ever was.
+    pub fn geocoded_location(&self, location: &str) -> Result<Option<(f64, f64)>> {
+        self.connection()
+            .query_row(
+                "SELECT latitude, longitude FROM geocoded_locations WHERE location = ?1",
+                params![location],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+    }
+
+    pub fn record_geocoded_location(
+        &self,
+        location: &str,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO geocoded_locations (location, latitude, longitude)
+             VALUES (?1, ?2, ?3)",
+            params![location, latitude, longitude],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_period(&self, period: &PastPeriod) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO report_history (site, date, computed_at, days_ahead, score,
+                 start, utc_offset, duration_hours, wind_min_mps, wind_max_mps, pop_max,
+                 wind_direction)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                period.site,
+                period.date,
+                period.computed_at,
+                period.days_ahead,
+                period.score,
+                period.start,
+                period.utc_offset,
+                period.duration_hours,
+                period.wind_min_mps,
+                period.wind_max_mps,
+                period.pop_max,
+                period.wind_direction
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The periods reported for `site` on days from `since`, a `YYYY-MM-DD`
+    /// date, in the order they were flown and computed.
+    pub fn past_periods(&self, site: &str, since: &str) -> Result<Vec<PastPeriod>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT site, date, computed_at, days_ahead, score, start, utc_offset,
+                    duration_hours, wind_min_mps, wind_max_mps, pop_max, wind_direction
+             FROM report_history
+             WHERE site = ?1 AND date >= ?2
+             ORDER BY date, computed_at, start",
+        )?;
+        let rows = statement.query_map(params![site, since], past_period)?;
+        rows.collect()
+    }
+
+    /// The periods of the latest report of every site and day from `since`,
+    /// by site and in the order they were flown.
+    pub fn latest_periods(&self, since: &str) -> Result<Vec<PastPeriod>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT h.site, h.date, h.computed_at, h.days_ahead, h.score, h.start, h.utc_offset,
+                    h.duration_hours, h.wind_min_mps, h.wind_max_mps, h.pop_max, h.wind_direction
+             FROM report_history h
+             JOIN (SELECT site, date, MAX(computed_at) AS computed_at
+                   FROM report_history GROUP BY site, date) latest
+               ON latest.site = h.site AND latest.date = h.date
+                  AND latest.computed_at = h.computed_at
+             WHERE h.date >= ?1
+             ORDER BY h.site, h.date, h.start",
+        )?;
+        let rows = statement.query_map(params![since], past_period)?;
+        rows.collect()
+    }
+
+    pub fn add_subscription(&self, chat_id: &str, site: &str) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR IGNORE INTO telegram_subscriptions (chat_id, site) VALUES (?1, ?2)",
+            params![chat_id, site],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_subscription(&self, chat_id: &str, site: &str) -> Result<()> {
+        self.connection().execute(
+            "DELETE FROM telegram_subscriptions WHERE chat_id = ?1 AND site = ?2",
+            params![chat_id, site],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_subscriptions(&self, chat_id: &str) -> Result<()> {
+        self.connection().execute(
+            "DELETE FROM telegram_subscriptions WHERE chat_id = ?1",
+            params![chat_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn subscriptions(&self) -> Result<Vec<Subscription>> {
+        let connection = self.connection();
+        let mut statement = connection
+            .prepare("SELECT chat_id, site FROM telegram_subscriptions ORDER BY chat_id, site")?;
+        let rows = statement.query_map([], |row| {
+            Ok(Subscription {
+                chat_id: row.get(0)?,
+                site: row.get(1)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn set_chat_style(&self, chat_id: &str, style: &str) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO chat_styles (chat_id, style) VALUES (?1, ?2)",
+            params![chat_id, style],
+        )?;
+        Ok(())
+    }
+
+    /// Message style picked by each chat through the bot.
+    pub fn chat_styles(&self) -> Result<HashMap<String, String>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT chat_id, style FROM chat_styles")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Subscribes the chat to the reports of a pilot profile, or back to
+    /// the ones of the sites with `None`.
+    pub fn set_chat_profile(&self, chat_id: &str, profile: Option<&str>) -> Result<()> {
+        match profile {
+            Some(profile) => self.connection().execute(
+                "INSERT OR REPLACE INTO chat_profiles (chat_id, profile) VALUES (?1, ?2)",
+                params![chat_id, profile],
+            )?,
+            None => self.connection().execute(
+                "DELETE FROM chat_profiles WHERE chat_id = ?1",
+                params![chat_id],
+            )?,
+        };
+        Ok(())
+    }
+
+    /// Pilot profile picked by each chat through the bot.
+    pub fn chat_profiles(&self) -> Result<HashMap<String, String>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT chat_id, profile FROM chat_profiles")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    pub fn defer_message(
+        &self,
+        chat_id: &str,
+        text: &str,
+        silent: bool,
+        send_at: i64,
+        expires_at: Option<i64>,
+        report_date: Option<&str>,
+    ) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO deferred_messages (chat_id, text, silent, send_at, expires_at, report_date)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![chat_id, text, silent, send_at, expires_at, report_date],
+        )?;
+        Ok(())
+    }
+
+    /// Drops the messages kept for `chat_id` with the reports of
+    /// `report_date`, a `YYYY-MM-DD` date, outdated by newer ones.
+    pub fn forget_deferred_reports(&self, chat_id: &str, report_date: &str) -> Result<()> {
+        self.connection().execute(
+            "DELETE FROM deferred_messages WHERE chat_id = ?1 AND report_date = ?2",
+            params![chat_id, report_date],
+        )?;
+        Ok(())
+    }
+
+    /// Deferred messages which may be sent at `now`, oldest first.
+    pub fn due_messages(&self, now: i64) -> Result<Vec<DeferredMessage>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT id, chat_id, text, silent, send_at, expires_at, report_date
+             FROM deferred_messages
+             WHERE send_at <= ?1 ORDER BY id",
+        )?;
+        let rows = statement.query_map(params![now], |row| {
+            Ok(DeferredMessage {
+                id: row.get(0)?,
+                chat_id: row.get(1)?,
+                text: row.get(2)?,
+                silent: row.get(3)?,
+                send_at: row.get(4)?,
+                expires_at: row.get(5)?,
+                report_date: row.get(6)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Holds back a deferred message until `send_at`.
+    pub fn postpone_message(&self, id: i64, send_at: i64) -> Result<()> {
+        self.connection().execute(
+            "UPDATE deferred_messages SET send_at = ?2 WHERE id = ?1",
+            params![id, send_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_deferred_message(&self, id: i64) -> Result<()> {
+        self.connection()
+            .execute("DELETE FROM deferred_messages WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Forgets the subscriptions, chat settings, site changes and deferred
+    /// messages, keeping the histories.
+    pub fn clear_bot_state(&self) -> Result<()> {
+        self.connection().execute_batch(
+            "DELETE FROM telegram_subscriptions;
+             DELETE FROM chat_styles;
+             DELETE FROM chat_profiles;
+             DELETE FROM site_changes;
+             DELETE FROM deferred_messages;",
+        )?;
+        Ok(())
+    }
+
+    pub fn set_site_change(&self, site: &str, setting: &str, value: &str) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO site_changes (site, setting, value) VALUES (?1, ?2, ?3)",
+            params![site, setting, value],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_site_change(&self, site: &str, setting: &str) -> Result<()> {
+        self.connection().execute(
+            "DELETE FROM site_changes WHERE site = ?1 AND setting = ?2",
+            params![site, setting],
+        )?;
+        Ok(())
+    }
+
+    /// Site changes, the most recent last.
+    pub fn site_changes(&self) -> Result<Vec<SiteChange>> {
+        let connection = self.connection();
+        let mut statement =
+            connection.prepare("SELECT site, setting, value FROM site_changes ORDER BY rowid")?;
+        let rows = statement.query_map([], |row| {
+            Ok(SiteChange {
+                site: row.get(0)?,
+                setting: row.get(1)?,
+                value: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+fn past_period(row: &rusqlite::Row) -> Result<PastPeriod> {
+    Ok(PastPeriod {
+        site: row.get(0)?,
+        date: row.get(1)?,
+        computed_at: row.get(2)?,
+        days_ahead: row.get(3)?,
+        score: row.get(4)?,
+        start: row.get(5)?,
+        utc_offset: row.get(6)?,
+        duration_hours: row.get(7)?,
+        wind_min_mps: row.get(8)?,
+        wind_max_mps: row.get(9)?,
+        pop_max: row.get(10)?,
+        wind_direction: row.get(11)?,
+    })
+}
@@ -0,0 +1,90 @@
+//! The systemd notification protocol, letting systemd supervise the daemons
+//! run as `Type=notify` services and restart them once hung, see
+//! sd_notify(3). Nothing is sent outside of systemd.
+
+use std::os::unix::net::UnixDatagram;
+use std::time::Duration;
+
+/// Sends `state`, e.g. "READY=1", to the socket systemd set up for the
+/// service. Failures are only logged.
+pub fn notify(state: &str) {
+    let path = match std::env::var_os("NOTIFY_SOCKET") {
+        Some(path) => path,
+        None => return,
+    };
+    let sent = UnixDatagram::unbound().and_then(|socket| {
+        let path = path.to_string_lossy();
+        match path.strip_prefix('@') {
+            // Abstract sockets, only known to Linux.
+            #[cfg(target_os = "linux")]
+            Some(name) => {
+                use std::os::linux::net::SocketAddrExt;
+                let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+                socket.send_to_addr(state.as_bytes(), &address)
+            }
+            _ => socket.send_to(state.as_bytes(), &*path),
+        }
+    });
+    if let Err(error) = sent {
+        tracing::warn!(%error, "failed to notify systemd");
+    }
+}
+
+/// Startup is complete.
+pub fn ready() {
+    notify("READY=1");
+}
+
+pub fn stopping() {
+    notify("STOPPING=1");
+}
+
+/// Shown by `systemctl status`, e.g. "next refresh at 18:30".
+pub fn status(text: &str) {
+    notify(&format!("STATUS={}", text));
+}
+
+/// Keep-alive pings for `WatchdogSec=`, systemd restarting the service when
+/// they stop.
+pub struct Watchdog {
+    interval: Option<tokio::time::Interval>,
+}
+
+impl Watchdog {
+    /// Pings at half the timeout set by systemd, or never without one.
+    pub fn new() -> Self {
+        Watchdog {
+            interval: watchdog_timeout().map(|timeout| tokio::time::interval(timeout / 2)),
+        }
+    }
+
+    /// Completes at the next ping, after sending it. Meant to be selected
+    /// along with the work of the daemon loop, so that a hung loop stops
+    /// the pings.
+    pub async fn tick(&mut self) {
+        match &mut self.interval {
+            Some(interval) => {
+                interval.tick().await;
+                notify("WATCHDOG=1");
+            }
+            None => std::future::pending().await,
+        }
+    }
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Watchdog::new()
+    }
+}
+
+/// The watchdog timeout of the service, when meant for this process.
+fn watchdog_timeout() -> Option<Duration> {
+    if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+        if pid.parse::<u32>().ok() != Some(std::process::id()) {
+            return None;
+        }
+    }
+    let micros: u64 = std::env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    Some(Duration::from_micros(micros)).filter(|timeout| !timeout.is_zero())
+}
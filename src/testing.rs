@@ -0,0 +1,287 @@
+//! Fixtures for tests of the reports and notifiers without network access:
+//! builders of forecasts, a provider serving them and a notifier keeping
+//! what it is sent.
+//!
+//! Reports leave out the hours already over, forecasts should be for days
+//! to come.
+
+use crate::config::{DaylightConfig, FlyingSite, WindSector};
+use crate::error::ProviderError;
+use crate::forecast_client::{
+    get_time_of_day, DayWeatherForecast, ForecastProvider, HourWeatherForecast, TimeOfDay,
+};
+use crate::measures::{Precipitation, Pressure, Temperature, Visibility, WindDirection, WindSpeed};
+use crate::notification::{Notifier, NotifyError};
+use crate::{SiteFlyAbilityReport, SkippedSite};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, TimeZone};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// `hour` o'clock on `date`, in UTC.
+pub fn at(date: NaiveDate, hour: u32) -> DateTime<FixedOffset> {
+    let time = date.and_hms_opt(hour, 0, 0).expect("an hour of the day");
+    FixedOffset::east_opt(0).unwrap().from_utc_datetime(&time)
+}
+
+/// A site around Annecy flyable in 1 to 8 m/s from the west.
+pub fn site(name: &str) -> FlyingSite {
+    FlyingSite::new(
+        name,
+        45.9,
+        6.1,
+        WindSpeed::MPS(1.0),
+        WindSpeed::MPS(8.0),
+        vec![WindSector::from((225, 315))],
+    )
+}
+
+/// An hour of flyable weather for `site`, unless told otherwise.
+pub struct HourBuilder {
+    hour: HourWeatherForecast,
+}
+
+impl HourBuilder {
+    /// A dry daylight hour with 4 m/s from the west, 20°C and few clouds.
+    pub fn new(time: DateTime<FixedOffset>) -> Self {
+        HourBuilder {
+            hour: HourWeatherForecast {
+                time,
+                duration: Duration::hours(1),
+                time_of_day: TimeOfDay::DAY,
+                temperature: Temperature::C(20.0),
+                feels_like: Temperature::C(20.0),
+                dew_point: Temperature::C(10.0),
+                wind_speed: WindSpeed::MPS(4.0),
+                wind_gust: None,
+                wind_direction: WindDirection::new(270),
+                clouds: 20,
+                pop: 0.0,
+                pressure: Some(Pressure::HPA(1015.0)),
+                humidity: Some(50),
+                uvi: Some(3.0),
+                visibility: Some(Visibility::KM(10.0)),
+                rain: None,
+                snow: None,
+                lapse_rate: None,
+                thermal_top_m: None,
+                level_winds: vec![],
+                surface_wind_speed: None,
+                waves: None,
+                tide_m: None,
+                prior_rain_mm: None,
+                freezing_level_m: None,
+                snow_depth_m: None,
+                spread: None,
+            },
+        }
+    }
+
+    pub fn time_of_day(mut self, time_of_day: TimeOfDay) -> Self {
+        self.hour.time_of_day = time_of_day;
+        self
+    }
+
+    pub fn wind(mut self, speed: WindSpeed, degree: i16) -> Self {
+        self.hour.wind_speed = speed;
+        self.hour.wind_direction = WindDirection::new(degree);
+        self
+    }
+
+    pub fn gust(mut self, gust: WindSpeed) -> Self {
+        self.hour.wind_gust = Some(gust);
+        self
+    }
+
+    /// Sets the felt temperature as well.
+    pub fn temperature(mut self, temperature: Temperature) -> Self {
+        self.hour.temperature = temperature;
+        self.hour.feels_like = temperature;
+        self
+    }
+
+    pub fn dew_point(mut self, dew_point: Temperature) -> Self {
+        self.hour.dew_point = dew_point;
+        self
+    }
+
+    /// Cloud cover in percent.
+    pub fn clouds(mut self, clouds: i16) -> Self {
+        self.hour.clouds = clouds;
+        self
+    }
+
+    /// Probability of precipitation, from 0 to 1.
+    pub fn pop(mut self, pop: f32) -> Self {
+        self.hour.pop = pop;
+        self
+    }
+
+    pub fn rain(mut self, rain: Precipitation) -> Self {
+        self.hour.rain = Some(rain);
+        self
+    }
+
+    /// Rain of the hours before, see `apply_prior_rain`.
+    pub fn prior_rain(mut self, mm: f32) -> Self {
+        self.hour.prior_rain_mm = Some(mm);
+        self
+    }
+
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.hour.visibility = Some(visibility);
+        self
+    }
+
+    pub fn lapse_rate(mut self, lapse_rate: f32) -> Self {
+        self.hour.lapse_rate = Some(lapse_rate);
+        self
+    }
+
+    pub fn thermal_top(mut self, meters: f32) -> Self {
+        self.hour.thermal_top_m = Some(meters);
+        self
+    }
+
+    pub fn build(self) -> HourWeatherForecast {
+        self.hour
+    }
+}
+
+/// A day of 24 hours built by `HourBuilder`, with daylight from 7:00 to
+/// 20:00 UTC.
+pub struct DayBuilder {
+    date: NaiveDate,
+    sunrise: DateTime<FixedOffset>,
+    sunset: DateTime<FixedOffset>,
+    hours: Vec<HourBuilder>,
+}
+
+impl DayBuilder {
+    pub fn new(date: NaiveDate) -> Self {
+        let day = DayBuilder {
+            date,
+            sunrise: at(date, 7),
+            sunset: at(date, 20),
+            hours: (0..24)
+                .map(|hour| HourBuilder::new(at(date, hour)))
+                .collect(),
+        };
+        day.daylight(7, 20)
+    }
+
+    /// Moves sunrise and sunset to these hours, with half an hour of civil
+    /// twilight around them, updating the time of day of every hour.
+    pub fn daylight(mut self, sunrise_hour: u32, sunset_hour: u32) -> Self {
+        self.sunrise = at(self.date, sunrise_hour);
+        self.sunset = at(self.date, sunset_hour);
+        let day = self.day();
+        self.hours = self
+            .hours
+            .into_iter()
+            .map(|hour| {
+                let time_of_day = get_time_of_day(
+                    hour.hour.time,
+                    hour.hour.duration,
+                    &day,
+                    &DaylightConfig::default(),
+                );
+                hour.time_of_day(time_of_day)
+            })
+            .collect();
+        self
+    }
+
+    /// Applies `change` to the hours from `from` o'clock until `until`.
+    pub fn hours<F: Fn(HourBuilder) -> HourBuilder>(
+        mut self,
+        from: u32,
+        until: u32,
+        change: F,
+    ) -> Self {
+        self.hours = self
+            .hours
+            .into_iter()
+            .enumerate()
+            .map(|(hour, builder)| {
+                if (from as usize..until as usize).contains(&hour) {
+                    change(builder)
+                } else {
+                    builder
+                }
+            })
+            .collect();
+        self
+    }
+
+    pub fn build(self) -> DayWeatherForecast {
+        let day = self.day();
+        DayWeatherForecast {
+            hourly: self.hours.into_iter().map(HourBuilder::build).collect(),
+            ..day
+        }
+    }
+
+    /// The day without its hours.
+    fn day(&self) -> DayWeatherForecast {
+        DayWeatherForecast {
+            date: self.date,
+            sunrise: self.sunrise,
+            sunset: self.sunset,
+            civil_dawn: self.sunrise - Duration::minutes(30),
+            civil_dusk: self.sunset + Duration::minutes(30),
+            hourly: vec![],
+            outlook: None,
+            alerts: vec![],
+            missing: BTreeMap::new(),
+        }
+    }
+}
+
+/// A provider serving the same forecast for any place, e.g. for
+/// `ForecastSources::with_provider`.
+pub struct FixedForecast(pub Vec<DayWeatherForecast>);
+
+#[async_trait]
+impl ForecastProvider for FixedForecast {
+    fn name(&self) -> &'static str {
+        "Fixed"
+    }
+
+    async fn get_raw_forecast(&self, _lat: f64, _lon: f64) -> Result<String, ProviderError> {
+        Ok(String::new())
+    }
+
+    fn parse_forecast(&self, _raw: &str) -> serde_json::Result<Vec<DayWeatherForecast>> {
+        Ok(self.0.clone())
+    }
+}
+
+/// A notifier keeping the reports of every call instead of sending them.
+#[derive(Default)]
+pub struct RecordingNotifier {
+    pub reports: Mutex<Vec<Vec<SiteFlyAbilityReport>>>,
+    pub skipped: Mutex<Vec<Vec<SkippedSite>>>,
+    pub error_summaries: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl Notifier for RecordingNotifier {
+    async fn notify(&self, reports: &[SiteFlyAbilityReport]) -> Result<(), NotifyError> {
+        self.reports.lock().unwrap().push(reports.to_vec());
+        Ok(())
+    }
+
+    async fn notify_nothing_flyable(&self, skipped: &[SkippedSite]) -> Result<(), NotifyError> {
+        self.skipped.lock().unwrap().push(skipped.to_vec());
+        Ok(())
+    }
+
+    async fn notify_errors(&self, summary: &str) -> Result<(), NotifyError> {
+        self.error_summaries
+            .lock()
+            .unwrap()
+            .push(summary.to_string());
+        Ok(())
+    }
+}
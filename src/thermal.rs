@@ -0,0 +1,134 @@
+use crate::forecast_client::{DayWeatherForecast, TimeOfDay};
+use crate::http::HttpClient;
+use reqwest::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Environmental lapse rates, in °C per km, separating the thermal classes.
+// The dry adiabatic rate is ~9.8 °C/km; the closer the air gets to it, the
+// stronger the thermals.
+const WEAK_LAPSE_RATE: f32 = 5.0;
+const MODERATE_LAPSE_RATE: f32 = 6.5;
+const STRONG_LAPSE_RATE: f32 = 8.0;
+
+#[derive(Deserialize, Debug)]
+struct PressureLevelHourly {
+    time: Vec<i64>,
+    temperature_2m: Vec<Option<f32>>,
+    #[serde(rename = "temperature_700hPa")]
+    temperature_700: Vec<Option<f32>>,
+    #[serde(rename = "geopotential_height_700hPa")]
+    height_700: Vec<Option<f32>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PressureLevelForecast {
+    elevation: f32,
+    hourly: PressureLevelHourly,
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Copy, Clone)]
+pub enum ThermalQuality {
+    Stable,
+    Weak,
+    Moderate,
+    Strong,
+}
+
+impl ThermalQuality {
+    pub fn from_lapse_rate(lapse_rate: f32) -> Self {
+        if lapse_rate >= STRONG_LAPSE_RATE {
+            ThermalQuality::Strong
+        } else if lapse_rate >= MODERATE_LAPSE_RATE {
+            ThermalQuality::Moderate
+        } else if lapse_rate >= WEAK_LAPSE_RATE {
+            ThermalQuality::Weak
+        } else {
+            ThermalQuality::Stable
+        }
+    }
+
+    pub fn is_usable(&self) -> bool {
+        *self >= ThermalQuality::Moderate
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ThermalQuality::Stable => "stable",
+            ThermalQuality::Weak => "weak",
+            ThermalQuality::Moderate => "moderate",
+            ThermalQuality::Strong => "strong",
+        }
+    }
+}
+
+/// Fetches pressure-level temperatures from Open-Meteo to estimate how
+/// thermic the air above a site is going to be.
+pub struct OpenMeteoClient {
+    url: String,
+    http: HttpClient,
+}
+
+impl OpenMeteoClient {
+    pub fn new(url: String, http: HttpClient) -> Self {
+        OpenMeteoClient { url, http }
+    }
+
+    pub fn name(&self) -> &'static str {
+        "Open-Meteo"
+    }
+
+    /// Lapse rate between the surface and 700 hPa (~3000 m) in °C/km, keyed
+    /// by unix timestamp of the forecast hour.
+    pub async fn get_lapse_rates(&self, lat: f64, lon: f64) -> Result<HashMap<i64, f32>> {
+        let (lat, lon) = (lat.to_string(), lon.to_string());
+        let raw_forecast: PressureLevelForecast = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("latitude", &lat[..]),
+                    ("longitude", &lon[..]),
+                    (
+                        "hourly",
+                        "temperature_2m,temperature_700hPa,geopotential_height_700hPa",
+                    ),
+                    ("timeformat", "unixtime"),
+                    ("forecast_days", "3"),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+
+        let hourly = raw_forecast.hourly;
+        let mut lapse_rates = HashMap::new();
+        for (i, time) in hourly.time.iter().enumerate() {
+            let level = (
+                hourly.temperature_2m.get(i).copied().flatten(),
+                hourly.temperature_700.get(i).copied().flatten(),
+                hourly.height_700.get(i).copied().flatten(),
+            );
+            if let (Some(surface_temp), Some(level_temp), Some(level_height)) = level {
+                let depth_km = (level_height - raw_forecast.elevation) / 1000.0;
+                if depth_km > 0.5 {
+                    lapse_rates.insert(*time, (surface_temp - level_temp) / depth_km);
+                }
+            }
+        }
+        Ok(lapse_rates)
+    }
+}
+
+/// Attaches lapse rates to the matching daylight hours of a forecast.
+pub fn apply_lapse_rates(forecasts: &mut [DayWeatherForecast], lapse_rates: &HashMap<i64, f32>) {
+    for day in forecasts.iter_mut() {
+        for hour in day.hourly.iter_mut() {
+            if hour.time_of_day != TimeOfDay::NIGHT {
+                hour.lapse_rate = lapse_rates.get(&hour.time.timestamp()).copied();
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Tide predictions from the NOAA CO-OPS API, for beach launches only
+//! flyable around low tide.
+
+use crate::forecast_client::DayWeatherForecast;
+use crate::http::HttpClient;
+use chrono::{NaiveDateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Hours of predictions, as many as the hourly forecast.
+const PREDICTION_HOURS: &str = "72";
+
+#[derive(Deserialize, Debug)]
+struct Prediction {
+    /// E.g. "2026-10-16 13:00", in GMT.
+    t: String,
+    /// Meters above the MLLW datum, as a string.
+    v: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct Predictions {
+    predictions: Vec<Prediction>,
+}
+
+pub struct TideClient {
+    url: String,
+    http: HttpClient,
+}
+
+impl TideClient {
+    pub fn new(url: String, http: HttpClient) -> Self {
+        TideClient { url, http }
+    }
+
+    pub fn name(&self) -> &'static str {
+        "NOAA"
+    }
+
+    /// Hourly tide heights at `station` in meters above mean lower low
+    /// water, keyed by unix timestamp.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_tides(&self, station: &str) -> reqwest::Result<HashMap<i64, f32>> {
+        let begin_date = Utc::now().format("%Y%m%d").to_string();
+        let predictions: Predictions = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("product", "predictions"),
+                    ("station", station),
+                    ("begin_date", &begin_date[..]),
+                    ("range", PREDICTION_HOURS),
+                    ("datum", "MLLW"),
+                    ("interval", "h"),
+                    ("units", "metric"),
+                    ("time_zone", "gmt"),
+                    ("format", "json"),
+                    ("application", "weather-notifier"),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        Ok(predictions
+            .predictions
+            .iter()
+            .filter_map(|prediction| {
+                let time = NaiveDateTime::parse_from_str(&prediction.t, "%Y-%m-%d %H:%M").ok()?;
+                let height = prediction.v.parse().ok()?;
+                Some((time.and_utc().timestamp(), height))
+            })
+            .collect())
+    }
+}
+
+/// Attaches the tide heights to the matching hours of a forecast.
+pub fn apply_tides(forecasts: &mut [DayWeatherForecast], tides: &HashMap<i64, f32>) {
+    for hour in forecasts.iter_mut().flat_map(|day| day.hourly.iter_mut()) {
+        hour.tide_m = tides.get(&hour.time.timestamp()).copied();
+    }
+}
@@ -0,0 +1,78 @@
+//! IANA time zones, e.g. "Europe/Paris", from the tz database built into
+//! chrono-tz so that forecasts spanning a DST change get the right offset
+//! on both sides of it.
+
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use std::io;
+
+/// A named zone of the tz database.
+#[derive(Debug, Clone)]
+pub struct Tz {
+    pub name: String,
+    zone: chrono_tz::Tz,
+}
+
+impl Tz {
+    /// The zone named `name`, failing for names unknown to the database.
+    pub fn load(name: &str) -> io::Result<Self> {
+        let zone = name.parse::<chrono_tz::Tz>().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown time zone \"{}\"", name),
+            )
+        })?;
+        Ok(Tz {
+            name: name.to_string(),
+            zone,
+        })
+    }
+
+    /// `timestamp` in the local time of the zone.
+    pub fn at(&self, timestamp: i64) -> DateTime<FixedOffset> {
+        self.zone
+            .timestamp_opt(timestamp, 0)
+            .unwrap()
+            .fixed_offset()
+    }
+
+    /// `local` time in the zone, the earlier of the two when the clocks go
+    /// back and `None` when going forward skips it.
+    pub fn from_local(&self, local: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        self.zone
+            .from_local_datetime(&local)
+            .earliest()
+            .map(|time| time.fixed_offset())
+    }
+}
+
+/// The current time in `zone`, the time zone of the server when `None`.
+pub fn now(zone: Option<&Tz>) -> DateTime<FixedOffset> {
+    match zone {
+        Some(zone) => zone.at(Utc::now().timestamp()),
+        None => Local::now().fixed_offset(),
+    }
+}
+
+/// The first `time` of day in `zone` from `now` on, an hour later when the
+/// clocks going forward skip it.
+pub fn next_local(
+    time: NaiveTime,
+    now: DateTime<FixedOffset>,
+    zone: Option<&Tz>,
+) -> DateTime<FixedOffset> {
+    let mut day = now.date_naive();
+    if now.time() > time {
+        day = day.succ_opt().unwrap_or(day);
+    }
+    let resolve = |local: NaiveDateTime| match zone {
+        Some(zone) => zone.from_local(local),
+        None => local
+            .and_local_timezone(Local)
+            .earliest()
+            .map(|time| time.fixed_offset()),
+    };
+    let local = day.and_time(time);
+    resolve(local)
+        .or_else(|| resolve(local + Duration::hours(1)))
+        .unwrap_or(now)
+}
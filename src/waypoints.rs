@@ -0,0 +1,127 @@
+//! Flying sites imported from the waypoints of KML or GPX files, as config
+//! stubs to be completed with the wind limits and sectors of each site.
+
+use crate::config::WindSector;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+pub struct Waypoint {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Meters above sea level.
+    pub elevation_m: Option<f32>,
+    /// Left for the pilot to fill in when empty.
+    pub wind_sectors: Vec<WindSector>,
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .and_then(|child| child.text())
+        .map(str::trim)
+}
+
+/// The named `Placemark` points, whose coordinates are `lon,lat[,alt]`.
+fn kml_waypoints(document: &roxmltree::Document) -> Vec<Waypoint> {
+    document
+        .descendants()
+        .filter(|node| node.has_tag_name("Placemark"))
+        .filter_map(|placemark| {
+            let point = placemark
+                .descendants()
+                .find(|node| node.has_tag_name("Point"))?;
+            let coordinates: Vec<&str> = child_text(point, "coordinates")?.split(',').collect();
+            Some(Waypoint {
+                name: child_text(placemark, "name")?.to_string(),
+                longitude: coordinates.first()?.trim().parse().ok()?,
+                latitude: coordinates.get(1)?.trim().parse().ok()?,
+                elevation_m: coordinates.get(2).and_then(|alt| alt.trim().parse().ok()),
+                wind_sectors: vec![],
+            })
+        })
+        .collect()
+}
+
+fn gpx_waypoints(document: &roxmltree::Document) -> Vec<Waypoint> {
+    document
+        .descendants()
+        .filter(|node| node.has_tag_name("wpt"))
+        .filter_map(|wpt| {
+            Some(Waypoint {
+                name: child_text(wpt, "name")?.to_string(),
+                latitude: wpt.attribute("lat")?.parse().ok()?,
+                longitude: wpt.attribute("lon")?.parse().ok()?,
+                elevation_m: child_text(wpt, "ele").and_then(|ele| ele.parse().ok()),
+                wind_sectors: vec![],
+            })
+        })
+        .collect()
+}
+
+/// The named waypoints of a `.kml` or `.gpx` file, skipping those without
+/// valid coordinates.
+pub fn read_waypoints(path: &Path) -> Result<Vec<Waypoint>, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)?;
+    let document = roxmltree::Document::parse(&contents)
+        .map_err(|error| format!("{}: {}", path.display(), error))?;
+    let extension = path.extension().and_then(|e| e.to_str());
+    match extension.map(str::to_lowercase).as_deref() {
+        Some("kml") => Ok(kml_waypoints(&document)),
+        Some("gpx") => Ok(gpx_waypoints(&document)),
+        _ => Err(format!("{} is neither a .kml nor a .gpx file", path.display()).into()),
+    }
+}
+
+fn toml_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// `[[sites]]` entries of `waypoints`, with wind limits and missing sectors
+/// to be adjusted to each site.
+pub fn site_stubs(waypoints: &[Waypoint]) -> String {
+    let mut stubs = String::new();
+    for waypoint in waypoints {
+        stubs.push_str(&format!(
+            "\n[[sites]]\nname = {}\nlatitude = {:.5}\nlongitude = {:.5}\n",
+            toml_string(&waypoint.name),
+            waypoint.latitude,
+            waypoint.longitude
+        ));
+        if let Some(elevation) = waypoint.elevation_m {
+            stubs.push_str(&format!("elevation_m = {:.0}\n", elevation));
+        }
+        stubs.push_str(
+            "min_flyable_wind = { type = \"MPS\", value = 1.0 }\n\
+             max_flyable_wind = { type = \"MPS\", value = 6.0 }\n",
+        );
+        if waypoint.wind_sectors.is_empty() {
+            stubs.push_str(
+                "# Placeholder accepting any direction, narrow it to those the site faces.\n\
+                 wind_sectors = [[0, 360]]\n",
+            );
+        } else {
+            let sectors: Vec<String> = waypoint
+                .wind_sectors
+                .iter()
+                .map(|sector| format!("[{}, {}]", sector.from, sector.to))
+                .collect();
+            stubs.push_str(&format!("wind_sectors = [{}]\n", sectors.join(", ")));
+        }
+    }
+    stubs
+}
@@ -0,0 +1,108 @@
+//! Freezing level and snow depth from the Open-Meteo Forecast API, for
+//! winter flying.
+
+use crate::forecast_client::DayWeatherForecast;
+use crate::http::HttpClient;
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+struct SnowHourly {
+    time: Vec<i64>,
+    freezing_level_height: Vec<Option<f32>>,
+    snow_depth: Vec<Option<f32>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SnowForecast {
+    hourly: SnowHourly,
+}
+
+/// The freezing level and snow for a single forecast hour.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Snowpack {
+    /// Meters AMSL.
+    pub freezing_level_m: Option<f32>,
+    /// Meters of snow on the ground.
+    pub snow_depth_m: Option<f32>,
+}
+
+pub struct WinterClient {
+    url: String,
+    http: HttpClient,
+}
+
+impl WinterClient {
+    pub fn new(url: String, http: HttpClient) -> Self {
+        WinterClient { url, http }
+    }
+
+    pub fn name(&self) -> &'static str {
+        "Open-Meteo"
+    }
+
+    /// The freezing level and snow depth keyed by unix timestamp of the
+    /// forecast hour.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn get_snowpack(
+        &self,
+        lat: f64,
+        lon: f64,
+    ) -> reqwest::Result<HashMap<i64, Snowpack>> {
+        let (lat, lon) = (lat.to_string(), lon.to_string());
+        let forecast: SnowForecast = self
+            .http
+            .send(|client| {
+                client.get(&self.url).query(&[
+                    ("latitude", &lat[..]),
+                    ("longitude", &lon[..]),
+                    ("hourly", "freezing_level_height,snow_depth"),
+                    ("timeformat", "unixtime"),
+                    ("forecast_days", "3"),
+                ])
+            })
+            .await?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+
+        let hourly = forecast.hourly;
+        let mut snowpack = HashMap::new();
+        for (i, time) in hourly.time.iter().enumerate() {
+            let hour = Snowpack {
+                freezing_level_m: hourly.freezing_level_height.get(i).copied().flatten(),
+                snow_depth_m: hourly.snow_depth.get(i).copied().flatten(),
+            };
+            if hour.freezing_level_m.is_some() || hour.snow_depth_m.is_some() {
+                snowpack.insert(*time, hour);
+            }
+        }
+        Ok(snowpack)
+    }
+}
+
+/// Attaches the freezing level and snow depth to the matching hours of a
+/// forecast.
+pub fn apply_snowpack(forecasts: &mut [DayWeatherForecast], snowpack: &HashMap<i64, Snowpack>) {
+    for hour in forecasts.iter_mut().flat_map(|day| day.hourly.iter_mut()) {
+        let snow = snowpack
+            .get(&hour.time.timestamp())
+            .copied()
+            .unwrap_or_default();
+        hour.freezing_level_m = snow.freezing_level_m;
+        hour.snow_depth_m = snow.snow_depth_m;
+    }
+}
+
+/// Whether `date` is in the winter months at `latitude`, November to March
+/// north of the equator and May to September south of it.
+pub fn is_winter(date: NaiveDate, latitude: f64) -> bool {
+    if latitude >= 0.0 {
+        date.month() >= 11 || date.month() <= 3
+    } else {
+        (5..=9).contains(&date.month())
+    }
+}
@@ -0,0 +1,130 @@
+//! Runs of the whole pipeline replaying the recorded OpenWeatherMap and
+//! Telegram responses of `tests/cassettes`.
+
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAY: i64 = 86_400;
+
+/// A directory of its own for the files of `test`.
+fn work_dir(test: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("weather-notifier-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Moves the recorded forecast by whole days to start today, as reports
+/// leave out the hours already over.
+fn shift_forecast(body: &str) -> String {
+    let mut forecast: Value = serde_json::from_str(body).unwrap();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let recorded = forecast["daily"][0]["dt"].as_i64().unwrap();
+    let shift = (now - now % DAY) - (recorded - recorded % DAY);
+    for day in forecast["daily"].as_array_mut().unwrap() {
+        for key in ["dt", "sunrise", "sunset"] {
+            day[key] = Value::from(day[key].as_i64().unwrap() + shift);
+        }
+    }
+    for hour in forecast["hourly"].as_array_mut().unwrap() {
+        hour["dt"] = Value::from(hour["dt"].as_i64().unwrap() + shift);
+    }
+    forecast.to_string()
+}
+
+/// Runs the notifier on the site the `cassette` was recorded for, with the
+/// top-level `settings`, returning its exit code.
+fn run(test: &str, cassette: &str, settings: &str) -> i32 {
+    let dir = work_dir(test);
+    let recorded = fs::read_to_string(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/cassettes")
+            .join(cassette),
+    )
+    .unwrap();
+    let mut interactions: Vec<Value> = serde_json::from_str(&recorded).unwrap();
+    for interaction in &mut interactions {
+        if interaction["url"]
+            .as_str()
+            .unwrap()
+            .contains("openweathermap")
+        {
+            let body = shift_forecast(interaction["body"].as_str().unwrap());
+            interaction["body"] = Value::from(body);
+        }
+    }
+    let cassette = dir.join("cassette.json");
+    fs::write(&cassette, serde_json::to_string(&interactions).unwrap()).unwrap();
+
+    let config = dir.join("config.toml");
+    fs::write(
+        &config,
+        format!(
+            r#"
+{settings}
+weather_api_version = "3.0"
+weather_api_token = "owm-token"
+
+[cache]
+dir = "{cache}"
+
+[[sites]]
+name = "Planfait"
+latitude = 45.86
+longitude = 6.18
+min_flyable_wind = {{ type = "MPS", value = 1.0 }}
+max_flyable_wind = {{ type = "MPS", value = 6.0 }}
+wind_sectors = [[250, 330]]
+
+[[notifiers]]
+type = "telegram"
+bot_token = "123456:telegram-token"
+chat_ids = ["-1001234567890"]
+
+[retry]
+attempts = 1
+
+[http.cassette]
+path = "{cassette}"
+mode = "replay"
+redact = ["owm-token", "123456:telegram-token"]
+"#,
+            settings = settings.replace("{dir}", &dir.display().to_string()),
+            cache = dir.join("cache").display(),
+            cassette = cassette.display(),
+        ),
+    )
+    .unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_weather-notifier"))
+        .arg("--config")
+        .arg(&config)
+        .status()
+        .unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    status.code().unwrap()
+}
+
+#[test]
+fn sends_the_report_to_telegram() {
+    assert_eq!(run("sent", "owm_telegram.json", ""), 0);
+}
+
+#[test]
+fn fails_when_telegram_refuses_the_message() {
+    // `EXIT_NOTIFY`, the notifier having failed.
+    assert_eq!(run("refused", "owm_telegram_forbidden.json", ""), 4);
+}
+
+#[test]
+fn fails_when_the_refused_message_is_kept_for_a_retry() {
+    let settings = r#"state_db = "{dir}/state.db""#;
+    assert_eq!(run("kept", "owm_telegram_forbidden.json", settings), 4);
+}